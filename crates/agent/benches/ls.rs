@@ -0,0 +1,51 @@
+//! Compares sequential and parallel directory traversal for the `ls` tool.
+
+use agent::tools::ls::Ls;
+use agent::util::providers::RealProvider;
+use criterion::{
+    Criterion,
+    criterion_group,
+    criterion_main,
+};
+
+/// Number of directories in the generated tree
+const DIR_COUNT: usize = 100;
+/// Number of files in each generated directory
+const FILES_PER_DIR: usize = 100;
+
+fn ls_benchmark(c: &mut Criterion) {
+    let temp_dir = tempfile::tempdir().unwrap();
+    for i in 0..DIR_COUNT {
+        let dir = temp_dir.path().join(format!("dir{}", i));
+        std::fs::create_dir_all(&dir).unwrap();
+        for j in 0..FILES_PER_DIR {
+            std::fs::write(dir.join(format!("file{}.txt", j)), "content").unwrap();
+        }
+    }
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let tool = Ls {
+        path: temp_dir.path().to_string_lossy().to_string(),
+        depth: Some(1),
+        ..Default::default()
+    };
+
+    let mut group = c.benchmark_group("ls");
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            runtime
+                .block_on(tool.execute_with_concurrency(&RealProvider, None, 1))
+                .unwrap()
+        });
+    });
+    group.bench_function("parallel", |b| {
+        b.iter(|| runtime.block_on(tool.execute(&RealProvider, None)).unwrap());
+    });
+    group.finish();
+}
+
+criterion_group!(benches, ls_benchmark);
+criterion_main!(benches);
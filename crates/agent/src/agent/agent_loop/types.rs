@@ -363,12 +363,32 @@ pub enum ImageFormat {
     Jpeg,
     Png,
     Webp,
+    /// Not accepted by the model, so images read in this format are converted before being sent.
+    #[serde(alias = "tif")]
+    #[strum(serialize = "tiff", serialize = "tif")]
+    Tiff,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum ImageSource {
     Bytes(#[serde(with = "serde_bytes")] Vec<u8>),
+    /// An image downloaded from `url`.
+    Url {
+        url: String,
+        #[serde(with = "serde_bytes")]
+        bytes: Vec<u8>,
+    },
+}
+
+impl ImageSource {
+    /// The encoded image.
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            ImageSource::Bytes(bytes) => bytes,
+            ImageSource::Url { bytes, .. } => bytes,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -571,11 +591,18 @@ mod tests {
         test_ser_deser!(ImageFormat, ImageFormat::Png, "png");
         test_ser_deser!(ImageFormat, ImageFormat::Webp, "webp");
         test_ser_deser!(ImageFormat, ImageFormat::Jpeg, "jpeg");
+        test_ser_deser!(ImageFormat, ImageFormat::Tiff, "tiff");
         assert_eq!(
             ImageFormat::from_str("jpg").unwrap(),
             ImageFormat::Jpeg,
             "expected 'jpg' to parse to {}",
             ImageFormat::Jpeg
         );
+        assert_eq!(
+            ImageFormat::from_str("tif").unwrap(),
+            ImageFormat::Tiff,
+            "expected 'tif' to parse to {}",
+            ImageFormat::Tiff
+        );
     }
 }
@@ -0,0 +1,211 @@
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use tokio::io::AsyncWriteExt as _;
+
+use crate::agent::agent_loop::types::ToolUseBlock;
+use crate::agent::task_executor::{
+    ToolExecutionEndEvent,
+    ToolExecutorResult,
+};
+use crate::agent::types::AgentSettings;
+use crate::agent::util::error::{
+    ErrorContext as _,
+    UtilError,
+};
+use crate::agent::util::{
+    is_sensitive_env_var,
+    truncate_safe,
+};
+
+/// Max number of bytes of each string in [AuditEvent::args_json], e.g. file contents written by
+/// fs_write.
+const MAX_ARG_STRING_BYTES: usize = 256;
+
+const REDACTED_ARG_VALUE: &str = "[REDACTED]";
+
+/// Records every tool execution to a JSONL file, one [AuditEvent] per line.
+#[derive(Debug)]
+pub struct AuditLogger {
+    path: PathBuf,
+    file: tokio::fs::File,
+}
+
+impl AuditLogger {
+    /// Whether or not audit logging is enabled for the given settings.
+    pub fn enabled(settings: &AgentSettings) -> bool {
+        settings.audit_log_path.is_some()
+    }
+
+    /// Opens the audit log at `path` in append mode, creating it if it does not exist.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, UtilError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("failed to create directory {}", parent.to_string_lossy()))?;
+        }
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .with_context(|| format!("failed to open {}", path.to_string_lossy()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends `event` to the audit log as a single line of JSON.
+    pub async fn log_event(&mut self, event: &AuditEvent) -> Result<(), UtilError> {
+        let mut line = serde_json::to_vec(event).context("failed to serialize audit event")?;
+        line.push(b'\n');
+        self.file
+            .write_all(&line)
+            .await
+            .with_context(|| format!("failed to write to {}", self.path.to_string_lossy()))?;
+        self.file
+            .flush()
+            .await
+            .with_context(|| format!("failed to write to {}", self.path.to_string_lossy()))?;
+        Ok(())
+    }
+}
+
+/// A single tool execution recorded by [AuditLogger].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEvent {
+    /// RFC 3339 timestamp of when the execution ended
+    pub timestamp: String,
+    /// Canonical name of the executed tool
+    pub tool: String,
+    /// The tool arguments as provided by the model, serialized as JSON. See [redact_args].
+    pub args_json: String,
+    /// Whether the tool executed successfully
+    pub success: bool,
+    pub duration_ms: u64,
+}
+
+impl AuditEvent {
+    pub fn new(tool_use: &ToolUseBlock, evt: &ToolExecutionEndEvent) -> Self {
+        Self {
+            timestamp: evt.end_time.to_rfc3339(),
+            tool: evt.tool.canonical_tool_name().as_full_name().to_string(),
+            args_json: redact_args(&tool_use.input).to_string(),
+            success: matches!(&evt.result, ToolExecutorResult::Completed { result: Ok(_), .. }),
+            duration_ms: evt.duration.as_millis() as u64,
+        }
+    }
+}
+
+/// Returns `args` with the values of keys that look like secrets, e.g. the `env` of executeCmd,
+/// redacted, and strings longer than [MAX_ARG_STRING_BYTES] truncated.
+fn redact_args(args: &serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match args {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let value = if is_sensitive_env_var(key) {
+                        Value::String(REDACTED_ARG_VALUE.to_string())
+                    } else {
+                        redact_args(value)
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(redact_args).collect()),
+        Value::String(s) if s.len() > MAX_ARG_STRING_BYTES => Value::String(format!(
+            "{}... [{} bytes]",
+            truncate_safe(s, MAX_ARG_STRING_BYTES),
+            s.len()
+        )),
+        value => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(tool: &str, success: bool) -> AuditEvent {
+        AuditEvent {
+            timestamp: "2025-01-01T00:00:00+00:00".to_string(),
+            tool: tool.to_string(),
+            args_json: serde_json::json!({ "path": "/tmp" }).to_string(),
+            success,
+            duration_ms: 5,
+        }
+    }
+
+    async fn read_events(path: &Path) -> Vec<AuditEvent> {
+        tokio::fs::read_to_string(path)
+            .await
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_audit_logger_writes_events_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("audit.jsonl");
+        let events = [event("fs_read", true), event("fs_write", false), event("ls", true)];
+
+        let mut logger = AuditLogger::open(&path).await.unwrap();
+        for evt in &events[..2] {
+            logger.log_event(evt).await.unwrap();
+        }
+        drop(logger);
+
+        // Reopening appends rather than truncating.
+        let mut logger = AuditLogger::open(&path).await.unwrap();
+        logger.log_event(&events[2]).await.unwrap();
+
+        assert_eq!(read_events(&path).await, events);
+    }
+
+    #[test]
+    fn test_redact_args() {
+        let args = serde_json::json!({
+            "command": "gh pr list",
+            "env": { "GITHUB_TOKEN": "ghp_123", "PAGER": "cat" },
+            "ops": [{ "content": "a".repeat(1000), "db_password": { "value": "hunter2" } }],
+            "limit": 5,
+        });
+        assert_eq!(
+            redact_args(&args),
+            serde_json::json!({
+                "command": "gh pr list",
+                "env": { "GITHUB_TOKEN": "[REDACTED]", "PAGER": "cat" },
+                "ops": [{
+                    "content": format!("{}... [1000 bytes]", "a".repeat(MAX_ARG_STRING_BYTES)),
+                    "db_password": "[REDACTED]",
+                }],
+                "limit": 5,
+            })
+        );
+    }
+
+    #[test]
+    fn test_audit_logger_enabled() {
+        let mut settings = AgentSettings::default();
+        assert!(!AuditLogger::enabled(&settings));
+        settings.audit_log_path = Some(PathBuf::from("audit.jsonl"));
+        assert!(AuditLogger::enabled(&settings));
+    }
+}
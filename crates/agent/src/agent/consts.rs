@@ -16,5 +16,19 @@ pub const MAX_TOOL_SPEC_DESCRIPTION_LEN: usize = 10_004;
 /// 10 MB
 pub const MAX_IMAGE_SIZE_BYTES: u64 = 10 * 1024 * 1024;
 
+/// Max width or height of an image accepted by the model API.
+pub const MAX_IMAGE_DIMENSION_PX: u32 = 8000;
+
+/// Max combined size of all text content in a single tool result sent to the model.
+pub const MAX_TOOL_RESPONSE_TEXT_BYTES: usize = 400_000;
+
+/// Name of the optional argument added to every tool spec for the model to explain why it is
+/// making a tool use. See [crate::agent::tools] for details.
 pub const TOOL_USE_PURPOSE_FIELD_NAME: &str = "__tool_use_purpose";
-pub const TOOL_USE_PURPOSE_FIELD_DESCRIPTION: &str = "A brief explanation why you are making this tool use.";
+/// Key in a built-in tool's input schema containing the version of the schema. See
+/// [crate::agent::tools] for details.
+pub const TOOL_VERSION_FIELD_NAME: &str = "x-tool-version";
+
+/// Max number of characters allowed in the [TOOL_USE_PURPOSE_FIELD_NAME] argument.
+pub const MAX_TOOL_USE_PURPOSE_LEN: usize = 500;
+pub const TOOL_USE_PURPOSE_FIELD_DESCRIPTION: &str = "Optional: the AI's stated reason for using this tool";
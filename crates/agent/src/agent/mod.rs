@@ -1,5 +1,6 @@
 pub mod agent_config;
 pub mod agent_loop;
+pub mod audit;
 pub mod consts;
 pub mod mcp;
 mod permissions;
@@ -55,6 +56,10 @@ use agent_loop::{
     AgentLoopId,
     LoopState,
 };
+use audit::{
+    AuditEvent,
+    AuditLogger,
+};
 use chrono::Utc;
 use consts::MAX_RESOURCE_FILE_LENGTH;
 use futures::stream::FuturesUnordered;
@@ -102,16 +107,20 @@ use tokio_stream::StreamExt as _;
 use tokio_util::sync::CancellationToken;
 use tool_utils::{
     SanitizedToolSpecs,
+    add_tool_examples,
     add_tool_use_purpose_arg,
+    localize_tool_descriptions,
     sanitize_tool_specs,
 };
 use tools::{
     Tool,
+    ToolContext,
     ToolExecutionError,
     ToolExecutionOutput,
     ToolExecutionOutputItem,
     ToolParseError,
     ToolParseErrorKind,
+    tool_output_channel,
 };
 use tracing::{
     debug,
@@ -138,6 +147,7 @@ use util::request_channel::new_request_channel;
 use crate::agent::consts::{
     DUMMY_TOOL_NAME,
     MAX_CONVERSATION_STATE_HISTORY_LEN,
+    MAX_TOOL_RESPONSE_TEXT_BYTES,
 };
 use crate::agent::mcp::McpManagerHandle;
 use crate::agent::tools::{
@@ -254,6 +264,8 @@ pub struct Agent {
 
     /// Configuration settings to alter agent behavior.
     settings: AgentSettings,
+    /// Records tool executions if enabled by [AgentSettings::audit_log_path].
+    audit_logger: Option<AuditLogger>,
 
     /// Cached result when creating a tool spec for sending to the backend.
     ///
@@ -315,6 +327,7 @@ impl Agent {
             agent_spawn_hooks: Default::default(),
             model,
             settings: snapshot.settings,
+            audit_logger: None,
             cached_tool_specs: None,
             cached_mcp_configs,
             working_directory: None,
@@ -340,6 +353,21 @@ impl Agent {
 
     /// TODO - do initialization logic depending on execution state
     async fn initialize(&mut self) {
+        if let Some(path) = &self.settings.audit_log_path {
+            match AuditLogger::open(path).await {
+                Ok(logger) => self.audit_logger = Some(logger),
+                Err(err) => error!(?err, ?path, "failed to open audit log"),
+            }
+        }
+
+        // Restore tool state persisted by a previous session.
+        if let Some(path) = self.settings.tool_state_path.as_ref().filter(|p| p.exists()) {
+            match ToolState::deserialize_from_disk(path).await {
+                Ok(tool_state) => self.tool_state = tool_state,
+                Err(err) => warn!(?err, ?path, "failed to load persisted tool state"),
+            }
+        }
+
         // Initialize MCP servers, waiting with timeout.
         {
             if !self.cached_mcp_configs.overridden_configs.is_empty() {
@@ -485,6 +513,12 @@ impl Agent {
                 }
             }
         }
+
+        if let Some(path) = &self.settings.tool_state_path {
+            if let Err(err) = self.tool_state.serialize_to_disk(path).await {
+                error!(?err, ?path, "failed to persist tool state");
+            }
+        }
     }
 
     fn active_state(&self) -> &ActiveState {
@@ -1141,6 +1175,19 @@ impl Agent {
     async fn handle_task_executor_event(&mut self, evt: TaskExecutorEvent) -> Result<(), AgentError> {
         debug!(?evt, "handling new task executor event");
         match evt {
+            TaskExecutorEvent::ToolExecutionOutput(evt) => {
+                let content = match evt.item {
+                    ToolExecutionOutputItem::Text(text) => ContentChunk::Text(text),
+                    ToolExecutionOutputItem::Json(value) => ContentChunk::Text(value.to_string()),
+                    ToolExecutionOutputItem::Image(image) => ContentChunk::Image(image),
+                };
+                self.agent_event_buf
+                    .push(AgentEvent::Update(UpdateEvent::ToolCallUpdate {
+                        tool_use_id: evt.id.tool_use_id().to_string(),
+                        content,
+                    }));
+                Ok(())
+            },
             TaskExecutorEvent::ToolExecutionEnd(evt) => self.handle_tool_execution_end(evt).await,
             TaskExecutorEvent::HookExecutionEnd(evt) => match evt.result {
                 HookExecutorResult::Completed { id, result, .. } => self.handle_hook_finished_event(id, result).await,
@@ -1152,6 +1199,16 @@ impl Agent {
     }
 
     async fn handle_tool_execution_end(&mut self, evt: ToolExecutionEndEvent) -> Result<(), AgentError> {
+        if let (Some(logger), ActiveState::ExecutingTools(executing_tools)) =
+            (self.audit_logger.as_mut(), &self.execution_state.active_state)
+        {
+            if let Some(tool) = executing_tools.get_tool(&evt.id) {
+                if let Err(err) = logger.log_event(&AuditEvent::new(&tool.tool_use_block, &evt)).await {
+                    error!(?err, "failed to write to the audit log");
+                }
+            }
+        }
+
         let ActiveState::ExecutingTools(executing_tools) = &mut self.execution_state.active_state else {
             warn!(
                 ?self.execution_state,
@@ -1330,6 +1387,12 @@ impl Agent {
             warn!(filtered_specs = ?sanitized_specs.filtered_specs(), "filtered some tool specs");
         }
         let mut tool_specs = sanitized_specs.tool_specs();
+        if let Some(locale) = self.agent_config.locale() {
+            localize_tool_descriptions(&sanitized_specs, &mut tool_specs, locale);
+        }
+        if self.agent_config.include_examples() {
+            add_tool_examples(&sanitized_specs, &mut tool_specs);
+        }
         add_tool_use_purpose_arg(&mut tool_specs);
         self.cached_tool_specs = Some(sanitized_specs);
         tool_specs
@@ -1475,21 +1538,75 @@ impl Agent {
                     .await
                     .map_err(ToolParseErrorKind::invalid_args),
                 BuiltInTool::FileWrite(t) => t
+                    .validate(
+                        &self.sys_provider,
+                        self.agent_config.tool_settings().map(|s| &s.fs_write),
+                    )
+                    .await
+                    .map_err(ToolParseErrorKind::invalid_args),
+                BuiltInTool::Grep(t) => t
                     .validate(&self.sys_provider)
                     .await
                     .map_err(ToolParseErrorKind::invalid_args),
-                BuiltInTool::Grep(_) => Ok(()),
                 BuiltInTool::Ls(t) => t
                     .validate(&self.sys_provider)
                     .await
                     .map_err(ToolParseErrorKind::invalid_args),
-                BuiltInTool::Mkdir(_) => Ok(()),
-                BuiltInTool::ExecuteCmd(_) => Ok(()),
+                BuiltInTool::Mkdir(t) => t
+                    .validate(&self.sys_provider)
+                    .await
+                    .map_err(ToolParseErrorKind::invalid_args),
+                BuiltInTool::ExecuteCmd(t) => t
+                    .validate(
+                        &self.sys_provider,
+                        self.agent_config.tool_settings().map(|s| &s.execute_cmd),
+                    )
+                    .await
+                    .map_err(ToolParseErrorKind::invalid_args),
                 BuiltInTool::Introspect(_) => Ok(()),
+                BuiltInTool::Chmod(t) => t
+                    .validate(&self.sys_provider)
+                    .await
+                    .map_err(ToolParseErrorKind::invalid_args),
+                BuiltInTool::Chown(t) => t
+                    .validate(&self.sys_provider)
+                    .await
+                    .map_err(ToolParseErrorKind::invalid_args),
+                BuiltInTool::Stat(t) => t
+                    .validate(&self.sys_provider)
+                    .await
+                    .map_err(ToolParseErrorKind::invalid_args),
+                BuiltInTool::RenderTemplate(t) => t
+                    .validate(&self.sys_provider)
+                    .await
+                    .map_err(ToolParseErrorKind::invalid_args),
+                BuiltInTool::Rm(t) => t
+                    .validate(&self.sys_provider, self.agent_config.tool_settings().map(|s| &s.rm))
+                    .await
+                    .map_err(ToolParseErrorKind::invalid_args),
                 BuiltInTool::SpawnSubagent => Ok(()),
                 BuiltInTool::ImageRead(t) => t.validate().await.map_err(ToolParseErrorKind::invalid_args),
+                BuiltInTool::SpellCheck(t) => t
+                    .validate(&self.sys_provider)
+                    .await
+                    .map_err(ToolParseErrorKind::invalid_args),
+            },
+            ToolKind::Mcp(t) => {
+                let canonical_name = t.canonical_tool_name();
+                let spec = self.cached_tool_specs.as_ref().and_then(|specs| {
+                    specs
+                        .tool_map()
+                        .values()
+                        .find(|s| s.canonical_name() == &canonical_name)
+                });
+                match spec {
+                    Some(spec) => {
+                        let schema = serde_json::Value::Object(spec.tool_spec().input_schema.clone());
+                        t.validate_params(&schema)
+                    },
+                    None => Ok(()),
+                }
             },
-            ToolKind::Mcp(_) => Ok(()),
         }
     }
 
@@ -1535,10 +1652,19 @@ impl Agent {
             let Some((block, tool)) = tools.iter().find(|(b, _)| &b.tool_use_id == tool_use_id) else {
                 continue;
             };
+            let context = tool.get_context(&self.sys_provider).await;
+            if let Some(ToolContext::FileWrite(ctx)) = &context {
+                info!(
+                    tool_use_id,
+                    summary = ctx.diff_summary(),
+                    "requesting approval for file write"
+                );
+            }
             self.agent_event_buf.push(AgentEvent::ApprovalRequest {
                 id: block.tool_use_id.clone(),
                 tool_use: (*block).clone(),
-                context: tool.get_context().await,
+                context,
+                preview: tool.preview(),
             });
         }
 
@@ -1572,27 +1698,61 @@ impl Agent {
         let (tx, rx) = oneshot::channel::<ToolState>();
 
         let provider = Arc::clone(&self.sys_provider);
+        let cancel_token = CancellationToken::new();
+        let tool_cancel_token = cancel_token.clone();
+        // Long-running tools stream their output while executing.
+        let mut output_rx = None;
 
         let fut: ToolFuture = match tool.kind {
             ToolKind::BuiltIn(builtin) => match builtin {
-                BuiltInTool::FileRead(t) => Box::pin(async move { t.execute(&provider).await }),
+                BuiltInTool::FileRead(t) => Box::pin(async move { t.execute(&provider, &tool_cancel_token).await }),
                 BuiltInTool::FileWrite(t) => {
                     let file_write = self.tool_state.file_write.clone();
                     let mut tool_state = ToolState { file_write };
                     Box::pin(async move {
-                        let res = t.execute(tool_state.file_write.as_mut(), &provider).await;
+                        let res = t
+                            .execute(tool_state.file_write.as_mut(), &provider, &tool_cancel_token)
+                            .await;
                         if res.is_ok() {
                             let _ = tx.send(tool_state);
                         }
                         res
                     })
                 },
-                BuiltInTool::ExecuteCmd(t) => Box::pin(async move { t.execute().await }),
+                BuiltInTool::ExecuteCmd(t) => {
+                    let t = t.with_settings(self.agent_config.tool_settings().map(|s| &s.execute_cmd));
+                    let (output_tx, rx) = tool_output_channel();
+                    output_rx = Some(rx);
+                    Box::pin(async move { t.execute_stream(&provider, &output_tx).await })
+                },
                 BuiltInTool::ImageRead(t) => Box::pin(async move { t.execute().await }),
-                BuiltInTool::Introspect(_) => panic!("unimplemented"),
-                BuiltInTool::Grep(_) => panic!("unimplemented"),
-                BuiltInTool::Ls(t) => Box::pin(async move { t.execute(&provider).await }),
-                BuiltInTool::Mkdir(_) => panic!("unimplemented"),
+                BuiltInTool::Introspect(t) => {
+                    let enabled_tools = self.get_tool_names().await;
+                    Box::pin(async move { t.execute(&provider, &enabled_tools).await })
+                },
+                BuiltInTool::Grep(t) => {
+                    let (output_tx, rx) = tool_output_channel();
+                    output_rx = Some(rx);
+                    Box::pin(async move { t.execute_stream(&provider, &output_tx).await })
+                },
+                BuiltInTool::Ls(t) => {
+                    let settings = self.agent_config.tool_settings().map(|s| s.ls.clone());
+                    let (output_tx, rx) = tool_output_channel();
+                    output_rx = Some(rx);
+                    Box::pin(async move { t.execute_stream(&provider, settings.as_ref(), &output_tx).await })
+                },
+                BuiltInTool::Mkdir(t) => Box::pin(async move { t.execute(&provider).await }),
+                BuiltInTool::SpellCheck(t) => Box::pin(async move { t.execute(&provider).await }),
+                BuiltInTool::Chmod(t) => Box::pin(async move { t.execute(&provider).await }),
+                BuiltInTool::Chown(t) => Box::pin(async move { t.execute(&provider).await }),
+                BuiltInTool::Stat(t) => Box::pin(async move { t.execute(&provider).await }),
+                BuiltInTool::RenderTemplate(t) => {
+                    Box::pin(async move { t.execute(&provider, &tool_cancel_token).await })
+                },
+                BuiltInTool::Rm(t) => {
+                    let settings = self.agent_config.tool_settings().map(|s| s.rm.clone());
+                    Box::pin(async move { t.execute(&provider, settings.as_ref()).await })
+                },
                 BuiltInTool::SpawnSubagent => panic!("unimplemented"),
             },
             ToolKind::Mcp(t) => {
@@ -1633,6 +1793,8 @@ impl Agent {
                 tool: tool_clone,
                 fut,
                 context_rx: rx,
+                output_rx,
+                cancel_token,
             })
             .await;
         Ok(())
@@ -1643,7 +1805,16 @@ impl Agent {
         for result in tool_results {
             match result {
                 ToolExecutorResult::Completed { id, result } => match result {
-                    Ok(res) => {
+                    Ok(mut res) => {
+                        // Final safety net against tool outputs flooding the context window.
+                        res.truncate_total(MAX_TOOL_RESPONSE_TEXT_BYTES, "...output truncated due to length");
+                        self.agent_event_buf
+                            .push(AgentEvent::Internal(InternalEvent::ToolOutputMetrics {
+                                tool_use_id: id.tool_use_id().to_string(),
+                                item_counts: res.item_count_by_type(),
+                                text_bytes: res.total_text_bytes(),
+                                image_bytes: res.total_image_bytes(),
+                            }));
                         let mut content_items = Vec::new();
                         for item in &res.items {
                             let content_item = match item {
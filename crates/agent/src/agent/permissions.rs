@@ -14,6 +14,7 @@ use crate::agent::tools::{
     BuiltInTool,
     ToolKind,
 };
+use crate::agent::util::cloud_storage::CloudUri;
 use crate::agent::util::error::UtilError;
 use crate::agent::util::glob::matches_any_pattern;
 
@@ -32,14 +33,14 @@ pub fn evaluate_tool_permission<P: SystemProvider>(
             BuiltInTool::FileRead(file_read) => evaluate_permission_for_paths(
                 &settings.fs_read.allowed_paths,
                 &settings.fs_read.denied_paths,
-                file_read.ops.iter().map(|op| &op.path),
+                file_read.paths(),
                 is_allowed,
                 provider,
             ),
             BuiltInTool::FileWrite(file_write) => evaluate_permission_for_paths(
                 &settings.fs_write.allowed_paths,
                 &settings.fs_write.denied_paths,
-                [file_write.path()],
+                file_write.paths(),
                 is_allowed,
                 provider,
             ),
@@ -59,13 +60,84 @@ pub fn evaluate_tool_permission<P: SystemProvider>(
                 is_allowed,
                 provider,
             ),
-            BuiltInTool::Grep(_) => Ok(PermissionEvalResult::Allow),
+            BuiltInTool::SpellCheck(spell_check) => evaluate_permission_for_paths(
+                &settings.fs_read.allowed_paths,
+                &settings.fs_read.denied_paths,
+                [&spell_check.path],
+                is_allowed,
+                provider,
+            ),
+            BuiltInTool::Grep(grep) => evaluate_permission_for_paths(
+                &settings.fs_read.allowed_paths,
+                &settings.fs_read.denied_paths,
+                [grep.path()],
+                is_allowed,
+                provider,
+            ),
 
             // Reuse the same settings for fs write
-            BuiltInTool::Mkdir(_) => Ok(PermissionEvalResult::Allow),
+            BuiltInTool::Mkdir(mkdir) => evaluate_permission_for_paths(
+                &settings.fs_write.allowed_paths,
+                &settings.fs_write.denied_paths,
+                [&mkdir.path],
+                is_allowed,
+                provider,
+            ),
 
             BuiltInTool::ExecuteCmd(_) => Ok(PermissionEvalResult::Allow),
             BuiltInTool::Introspect(_) => Ok(PermissionEvalResult::Allow),
+            BuiltInTool::Chmod(t) => evaluate_permission_for_paths(
+                &settings.fs_write.allowed_paths,
+                &settings.fs_write.denied_paths,
+                [&t.path],
+                is_allowed,
+                provider,
+            ),
+            BuiltInTool::Chown(t) => evaluate_permission_for_paths(
+                &settings.fs_write.allowed_paths,
+                &settings.fs_write.denied_paths,
+                [&t.path],
+                is_allowed,
+                provider,
+            ),
+            BuiltInTool::Stat(t) => evaluate_permission_for_paths(
+                &settings.fs_read.allowed_paths,
+                &settings.fs_read.denied_paths,
+                [&t.path],
+                is_allowed,
+                provider,
+            ),
+            // The template is only read, and the output is written.
+            BuiltInTool::RenderTemplate(t) => {
+                let read = evaluate_permission_for_paths(
+                    &settings.fs_read.allowed_paths,
+                    &settings.fs_read.denied_paths,
+                    [&t.template_path],
+                    is_allowed,
+                    provider,
+                )?;
+                let write = evaluate_permission_for_paths(
+                    &settings.fs_write.allowed_paths,
+                    &settings.fs_write.denied_paths,
+                    [&t.output_path],
+                    is_allowed,
+                    provider,
+                )?;
+                Ok(match (read, write) {
+                    (deny @ PermissionEvalResult::Deny { .. }, _) | (_, deny @ PermissionEvalResult::Deny { .. }) => {
+                        deny
+                    },
+                    (PermissionEvalResult::Ask, _) | (_, PermissionEvalResult::Ask) => PermissionEvalResult::Ask,
+                    (PermissionEvalResult::Allow, PermissionEvalResult::Allow) => PermissionEvalResult::Allow,
+                })
+            },
+            BuiltInTool::Rm(t) => evaluate_permission_for_paths(
+                &settings.fs_write.allowed_paths,
+                &settings.fs_write.denied_paths,
+                [&t.path],
+                is_allowed,
+                provider,
+            ),
             BuiltInTool::SpawnSubagent => Ok(PermissionEvalResult::Allow),
         },
         ToolKind::Mcp(_) => Ok(if is_allowed {
@@ -76,6 +148,12 @@ pub fn evaluate_tool_permission<P: SystemProvider>(
     }
 }
 
+/// Evaluates the permission for a set of paths against the allowed and denied paths.
+///
+/// Remote locations (e.g. `s3://bucket/key` or `https://example.com/image.png`) are not local paths, and so are only matched against
+/// the allowed and denied paths that are themselves remote locations, without canonicalization.
+/// Otherwise, a relative path like `s3://bucket/key` would match a local allowed path under the
+/// current directory.
 fn evaluate_permission_for_paths<T, U, P>(
     allowed_paths: &[String],
     denied_paths: &[String],
@@ -88,12 +166,22 @@ where
     U: AsRef<str>,
     P: SystemProvider,
 {
-    let allowed_paths = canonicalize_paths(allowed_paths, provider);
-    let denied_paths = canonicalize_paths(denied_paths, provider);
+    let (remote_allowed_paths, allowed_paths): (Vec<_>, Vec<_>) =
+        allowed_paths.iter().partition(|p| is_remote_location(p));
+    let (remote_denied_paths, denied_paths): (Vec<_>, Vec<_>) =
+        denied_paths.iter().partition(|p| is_remote_location(p));
+    let allowed_paths = canonicalize_paths(&allowed_paths, provider);
+    let denied_paths = canonicalize_paths(&denied_paths, provider);
     let mut ask = false;
     for path in paths_to_check {
-        let path = canonicalize_path_sys(path, provider)?;
-        match evaluate_permission_for_path(path, allowed_paths.iter(), denied_paths.iter()) {
+        let path = path.as_ref();
+        let result = if is_remote_location(path) {
+            evaluate_permission_for_path(path, remote_allowed_paths.iter(), remote_denied_paths.iter())
+        } else {
+            let path = canonicalize_path_sys(path, provider)?;
+            evaluate_permission_for_path(path, allowed_paths.iter(), denied_paths.iter())
+        };
+        match result {
             PermissionCheckResult::Denied(items) => {
                 return Ok(PermissionEvalResult::Deny {
                     reason: items.join(", "),
@@ -110,7 +198,12 @@ where
     })
 }
 
-fn canonicalize_paths<P: SystemProvider>(paths: &[String], provider: &P) -> Vec<String> {
+/// Whether `path` refers to a location that is not on the local filesystem.
+fn is_remote_location(path: &str) -> bool {
+    CloudUri::is_cloud_uri(path) || path.starts_with("http://") || path.starts_with("https://")
+}
+
+fn canonicalize_paths<P: SystemProvider>(paths: &[&String], provider: &P) -> Vec<String> {
     paths
         .iter()
         .filter_map(|p| canonicalize_path_sys(p, provider).ok())
@@ -223,6 +316,62 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_evaluate_permission_for_remote_paths() {
+        let sys = TestProvider::new();
+        let eval = |allowed: &[&str], denied: &[&str], path: &str| {
+            let allowed = allowed.iter().map(|p| (*p).to_string()).collect::<Vec<_>>();
+            let denied = denied.iter().map(|p| (*p).to_string()).collect::<Vec<_>>();
+            evaluate_permission_for_paths(&allowed, &denied, [path], false, &sys).unwrap()
+        };
+
+        // Local allowed paths never match a remote location.
+        assert!(matches!(
+            eval(&["."], &[], "s3://bucket/key"),
+            PermissionEvalResult::Ask
+        ));
+        assert!(matches!(
+            eval(&["s3:"], &[], "s3://bucket/key"),
+            PermissionEvalResult::Ask
+        ));
+        assert!(matches!(
+            eval(&["**"], &[], "gcs://bucket/key"),
+            PermissionEvalResult::Ask
+        ));
+
+        assert!(matches!(
+            eval(&["s3://bucket"], &[], "s3://bucket/dir/key"),
+            PermissionEvalResult::Allow
+        ));
+        assert!(matches!(
+            eval(&["s3://bucket"], &[], "s3://other-bucket/key"),
+            PermissionEvalResult::Ask
+        ));
+        assert!(matches!(
+            eval(&["s3://bucket"], &["s3://bucket/secret"], "s3://bucket/secret/key"),
+            PermissionEvalResult::Deny { .. }
+        ));
+
+        assert!(matches!(
+            eval(&["."], &[], "https://example.com/image.png"),
+            PermissionEvalResult::Ask
+        ));
+        assert!(matches!(
+            eval(&["https://example.com"], &[], "https://example.com/image.png"),
+            PermissionEvalResult::Allow
+        ));
+        assert!(matches!(
+            eval(&["https://example.com"], &[], "http://example.com/image.png"),
+            PermissionEvalResult::Ask
+        ));
+
+        // Remote allowed paths never match a local path.
+        assert!(matches!(
+            eval(&["s3://**"], &[], "src/main.rs"),
+            PermissionEvalResult::Ask
+        ));
+    }
+
     #[test]
     fn test_evaluate_permission_for_path() {
         let sys = TestProvider::new();
@@ -311,4 +460,61 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_evaluate_fs_read_repo_path() {
+        let sys = TestProvider::new();
+        let mut settings = ToolSettings::default();
+        settings.fs_read.allowed_paths = vec!["project".to_string()];
+        settings.fs_read.denied_paths = vec!["secret".to_string()];
+        let eval = |repo_path: Option<&str>| {
+            let fs_read = serde_json::from_value(serde_json::json!({
+                "ops": [{ "path": "project/file.txt", "git_object": "0".repeat(40), "repo_path": repo_path }]
+            }))
+            .unwrap();
+            let tool = ToolKind::BuiltIn(BuiltInTool::FileRead(fs_read));
+            evaluate_tool_permission(&HashSet::new(), &settings, &tool, &sys).unwrap()
+        };
+
+        assert!(matches!(eval(None), PermissionEvalResult::Allow));
+        assert!(matches!(eval(Some("project")), PermissionEvalResult::Allow));
+        assert!(matches!(eval(Some("other")), PermissionEvalResult::Ask));
+        assert!(matches!(eval(Some("secret")), PermissionEvalResult::Deny { .. }));
+    }
+
+    #[test]
+    fn test_evaluate_render_template() {
+        let sys = TestProvider::new();
+        let mut settings = ToolSettings::default();
+        settings.fs_read.allowed_paths = vec!["templates".to_string()];
+        settings.fs_write.allowed_paths = vec!["out".to_string()];
+        settings.fs_write.denied_paths = vec!["secret".to_string()];
+        let eval = |template_path: &str, output_path: &str| {
+            let render_template = serde_json::from_value(serde_json::json!({
+                "template_path": template_path,
+                "output_path": output_path,
+            }))
+            .unwrap();
+            let tool = ToolKind::BuiltIn(BuiltInTool::RenderTemplate(render_template));
+            evaluate_tool_permission(&HashSet::new(), &settings, &tool, &sys).unwrap()
+        };
+
+        assert!(matches!(
+            eval("templates/a.tera", "out/a.txt"),
+            PermissionEvalResult::Allow
+        ));
+        assert!(matches!(eval("other/a.tera", "out/a.txt"), PermissionEvalResult::Ask));
+        assert!(matches!(
+            eval("templates/a.tera", "other/a.txt"),
+            PermissionEvalResult::Ask
+        ));
+        assert!(matches!(
+            eval("templates/a.tera", "secret/a.txt"),
+            PermissionEvalResult::Deny { .. }
+        ));
+        assert!(matches!(
+            eval("other/a.tera", "secret/a.txt"),
+            PermissionEvalResult::Deny { .. }
+        ));
+    }
 }
@@ -24,6 +24,7 @@ use super::tools::{
     Tool,
     ToolExecutionError,
     ToolExecutionOutput,
+    ToolOutputTypeCounts,
 };
 use super::types::AgentSnapshot;
 
@@ -68,6 +69,8 @@ pub enum AgentEvent {
         tool_use: ToolUseBlock,
         /// Tool-specific context about the requested operation
         context: Option<super::tools::ToolContext>,
+        /// Short, human-readable description of the tool use, see [super::tools::Tool::preview]
+        preview: Option<String>,
     },
 
     /// Lower-level events associated with the agent's execution. Generally only useful for
@@ -104,7 +107,11 @@ pub enum UpdateEvent {
     /// Sent once at the beginning of a tool use.
     ToolCall(ToolCall),
     /// Sent (optionally multiple times) to report the status of a tool execution.
-    ToolCallUpdate { content: ContentChunk },
+    ToolCallUpdate {
+        /// The tool use id of the executing tool
+        tool_use_id: String,
+        content: ContentChunk,
+    },
     /// Sent once at the end of a tool execution.
     ToolCallFinished {
         /// The tool that was executed
@@ -306,4 +313,12 @@ pub enum InternalEvent {
     ToolPermissionEvalResult { tool: Tool, result: PermissionEvalResult },
     /// Events specific to tool and hook execution
     TaskExecutor(Box<TaskExecutorEvent>),
+    /// The kinds and sizes of the data returned by a successful tool execution, as sent to the
+    /// model.
+    ToolOutputMetrics {
+        tool_use_id: String,
+        item_counts: ToolOutputTypeCounts,
+        text_bytes: usize,
+        image_bytes: usize,
+    },
 }
@@ -31,6 +31,8 @@ use crate::agent::agent_loop::types::ToolUseBlock;
 use crate::agent::tools::{
     Tool,
     ToolExecutionOutput,
+    ToolExecutionOutputItem,
+    ToolExecutionOutputStream,
     ToolExecutionResult,
     ToolState,
 };
@@ -135,19 +137,54 @@ impl TaskExecutor {
 
     fn handle_tool_execute_request(&mut self, req: StartToolExecution) {
         let result_tx = self.execute_result_tx.clone();
-        let cancel_token = CancellationToken::new();
+        let cancel_token = req.cancel_token;
 
         let id_clone = req.id.clone();
         let cancel_token_clone = cancel_token.clone();
+        let mut fut = req.fut;
+        let mut output_rx = req.output_rx;
         tokio::spawn(async move {
-            tokio::select! {
-                _ = cancel_token_clone.cancelled() => {
-                    let _ = result_tx.send(ExecutorResult::Tool(ToolExecutorResult::Cancelled { id: id_clone })).await;
+            let send_output = |item: ToolExecutionOutputItem| {
+                result_tx.send(ExecutorResult::ToolOutput(ToolExecutionOutputEvent {
+                    id: id_clone.clone(),
+                    item,
+                }))
+            };
+            let mut streamed = Vec::new();
+            let result = loop {
+                tokio::select! {
+                    _ = cancel_token_clone.cancelled() => {
+                        let _ = result_tx.send(ExecutorResult::Tool(ToolExecutorResult::Cancelled { id: id_clone })).await;
+                        return;
+                    }
+                    Some(Ok(item)) = next_output(output_rx.as_mut()) => {
+                        streamed.push(item.clone());
+                        let _ = send_output(item).await;
+                    }
+                    result = &mut fut => break result,
                 }
-                result = req.fut => {
-                    let _ = result_tx.send(ExecutorResult::Tool(ToolExecutorResult::Completed { id: id_clone, result })).await;
+            };
+            // Dropping the future closes the output channel, so that the items sent right before
+            // the tool finished can be drained.
+            drop(fut);
+            if let Some(output_rx) = &mut output_rx {
+                while let Some(Ok(item)) = output_rx.next().await {
+                    streamed.push(item.clone());
+                    let _ = send_output(item).await;
                 }
             }
+            // Streamed items are left out of the result returned by the tool.
+            let result = result.map(|mut output| {
+                streamed.append(&mut output.items);
+                output.items = streamed;
+                output
+            });
+            let _ = result_tx
+                .send(ExecutorResult::Tool(ToolExecutorResult::Completed {
+                    id: id_clone,
+                    result,
+                }))
+                .await;
         });
 
         let start_time = Utc::now();
@@ -248,6 +285,11 @@ impl TaskExecutor {
 
     async fn handle_execute_result(&mut self, result: ExecutorResult) {
         match result {
+            ExecutorResult::ToolOutput(evt) => {
+                if self.executing_tools.contains_key(&evt.id) {
+                    self.event_buf.push(TaskExecutorEvent::ToolExecutionOutput(evt));
+                }
+            },
             ExecutorResult::Tool(result) => {
                 debug_assert!(self.executing_tools.contains_key(result.id()));
                 if let Some(x) = self.executing_tools.remove(result.id()) {
@@ -282,6 +324,17 @@ impl TaskExecutor {
     }
 }
 
+/// Receives the next item streamed by a tool, never resolving if the tool does not stream its
+/// output.
+async fn next_output(
+    output_rx: Option<&mut ToolExecutionOutputStream>,
+) -> Option<Result<ToolExecutionOutputItem, crate::agent::tools::ToolExecutionError>> {
+    match output_rx {
+        Some(rx) => rx.next().await,
+        None => std::future::pending().await,
+    }
+}
+
 impl Default for TaskExecutor {
     fn default() -> Self {
         Self::new()
@@ -304,6 +357,13 @@ pub struct StartToolExecution {
     pub fut: ToolFuture,
     /// A receiver for tool state
     pub context_rx: oneshot::Receiver<ToolState>,
+    /// Output items streamed by the tool while executing, if supported. Each item is emitted as a
+    /// [TaskExecutorEvent::ToolExecutionOutput], and included in the final result ahead of the
+    /// items returned by the tool.
+    pub output_rx: Option<ToolExecutionOutputStream>,
+    /// Token used to cancel the tool execution. Tools that perform long-running work may also
+    /// hold a clone of this token in order to clean up after themselves when cancelled.
+    pub cancel_token: CancellationToken,
 }
 
 impl std::fmt::Debug for StartToolExecution {
@@ -313,6 +373,8 @@ impl std::fmt::Debug for StartToolExecution {
             .field("tool", &self.tool)
             .field("fut", &"<ToolFuture>")
             .field("context_rx", &self.context_rx)
+            .field("output_rx", &self.output_rx)
+            .field("cancel_token", &self.cancel_token)
             .finish()
     }
 }
@@ -346,6 +408,8 @@ struct ExecutingHook {
 pub enum TaskExecutorEvent {
     /// A tool has started executing
     ToolExecutionStart(ToolExecutionStartEvent),
+    /// A tool sent an output item while executing
+    ToolExecutionOutput(ToolExecutionOutputEvent),
     /// A tool completed executing
     ToolExecutionEnd(ToolExecutionEndEvent),
 
@@ -363,6 +427,13 @@ pub struct ToolExecutionStartEvent {
     pub start_time: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolExecutionOutputEvent {
+    /// Identifier for the tool execution
+    pub id: ToolExecutionId,
+    pub item: ToolExecutionOutputItem,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolExecutionEndEvent {
     /// Identifier for the tool execution
@@ -416,6 +487,7 @@ impl ToolExecutionId {
 #[allow(clippy::large_enum_variant)]
 pub enum ExecutorResult {
     Tool(ToolExecutorResult),
+    ToolOutput(ToolExecutionOutputEvent),
     Hook(HookExecutorResult),
 }
 
@@ -733,4 +805,79 @@ mod tests {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn test_tool_execution_streamed_output() {
+        use crate::agent::agent_config::parse::CanonicalToolName;
+        use crate::agent::tools::{
+            BuiltInToolName,
+            tool_output_channel,
+        };
+
+        let mut executor = TaskExecutor::new();
+        let (output_tx, output_rx) = tool_output_channel();
+        let (_, context_rx) = oneshot::channel();
+        let fut: ToolFuture = Box::pin(async move {
+            for line in ["first", "second"] {
+                output_tx
+                    .send(Ok(ToolExecutionOutputItem::Text(line.to_string())))
+                    .await
+                    .unwrap();
+            }
+            Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Text(
+                "last".to_string(),
+            )]))
+        });
+        executor
+            .start_tool_execution(StartToolExecution {
+                id: ToolExecutionId::new("tooluse_1".to_string()),
+                tool: Tool::parse(
+                    &CanonicalToolName::BuiltIn(BuiltInToolName::Ls),
+                    serde_json::json!({ "path": "." }),
+                )
+                .unwrap(),
+                fut,
+                context_rx,
+                output_rx: Some(output_rx),
+                cancel_token: CancellationToken::new(),
+            })
+            .await;
+
+        let mut events = Vec::new();
+        run_with_timeout(Duration::from_millis(1000), async {
+            let mut event_buf = Vec::new();
+            loop {
+                executor.recv_next(&mut event_buf).await;
+                let is_end = event_buf
+                    .iter()
+                    .any(|ev| matches!(ev, TaskExecutorEvent::ToolExecutionEnd(_)));
+                events.append(&mut event_buf);
+                if is_end {
+                    break;
+                }
+            }
+        })
+        .await;
+
+        let text = |item: &ToolExecutionOutputItem| match item {
+            ToolExecutionOutputItem::Text(text) => text.clone(),
+            other => panic!("unexpected item: {:?}", other),
+        };
+        let streamed = events
+            .iter()
+            .filter_map(|ev| match ev {
+                TaskExecutorEvent::ToolExecutionOutput(evt) => Some(text(&evt.item)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(streamed, ["first", "second"]);
+        // Streamed items are included in the result, ahead of the returned items.
+        let Some(TaskExecutorEvent::ToolExecutionEnd(end)) = events.last() else {
+            panic!("expected the tool execution to end last: {:?}", events);
+        };
+        let output = end.result.tool_execution_output().unwrap();
+        assert_eq!(output.items.iter().map(text).collect::<Vec<_>>(), [
+            "first", "second", "last"
+        ]);
+    }
 }
@@ -12,10 +12,14 @@ use super::consts::{
     MAX_TOOL_NAME_LEN,
     MAX_TOOL_SPEC_DESCRIPTION_LEN,
     RTS_VALID_TOOL_NAME_REGEX,
-    TOOL_USE_PURPOSE_FIELD_DESCRIPTION,
     TOOL_USE_PURPOSE_FIELD_NAME,
 };
-use super::tools::BuiltInTool;
+use super::tools::{
+    BuiltInTool,
+    ToolExample,
+    format_tool_examples,
+    tool_use_purpose_schema,
+};
 
 /// Categorizes different types of tool name validation failures according to the requirements by
 /// the RTS API.
@@ -108,6 +112,10 @@ impl SanitizedToolSpec {
     pub fn canonical_name(&self) -> &CanonicalToolName {
         &self.canonical_name
     }
+
+    pub fn tool_spec(&self) -> &ToolSpec {
+        &self.tool_spec
+    }
 }
 
 /// Creates a set of tool specs to send to the model.
@@ -243,7 +251,7 @@ pub fn sanitize_tool_specs(
                     ));
                 }
                 spec.name = sanitized_name.clone();
-                spec.description.truncate(MAX_TOOL_SPEC_DESCRIPTION_LEN);
+                truncate_description(&mut spec.description);
                 tool_map.insert(sanitized_name, SanitizedToolSpec {
                     canonical_name,
                     tool_spec: spec,
@@ -259,8 +267,62 @@ pub fn sanitize_tool_specs(
     }
 }
 
+/// Truncates `description` to at most [MAX_TOOL_SPEC_DESCRIPTION_LEN] bytes, on a char boundary
+/// so that translated descriptions can be truncated as well.
+fn truncate_description(description: &mut String) {
+    if description.len() <= MAX_TOOL_SPEC_DESCRIPTION_LEN {
+        return;
+    }
+    let mut len = MAX_TOOL_SPEC_DESCRIPTION_LEN;
+    while !description.is_char_boundary(len) {
+        len -= 1;
+    }
+    description.truncate(len);
+}
+
+/// Replaces the descriptions of built-in tools with their translations for the given locale,
+/// truncated to the same max length as other tool descriptions.
+pub fn localize_tool_descriptions(sanitized_specs: &SanitizedToolSpecs, tool_specs: &mut [ToolSpec], locale: &str) {
+    for spec in tool_specs {
+        let Some(CanonicalToolName::BuiltIn(name)) =
+            sanitized_specs.tool_map().get(&spec.name).map(|s| &s.canonical_name)
+        else {
+            continue;
+        };
+        spec.description = BuiltInTool::localized_description(name, locale);
+        truncate_description(&mut spec.description);
+    }
+}
+
+/// Appends the examples of built-in tools to their descriptions, leaving out examples that would
+/// exceed the max description length.
+pub fn add_tool_examples(sanitized_specs: &SanitizedToolSpecs, tool_specs: &mut [ToolSpec]) {
+    for spec in tool_specs {
+        let Some(CanonicalToolName::BuiltIn(name)) =
+            sanitized_specs.tool_map().get(&spec.name).map(|s| &s.canonical_name)
+        else {
+            continue;
+        };
+        append_examples(&mut spec.description, BuiltInTool::examples(name));
+    }
+}
+
+/// Appends as many of `examples` to `description` as fit within [MAX_TOOL_SPEC_DESCRIPTION_LEN],
+/// dropping the last examples first.
+fn append_examples(description: &mut String, mut examples: Vec<ToolExample>) {
+    while !examples.is_empty() {
+        let section = format_tool_examples(&examples);
+        if description.len() + section.len() <= MAX_TOOL_SPEC_DESCRIPTION_LEN {
+            description.push_str(&section);
+            return;
+        }
+        examples.pop();
+    }
+}
+
 /// Adds an argument to each tool spec called [TOOL_USE_PURPOSE_FIELD_NAME] in order for the model
-/// to provide extra context why the tool use is being made.
+/// to provide extra context why the tool use is being made. Built-in tool specs already include
+/// it.
 pub fn add_tool_use_purpose_arg(tool_specs: &mut Vec<ToolSpec>) {
     for spec in tool_specs {
         let Some(arg_type) = spec.input_schema.get("type").and_then(|v| v.as_str()) else {
@@ -273,20 +335,59 @@ pub fn add_tool_use_purpose_arg(tool_specs: &mut Vec<ToolSpec>) {
             continue;
         };
         if !properties.contains_key(TOOL_USE_PURPOSE_FIELD_NAME) {
-            let obj = serde_json::Value::Object(
-                [
-                    (
-                        "description".to_string(),
-                        serde_json::Value::String(TOOL_USE_PURPOSE_FIELD_DESCRIPTION.to_string()),
-                    ),
-                    ("type".to_string(), serde_json::Value::String("string".to_string())),
-                ]
-                .into_iter()
-                .collect::<serde_json::Map<_, _>>(),
-            );
-            properties.insert(TOOL_USE_PURPOSE_FIELD_NAME.to_string(), obj);
+            properties.insert(TOOL_USE_PURPOSE_FIELD_NAME.to_string(), tool_use_purpose_schema());
         }
     }
 }
 
 // pub fn parse_tool() -> Result<Tool,
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_description() {
+        let mut description = "a".repeat(MAX_TOOL_SPEC_DESCRIPTION_LEN);
+        truncate_description(&mut description);
+        assert_eq!(description.len(), MAX_TOOL_SPEC_DESCRIPTION_LEN);
+
+        // Multi-byte characters are never split.
+        let mut description = "目".repeat(MAX_TOOL_SPEC_DESCRIPTION_LEN);
+        truncate_description(&mut description);
+        assert!(description.len() <= MAX_TOOL_SPEC_DESCRIPTION_LEN);
+        assert!(description.len() > MAX_TOOL_SPEC_DESCRIPTION_LEN - "目".len());
+        assert!(description.chars().all(|c| c == '目'));
+    }
+
+    #[test]
+    fn test_append_examples() {
+        let examples = || {
+            vec![
+                ToolExample {
+                    input: serde_json::json!({ "path": "a" }),
+                    description: "first".to_string(),
+                },
+                ToolExample {
+                    input: serde_json::json!({ "path": "b" }),
+                    description: "second".to_string(),
+                },
+            ]
+        };
+
+        let mut description = "description".to_string();
+        append_examples(&mut description, examples());
+        assert_eq!(description, format!("description{}", format_tool_examples(&examples())));
+
+        // Examples that would exceed the max length are dropped.
+        let len = MAX_TOOL_SPEC_DESCRIPTION_LEN - format_tool_examples(&examples()[..1]).len();
+        let mut description = "a".repeat(len);
+        append_examples(&mut description, examples());
+        assert_eq!(description.len(), MAX_TOOL_SPEC_DESCRIPTION_LEN);
+        assert!(description.contains("first") && !description.contains("second"));
+
+        let mut description = "a".repeat(MAX_TOOL_SPEC_DESCRIPTION_LEN);
+        append_examples(&mut description, examples());
+        assert_eq!(description.len(), MAX_TOOL_SPEC_DESCRIPTION_LEN);
+    }
+}
@@ -0,0 +1,359 @@
+use std::path::PathBuf;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::{
+    BuiltInToolName,
+    BuiltInToolTrait,
+    ToolExecutionError,
+    ToolExecutionOutput,
+    ToolExecutionOutputItem,
+    ToolExecutionResult,
+    ToolLocale,
+};
+use crate::util::path::canonicalize_path_sys;
+use crate::util::providers::SystemProvider;
+
+const CHMOD_TOOL_DESCRIPTION: &str = r#"
+A tool for changing the permissions of files and directories.
+
+HOW TO USE:
+- Provide the path to the file or directory
+- Provide the mode as either an octal string (e.g. "0644", "755") or a symbolic string (e.g. "u+x", "go-w", "a=r")
+- Optionally set recursive to true to change the permissions of all entries within a directory
+
+LIMITATIONS:
+- Not supported on Windows
+- Symbolic links are not followed when changing permissions recursively
+"#;
+
+const CHMOD_TOOL_DESCRIPTION_ZH_CN: &str = r#"
+用于更改文件和目录权限的工具。
+
+使用方法：
+- 提供文件或目录的路径
+- 以八进制字符串（例如 "0644"、"755"）或符号字符串（例如 "u+x"、"go-w"、"a=r"）提供权限模式
+- 可选地将 recursive 设置为 true，以更改目录中所有条目的权限
+
+限制：
+- 不支持 Windows
+- 递归更改权限时不会跟随符号链接
+"#;
+
+const CHMOD_TOOL_DESCRIPTION_JA: &str = r#"
+ファイルとディレクトリのパーミッションを変更するためのツールです。
+
+使い方:
+- ファイルまたはディレクトリのパスを指定します
+- モードを 8 進数の文字列 (例: "0644"、"755") またはシンボリック文字列 (例: "u+x"、"go-w"、"a=r") で指定します
+- 必要に応じて recursive を true に設定し、ディレクトリ内のすべてのエントリのパーミッションを変更します
+
+制限事項:
+- Windows ではサポートされていません
+- 再帰的にパーミッションを変更する場合、シンボリックリンクはたどりません
+"#;
+
+const CHMOD_SCHEMA: &str = r#"
+{
+    "type": "object",
+    "properties": {
+        "path": {
+            "type": "string",
+            "description": "Path to the file or directory"
+        },
+        "mode": {
+            "type": "string",
+            "description": "Octal (e.g. \"0644\") or symbolic (e.g. \"u+x\") mode"
+        },
+        "recursive": {
+            "type": "boolean",
+            "description": "Whether or not to change permissions recursively",
+            "default": false
+        }
+    },
+    "required": [
+        "path",
+        "mode"
+    ]
+}
+"#;
+
+impl BuiltInToolTrait for Chmod {
+    fn name() -> BuiltInToolName {
+        BuiltInToolName::Chmod
+    }
+
+    fn description() -> std::borrow::Cow<'static, str> {
+        CHMOD_TOOL_DESCRIPTION.into()
+    }
+
+    fn description_for_locale(locale: &str) -> std::borrow::Cow<'static, str> {
+        match ToolLocale::parse(locale) {
+            Some(ToolLocale::ZhCn) => CHMOD_TOOL_DESCRIPTION_ZH_CN.into(),
+            Some(ToolLocale::Ja) => CHMOD_TOOL_DESCRIPTION_JA.into(),
+            None => Self::description(),
+        }
+    }
+
+    fn input_schema() -> std::borrow::Cow<'static, str> {
+        CHMOD_SCHEMA.into()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chmod {
+    pub path: String,
+    pub mode: String,
+    pub recursive: Option<bool>,
+}
+
+impl Chmod {
+    pub async fn validate<P: SystemProvider>(&self, provider: &P) -> Result<(), String> {
+        let path = self.canonical_path(provider)?;
+        if !path.exists() {
+            return Err(format!("'{}' does not exist", path.to_string_lossy()));
+        }
+        self.mode.parse::<Mode>()?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    pub async fn execute<P: SystemProvider>(&self, provider: &P) -> ToolExecutionResult {
+        let path = self.canonical_path(provider)?;
+        let mode = self.mode.parse::<Mode>()?;
+
+        let mut changed = 0;
+        let mut queue = vec![path];
+        while let Some(path) = queue.pop() {
+            let md = tokio::fs::symlink_metadata(&path)
+                .await
+                .map_err(|e| ToolExecutionError::from_io_result("failed to get metadata", Some(&path), e))?;
+            if md.is_symlink() && changed > 0 {
+                // Don't follow symlinks found while recursing.
+                continue;
+            }
+
+            set_mode(&path, &mode).await?;
+            changed += 1;
+
+            if self.recursive() && md.is_dir() {
+                let mut read_dir = tokio::fs::read_dir(&path)
+                    .await
+                    .map_err(|e| ToolExecutionError::from_io_result("failed to read directory", Some(&path), e))?;
+                while let Some(ent) = read_dir
+                    .next_entry()
+                    .await
+                    .map_err(|e| ToolExecutionError::from_io_result("failed to read directory", Some(&path), e))?
+                {
+                    queue.push(ent.path());
+                }
+            }
+        }
+
+        Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Text(format!(
+            "Changed the mode of {} {}",
+            changed,
+            if changed == 1 { "entry" } else { "entries" }
+        ))]))
+    }
+
+    #[cfg(windows)]
+    pub async fn execute<P: SystemProvider>(&self, _provider: &P) -> ToolExecutionResult {
+        Err(ToolExecutionError::Custom("chmod not supported on Windows".to_string()))
+    }
+
+    fn recursive(&self) -> bool {
+        self.recursive.unwrap_or(false)
+    }
+
+    fn canonical_path<P: SystemProvider>(&self, provider: &P) -> Result<PathBuf, String> {
+        Ok(PathBuf::from(
+            canonicalize_path_sys(&self.path, provider).map_err(|e| e.to_string())?,
+        ))
+    }
+}
+
+#[cfg(unix)]
+async fn set_mode(path: &std::path::Path, mode: &Mode) -> Result<(), ToolExecutionError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut permissions = tokio::fs::metadata(path)
+        .await
+        .map_err(|e| ToolExecutionError::from_io_result("failed to get metadata", Some(path), e))?
+        .permissions();
+    permissions.set_mode(mode.apply(permissions.mode()));
+    tokio::fs::set_permissions(path, permissions)
+        .await
+        .map_err(|e| ToolExecutionError::from_io_result("failed to set permissions", Some(path), e))
+}
+
+/// A file mode, as accepted by `chmod`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    /// Absolute permission bits, e.g. `0644`
+    Octal(u32),
+    /// Comma separated clauses relative to the current mode, e.g. `u+x,go-w`
+    Symbolic(Vec<SymbolicClause>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SymbolicClause {
+    /// Mask of the permission bits for the users the clause applies to
+    who: u32,
+    op: SymbolicOp,
+    /// Permission bits to add, remove, or set, already masked by `who`
+    perms: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SymbolicOp {
+    Add,
+    Remove,
+    Set,
+}
+
+impl Mode {
+    /// Returns the new permission bits after applying this mode to `current`.
+    fn apply(&self, current: u32) -> u32 {
+        match self {
+            Mode::Octal(mode) => (current & !0o7777) | mode,
+            Mode::Symbolic(clauses) => clauses.iter().fold(current, |mode, clause| match clause.op {
+                SymbolicOp::Add => mode | clause.perms,
+                SymbolicOp::Remove => mode & !clause.perms,
+                SymbolicOp::Set => (mode & !clause.who) | clause.perms,
+            }),
+        }
+    }
+}
+
+/// Parses an octal mode of at most four digits, e.g. `0644` or `755`.
+pub fn parse_octal_mode(s: &str) -> Result<u32, String> {
+    match u32::from_str_radix(s, 8) {
+        Ok(mode) if !s.is_empty() && s.len() <= 4 && s.chars().all(|c| c.is_ascii_digit()) => Ok(mode),
+        _ => Err(format!("'{}' is not a valid octal mode", s)),
+    }
+}
+
+impl std::str::FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err("mode must not be empty".to_string());
+        }
+
+        if s.chars().all(|c| c.is_ascii_digit()) {
+            return parse_octal_mode(s).map(Mode::Octal);
+        }
+
+        let mut clauses = Vec::new();
+        for clause in s.split(',') {
+            let op_idx = clause
+                .find(['+', '-', '='])
+                .ok_or_else(|| format!("'{}' is missing an operator, expected one of '+', '-', '='", clause))?;
+            let (who_str, rest) = clause.split_at(op_idx);
+            let mut who = 0;
+            for c in who_str.chars() {
+                who |= match c {
+                    'u' => 0o700,
+                    'g' => 0o070,
+                    'o' => 0o007,
+                    'a' => 0o777,
+                    _ => return Err(format!("'{}' is not a valid user class in '{}'", c, clause)),
+                };
+            }
+            if who == 0 {
+                who = 0o777;
+            }
+            let op = match rest.chars().next() {
+                Some('+') => SymbolicOp::Add,
+                Some('-') => SymbolicOp::Remove,
+                _ => SymbolicOp::Set,
+            };
+            let mut perms = 0;
+            for c in rest.chars().skip(1) {
+                perms |= match c {
+                    'r' => 0o444,
+                    'w' => 0o222,
+                    'x' => 0o111,
+                    _ => return Err(format!("'{}' is not a valid permission in '{}'", c, clause)),
+                };
+            }
+            clauses.push(SymbolicClause {
+                who,
+                op,
+                perms: perms & who,
+            });
+        }
+        Ok(Mode::Symbolic(clauses))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!("0644".parse::<Mode>().unwrap(), Mode::Octal(0o644));
+        assert_eq!("755".parse::<Mode>().unwrap(), Mode::Octal(0o755));
+        assert!("0999".parse::<Mode>().is_err());
+        assert!("12345".parse::<Mode>().is_err());
+        assert!("u+z".parse::<Mode>().is_err());
+        assert!("k+x".parse::<Mode>().is_err());
+        assert!("ux".parse::<Mode>().is_err());
+        assert!("".parse::<Mode>().is_err());
+    }
+
+    #[test]
+    fn test_apply_symbolic_mode() {
+        macro_rules! assert_apply {
+            ($mode:expr, $current:expr, $expected:expr) => {
+                assert_eq!(
+                    $mode.parse::<Mode>().unwrap().apply($current),
+                    $expected,
+                    "applying {} to {:o}",
+                    $mode,
+                    $current
+                );
+            };
+        }
+        assert_apply!("u+x", 0o644, 0o744);
+        assert_apply!("+x", 0o644, 0o755);
+        assert_apply!("go-w", 0o666, 0o644);
+        assert_apply!("a=r", 0o755, 0o444);
+        assert_apply!("u=rwx,g=rx,o=", 0o777, 0o750);
+        assert_apply!("0600", 0o100644, 0o100600);
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_chmod_recursive() {
+        use std::os::unix::fs::PermissionsExt;
+
+        use crate::util::test::TestBase;
+
+        let test_base = TestBase::new()
+            .await
+            .with_file(("dir/a.sh", "echo a"))
+            .await
+            .with_file(("dir/nested/b.sh", "echo b"))
+            .await;
+
+        let tool = Chmod {
+            path: test_base.join("dir").to_string_lossy().to_string(),
+            mode: "u+x".to_string(),
+            recursive: Some(true),
+        };
+        assert!(tool.validate(&test_base).await.is_ok());
+        tool.execute(&test_base).await.unwrap();
+
+        for path in ["dir/a.sh", "dir/nested/b.sh"] {
+            let mode = std::fs::metadata(test_base.join(path)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o100, 0o100, "{} should be executable", path);
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use std::path::PathBuf;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::{
+    BuiltInToolName,
+    BuiltInToolTrait,
+    ToolExecutionError,
+    ToolExecutionResult,
+    ToolLocale,
+};
+#[cfg(unix)]
+use super::{
+    ToolExecutionOutput,
+    ToolExecutionOutputItem,
+};
+use crate::util::path::canonicalize_path_sys;
+use crate::util::providers::SystemProvider;
+
+const CHOWN_TOOL_DESCRIPTION: &str = r#"
+A tool for changing the user and/or group ownership of files and directories.
+
+HOW TO USE:
+- Provide the path to the file or directory
+- Provide the name of the new owning user, the new owning group, or both
+- Optionally set recursive to true to change the ownership of all entries within a directory
+
+LIMITATIONS:
+- Not supported on Windows
+- Ownership can only be given to another user when running as root
+- Symbolic links are not followed when changing ownership recursively
+"#;
+
+const CHOWN_TOOL_DESCRIPTION_ZH_CN: &str = r#"
+用于更改文件和目录的所属用户和/或所属组的工具。
+
+使用方法：
+- 提供文件或目录的路径
+- 提供新的所属用户名、新的所属组名，或两者都提供
+- 可选地将 recursive 设置为 true，以更改目录中所有条目的所有权
+
+限制：
+- 不支持 Windows
+- 只有以 root 身份运行时才能将所有权转让给其他用户
+- 递归更改所有权时不会跟随符号链接
+"#;
+
+const CHOWN_TOOL_DESCRIPTION_JA: &str = r#"
+ファイルとディレクトリの所有ユーザーや所有グループを変更するためのツールです。
+
+使い方:
+- ファイルまたはディレクトリのパスを指定します
+- 新しい所有ユーザー名、新しい所有グループ名、またはその両方を指定します
+- 必要に応じて recursive を true に設定し、ディレクトリ内のすべてのエントリの所有権を変更します
+
+制限事項:
+- Windows ではサポートされていません
+- 所有権を他のユーザーに譲渡できるのは root として実行している場合のみです
+- 再帰的に所有権を変更する場合、シンボリックリンクはたどりません
+"#;
+
+const CHOWN_SCHEMA: &str = r#"
+{
+    "type": "object",
+    "properties": {
+        "path": {
+            "type": "string",
+            "description": "Path to the file or directory"
+        },
+        "user": {
+            "type": "string",
+            "description": "Name of the new owning user"
+        },
+        "group": {
+            "type": "string",
+            "description": "Name of the new owning group"
+        },
+        "recursive": {
+            "type": "boolean",
+            "description": "Whether or not to change ownership recursively",
+            "default": false
+        }
+    },
+    "required": [
+        "path"
+    ]
+}
+"#;
+
+impl BuiltInToolTrait for Chown {
+    fn name() -> BuiltInToolName {
+        BuiltInToolName::Chown
+    }
+
+    fn description() -> std::borrow::Cow<'static, str> {
+        CHOWN_TOOL_DESCRIPTION.into()
+    }
+
+    fn description_for_locale(locale: &str) -> std::borrow::Cow<'static, str> {
+        match ToolLocale::parse(locale) {
+            Some(ToolLocale::ZhCn) => CHOWN_TOOL_DESCRIPTION_ZH_CN.into(),
+            Some(ToolLocale::Ja) => CHOWN_TOOL_DESCRIPTION_JA.into(),
+            None => Self::description(),
+        }
+    }
+
+    fn input_schema() -> std::borrow::Cow<'static, str> {
+        CHOWN_SCHEMA.into()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chown {
+    pub path: String,
+    pub user: Option<String>,
+    pub group: Option<String>,
+    pub recursive: Option<bool>,
+}
+
+impl Chown {
+    pub async fn validate<P: SystemProvider>(&self, provider: &P) -> Result<(), String> {
+        if self.user.is_none() && self.group.is_none() {
+            return Err("At least one of user or group must be provided".to_string());
+        }
+        let path = self.canonical_path(provider)?;
+        if !path.exists() {
+            return Err(format!("'{}' does not exist", path.to_string_lossy()));
+        }
+        #[cfg(unix)]
+        self.resolve_ids()?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    pub async fn execute<P: SystemProvider>(&self, provider: &P) -> ToolExecutionResult {
+        let path = self.canonical_path(provider)?;
+        let (uid, gid) = self.resolve_ids()?;
+
+        // Only root is allowed to give files away to other users.
+        let euid = nix::unistd::geteuid();
+        if uid.is_some_and(|uid| !euid.is_root() && uid != euid) {
+            return Err(ToolExecutionError::Custom("permission denied".to_string()));
+        }
+
+        let mut changed = 0;
+        let mut queue = vec![path];
+        while let Some(path) = queue.pop() {
+            let md = tokio::fs::symlink_metadata(&path)
+                .await
+                .map_err(|e| ToolExecutionError::from_io_result("failed to get metadata", Some(&path), e))?;
+            if md.is_symlink() && changed > 0 {
+                // Don't follow symlinks found while recursing.
+                continue;
+            }
+
+            chown(&path, uid, gid)?;
+            changed += 1;
+
+            if self.recursive() && md.is_dir() {
+                let mut read_dir = tokio::fs::read_dir(&path)
+                    .await
+                    .map_err(|e| ToolExecutionError::from_io_result("failed to read directory", Some(&path), e))?;
+                while let Some(ent) = read_dir
+                    .next_entry()
+                    .await
+                    .map_err(|e| ToolExecutionError::from_io_result("failed to read directory", Some(&path), e))?
+                {
+                    queue.push(ent.path());
+                }
+            }
+        }
+
+        Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Text(format!(
+            "Changed the ownership of {} {}",
+            changed,
+            if changed == 1 { "entry" } else { "entries" }
+        ))]))
+    }
+
+    #[cfg(windows)]
+    pub async fn execute<P: SystemProvider>(&self, _provider: &P) -> ToolExecutionResult {
+        Err(ToolExecutionError::Custom("chown not supported on Windows".to_string()))
+    }
+
+    /// Resolves the configured user and group names to their ids.
+    #[cfg(unix)]
+    fn resolve_ids(&self) -> Result<(Option<nix::unistd::Uid>, Option<nix::unistd::Gid>), String> {
+        use nix::unistd::{
+            Group,
+            User,
+        };
+
+        let uid = match &self.user {
+            Some(name) => Some(
+                User::from_name(name)
+                    .map_err(|e| format!("failed to look up user '{}': {}", name, e))?
+                    .ok_or_else(|| format!("user '{}' does not exist", name))?
+                    .uid,
+            ),
+            None => None,
+        };
+        let gid = match &self.group {
+            Some(name) => Some(
+                Group::from_name(name)
+                    .map_err(|e| format!("failed to look up group '{}': {}", name, e))?
+                    .ok_or_else(|| format!("group '{}' does not exist", name))?
+                    .gid,
+            ),
+            None => None,
+        };
+        Ok((uid, gid))
+    }
+
+    fn recursive(&self) -> bool {
+        self.recursive.unwrap_or(false)
+    }
+
+    fn canonical_path<P: SystemProvider>(&self, provider: &P) -> Result<PathBuf, String> {
+        Ok(PathBuf::from(
+            canonicalize_path_sys(&self.path, provider).map_err(|e| e.to_string())?,
+        ))
+    }
+}
+
+#[cfg(unix)]
+fn chown(
+    path: &std::path::Path,
+    uid: Option<nix::unistd::Uid>,
+    gid: Option<nix::unistd::Gid>,
+) -> Result<(), ToolExecutionError> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| ToolExecutionError::Custom(format!("invalid path {}: {}", path.to_string_lossy(), e)))?;
+    // -1 leaves the corresponding id unchanged.
+    let uid = uid.map_or(u32::MAX as libc::uid_t, |uid| uid.as_raw());
+    let gid = gid.map_or(u32::MAX as libc::gid_t, |gid| gid.as_raw());
+    let res = unsafe { libc::chown(c_path.as_ptr(), uid, gid) };
+    if res != 0 {
+        return Err(ToolExecutionError::from_io_result(
+            "failed to change ownership",
+            Some(path),
+            std::io::Error::last_os_error(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test::TestBase;
+
+    #[tokio::test]
+    async fn test_chown_validate() {
+        let test_base = TestBase::new().await.with_file(("file.txt", "hello")).await;
+
+        let tool = Chown {
+            path: test_base.join("file.txt").to_string_lossy().to_string(),
+            user: None,
+            group: None,
+            recursive: None,
+        };
+        assert!(tool.validate(&test_base).await.is_err(), "user or group is required");
+
+        let tool = Chown {
+            path: test_base.join("missing.txt").to_string_lossy().to_string(),
+            user: Some("root".to_string()),
+            group: None,
+            recursive: None,
+        };
+        assert!(tool.validate(&test_base).await.is_err(), "path must exist");
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_chown_current_user() {
+        use std::os::unix::fs::MetadataExt;
+
+        let test_base = TestBase::new()
+            .await
+            .with_file(("dir/a.txt", "a"))
+            .await
+            .with_file(("dir/nested/b.txt", "b"))
+            .await;
+
+        let euid = nix::unistd::geteuid();
+        let user = nix::unistd::User::from_uid(euid).unwrap().unwrap();
+        let tool = Chown {
+            path: test_base.join("dir").to_string_lossy().to_string(),
+            user: Some(user.name.clone()),
+            group: None,
+            recursive: Some(true),
+        };
+        tool.validate(&test_base).await.unwrap();
+        tool.execute(&test_base).await.unwrap();
+        for path in ["dir/a.txt", "dir/nested/b.txt"] {
+            assert_eq!(std::fs::metadata(test_base.join(path)).unwrap().uid(), euid.as_raw());
+        }
+
+        let tool = Chown {
+            path: test_base.join("dir").to_string_lossy().to_string(),
+            user: Some("this-user-does-not-exist".to_string()),
+            group: None,
+            recursive: None,
+        };
+        assert!(tool.validate(&test_base).await.is_err());
+
+        // Giving files away to another user requires root.
+        if !euid.is_root() {
+            let tool = Chown {
+                path: test_base.join("dir").to_string_lossy().to_string(),
+                user: Some("root".to_string()),
+                group: None,
+                recursive: None,
+            };
+            assert!(tool.execute(&test_base).await.is_err());
+        }
+    }
+}
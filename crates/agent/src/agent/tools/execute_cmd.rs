@@ -3,6 +3,7 @@
 
 use std::collections::HashMap;
 use std::process::Stdio;
+use std::time::Duration;
 
 use bstr::ByteSlice as _;
 use schemars::{
@@ -13,22 +14,45 @@ use serde::{
     Deserialize,
     Serialize,
 };
-use tokio::process::Command;
+use tokio::io::{
+    AsyncRead,
+    AsyncReadExt as _,
+    AsyncWriteExt as _,
+};
+use tokio::process::{
+    Child,
+    ChildStdin,
+    Command,
+};
+use tracing::warn;
 
 use super::{
     BuiltInToolName,
     BuiltInToolTrait,
+    ToolExample,
     ToolExecutionError,
     ToolExecutionOutput,
     ToolExecutionOutputItem,
     ToolExecutionResult,
+    ToolLocale,
+    ToolOutputSender,
+    send_output,
 };
+use crate::agent::agent_config::definitions::ExecuteCmdSettings;
 use crate::agent::util::consts::{
     USER_AGENT_APP_NAME,
     USER_AGENT_ENV_VAR,
     USER_AGENT_VERSION_KEY,
     USER_AGENT_VERSION_VALUE,
 };
+use crate::agent::util::path::canonicalize_path_sys;
+use crate::agent::util::providers::SystemProvider;
+#[cfg(any(test, feature = "test-util"))]
+use crate::agent::util::test::CommandStub;
+use crate::agent::util::{
+    is_sensitive_env_var,
+    truncate_output_in_place,
+};
 
 const EXECUTE_CMD_TOOL_DESCRIPTION: &str = r#"
 A tool for executing bash commands.
@@ -38,16 +62,81 @@ WHEN TO USE THIS TOOL:
 
 HOW TO USE:
 - Provide the command to execute
+- On Linux, optionally provide resourceLimits to cap the memory, CPU time, and file sizes used by the command
+- On Linux, optionally set isolate to true to prevent the command from seeing other processes or accessing the network
+- Optionally provide pipeTo with another command to pipe this command's stdout into, instead of using `|`
+- Optionally provide timeoutSecs to kill the command if it is still running after that many seconds
+- Optionally provide workingDir to run the command in a directory other than the current working directory, instead of using `cd`
+- Optionally provide shell to run the command with sh, zsh, or fish instead of bash
+- Optionally provide env to set environment variables for the command, instead of exporting them in the command. Values can reference other environment variables with ${env:VAR_NAME}
+- Optionally provide stdin to write text to the standard input of the command, e.g. for `bc` or `python -`, instead of using `echo ... |` or a heredoc
+- Output is limited to maxOutputBytes (64KB by default). Longer output is cut at a line boundary and ends with `[output truncated: N bytes omitted]`, so prefer commands that filter their output, e.g. with `head` or `grep`
 
 FEATURES:
 
 LIMITATIONS:
 - Does not respect user's bash profile or aliases
+- Commands that redirect output to a file (e.g. `>`, `>>`, `2>`, `&>`) are rejected unless detectRedirects is false. Redirecting to /dev/null and duplicating file descriptors (e.g. `2>&1`) are allowed
+- Environment variables with names containing TOKEN, SECRET, PASSWORD, API_KEY, or CREDENTIAL are not passed to the command unless allowSensitiveEnv is true
 
 TIPS:
 - Use the fileRead and fileWrite tools for reading and modifying files
 "#;
 
+const EXECUTE_CMD_TOOL_DESCRIPTION_ZH_CN: &str = r#"
+用于执行 bash 命令的工具。
+
+何时使用此工具：
+- 仅在其他可用工具都无法完成任务时作为最后手段使用
+
+使用方法：
+- 提供要执行的命令
+- 在 Linux 上，可选地提供 resourceLimits，以限制命令使用的内存、CPU 时间和文件大小
+- 在 Linux 上，可选地将 isolate 设置为 true，以阻止命令查看其他进程或访问网络
+- 可选地在 pipeTo 中提供另一个命令，将此命令的 stdout 通过管道传给它，而不是使用 `|`
+- 可选地提供 timeoutSecs，如果命令在该秒数后仍在运行，则终止该命令
+- 可选地提供 workingDir，在当前工作目录以外的目录中运行命令，而不是使用 `cd`
+- 可选地提供 shell，使用 sh、zsh 或 fish 而不是 bash 运行命令
+- 可选地提供 env，为命令设置环境变量，而不是在命令中导出它们。值可以使用 ${env:VAR_NAME} 引用其他环境变量
+- 可选地提供 stdin，将文本写入命令的标准输入（例如用于 `bc` 或 `python -`），而不是使用 `echo ... |` 或 heredoc
+- 输出限制为 maxOutputBytes（默认 64KB）。更长的输出会在行边界处截断，并以 `[output truncated: N bytes omitted]` 结尾，因此请优先使用会过滤输出的命令，例如使用 `head` 或 `grep`
+
+限制：
+- 不会加载用户的 bash 配置文件或别名
+- 将输出重定向到文件的命令（例如 `>`、`>>`、`2>`、`&>`）会被拒绝，除非 detectRedirects 为 false。允许重定向到 /dev/null 和复制文件描述符（例如 `2>&1`）
+- 名称包含 TOKEN、SECRET、PASSWORD、API_KEY 或 CREDENTIAL 的环境变量不会传递给命令，除非 allowSensitiveEnv 为 true
+
+提示：
+- 读取和修改文件请使用 fileRead 和 fileWrite 工具
+"#;
+
+const EXECUTE_CMD_TOOL_DESCRIPTION_JA: &str = r#"
+bash コマンドを実行するためのツールです。
+
+このツールを使用する場面:
+- 他に利用可能なツールでタスクを達成できない場合の最後の手段としてのみ使用します
+
+使い方:
+- 実行するコマンドを指定します
+- Linux では、必要に応じて resourceLimits を指定し、コマンドが使用するメモリ、CPU 時間、ファイルサイズを制限します
+- Linux では、必要に応じて isolate を true に設定し、コマンドが他のプロセスを参照したりネットワークにアクセスしたりできないようにします
+- 必要に応じて pipeTo に別のコマンドを指定し、`|` を使う代わりにこのコマンドの stdout をそのコマンドにパイプします
+- 必要に応じて timeoutSecs を指定し、その秒数が経過してもコマンドが実行中の場合は強制終了します
+- 必要に応じて workingDir を指定し、`cd` を使う代わりに現在の作業ディレクトリ以外のディレクトリでコマンドを実行します
+- 必要に応じて shell を指定し、bash の代わりに sh、zsh、fish でコマンドを実行します
+- 必要に応じて env を指定し、コマンド内でエクスポートする代わりにコマンドの環境変数を設定します。値では ${env:VAR_NAME} で他の環境変数を参照できます
+- 必要に応じて stdin を指定し、`echo ... |` やヒアドキュメントを使う代わりに、コマンドの標準入力にテキストを書き込みます (例: `bc` や `python -`)
+- 出力は maxOutputBytes (デフォルトは 64KB) に制限されます。それより長い出力は行の区切りで切り捨てられ、`[output truncated: N bytes omitted]` で終わるため、`head` や `grep` などで出力を絞り込むコマンドを優先してください
+
+制限事項:
+- ユーザーの bash プロファイルやエイリアスは反映されません
+- 出力をファイルにリダイレクトするコマンド (例: `>`、`>>`、`2>`、`&>`) は、detectRedirects が false でない限り拒否されます。/dev/null へのリダイレクトとファイル記述子の複製 (例: `2>&1`) は許可されます
+- 名前に TOKEN、SECRET、PASSWORD、API_KEY、CREDENTIAL を含む環境変数は、allowSensitiveEnv が true でない限りコマンドに渡されません
+
+ヒント:
+- ファイルの読み取りと変更には fileRead ツールと fileWrite ツールを使用してください
+"#;
+
 const EXECUTE_CMD_SCHEMA: &str = r#"
 {
     "type": "object",
@@ -55,6 +144,73 @@ const EXECUTE_CMD_SCHEMA: &str = r#"
         "command": {
             "type": "string",
             "description": "Command to execute"
+        },
+        "detectRedirects": {
+            "type": "boolean",
+            "description": "Whether or not to reject commands that redirect output to a file",
+            "default": true
+        },
+        "isolate": {
+            "type": "boolean",
+            "description": "Whether or not to run the command in isolated PID, network, and UTS namespaces, preventing it from seeing other processes, making network connections, or changing the hostname. Only supported on Linux.",
+            "default": false
+        },
+        "allowSensitiveEnv": {
+            "type": "boolean",
+            "description": "Whether or not to pass environment variables with names containing TOKEN, SECRET, PASSWORD, API_KEY, or CREDENTIAL to the command. When false, they are removed from the command's environment.",
+            "default": false
+        },
+        "pipeTo": {
+            "type": "object",
+            "description": "Another command, with the same properties as this one, to pipe the stdout of this command into. Only the stdout, stderr, and exit status of the last command in the chain are returned. At most 5 commands can be chained."
+        },
+        "timeoutSecs": {
+            "type": "integer",
+            "description": "Number of seconds to wait for the command to exit before killing it. Only the value on the first command applies, and covers the whole chain of piped commands. Must be greater than 0.",
+            "minimum": 1
+        },
+        "workingDir": {
+            "type": "string",
+            "description": "Path to the directory to run the command in. Defaults to the current working directory. Must be an existing directory."
+        },
+        "env": {
+            "type": "object",
+            "description": "Environment variables to set for the command, overriding inherited values. Values can reference other environment variables with ${env:VAR_NAME}. Names must not be empty or contain '=' or null characters.",
+            "additionalProperties": {
+                "type": "string"
+            }
+        },
+        "stdin": {
+            "type": "string",
+            "description": "UTF-8 text to write to the standard input of the command. Standard input is closed after it is written."
+        },
+        "maxOutputBytes": {
+            "type": "integer",
+            "description": "Max number of bytes of stdout and stderr to return, combined. Output beyond this is omitted, keeping complete lines where possible. Only the value on the first command applies. Defaults to 65536.",
+            "minimum": 1
+        },
+        "shell": {
+            "type": "string",
+            "description": "Shell to run the command with. Must be installed on the system. Defaults to bash.",
+            "enum": ["bash", "sh", "zsh", "fish"]
+        },
+        "resourceLimits": {
+            "type": "object",
+            "description": "Resource limits to enforce on the command. Only supported on Linux.",
+            "properties": {
+                "maxMemoryMb": {
+                    "type": "integer",
+                    "description": "Maximum size of the virtual address space in megabytes"
+                },
+                "maxCpuSeconds": {
+                    "type": "integer",
+                    "description": "Maximum CPU time in seconds"
+                },
+                "maxFileSizeMb": {
+                    "type": "integer",
+                    "description": "Maximum size of a file that can be written in megabytes"
+                }
+            }
         }
     },
     "required": [
@@ -72,64 +228,873 @@ impl BuiltInToolTrait for ExecuteCmd {
         EXECUTE_CMD_TOOL_DESCRIPTION.into()
     }
 
+    fn description_for_locale(locale: &str) -> std::borrow::Cow<'static, str> {
+        match ToolLocale::parse(locale) {
+            Some(ToolLocale::ZhCn) => EXECUTE_CMD_TOOL_DESCRIPTION_ZH_CN.into(),
+            Some(ToolLocale::Ja) => EXECUTE_CMD_TOOL_DESCRIPTION_JA.into(),
+            None => Self::description(),
+        }
+    }
+
     fn input_schema() -> std::borrow::Cow<'static, str> {
         EXECUTE_CMD_SCHEMA.into()
     }
+
+    fn constraints() -> Vec<String> {
+        vec![
+            "Do not run fork bombs or commands that recursively spawn processes without bound".to_string(),
+            "Do not run commands that delete or overwrite files outside of the user's workspace unless explicitly asked".to_string(),
+        ]
+    }
+
+    fn examples() -> Vec<ToolExample> {
+        vec![
+            ToolExample::new(
+                "Show the status of a git repository",
+                serde_json::json!({ "command": "git status" }),
+            ),
+            ToolExample::new(
+                "Run a project's tests from a subdirectory",
+                serde_json::json!({ "command": "cargo test", "workingDir": "crates/app" }),
+            ),
+        ]
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct ExecuteCmd {
     pub command: String,
+    /// Whether commands containing output redirection should be rejected. Defaults to true.
+    pub detect_redirects: Option<bool>,
+    /// Resource limits applied to the spawned process. Only supported on Linux.
+    pub resource_limits: Option<ExecuteCmdLimits>,
+    /// Whether to run the command in new PID, network, and UTS namespaces. Only supported on
+    /// Linux.
+    pub isolate: Option<bool>,
+    /// Command to pipe the stdout of this command into, equivalent to `command | pipe_to`.
+    pub pipe_to: Option<Box<ExecuteCmd>>,
+    /// Whether environment variables that look like secrets should be passed to the command.
+    /// Defaults to false.
+    pub allow_sensitive_env: Option<bool>,
+    /// Number of seconds to wait for the command to exit before killing it.
+    pub timeout_secs: Option<u64>,
+    /// Directory to run the command in. Defaults to the current working directory.
+    pub working_dir: Option<String>,
+    /// Environment variables to set for the command, overriding inherited values. `${env:VAR}`
+    /// references in the values are expanded.
+    pub env: Option<HashMap<String, String>>,
+    /// Text written to the stdin of the command, which is closed afterwards.
+    pub stdin: Option<String>,
+    /// Max number of bytes of output returned. Defaults to [DEFAULT_MAX_OUTPUT_BYTES].
+    pub max_output_bytes: Option<u64>,
+    /// Shell to run the command with, one of [SUPPORTED_SHELLS]. Defaults to the
+    /// `AMAZON_Q_CHAT_SHELL` environment variable, or bash.
+    pub shell: Option<String>,
 }
 
+/// Shells that can be selected with [ExecuteCmd::shell]. All of them accept the command with `-c`.
+const SUPPORTED_SHELLS: &[&str] = &["bash", "sh", "zsh", "fish"];
+
+/// Default for [ExecuteCmd::max_output_bytes].
+const DEFAULT_MAX_OUTPUT_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteCmdLimits {
+    pub max_memory_mb: Option<u64>,
+    pub max_cpu_seconds: Option<u64>,
+    pub max_file_size_mb: Option<u64>,
+}
+
+impl ExecuteCmdLimits {
+    /// Returns the limits as `(resource, soft, hard)` rlimit values, or an error if a limit is out
+    /// of range.
+    #[cfg(target_os = "linux")]
+    fn rlimits(&self) -> Result<Vec<(nix::sys::resource::Resource, u64, u64)>, String> {
+        use nix::sys::resource::Resource;
+
+        let mb_to_bytes = |name: &str, mb: u64| {
+            mb.checked_mul(1024 * 1024)
+                .ok_or_else(|| format!("resourceLimits.{} is too large", name))
+        };
+        let mut rlimits = Vec::new();
+        if let Some(mb) = self.max_memory_mb {
+            let bytes = mb_to_bytes("maxMemoryMb", mb)?;
+            rlimits.push((Resource::RLIMIT_AS, bytes, bytes));
+        }
+        if let Some(secs) = self.max_cpu_seconds {
+            // SIGXCPU is sent at the soft limit, and SIGKILL at the hard limit.
+            let hard = secs
+                .checked_add(1)
+                .ok_or_else(|| "resourceLimits.maxCpuSeconds is too large".to_string())?;
+            rlimits.push((Resource::RLIMIT_CPU, secs, hard));
+        }
+        if let Some(mb) = self.max_file_size_mb {
+            let bytes = mb_to_bytes("maxFileSizeMb", mb)?;
+            rlimits.push((Resource::RLIMIT_FSIZE, bytes, bytes));
+        }
+        Ok(rlimits)
+    }
+
+    /// Whether the process was killed by a signal sent when exceeding one of these limits.
+    fn is_exceeded_by(&self, status: &std::process::ExitStatus) -> bool {
+        use std::os::unix::process::ExitStatusExt as _;
+
+        match status.signal() {
+            Some(libc::SIGXCPU) => true,
+            Some(libc::SIGKILL) => self.max_cpu_seconds.is_some(),
+            Some(libc::SIGXFSZ) => self.max_file_size_mb.is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// Sets the given `(resource, soft, hard)` limits for the current process. Only
+/// async-signal-safe functions are called so that this can be run in a forked child before exec.
+#[cfg(target_os = "linux")]
+fn apply_rlimits(rlimits: &[(nix::sys::resource::Resource, u64, u64)]) -> std::io::Result<()> {
+    for &(resource, soft, hard) in rlimits {
+        nix::sys::resource::setrlimit(resource, soft, hard)?;
+    }
+    Ok(())
+}
+
+/// The max number of commands that can be chained with [ExecuteCmd::pipe_to].
+const MAX_PIPE_DEPTH: usize = 5;
+
 impl ExecuteCmd {
     pub fn tool_schema() -> serde_json::Value {
         let schema = schema_for!(Self);
         serde_json::to_value(schema).expect("creating tool schema should not fail")
     }
 
-    pub async fn validate(&self) -> Result<(), String> {
+    /// Applies the configured `settings` to the command, capping [Self::timeout_secs] at
+    /// [ExecuteCmdSettings::timeout_secs].
+    pub fn with_settings(mut self, settings: Option<&ExecuteCmdSettings>) -> Self {
+        if let Some(max) = settings.and_then(|s| s.timeout_secs) {
+            self.timeout_secs = Some(self.timeout_secs.map_or(max, |secs| secs.min(max)));
+        }
+        self
+    }
+
+    pub async fn validate<P: SystemProvider>(
+        &self,
+        provider: &P,
+        settings: Option<&ExecuteCmdSettings>,
+    ) -> Result<(), String> {
+        let pipeline = self.pipeline();
+        if pipeline.len() > MAX_PIPE_DEPTH {
+            return Err(format!(
+                "at most {} commands can be piped together, found {}",
+                MAX_PIPE_DEPTH,
+                pipeline.len()
+            ));
+        }
+        for (i, cmd) in pipeline.iter().enumerate() {
+            if i > 0 && cmd.stdin.is_some() {
+                return Err("stdin can only be provided to the first command in a pipeline".to_string());
+            }
+            cmd.validate_command(provider, settings)?;
+        }
+        Ok(())
+    }
+
+    fn validate_command<P: SystemProvider>(
+        &self,
+        provider: &P,
+        settings: Option<&ExecuteCmdSettings>,
+    ) -> Result<(), String> {
         if self.command.is_empty() {
-            Err("Command must not be empty".to_string())
-        } else {
-            Ok(())
+            return Err("Command must not be empty".to_string());
+        }
+        if settings.is_some_and(|s| !s.is_command_allowed(&self.command)) {
+            return Err("command not in allow-list".to_string());
+        }
+        // Environment variables such as PATH, BASH_ENV, or LD_PRELOAD, and a different shell, can
+        // change what an allowed command actually runs.
+        if settings.is_some_and(|s| s.command_allowlist.is_some()) {
+            if self.env.as_ref().is_some_and(|env| !env.is_empty()) {
+                return Err("env cannot be set when a command allow-list is configured".to_string());
+            }
+            if self.shell.is_some() {
+                return Err("shell cannot be set when a command allow-list is configured".to_string());
+            }
+        }
+        if self.detect_redirects.unwrap_or(true) {
+            if let Some(redirect) = find_output_redirect(&self.command) {
+                return Err(format!(
+                    "output redirection '{}' detected; use fsWrite instead",
+                    redirect
+                ));
+            }
+        }
+        if cfg!(not(target_os = "linux")) && self.resource_limits.is_some() {
+            return Err("resource limits are only supported on Linux".to_string());
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(limits) = &self.resource_limits {
+            limits.rlimits()?;
+        }
+        if cfg!(not(target_os = "linux")) && self.isolate() {
+            return Err("isolation is only supported on Linux".to_string());
+        }
+        if self.timeout_secs == Some(0) {
+            return Err("timeoutSecs must be greater than 0".to_string());
+        }
+        if self.max_output_bytes == Some(0) {
+            return Err("maxOutputBytes must be greater than 0".to_string());
+        }
+        if let Some(shell) = &self.shell {
+            if !SUPPORTED_SHELLS.contains(&shell.as_str()) {
+                return Err(format!(
+                    "Unsupported shell '{}', expected one of: {}",
+                    shell,
+                    SUPPORTED_SHELLS.join(", ")
+                ));
+            }
+            if which::which(shell).is_err() {
+                let available = SUPPORTED_SHELLS
+                    .iter()
+                    .filter(|s| which::which(s).is_ok())
+                    .copied()
+                    .collect::<Vec<_>>();
+                return Err(format!(
+                    "Shell '{}' was not found on PATH. Available shells: {}",
+                    shell,
+                    available.join(", ")
+                ));
+            }
+        }
+        for name in self.env.iter().flat_map(|env| env.keys()) {
+            if name.is_empty() || name.contains(['=', '\0']) {
+                return Err(format!("Invalid environment variable name: {:?}", name));
+            }
+        }
+        if self.stdin.is_some() && !reads_stdin(&self.command) {
+            warn!(command = %self.command, "stdin was provided to a command that does not appear to read it");
+        }
+        if let Some(working_dir) = self.working_dir(provider)? {
+            match std::fs::metadata(&working_dir) {
+                Ok(metadata) if !metadata.is_dir() => {
+                    return Err(format!("Path is not a directory: {}", working_dir));
+                },
+                Ok(_) => (),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    return Err(format!("Directory not found: {}", working_dir));
+                },
+                Err(e) => {
+                    return Err(format!(
+                        "failed to check file metadata for path '{}': {}",
+                        working_dir, e
+                    ));
+                },
+            }
         }
+        Ok(())
     }
 
-    pub async fn execute(&self) -> ToolExecutionResult {
-        let shell = std::env::var("AMAZON_Q_CHAT_SHELL").unwrap_or("bash".to_string());
+    /// Returns the canonicalized [ExecuteCmd::working_dir], if set.
+    fn working_dir<P: SystemProvider>(&self, provider: &P) -> Result<Option<String>, String> {
+        self.working_dir
+            .as_ref()
+            .map(|dir| canonicalize_path_sys(dir, provider).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    pub async fn execute<P: SystemProvider>(&self, provider: &P) -> ToolExecutionResult {
+        self.execute_inner(provider, None).await
+    }
+
+    /// Same as [ExecuteCmd::execute], except that stdout is sent through `output` while the
+    /// command is running, as text items of complete lines. The returned JSON item then has no
+    /// `stdout` field. [ExecuteCmd::max_output_bytes] applies to stdout as it is sent, and stderr
+    /// is limited to what is left.
+    pub async fn execute_stream<P: SystemProvider>(
+        &self,
+        provider: &P,
+        output: &ToolOutputSender,
+    ) -> ToolExecutionResult {
+        self.execute_inner(provider, Some(output)).await
+    }
 
-        let env_vars = env_vars_with_user_agent();
+    async fn execute_inner<P: SystemProvider>(
+        &self,
+        provider: &P,
+        output: Option<&ToolOutputSender>,
+    ) -> ToolExecutionResult {
+        let pipeline = self.pipeline();
+        #[cfg(any(test, feature = "test-util"))]
+        if let Some(stub) = provider.command_stub(&self.commands().join(" | ")) {
+            return Ok(stubbed_output(stub));
+        }
+
+        // Each command's stdout is copied into the stdin of the next. Only the stderr of the last
+        // command is returned.
+        let mut children = Vec::new();
+        let mut copy_tasks = Vec::new();
+        let mut prev_stdout = None;
+        for (i, cmd) in pipeline.iter().enumerate() {
+            let is_last = i + 1 == pipeline.len();
+            let stdin = if prev_stdout.is_some() || cmd.stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::inherit()
+            };
+            let stderr = if is_last { Stdio::piped() } else { Stdio::null() };
+            let working_dir = cmd.working_dir(provider)?;
+            let mut child = cmd.spawn(stdin, stderr, working_dir.as_deref())?;
+            if let Some(input) = cmd.stdin.clone() {
+                if let Some(stdin) = child.stdin.take() {
+                    copy_tasks.push(tokio::spawn(write_stdin(stdin, input)));
+                }
+            }
+            if let (Some(mut stdout), Some(mut stdin)) = (prev_stdout.take(), child.stdin.take()) {
+                copy_tasks.push(tokio::spawn(async move {
+                    // Fails with a broken pipe if the next command exits without reading all of
+                    // its input, in which case the previous command receives SIGPIPE.
+                    let _ = tokio::io::copy(&mut stdout, &mut stdin).await;
+                }));
+            }
+            if !is_last {
+                prev_stdout = child.stdout.take();
+            }
+            children.push((cmd, child));
+        }
+
+        let (_, last_child) = children.last_mut().expect("pipeline should not be empty");
+        let stdout = last_child.stdout.take();
+        let stderr_task = tokio::spawn(read_to_end(last_child.stderr.take()));
+        let max_output_bytes = self.max_output_bytes();
+        // Streamed stdout is sent while waiting rather than on a separate task, so that nothing is
+        // sent after the command times out.
+        let (stdout_task, stream_stdout) = match output {
+            Some(output) => (None, Some(stream_output(stdout, output, max_output_bytes))),
+            None => (Some(tokio::spawn(read_to_end(stdout))), None),
+        };
+        let wait_children = async {
+            // Wait for the last command first, since earlier commands may be blocked writing to it.
+            let mut exit_status = None;
+            for (cmd, child) in children.iter_mut().rev() {
+                let status = child.wait().await.map_err(|e| {
+                    ToolExecutionError::from_io_result(format!("No exit status for '{}'", &cmd.command), None, e)
+                })?;
+                if cmd.resource_limits.as_ref().is_some_and(|l| l.is_exceeded_by(&status)) {
+                    return Err(ToolExecutionError::Custom("resource limit exceeded".to_string()));
+                }
+                exit_status.get_or_insert(status);
+            }
+            Ok(exit_status.expect("pipeline should not be empty"))
+        };
+        let wait_all = async {
+            let stream_stdout = async {
+                match stream_stdout {
+                    Some(stream) => Some(stream.await),
+                    None => None,
+                }
+            };
+            let (exit_status, streamed) = tokio::join!(wait_children, stream_stdout);
+            exit_status.map(|status| (status, streamed))
+        };
+        let (exit_status, streamed) = match self.timeout_secs {
+            Some(secs) => match tokio::time::timeout(Duration::from_secs(secs), wait_all).await {
+                Ok(result) => result?,
+                Err(_) => {
+                    for (_, child) in &mut children {
+                        kill_process_group(child).await;
+                    }
+                    return Err(ToolExecutionError::Custom(format!(
+                        "Command timed out after {} seconds",
+                        secs
+                    )));
+                },
+            },
+            None => wait_all.await?,
+        };
+        for task in copy_tasks {
+            let _ = task.await;
+        }
+
+        let stderr = stderr_task.await.unwrap_or_default();
+        let mut clean_stderr = sanitize_unicode_tags(stderr.to_str_lossy());
+        let (result, truncated_bytes) = match (stdout_task, streamed) {
+            (Some(stdout_task), _) => {
+                let stdout = stdout_task.await.unwrap_or_default();
+                let mut clean_stdout = sanitize_unicode_tags(stdout.to_str_lossy());
+                let truncated_bytes = truncate_output(&mut clean_stdout, &mut clean_stderr, max_output_bytes);
+                let result = serde_json::json!({
+                    "exit_status": exit_status.to_string(),
+                    "stdout": clean_stdout,
+                    "stderr": clean_stderr,
+                });
+                (result, truncated_bytes)
+            },
+            (None, streamed) => {
+                let StreamedOutput { sent, omitted } = streamed.unwrap_or_default();
+                let truncated_bytes =
+                    omitted + truncate_output_in_place(&mut clean_stderr, max_output_bytes.saturating_sub(sent));
+                let result = serde_json::json!({
+                    "exit_status": exit_status.to_string(),
+                    "stderr": clean_stderr,
+                });
+                (result, truncated_bytes)
+            },
+        };
+
+        Ok(ToolExecutionOutput {
+            items: vec![
+                ToolExecutionOutputItem::Json(result),
+                exit_code_item(exit_status.code().unwrap_or(-1), truncated_bytes),
+            ],
+        })
+    }
 
-        let child = Command::new(shell)
-            .arg("-c")
+    /// Returns this command followed by each command chained with [ExecuteCmd::pipe_to].
+    fn pipeline(&self) -> Vec<&ExecuteCmd> {
+        std::iter::successors(Some(self), |cmd| cmd.pipe_to.as_deref()).collect()
+    }
+
+    /// Returns the command strings of the [pipeline](Self::pipeline), in order.
+    pub fn commands(&self) -> Vec<&str> {
+        self.pipeline().into_iter().map(|cmd| cmd.command.as_str()).collect()
+    }
+
+    /// Spawns the command with a piped stdout, in `working_dir` if given.
+    fn spawn(&self, stdin: Stdio, stderr: Stdio, working_dir: Option<&str>) -> Result<Child, ToolExecutionError> {
+        let shell = match &self.shell {
+            Some(shell) => shell.clone(),
+            None => std::env::var("AMAZON_Q_CHAT_SHELL").unwrap_or("bash".to_string()),
+        };
+
+        let mut env_vars = env_vars_with_user_agent();
+        let removed_env_vars = if self.allow_sensitive_env.unwrap_or(false) {
+            Vec::new()
+        } else {
+            remove_sensitive_env_vars(&mut env_vars)
+        };
+        // Variables passed explicitly are never removed.
+        if let Some(env) = &self.env {
+            let mut env = env.clone();
+            crate::agent::util::expand_env_vars(&mut env);
+            env_vars.extend(env);
+        }
+
+        let mut cmd = Command::new(shell);
+        for name in removed_env_vars {
+            cmd.env_remove(name);
+        }
+        cmd.arg("-c")
             .arg(&self.command)
             .envs(env_vars)
-            .stdin(Stdio::inherit())
+            .stdin(stdin)
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| ToolExecutionError::io(format!("Failed to spawn command '{}'", &self.command), e))?;
+            .stderr(stderr)
+            // Cancelling the execution drops the child.
+            .kill_on_drop(true)
+            // Processes started by the command stay in its group, so that they can be killed
+            // along with it on timeout.
+            .process_group(0);
+        if let Some(working_dir) = working_dir {
+            cmd.current_dir(working_dir);
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(limits) = &self.resource_limits {
+            let rlimits = limits.rlimits()?;
+            // SAFETY: setrlimit is async-signal-safe.
+            unsafe {
+                cmd.pre_exec(move || apply_rlimits(&rlimits));
+            }
+        }
+        #[cfg(target_os = "linux")]
+        if self.isolate() {
+            let id_maps = IdMaps::current();
+            // SAFETY: only async-signal-safe functions are called, see [isolate_namespaces].
+            unsafe {
+                cmd.pre_exec(move || isolate_namespaces(&id_maps));
+            }
+        }
 
-        let output = child
-            .wait_with_output()
-            .await
-            .map_err(|e| ToolExecutionError::io(format!("No exit status for '{}'", &self.command), e))?;
+        cmd.spawn().map_err(|e| {
+            // Namespaces were added to unshare in Linux 3.8.
+            if self.isolate() && e.raw_os_error() == Some(libc::EINVAL) {
+                return ToolExecutionError::Custom("isolation requires Linux 3.8+".to_string());
+            }
+            ToolExecutionError::from_io_result(format!("Failed to spawn command '{}'", &self.command), None, e)
+        })
+    }
 
-        let exit_status = output.status;
-        let clean_stdout = sanitize_unicode_tags(output.stdout.to_str_lossy());
-        let clean_stderr = sanitize_unicode_tags(output.stderr.to_str_lossy());
+    fn isolate(&self) -> bool {
+        self.isolate.unwrap_or(false)
+    }
 
-        let result = serde_json::json!({
-            "exit_status": exit_status.to_string(),
-            "stdout": clean_stdout,
-            "stderr": clean_stderr,
-        });
+    fn max_output_bytes(&self) -> usize {
+        self.max_output_bytes.unwrap_or(DEFAULT_MAX_OUTPUT_BYTES) as usize
+    }
+}
 
-        Ok(ToolExecutionOutput {
-            items: vec![ToolExecutionOutputItem::Json(result)],
-        })
+/// Writes `input` to the stdin of a child process, closing it afterwards. Fails with a broken pipe
+/// if the child exits without reading all of it, which is ignored.
+async fn write_stdin(mut stdin: ChildStdin, input: String) {
+    let _ = stdin.write_all(input.as_bytes()).await;
+}
+
+/// Reads the piped output of a child process until EOF, returning whatever was read if the read
+/// fails.
+async fn read_to_end(reader: Option<impl AsyncRead + Unpin>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    if let Some(mut reader) = reader {
+        let _ = reader.read_to_end(&mut buf).await;
+    }
+    buf
+}
+
+/// Bytes of stdout sent and omitted by [stream_output].
+#[derive(Debug, Clone, Copy, Default)]
+struct StreamedOutput {
+    sent: usize,
+    omitted: usize,
+}
+
+/// Reads the piped output of a child process until EOF, sending each chunk of complete lines
+/// through `output` as it is read. Once a line doesn't fit within `max_bytes`, the rest of the
+/// output is read but not sent, so that the child doesn't block writing to the pipe.
+async fn stream_output(
+    reader: Option<impl AsyncRead + Unpin>,
+    output: &ToolOutputSender,
+    max_bytes: usize,
+) -> StreamedOutput {
+    let mut streamed = StreamedOutput::default();
+    let Some(mut reader) = reader else {
+        return streamed;
+    };
+    let mut buf = vec![0; 8 * 1024];
+    // The incomplete last line read so far.
+    let mut pending = Vec::new();
+    loop {
+        let n = match reader.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        pending.extend_from_slice(&buf[..n]);
+        if let Some(end) = pending.rfind_byte(b'\n') {
+            let lines = pending.drain(..=end).collect::<Vec<_>>();
+            send_lines(output, &lines, &mut streamed, max_bytes).await;
+        }
+        if streamed.omitted > 0 || streamed.sent + pending.len() > max_bytes {
+            streamed.omitted += pending.len();
+            pending.clear();
+        }
+    }
+    send_lines(output, &pending, &mut streamed, max_bytes).await;
+    streamed
+}
+
+/// Sends `lines` as a text item, leaving out the lines that don't fit within `max_bytes`.
+async fn send_lines(output: &ToolOutputSender, lines: &[u8], streamed: &mut StreamedOutput, max_bytes: usize) {
+    let remaining = if streamed.omitted > 0 {
+        0
+    } else {
+        max_bytes.saturating_sub(streamed.sent)
+    };
+    let kept = if lines.len() <= remaining {
+        lines
+    } else {
+        let end = lines[..remaining].rfind_byte(b'\n').map_or(0, |i| i + 1);
+        &lines[..end]
+    };
+    streamed.omitted += lines.len() - kept.len();
+    if !kept.is_empty() {
+        streamed.sent += kept.len();
+        let text = sanitize_unicode_tags(kept.to_str_lossy());
+        send_output(output, ToolExecutionOutputItem::Text(text)).await;
+    }
+}
+
+/// Removes environment variables whose names look like they hold secrets, according to
+/// [is_sensitive_env_var], so that they aren't
+/// leaked into the output of commands. Returns the names of the removed variables, which must
+/// also be removed from the inherited environment of the spawned command.
+///
+/// The variables are removed rather than replaced with a placeholder, so that tools like `aws`
+/// and `gh` fall back to their other credential sources instead of failing on an invalid value.
+fn remove_sensitive_env_vars(env_vars: &mut HashMap<String, String>) -> Vec<String> {
+    let mut removed = Vec::new();
+    env_vars.retain(|name, _| {
+        if is_sensitive_env_var(name) {
+            tracing::warn!(%name, "removing sensitive environment variable");
+            removed.push(name.clone());
+            false
+        } else {
+            true
+        }
+    });
+    removed
+}
+
+/// Contents of the `uid_map` and `gid_map` files written by [isolate_namespaces], mapping the
+/// current user and group to themselves within a new user namespace.
+///
+/// These are formatted before forking since allocating is not async-signal-safe.
+#[cfg(target_os = "linux")]
+struct IdMaps {
+    uid_map: String,
+    gid_map: String,
+}
+
+#[cfg(target_os = "linux")]
+impl IdMaps {
+    fn current() -> Self {
+        let uid = nix::unistd::getuid();
+        let gid = nix::unistd::getgid();
+        Self {
+            uid_map: format!("{uid} {uid} 1"),
+            gid_map: format!("{gid} {gid} 1"),
+        }
+    }
+}
+
+/// Writes `contents` to the file at `path` using only async-signal-safe functions.
+#[cfg(target_os = "linux")]
+fn write_proc_file(path: &std::ffi::CStr, contents: &str) -> std::io::Result<()> {
+    // SAFETY: path is a valid C string, and contents is valid for contents.len() bytes.
+    unsafe {
+        let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let written = libc::write(fd, contents.as_ptr().cast(), contents.len());
+        let result = if written < 0 {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(())
+        };
+        libc::close(fd);
+        result
+    }
+}
+
+/// Closes every file descriptor of the current process other than stdin, stdout, stderr, and
+/// `keep`, using only async-signal-safe functions.
+#[cfg(target_os = "linux")]
+fn close_fds_except(keep: std::os::fd::RawFd) {
+    let close_range = |first: libc::c_uint, last: libc::c_uint| {
+        if first > last {
+            return;
+        }
+        // SAFETY: close_range and close are async-signal-safe. close_range was added in Linux 5.9.
+        unsafe {
+            if libc::syscall(libc::SYS_close_range, first, last, 0) != 0 {
+                for fd in first..=last.min(u16::MAX as libc::c_uint) {
+                    libc::close(fd as libc::c_int);
+                }
+            }
+        }
+    };
+    let keep = keep as libc::c_uint;
+    close_range(3, keep.wrapping_sub(1));
+    close_range(keep + 1, libc::c_uint::MAX);
+}
+
+/// Kills every process in the process group of `child`, i.e. the command and any processes it
+/// started that are still running, and waits for the command to exit.
+async fn kill_process_group(child: &mut Child) {
+    if let Some(pid) = child.id() {
+        let pgid = nix::unistd::Pid::from_raw(pid as i32);
+        if let Err(err) = nix::sys::signal::killpg(pgid, nix::sys::signal::Signal::SIGKILL) {
+            warn!(?err, pid, "failed to kill the process group of the command");
+        }
+    }
+    let _ = child.kill().await;
+}
+
+/// Moves the current process into new PID, network, and UTS namespaces. Intended to be run in a
+/// forked child before exec.
+///
+/// When not running as root, a new user namespace is created as well in which the current user
+/// and group are mapped to themselves, so that the command runs with the same ids rather than as
+/// the overflow user `nobody`.
+///
+/// Only children of the caller are placed in the new PID namespace, so this forks again: the
+/// child returns to exec the command as PID 1 of the namespace, while the parent waits for it
+/// and exits with the same status. The child is killed when the parent exits, e.g. when killed
+/// on timeout, which in turn kills every other process in the namespace.
+#[cfg(target_os = "linux")]
+fn isolate_namespaces(id_maps: &IdMaps) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd as _;
+
+    use nix::sched::{
+        CloneFlags,
+        unshare,
+    };
+    use nix::sys::wait::{
+        WaitStatus,
+        waitpid,
+    };
+    use nix::unistd::{
+        ForkResult,
+        fork,
+    };
+
+    let mut flags = CloneFlags::CLONE_NEWPID | CloneFlags::CLONE_NEWNET | CloneFlags::CLONE_NEWUTS;
+    // Creating namespaces requires CAP_SYS_ADMIN, which unprivileged users only have within a
+    // new user namespace.
+    let new_user_ns = !nix::unistd::geteuid().is_root();
+    if new_user_ns {
+        flags |= CloneFlags::CLONE_NEWUSER;
+    }
+    unshare(flags)?;
+    if new_user_ns {
+        // setgroups must be denied before an unprivileged process can write the gid map.
+        write_proc_file(c"/proc/self/setgroups", "deny")?;
+        write_proc_file(c"/proc/self/uid_map", &id_maps.uid_map)?;
+        write_proc_file(c"/proc/self/gid_map", &id_maps.gid_map)?;
+    }
+
+    // Used by the child to detect whether the parent exited before the parent death signal was
+    // set: the read end only reaches EOF once the parent's write end is closed.
+    let (read_fd, write_fd) = nix::unistd::pipe2(nix::fcntl::OFlag::O_CLOEXEC | nix::fcntl::OFlag::O_NONBLOCK)?;
+
+    // SAFETY: the child only calls exec, and the parent only async-signal-safe functions.
+    match unsafe { fork() }? {
+        ForkResult::Child => {
+            drop(write_fd);
+            // SAFETY: prctl and read are async-signal-safe.
+            unsafe {
+                libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+                let mut buf = [0u8; 1];
+                if libc::read(read_fd.as_raw_fd(), buf.as_mut_ptr().cast(), 1) == 0 {
+                    libc::_exit(1);
+                }
+            }
+            Ok(())
+        },
+        ForkResult::Parent { child } => {
+            drop(read_fd);
+            // The parent never execs, so it would otherwise hold the pipe used by the caller to
+            // wait for exec open until the command exits.
+            close_fds_except(write_fd.as_raw_fd());
+            let code = match waitpid(child, None) {
+                Ok(WaitStatus::Exited(_, code)) => code,
+                Ok(WaitStatus::Signaled(_, signal, _)) => {
+                    // Terminate with the same signal so that it is reported to the caller.
+                    unsafe {
+                        libc::signal(signal as libc::c_int, libc::SIG_DFL);
+                        libc::raise(signal as libc::c_int);
+                    }
+                    128 + signal as i32
+                },
+                _ => 1,
+            };
+            unsafe { libc::_exit(code) }
+        },
+    }
+}
+
+/// Returns the first output redirection operator in `command` that occurs outside of quotes,
+/// e.g. `>`, `>>`, `2>`, or `&>`.
+///
+/// Duplicating or closing a file descriptor (e.g. `2>&1`, `>&2`, `>&-`), redirecting to
+/// `/dev/null`, and process substitution (`>(...)`) are not considered redirections since they do
+/// not write to a file.
+fn find_output_redirect(command: &str) -> Option<String> {
+    let chars = command.chars().collect::<Vec<_>>();
+    let mut in_single_quote = false;
+    let mut in_double_quote = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' if !in_single_quote => i += 1,
+            '\'' if !in_double_quote => in_single_quote = !in_single_quote,
+            '"' if !in_single_quote => in_double_quote = !in_double_quote,
+            '>' if !in_single_quote && !in_double_quote => {
+                let mut end = i + 1;
+                if chars.get(end) == Some(&'>') {
+                    end += 1;
+                }
+                let is_fd_dup =
+                    chars.get(end) == Some(&'&') && chars.get(end + 1).is_some_and(|c| c.is_ascii_digit() || *c == '-');
+                let is_process_substitution = end == i + 1 && chars.get(end) == Some(&'(');
+                if !is_fd_dup && !is_process_substitution && !is_dev_null_target(&chars[end..]) {
+                    let start = match i.checked_sub(1).map(|j| chars[j]) {
+                        Some(p) if p == '&' || p.is_ascii_digit() => i - 1,
+                        _ => i,
+                    };
+                    return Some(chars[start..end].iter().collect());
+                }
+                i = end;
+                continue;
+            },
+            _ => (),
+        }
+        i += 1;
     }
+    None
+}
+
+/// Whether the target of a redirection operator, given the characters following the operator, is
+/// `/dev/null`.
+fn is_dev_null_target(rest: &[char]) -> bool {
+    let target = rest
+        .iter()
+        .skip_while(|c| c.is_whitespace())
+        .take_while(|c| !c.is_whitespace() && !matches!(c, ';' | '|' | '&' | '(' | ')' | '<' | '>'))
+        .collect::<String>();
+    target == "/dev/null"
+}
+
+/// Programs that are commonly given input on stdin, used by [reads_stdin].
+const STDIN_PROGRAMS: &[&str] = &[
+    "awk", "base64", "bash", "bc", "cat", "dc", "grep", "head", "jq", "node", "openssl", "patch", "perl", "python",
+    "python3", "read", "ruby", "sed", "sh", "sort", "tail", "tee", "tr", "uniq", "wc", "xargs",
+];
+
+/// Heuristic for whether `command` reads from stdin: it contains a pipe, or runs one of
+/// [STDIN_PROGRAMS].
+fn reads_stdin(command: &str) -> bool {
+    command.contains('|')
+        || command
+            .split(|c: char| c.is_whitespace() || matches!(c, ';' | '&' | '(' | ')'))
+            .map(|word| word.rsplit('/').next().unwrap_or(word))
+            .any(|program| STDIN_PROGRAMS.contains(&program))
+}
+
+/// Truncates `stdout` and `stderr` so that their combined length is at most `max_bytes`,
+/// returning the number of bytes omitted. If both need to be truncated, each keeps at least half
+/// of `max_bytes`, so that errors are not hidden by a large stdout.
+fn truncate_output(stdout: &mut String, stderr: &mut String, max_bytes: usize) -> usize {
+    if stdout.len() + stderr.len() <= max_bytes {
+        return 0;
+    }
+    let stderr_bytes = stderr
+        .len()
+        .min((max_bytes / 2).max(max_bytes.saturating_sub(stdout.len())));
+    truncate_output_in_place(stdout, max_bytes - stderr_bytes) + truncate_output_in_place(stderr, stderr_bytes)
+}
+
+/// Creates the tool output for a stubbed command, matching the structure of a real execution.
+#[cfg(any(test, feature = "test-util"))]
+fn stubbed_output(stub: CommandStub) -> ToolExecutionOutput {
+    let result = serde_json::json!({
+        "exit_status": format!("exit status: {}", stub.exit_code),
+        "stdout": stub.output,
+        "stderr": "",
+    });
+    ToolExecutionOutput::new(vec![
+        ToolExecutionOutputItem::Json(result),
+        exit_code_item(stub.exit_code, 0),
+    ])
+}
+
+/// Returns the exit code of a command as a machine-readable item, appended after the command's
+/// output. Commands without an exit code, e.g. killed by a signal, report -1. `truncated_bytes` is
+/// the number of bytes of output omitted by [ExecuteCmd::max_output_bytes].
+fn exit_code_item(exit_code: i32, truncated_bytes: usize) -> ToolExecutionOutputItem {
+    ToolExecutionOutputItem::Json(serde_json::json!({
+        "exit_code": exit_code,
+        "success": exit_code == 0,
+        "truncated_bytes": truncated_bytes,
+    }))
 }
 
 /// Returns `true` if the character is from an invisible or control Unicode range
@@ -208,6 +1173,696 @@ fn env_vars_with_user_agent() -> HashMap<String, String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent::util::test::TestBase;
+
+    #[tokio::test]
+    async fn test_execute_command_stub() {
+        let test_base = TestBase::new()
+            .await
+            .with_command_stub("cargo test*", "stubbed output", 101);
+
+        let tool = ExecuteCmd {
+            command: "cargo test --workspace".to_string(),
+            ..Default::default()
+        };
+        let output = tool.execute(&test_base).await.unwrap();
+        let ToolExecutionOutputItem::Json(result) = &output.items[0] else {
+            panic!("expected json output");
+        };
+        assert_eq!(result["stdout"], "stubbed output");
+        assert_eq!(result["exit_status"], "exit status: 101");
+        assert!(matches!(
+            &output.items[1],
+            ToolExecutionOutputItem::Json(v) if *v == serde_json::json!({"exit_code": 101, "success": false, "truncated_bytes": 0})
+        ));
+
+        // Piped commands are matched against the whole pipeline.
+        let test_base = test_base.with_command_stub("cargo test | grep FAILED", "piped output", 1);
+        let tool = ExecuteCmd {
+            pipe_to: Some(Box::new(ExecuteCmd {
+                command: "grep FAILED".to_string(),
+                ..Default::default()
+            })),
+            command: "cargo test".to_string(),
+            ..Default::default()
+        };
+        let output = tool.execute(&test_base).await.unwrap();
+        let ToolExecutionOutputItem::Json(result) = &output.items[0] else {
+            panic!("expected json output");
+        };
+        assert_eq!(result["stdout"], "piped output");
+    }
+
+    #[test]
+    fn test_find_output_redirect() {
+        assert_eq!(find_output_redirect("echo hi > out.txt"), Some(">".to_string()));
+        assert_eq!(find_output_redirect("echo hi >>out.txt"), Some(">>".to_string()));
+        assert_eq!(find_output_redirect("cargo build 2> err.txt"), Some("2>".to_string()));
+        assert_eq!(find_output_redirect("cargo build &> all.txt"), Some("&>".to_string()));
+        assert_eq!(find_output_redirect("cat a | grep b 2>&1"), None);
+        assert_eq!(find_output_redirect("echo 'a > b'"), None);
+        assert_eq!(find_output_redirect("echo \"a >> b\" | wc -l"), None);
+        assert_eq!(find_output_redirect("echo a \\> b"), None);
+        assert_eq!(find_output_redirect("diff <(ls a) >(cat)"), None);
+        assert_eq!(find_output_redirect("echo \"it's\" > out"), Some(">".to_string()));
+        assert_eq!(find_output_redirect("make 2>/dev/null"), None);
+        assert_eq!(find_output_redirect("make > /dev/null 2>&1"), None);
+        assert_eq!(find_output_redirect("make &>/dev/null; echo done"), None);
+        assert_eq!(find_output_redirect("echo error >&2"), None);
+        assert_eq!(find_output_redirect("echo hi 1>&-"), None);
+        assert_eq!(find_output_redirect("echo hi >/dev/nullx"), Some(">".to_string()));
+        assert_eq!(find_output_redirect("echo hi >&out.txt"), Some(">".to_string()));
+        assert_eq!(
+            find_output_redirect("make 2>/dev/null > build.log"),
+            Some(">".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_rejects_redirects() {
+        let test_base = TestBase::new().await;
+        let tool = ExecuteCmd {
+            command: "echo hi > out.txt".to_string(),
+            ..Default::default()
+        };
+        let err = tool.validate(&test_base, None).await.unwrap_err();
+        assert_eq!(err, "output redirection '>' detected; use fsWrite instead");
+
+        // Every command in a pipeline is checked.
+        let mut tool = ExecuteCmd {
+            command: "echo hi".to_string(),
+            pipe_to: Some(Box::new(tool.clone())),
+            ..tool
+        };
+        assert!(tool.validate(&test_base, None).await.is_err());
+        tool.pipe_to.as_mut().unwrap().detect_redirects = Some(false);
+        tool.validate(&test_base, None).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn test_execute_resource_limits() {
+        let test_base = TestBase::new().await;
+        let limited = |command: String, limits: ExecuteCmdLimits| ExecuteCmd {
+            command,
+            resource_limits: Some(limits),
+            ..Default::default()
+        };
+
+        let tool = limited("echo hello".to_string(), ExecuteCmdLimits {
+            max_memory_mb: Some(512),
+            max_cpu_seconds: Some(10),
+            max_file_size_mb: Some(1),
+        });
+        let output = tool.execute(&test_base).await.unwrap();
+        let ToolExecutionOutputItem::Json(result) = &output.items[0] else {
+            panic!("expected json output");
+        };
+        assert_eq!(result["stdout"], "hello\n");
+
+        let tool = limited("while :; do :; done".to_string(), ExecuteCmdLimits {
+            max_cpu_seconds: Some(1),
+            ..Default::default()
+        });
+        let err = tool.execute(&test_base).await.unwrap_err();
+        assert_eq!(err.to_string(), "resource limit exceeded");
+
+        let out = test_base.join("out.bin");
+        let tool = limited(
+            format!("dd if=/dev/zero of={} bs=1M count=2", out.to_string_lossy()),
+            ExecuteCmdLimits {
+                max_file_size_mb: Some(1),
+                ..Default::default()
+            },
+        );
+        let err = tool.execute(&test_base).await.unwrap_err();
+        assert_eq!(err.to_string(), "resource limit exceeded");
+        assert!(std::fs::metadata(&out).unwrap().len() <= 1024 * 1024);
+
+        // Signals unrelated to the configured limits are reported as is.
+        let tool = limited("kill -9 $$".to_string(), ExecuteCmdLimits {
+            max_memory_mb: Some(512),
+            ..Default::default()
+        });
+        let output = tool.execute(&test_base).await.unwrap();
+        let ToolExecutionOutputItem::Json(result) = &output.items[0] else {
+            panic!("expected json output");
+        };
+        assert!(result["exit_status"].as_str().unwrap().contains("SIGKILL"));
+
+        let tool = limited("echo hello".to_string(), ExecuteCmdLimits {
+            max_memory_mb: Some(u64::MAX),
+            ..Default::default()
+        });
+        assert!(tool.validate(&test_base, None).await.is_err());
+    }
+
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn test_execute_isolate() {
+        let test_base = TestBase::new().await;
+        let tool = ExecuteCmd {
+            command: "echo $$; cat /proc/net/dev".to_string(),
+            isolate: Some(true),
+            ..Default::default()
+        };
+        let output = match tool.execute(&test_base).await {
+            Ok(output) => output,
+            Err(err) if err.to_string().contains("Operation not permitted") => {
+                eprintln!("skipping test, namespaces are not permitted: {}", err);
+                return;
+            },
+            Err(err) => panic!("{}", err),
+        };
+        let ToolExecutionOutputItem::Json(result) = &output.items[0] else {
+            panic!("expected json output");
+        };
+        let stdout = result["stdout"].as_str().unwrap();
+        let mut lines = stdout.lines();
+        assert_eq!(lines.next(), Some("1"), "the shell should be PID 1: {}", stdout);
+        // Only the loopback interface exists in a new network namespace.
+        let interfaces = lines
+            .skip(2)
+            .filter_map(|line| line.split(':').next())
+            .map(str::trim)
+            .collect::<Vec<_>>();
+        assert_eq!(interfaces, vec!["lo"]);
+    }
+
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn test_execute_isolate_timeout() {
+        let test_base = TestBase::new().await;
+        let marker = test_base.join("marker");
+        let tool = |command: String, timeout_secs: Option<u64>| ExecuteCmd {
+            command,
+            isolate: Some(true),
+            timeout_secs,
+            ..Default::default()
+        };
+
+        // The command runs as the current user rather than an unmapped one.
+        let output = match tool("id -u; id -g".to_string(), None).execute(&test_base).await {
+            Ok(output) => output,
+            Err(err) if err.to_string().contains("Operation not permitted") => {
+                eprintln!("skipping test, namespaces are not permitted: {}", err);
+                return;
+            },
+            Err(err) => panic!("{}", err),
+        };
+        let ToolExecutionOutputItem::Json(result) = &output.items[0] else {
+            panic!("expected json output");
+        };
+        assert_eq!(
+            result["stdout"],
+            format!("{}\n{}\n", nix::unistd::getuid(), nix::unistd::getgid())
+        );
+
+        // Killing the command on timeout kills every process in the namespace.
+        let command = format!("sleep 2 && touch {}", marker.display());
+        let err = tool(command, Some(1)).execute(&test_base).await.unwrap_err();
+        assert_eq!(err.to_string(), "Command timed out after 1 seconds");
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        assert!(!marker.exists(), "the command should have been killed");
+    }
+
+    #[test]
+    fn test_remove_sensitive_env_vars() {
+        let mut env_vars: HashMap<String, String> = [
+            ("GITHUB_TOKEN", "ghp_123"),
+            ("db_password", "hunter2"),
+            ("OPENAI_API_KEY", "sk-123"),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+            ("GOOGLE_APPLICATION_CREDENTIALS", "/creds.json"),
+            ("PATH", "/usr/bin"),
+            ("API_URL", "https://example.com"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let mut removed = remove_sensitive_env_vars(&mut env_vars);
+        removed.sort();
+
+        assert_eq!(removed, vec![
+            "AWS_SECRET_ACCESS_KEY",
+            "GITHUB_TOKEN",
+            "GOOGLE_APPLICATION_CREDENTIALS",
+            "OPENAI_API_KEY",
+            "db_password",
+        ]);
+        assert_eq!(env_vars.len(), 2);
+        assert_eq!(env_vars["PATH"], "/usr/bin");
+        assert_eq!(env_vars["API_URL"], "https://example.com");
+    }
+
+    #[tokio::test]
+    async fn test_execute_removes_sensitive_env() {
+        // Modifying the environment of this process would race with other tests reading it, so
+        // this test is rerun in a child process with the variable set instead.
+        if std::env::var_os("EXECUTE_CMD_TEST_TOKEN").is_none() {
+            let (_, module) = module_path!().split_once("::").unwrap();
+            let output = std::process::Command::new(std::env::current_exe().unwrap())
+                .args(["--exact", &format!("{}::test_execute_removes_sensitive_env", module)])
+                .env("EXECUTE_CMD_TEST_TOKEN", "abc123")
+                .output()
+                .unwrap();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(output.status.success() && stdout.contains("1 passed"), "{}", stdout);
+            return;
+        }
+
+        let test_base = &TestBase::new().await;
+        let run = |allow_sensitive_env: Option<bool>| async move {
+            let tool = ExecuteCmd {
+                command: "printf '%s' \"${EXECUTE_CMD_TEST_TOKEN-unset}\"".to_string(),
+                allow_sensitive_env,
+                ..Default::default()
+            };
+            let output = tool.execute(test_base).await.unwrap();
+            let ToolExecutionOutputItem::Json(result) = &output.items[0] else {
+                panic!("expected json output");
+            };
+            result["stdout"].as_str().unwrap().to_string()
+        };
+
+        assert_eq!(run(None).await, "unset");
+        assert_eq!(run(Some(true)).await, "abc123");
+    }
+
+    #[tokio::test]
+    async fn test_execute_pipe_to() {
+        let test_base = TestBase::new().await;
+        fn chain(commands: &[&str]) -> ExecuteCmd {
+            let (&first, rest) = commands.split_first().unwrap();
+            ExecuteCmd {
+                command: first.to_string(),
+                pipe_to: (!rest.is_empty()).then(|| Box::new(chain(rest))),
+                ..Default::default()
+            }
+        }
+        let json = |output: ToolExecutionOutput| {
+            let ToolExecutionOutputItem::Json(result) = &output.items[0] else {
+                panic!("expected json output");
+            };
+            result.clone()
+        };
+
+        let tool = chain(&["printf 'b\\na\\nb\\nc\\n'", "sort", "uniq -c", "wc -l"]);
+        tool.validate(&test_base, None).await.unwrap();
+        let result = json(tool.execute(&test_base).await.unwrap());
+        assert_eq!(result["stdout"].as_str().unwrap().trim(), "3");
+
+        // Only the output and exit status of the last command are returned.
+        let tool = chain(&["echo first; echo err >&2", "cat; echo last >&2; exit 3"]);
+        let output = tool.execute(&test_base).await.unwrap();
+        assert!(matches!(
+            &output.items[1],
+            ToolExecutionOutputItem::Json(v) if *v == serde_json::json!({"exit_code": 3, "success": false, "truncated_bytes": 0})
+        ));
+        let result = json(output);
+        assert_eq!(result["stdout"], "first\n");
+        assert_eq!(result["stderr"], "last\n");
+        assert!(result["exit_status"].as_str().unwrap().contains('3'), "{}", result);
+
+        // The next command exiting early doesn't hang the pipeline.
+        let tool = chain(&["yes", "head -n 2"]);
+        let result = json(tool.execute(&test_base).await.unwrap());
+        assert_eq!(result["stdout"], "y\ny\n");
+
+        chain(&["echo"; MAX_PIPE_DEPTH])
+            .validate(&test_base, None)
+            .await
+            .unwrap();
+        let err = chain(&["echo"; MAX_PIPE_DEPTH + 1])
+            .validate(&test_base, None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("at most 5 commands"), "{}", err);
+        assert!(chain(&["echo", ""]).validate(&test_base, None).await.is_err());
+        assert!(
+            chain(&["echo", "cat > out.txt"])
+                .validate(&test_base, None)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_timeout() {
+        let test_base = TestBase::new().await;
+        let with_timeout = |command: &str, timeout_secs: Option<u64>| ExecuteCmd {
+            command: command.to_string(),
+            timeout_secs,
+            ..Default::default()
+        };
+
+        let output = with_timeout("echo hello", Some(5)).execute(&test_base).await.unwrap();
+        let ToolExecutionOutputItem::Json(result) = &output.items[0] else {
+            panic!("expected json output");
+        };
+        assert_eq!(result["stdout"], "hello\n");
+
+        let start = std::time::Instant::now();
+        let err = with_timeout("sleep 10", Some(1)).execute(&test_base).await.unwrap_err();
+        assert_eq!(err.to_string(), "Command timed out after 1 seconds");
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+        let mut tool = with_timeout("yes", Some(1));
+        tool.pipe_to = Some(Box::new(with_timeout("cat > /dev/null", None)));
+        let err = tool.execute(&test_base).await.unwrap_err();
+        assert_eq!(err.to_string(), "Command timed out after 1 seconds");
+
+        // Processes started by the command are killed as well.
+        let marker = test_base.join("marker");
+        let command = format!("(sleep 2 && touch {}) & wait", marker.display());
+        let err = with_timeout(&command, Some(1)).execute(&test_base).await.unwrap_err();
+        assert_eq!(err.to_string(), "Command timed out after 1 seconds");
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        assert!(!marker.exists(), "the background process should have been killed");
+
+        assert!(
+            with_timeout("echo hello", Some(1))
+                .validate(&test_base, None)
+                .await
+                .is_ok()
+        );
+        assert_eq!(
+            with_timeout("echo hello", Some(0)).validate(&test_base, None).await,
+            Err("timeoutSecs must be greater than 0".to_string())
+        );
+    }
+
+    #[test]
+    fn test_with_settings_caps_timeout() {
+        let tool = |timeout_secs: Option<u64>| ExecuteCmd {
+            command: "echo hello".to_string(),
+            timeout_secs,
+            ..Default::default()
+        };
+        let settings = ExecuteCmdSettings {
+            timeout_secs: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(tool(None).with_settings(Some(&settings)).timeout_secs, Some(30));
+        assert_eq!(tool(Some(10)).with_settings(Some(&settings)).timeout_secs, Some(10));
+        assert_eq!(tool(Some(60)).with_settings(Some(&settings)).timeout_secs, Some(30));
+        assert_eq!(tool(Some(60)).with_settings(None).timeout_secs, Some(60));
+        assert_eq!(
+            tool(None)
+                .with_settings(Some(&ExecuteCmdSettings::default()))
+                .timeout_secs,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_working_dir() {
+        let test_base = TestBase::new().await.with_file(("sub/file.txt", "hello")).await;
+        let in_dir = |working_dir: &str| ExecuteCmd {
+            command: "pwd".to_string(),
+            working_dir: Some(working_dir.to_string()),
+            ..Default::default()
+        };
+
+        // Relative paths are resolved against the cwd of the provider.
+        let tool = in_dir("sub");
+        tool.validate(&test_base, None).await.unwrap();
+        let output = tool.execute(&test_base).await.unwrap();
+        let ToolExecutionOutputItem::Json(result) = &output.items[0] else {
+            panic!("expected json output");
+        };
+        let expected = test_base.join("sub").canonicalize().unwrap();
+        assert_eq!(result["stdout"].as_str().unwrap().trim(), expected.to_string_lossy());
+
+        let err = in_dir("missing").validate(&test_base, None).await.unwrap_err();
+        assert!(err.starts_with("Directory not found: "), "{}", err);
+        let err = in_dir("sub/file.txt").validate(&test_base, None).await.unwrap_err();
+        assert!(err.starts_with("Path is not a directory: "), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_execute_stdin() {
+        let test_base = TestBase::new().await;
+        let with_stdin = |command: &str, stdin: &str| ExecuteCmd {
+            command: command.to_string(),
+            stdin: Some(stdin.to_string()),
+            ..Default::default()
+        };
+        let stdout = |output: ToolExecutionOutput| {
+            let ToolExecutionOutputItem::Json(result) = &output.items[0] else {
+                panic!("expected json output");
+            };
+            result["stdout"].as_str().unwrap().to_string()
+        };
+
+        let tool = with_stdin("tr a-z A-Z", "hello\nworld\n");
+        tool.validate(&test_base, None).await.unwrap();
+        assert_eq!(stdout(tool.execute(&test_base).await.unwrap()), "HELLO\nWORLD\n");
+
+        // Commands that exit without reading stdin don't hang.
+        let tool = with_stdin("echo done", &"x".repeat(1024 * 1024));
+        assert_eq!(stdout(tool.execute(&test_base).await.unwrap()), "done\n");
+
+        // Only the first command of a pipeline reads the provided stdin.
+        let tool = ExecuteCmd {
+            pipe_to: Some(Box::new(with_stdin("wc -l", "ignored"))),
+            ..with_stdin("cat", "a\nb\n")
+        };
+        let err = tool.validate(&test_base, None).await.unwrap_err();
+        assert!(err.contains("first command in a pipeline"), "{}", err);
+        let tool = ExecuteCmd {
+            pipe_to: Some(Box::new(ExecuteCmd {
+                stdin: None,
+                ..with_stdin("wc -l", "")
+            })),
+            ..with_stdin("cat", "a\nb\n")
+        };
+        tool.validate(&test_base, None).await.unwrap();
+        assert_eq!(stdout(tool.execute(&test_base).await.unwrap()).trim(), "2");
+    }
+
+    #[tokio::test]
+    async fn test_execute_max_output_bytes() {
+        let test_base = TestBase::new().await;
+        let with_limit = |command: &str, max_output_bytes: Option<u64>| ExecuteCmd {
+            command: command.to_string(),
+            max_output_bytes,
+            ..Default::default()
+        };
+        let test_base = &test_base;
+        let run = |tool: ExecuteCmd| async move {
+            let output = tool.execute(test_base).await.unwrap();
+            let (ToolExecutionOutputItem::Json(result), ToolExecutionOutputItem::Json(exit)) =
+                (&output.items[0], &output.items[1])
+            else {
+                panic!("expected json output");
+            };
+            (
+                result["stdout"].as_str().unwrap().to_string(),
+                result["stderr"].as_str().unwrap().to_string(),
+                exit["truncated_bytes"].as_u64().unwrap(),
+            )
+        };
+
+        let (stdout, _, truncated) = run(with_limit("seq 1 5", Some(100))).await;
+        assert_eq!((stdout.as_str(), truncated), ("1\n2\n3\n4\n5\n", 0));
+
+        // Output is cut after a complete line.
+        let (stdout, _, truncated) = run(with_limit("seq 1 100000", None)).await;
+        assert!(stdout.len() <= DEFAULT_MAX_OUTPUT_BYTES as usize);
+        assert!(truncated > 0);
+        let (stdout, _, truncated) = run(with_limit("seq 1 1000", Some(100))).await;
+        assert!(stdout.len() <= 100, "{}", stdout);
+        let (kept, marker) = stdout.split_once("\n[").unwrap();
+        assert!(
+            kept.lines().enumerate().all(|(i, line)| line == (i + 1).to_string()),
+            "{}",
+            stdout
+        );
+        assert_eq!(marker, format!("output truncated: {} bytes omitted]", truncated));
+        assert_eq!(kept.len() as u64 + truncated, 3893);
+
+        // stderr keeps at least half of the limit.
+        let (stdout, stderr, _) = run(with_limit("seq 1 1000; echo error >&2", Some(100))).await;
+        assert_eq!(stderr, "error\n");
+        assert!(stdout.len() + stderr.len() <= 100);
+        let (stdout, stderr, _) = run(with_limit("seq 1 1000; seq 1 1000 >&2", Some(200))).await;
+        assert!(stdout.len() <= 100 && stderr.len() <= 100, "{} {}", stdout, stderr);
+
+        let err = with_limit("echo", Some(0)).validate(test_base, None).await.unwrap_err();
+        assert_eq!(err, "maxOutputBytes must be greater than 0");
+    }
+
+    #[tokio::test]
+    async fn test_stream_output() {
+        let stream = |input: &'static str, max_bytes: usize| async move {
+            let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+            let streamed = stream_output(Some(input.as_bytes()), &tx, max_bytes).await;
+            drop(tx);
+            let mut sent = String::new();
+            while let Some(Ok(ToolExecutionOutputItem::Text(text))) = rx.recv().await {
+                sent.push_str(&text);
+            }
+            (sent, streamed.sent, streamed.omitted)
+        };
+
+        assert_eq!(stream("1\n2\n3", 100).await, ("1\n2\n3".to_string(), 5, 0));
+        // Output is cut after the last complete line that fits.
+        assert_eq!(stream("1\n2\n3\n", 5).await, ("1\n2\n".to_string(), 4, 2));
+        assert_eq!(stream("long line\n1\n", 4).await, (String::new(), 0, 12));
+    }
+
+    #[tokio::test]
+    async fn test_execute_shell() {
+        let test_base = TestBase::new().await;
+        let with_shell = |shell: &str| ExecuteCmd {
+            command: "echo $0".to_string(),
+            shell: Some(shell.to_string()),
+            ..Default::default()
+        };
+
+        let tool = with_shell("sh");
+        tool.validate(&test_base, None).await.unwrap();
+        let output = tool.execute(&test_base).await.unwrap();
+        let ToolExecutionOutputItem::Json(result) = &output.items[0] else {
+            panic!("expected json output");
+        };
+        assert_eq!(result["stdout"], "sh\n");
+
+        let err = with_shell("python3").validate(&test_base, None).await.unwrap_err();
+        assert!(err.starts_with("Unsupported shell 'python3'"), "{}", err);
+        if which::which("fish").is_err() {
+            let err = with_shell("fish").validate(&test_base, None).await.unwrap_err();
+            assert!(
+                err.starts_with("Shell 'fish' was not found on PATH. Available shells: "),
+                "{}",
+                err
+            );
+            assert!(err.contains("sh"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_reads_stdin() {
+        for command in ["bc -l", "/usr/bin/python3 -", "cd src && cat", "echo a | wc -l"] {
+            assert!(reads_stdin(command), "{}", command);
+        }
+        for command in ["ls -la", "cargo build", "git status"] {
+            assert!(!reads_stdin(command), "{}", command);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_env() {
+        let test_base = TestBase::new().await;
+        let with_env = |env: &[(&str, &str)]| ExecuteCmd {
+            command: format!("echo \"$GREETING|$MY_API_TOKEN|${}\"", USER_AGENT_ENV_VAR),
+            env: Some(env.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect()),
+            ..Default::default()
+        };
+
+        // Explicit values are expanded, override the user agent, and are not removed.
+        let tool = with_env(&[
+            ("GREETING", "hello ${env:HOME}"),
+            ("MY_API_TOKEN", "secret"),
+            (USER_AGENT_ENV_VAR, "custom"),
+        ]);
+        tool.validate(&test_base, None).await.unwrap();
+        let output = tool.execute(&test_base).await.unwrap();
+        let ToolExecutionOutputItem::Json(result) = &output.items[0] else {
+            panic!("expected json output");
+        };
+        assert_eq!(
+            result["stdout"],
+            format!("hello {}|secret|custom\n", std::env::var("HOME").unwrap())
+        );
+
+        for name in ["", "A=B", "A\0B"] {
+            let err = with_env(&[(name, "value")])
+                .validate(&test_base, None)
+                .await
+                .unwrap_err();
+            assert!(err.starts_with("Invalid environment variable name"), "{}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_command_allowlist() {
+        let test_base = TestBase::new().await;
+        let settings = ExecuteCmdSettings {
+            command_allowlist: Some(vec!["cargo".to_string(), "git".to_string()]),
+            ..Default::default()
+        };
+
+        for command in ["cargo build", "git status"] {
+            let tool = ExecuteCmd {
+                command: command.to_string(),
+                ..Default::default()
+            };
+            assert!(
+                tool.validate(&test_base, Some(&settings)).await.is_ok(),
+                "{} should be allowed",
+                command
+            );
+        }
+
+        for command in ["cargo", "python main.py", "cargo build; rm -rf ~"] {
+            let tool = ExecuteCmd {
+                command: command.to_string(),
+                ..Default::default()
+            };
+            assert_eq!(
+                tool.validate(&test_base, Some(&settings)).await,
+                Err("command not in allow-list".to_string()),
+                "{} should be denied",
+                command
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_command_allowlist_rejects_env_and_shell() {
+        let test_base = TestBase::new().await;
+        let settings = ExecuteCmdSettings {
+            command_allowlist: Some(vec!["cargo".to_string()]),
+            ..Default::default()
+        };
+
+        for name in ["BASH_ENV", "LD_PRELOAD", "PATH"] {
+            let tool = ExecuteCmd {
+                command: "cargo build".to_string(),
+                env: Some(HashMap::from([(name.to_string(), "/tmp/payload".to_string())])),
+                ..Default::default()
+            };
+            assert_eq!(
+                tool.validate(&test_base, Some(&settings)).await,
+                Err("env cannot be set when a command allow-list is configured".to_string()),
+                "{} should be denied",
+                name
+            );
+        }
+
+        let tool = ExecuteCmd {
+            command: "cargo build".to_string(),
+            shell: Some("sh".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            tool.validate(&test_base, Some(&settings)).await,
+            Err("shell cannot be set when a command allow-list is configured".to_string())
+        );
+
+        // Both are allowed without an allow-list.
+        let tool = ExecuteCmd {
+            command: "cargo build".to_string(),
+            env: Some(HashMap::from([("PATH".to_string(), "/usr/bin".to_string())])),
+            shell: Some("sh".to_string()),
+            ..Default::default()
+        };
+        tool.validate(&test_base, Some(&ExecuteCmdSettings::default()))
+            .await
+            .unwrap();
+    }
 
     #[test]
     fn is_hidden_recognises_all_ranges() {
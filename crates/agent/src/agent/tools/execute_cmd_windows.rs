@@ -3,6 +3,7 @@
 
 use std::collections::HashMap;
 use std::process::Stdio;
+use std::time::Duration;
 
 use bstr::ByteSlice as _;
 use schemars::{
@@ -13,22 +14,37 @@ use serde::{
     Deserialize,
     Serialize,
 };
+use tokio::io::{
+    AsyncRead,
+    AsyncReadExt as _,
+    AsyncWriteExt as _,
+};
 use tokio::process::Command;
+use tracing::warn;
 
 use super::{
     BuiltInToolName,
     BuiltInToolTrait,
+    ToolExample,
     ToolExecutionError,
     ToolExecutionOutput,
     ToolExecutionOutputItem,
     ToolExecutionResult,
+    ToolLocale,
+    ToolOutputSender,
 };
+use crate::agent::agent_config::definitions::ExecuteCmdSettings;
 use crate::agent::util::consts::{
     USER_AGENT_APP_NAME,
     USER_AGENT_ENV_VAR,
     USER_AGENT_VERSION_KEY,
     USER_AGENT_VERSION_VALUE,
 };
+use crate::agent::util::path::canonicalize_path_sys;
+use crate::agent::util::providers::SystemProvider;
+#[cfg(any(test, feature = "test-util"))]
+use crate::agent::util::test::CommandStub;
+use crate::agent::util::truncate_output_in_place;
 
 const EXECUTE_CMD_TOOL_DESCRIPTION: &str = r#"
 A tool for executing PowerShell commands.
@@ -38,6 +54,11 @@ WHEN TO USE THIS TOOL:
 
 HOW TO USE:
 - Provide the command to execute
+- Optionally provide timeoutSecs to kill the command if it is still running after that many seconds
+- Optionally provide workingDir to run the command in a directory other than the current working directory, instead of using `cd`
+- Optionally provide env to set environment variables for the command, instead of exporting them in the command. Values can reference other environment variables with ${env:VAR_NAME}
+- Optionally provide stdin to write text to the standard input of the command, e.g. for `bc` or `python -`, instead of using `echo ... |` or a heredoc
+- Output is limited to maxOutputBytes (64KB by default). Longer output is cut at a line boundary and ends with `[output truncated: N bytes omitted]`, so prefer commands that filter their output, e.g. with `head` or `grep`
 
 FEATURES:
 
@@ -48,6 +69,48 @@ TIPS:
 - Use the fileRead and fileWrite tools for reading and modifying files
 "#;
 
+const EXECUTE_CMD_TOOL_DESCRIPTION_ZH_CN: &str = r#"
+用于执行 PowerShell 命令的工具。
+
+何时使用此工具：
+- 仅在其他可用工具都无法完成任务时作为最后手段使用
+
+使用方法：
+- 提供要执行的命令
+- 可选地提供 timeoutSecs，如果命令在该秒数后仍在运行，则终止该命令
+- 可选地提供 workingDir，在当前工作目录以外的目录中运行命令，而不是使用 `cd`
+- 可选地提供 env，为命令设置环境变量，而不是在命令中导出它们。值可以使用 ${env:VAR_NAME} 引用其他环境变量
+- 可选地提供 stdin，将文本写入命令的标准输入（例如用于 `bc` 或 `python -`），而不是使用 `echo ... |` 或 heredoc
+- 输出限制为 maxOutputBytes（默认 64KB）。更长的输出会在行边界处截断，并以 `[output truncated: N bytes omitted]` 结尾，因此请优先使用会过滤输出的命令，例如使用 `head` 或 `grep`
+
+限制：
+- 不会加载用户的 PowerShell 配置文件
+
+提示：
+- 读取和修改文件请使用 fileRead 和 fileWrite 工具
+"#;
+
+const EXECUTE_CMD_TOOL_DESCRIPTION_JA: &str = r#"
+PowerShell コマンドを実行するためのツールです。
+
+このツールを使用する場面:
+- 他に利用可能なツールでタスクを達成できない場合の最後の手段としてのみ使用します
+
+使い方:
+- 実行するコマンドを指定します
+- 必要に応じて timeoutSecs を指定し、その秒数が経過してもコマンドが実行中の場合は強制終了します
+- 必要に応じて workingDir を指定し、`cd` を使う代わりに現在の作業ディレクトリ以外のディレクトリでコマンドを実行します
+- 必要に応じて env を指定し、コマンド内でエクスポートする代わりにコマンドの環境変数を設定します。値では ${env:VAR_NAME} で他の環境変数を参照できます
+- 必要に応じて stdin を指定し、`echo ... |` やヒアドキュメントを使う代わりに、コマンドの標準入力にテキストを書き込みます (例: `bc` や `python -`)
+- 出力は maxOutputBytes (デフォルトは 64KB) に制限されます。それより長い出力は行の区切りで切り捨てられ、`[output truncated: N bytes omitted]` で終わるため、`head` や `grep` などで出力を絞り込むコマンドを優先してください
+
+制限事項:
+- ユーザーの PowerShell プロファイルは反映されません
+
+ヒント:
+- ファイルの読み取りと変更には fileRead ツールと fileWrite ツールを使用してください
+"#;
+
 const EXECUTE_CMD_SCHEMA: &str = r#"
 {
     "type": "object",
@@ -55,6 +118,31 @@ const EXECUTE_CMD_SCHEMA: &str = r#"
         "command": {
             "type": "string",
             "description": "Command to execute"
+        },
+        "timeoutSecs": {
+            "type": "integer",
+            "description": "Number of seconds to wait for the command to exit before killing it. Must be greater than 0.",
+            "minimum": 1
+        },
+        "workingDir": {
+            "type": "string",
+            "description": "Path to the directory to run the command in. Defaults to the current working directory. Must be an existing directory."
+        },
+        "env": {
+            "type": "object",
+            "description": "Environment variables to set for the command, overriding inherited values. Values can reference other environment variables with ${env:VAR_NAME}. Names must not be empty or contain '=' or null characters.",
+            "additionalProperties": {
+                "type": "string"
+            }
+        },
+        "stdin": {
+            "type": "string",
+            "description": "UTF-8 text to write to the standard input of the command. Standard input is closed after it is written."
+        },
+        "maxOutputBytes": {
+            "type": "integer",
+            "description": "Max number of bytes of stdout and stderr to return, combined. Output beyond this is omitted, keeping complete lines where possible. Defaults to 65536.",
+            "minimum": 1
         }
     },
     "required": [
@@ -72,36 +160,156 @@ impl BuiltInToolTrait for ExecuteCmd {
         EXECUTE_CMD_TOOL_DESCRIPTION.into()
     }
 
+    fn description_for_locale(locale: &str) -> std::borrow::Cow<'static, str> {
+        match ToolLocale::parse(locale) {
+            Some(ToolLocale::ZhCn) => EXECUTE_CMD_TOOL_DESCRIPTION_ZH_CN.into(),
+            Some(ToolLocale::Ja) => EXECUTE_CMD_TOOL_DESCRIPTION_JA.into(),
+            None => Self::description(),
+        }
+    }
+
     fn input_schema() -> std::borrow::Cow<'static, str> {
         EXECUTE_CMD_SCHEMA.into()
     }
+
+    fn constraints() -> Vec<String> {
+        vec![
+            "Do not run fork bombs or commands that recursively spawn processes without bound".to_string(),
+            "Do not run commands that delete or overwrite files outside of the user's workspace unless explicitly asked".to_string(),
+        ]
+    }
+
+    fn examples() -> Vec<ToolExample> {
+        vec![
+            ToolExample::new(
+                "Show the status of a git repository",
+                serde_json::json!({ "command": "git status" }),
+            ),
+            ToolExample::new(
+                "List the running processes named node",
+                serde_json::json!({ "command": "Get-Process -Name node" }),
+            ),
+        ]
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct ExecuteCmd {
     pub command: String,
+    /// Number of seconds to wait for the command to exit before killing it.
+    pub timeout_secs: Option<u64>,
+    /// Directory to run the command in. Defaults to the current working directory.
+    pub working_dir: Option<String>,
+    /// Environment variables to set for the command, overriding inherited values. `${env:VAR}`
+    /// references in the values are expanded.
+    pub env: Option<HashMap<String, String>>,
+    /// Text written to the stdin of the command, which is closed afterwards.
+    pub stdin: Option<String>,
+    /// Max number of bytes of output returned. Defaults to [DEFAULT_MAX_OUTPUT_BYTES].
+    pub max_output_bytes: Option<u64>,
 }
 
+/// Default for [ExecuteCmd::max_output_bytes].
+const DEFAULT_MAX_OUTPUT_BYTES: u64 = 64 * 1024;
+
 impl ExecuteCmd {
     pub fn tool_schema() -> serde_json::Value {
         let schema = schema_for!(Self);
         serde_json::to_value(schema).expect("creating tool schema should not fail")
     }
 
-    pub async fn validate(&self) -> Result<(), String> {
+    /// Applies the configured `settings` to the command, capping [Self::timeout_secs] at
+    /// [ExecuteCmdSettings::timeout_secs].
+    pub fn with_settings(mut self, settings: Option<&ExecuteCmdSettings>) -> Self {
+        if let Some(max) = settings.and_then(|s| s.timeout_secs) {
+            self.timeout_secs = Some(self.timeout_secs.map_or(max, |secs| secs.min(max)));
+        }
+        self
+    }
+
+    pub async fn validate<P: SystemProvider>(
+        &self,
+        provider: &P,
+        settings: Option<&ExecuteCmdSettings>,
+    ) -> Result<(), String> {
         if self.command.is_empty() {
-            Err("Command must not be empty".to_string())
-        } else {
-            Ok(())
+            return Err("Command must not be empty".to_string());
+        }
+        if settings.is_some_and(|s| !s.is_command_allowed(&self.command)) {
+            return Err("command not in allow-list".to_string());
+        }
+        if self.timeout_secs == Some(0) {
+            return Err("timeoutSecs must be greater than 0".to_string());
         }
+        if self.max_output_bytes == Some(0) {
+            return Err("maxOutputBytes must be greater than 0".to_string());
+        }
+        for name in self.env.iter().flat_map(|env| env.keys()) {
+            if name.is_empty() || name.contains(['=', '\0']) {
+                return Err(format!("Invalid environment variable name: {:?}", name));
+            }
+        }
+        if self.stdin.is_some() && !reads_stdin(&self.command) {
+            warn!(command = %self.command, "stdin was provided to a command that does not appear to read it");
+        }
+        if let Some(working_dir) = self.working_dir(provider)? {
+            match std::fs::metadata(&working_dir) {
+                Ok(metadata) if !metadata.is_dir() => {
+                    return Err(format!("Path is not a directory: {}", working_dir));
+                },
+                Ok(_) => (),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    return Err(format!("Directory not found: {}", working_dir));
+                },
+                Err(e) => {
+                    return Err(format!(
+                        "failed to check file metadata for path '{}': {}",
+                        working_dir, e
+                    ));
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the canonicalized [ExecuteCmd::working_dir], if set.
+    fn working_dir<P: SystemProvider>(&self, provider: &P) -> Result<Option<String>, String> {
+        self.working_dir
+            .as_ref()
+            .map(|dir| canonicalize_path_sys(dir, provider).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    /// Returns the command strings that are executed, in order.
+    pub fn commands(&self) -> Vec<&str> {
+        vec![self.command.as_str()]
     }
 
-    pub async fn execute(&self) -> ToolExecutionResult {
+    /// Same as [ExecuteCmd::execute]. Output is not sent until the command exits, since stdout
+    /// and stderr are combined into a single text item on Windows.
+    pub async fn execute_stream<P: SystemProvider>(
+        &self,
+        provider: &P,
+        _output: &ToolOutputSender,
+    ) -> ToolExecutionResult {
+        self.execute(provider).await
+    }
+
+    pub async fn execute<P: SystemProvider>(&self, provider: &P) -> ToolExecutionResult {
+        #[cfg(any(test, feature = "test-util"))]
+        if let Some(stub) = provider.command_stub(&self.command) {
+            return Ok(stubbed_output(stub));
+        }
+
         let shell = std::env::var("AMAZON_Q_CHAT_SHELL").unwrap_or("pwsh".to_string());
 
         let mut env_vars = HashMap::new();
         env_vars.insert(USER_AGENT_ENV_VAR.to_string(), USER_AGENT_APP_NAME.to_string());
         env_vars.insert(USER_AGENT_VERSION_KEY.to_string(), USER_AGENT_VERSION_VALUE.to_string());
+        if let Some(env) = &self.env {
+            env_vars.extend(env.clone());
+        }
         crate::agent::util::expand_env_vars(&mut env_vars);
 
         let mut cmd = Command::new(&shell);
@@ -110,18 +318,48 @@ impl ExecuteCmd {
             .arg("-Command")
             .arg(&self.command)
             .envs(env_vars)
-            .stdin(Stdio::null())
+            .stdin(if self.stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+            .stderr(Stdio::piped())
+            // Cancelling the execution drops the child.
+            .kill_on_drop(true);
+        if let Some(working_dir) = self.working_dir(provider)? {
+            cmd.current_dir(working_dir);
+        }
 
-        let output = cmd
-            .output()
-            .await
+        let mut child = cmd
+            .spawn()
             .map_err(|e| ToolExecutionError::Custom(format!("failed to execute command: {}", e)))?;
+        if let (Some(input), Some(mut stdin)) = (self.stdin.clone(), child.stdin.take()) {
+            // Fails with a broken pipe if the command exits without reading all of its input.
+            tokio::spawn(async move {
+                let _ = stdin.write_all(input.as_bytes()).await;
+            });
+        }
+        let stdout_task = tokio::spawn(read_to_end(child.stdout.take()));
+        let stderr_task = tokio::spawn(read_to_end(child.stderr.take()));
+        let status = match self.timeout_secs {
+            Some(secs) => match tokio::time::timeout(Duration::from_secs(secs), child.wait()).await {
+                Ok(status) => status,
+                Err(_) => {
+                    let _ = child.kill().await;
+                    return Err(ToolExecutionError::Custom(format!(
+                        "Command timed out after {} seconds",
+                        secs
+                    )));
+                },
+            },
+            None => child.wait().await,
+        }
+        .map_err(|e| ToolExecutionError::Custom(format!("failed to execute command: {}", e)))?;
 
-        let stdout = output.stdout.to_str_lossy().to_string();
-        let stderr = output.stderr.to_str_lossy().to_string();
-        let exit_code = output.status.code().unwrap_or(-1);
+        let stdout = stdout_task.await.unwrap_or_default().to_str_lossy().to_string();
+        let stderr = stderr_task.await.unwrap_or_default().to_str_lossy().to_string();
+        let exit_code = status.code().unwrap_or(-1);
 
         let mut result = String::new();
         if !stdout.is_empty() {
@@ -136,42 +374,116 @@ impl ExecuteCmd {
         if result.is_empty() {
             result = format!("Command exited with code {}", exit_code);
         }
+        let max_output_bytes = self.max_output_bytes.unwrap_or(DEFAULT_MAX_OUTPUT_BYTES) as usize;
+        let truncated_bytes = truncate_output_in_place(&mut result, max_output_bytes);
+
+        Ok(ToolExecutionOutput::new(vec![
+            ToolExecutionOutputItem::Text(result),
+            exit_code_item(exit_code, truncated_bytes),
+        ]))
+    }
+}
 
-        Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Text(result)]))
+/// Reads the piped output of a child process until EOF, returning whatever was read if the read
+/// fails.
+async fn read_to_end(reader: Option<impl AsyncRead + Unpin>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    if let Some(mut reader) = reader {
+        let _ = reader.read_to_end(&mut buf).await;
     }
+    buf
+}
+
+/// Programs and variables that are commonly used to read stdin, used by [reads_stdin].
+const STDIN_PROGRAMS: &[&str] = &[
+    "$input",
+    "findstr",
+    "more",
+    "node",
+    "openssl",
+    "python",
+    "python3",
+    "read-host",
+    "sort",
+];
+
+/// Heuristic for whether `command` reads from stdin: it contains a pipe, or uses one of
+/// [STDIN_PROGRAMS].
+fn reads_stdin(command: &str) -> bool {
+    command.contains('|')
+        || command
+            .split(|c: char| c.is_whitespace() || matches!(c, ';' | '&' | '(' | ')'))
+            .map(|word| word.rsplit(['/', '\\']).next().unwrap_or(word).to_lowercase())
+            .any(|program| STDIN_PROGRAMS.contains(&program.trim_end_matches(".exe")))
+}
+
+/// Creates the tool output for a stubbed command, matching the structure of a real execution.
+#[cfg(any(test, feature = "test-util"))]
+fn stubbed_output(stub: CommandStub) -> ToolExecutionOutput {
+    let result = if stub.output.is_empty() {
+        format!("Command exited with code {}", stub.exit_code)
+    } else {
+        stub.output
+    };
+    ToolExecutionOutput::new(vec![
+        ToolExecutionOutputItem::Text(result),
+        exit_code_item(stub.exit_code, 0),
+    ])
+}
+
+/// Returns the exit code of a command as a machine-readable item, appended after the command's
+/// output. Commands without an exit code, e.g. killed by a signal, report -1. `truncated_bytes` is
+/// the number of bytes of output omitted by [ExecuteCmd::max_output_bytes].
+fn exit_code_item(exit_code: i32, truncated_bytes: usize) -> ToolExecutionOutputItem {
+    ToolExecutionOutputItem::Json(serde_json::json!({
+        "exit_code": exit_code,
+        "success": exit_code == 0,
+        "truncated_bytes": truncated_bytes,
+    }))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent::util::test::TestBase;
 
     #[tokio::test]
     async fn test_execute_simple_command() {
+        let test_base = TestBase::new().await;
         let tool = ExecuteCmd {
             command: "echo 'hello world'".to_string(),
+            ..Default::default()
         };
 
-        assert!(tool.validate().await.is_ok());
-        let result = tool.execute().await.unwrap();
-        assert_eq!(result.items.len(), 1);
+        assert!(tool.validate(&test_base, None).await.is_ok());
+        let result = tool.execute(&test_base).await.unwrap();
+        assert_eq!(result.items.len(), 2);
     }
 
     #[tokio::test]
     async fn test_validate_empty_command() {
+        let test_base = TestBase::new().await;
         let tool = ExecuteCmd {
             command: String::new(),
+            ..Default::default()
         };
 
-        assert!(tool.validate().await.is_err());
+        assert!(tool.validate(&test_base, None).await.is_err());
     }
 
     #[tokio::test]
     async fn test_execute_with_exit_code() {
+        let test_base = TestBase::new().await;
         let tool = ExecuteCmd {
             command: "exit 42".to_string(),
+            ..Default::default()
         };
 
-        let result = tool.execute().await.unwrap();
-        assert_eq!(result.items.len(), 1);
+        let result = tool.execute(&test_base).await.unwrap();
+        assert_eq!(result.items.len(), 2);
+        assert!(matches!(
+            &result.items[1],
+            ToolExecutionOutputItem::Json(v) if *v == serde_json::json!({"exit_code": 42, "success": false, "truncated_bytes": 0})
+        ));
     }
 }
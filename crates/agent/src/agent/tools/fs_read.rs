@@ -1,6 +1,12 @@
-use std::path::PathBuf;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::sync::Arc;
 
+use bstr::ByteSlice as _;
 use futures::StreamExt;
+use regex::Regex;
 use schemars::{
     JsonSchema,
     schema_for,
@@ -9,12 +15,20 @@ use serde::{
     Deserialize,
     Serialize,
 };
+use sha2::{
+    Digest as _,
+    Sha256,
+};
 use tokio::fs;
 use tokio::io::{
     AsyncBufReadExt,
+    AsyncReadExt,
+    AsyncSeekExt,
     BufReader,
 };
+use tokio::task::JoinSet;
 use tokio_stream::wrappers::LinesStream;
+use tokio_util::sync::CancellationToken;
 
 use super::{
     BuiltInToolName,
@@ -23,11 +37,30 @@ use super::{
     ToolExecutionOutput,
     ToolExecutionOutputItem,
     ToolExecutionResult,
+    ToolLocale,
+};
+use crate::util::cloud_storage::{
+    self,
+    CloudStorageConfig,
+    CloudUri,
 };
 use crate::util::path::canonicalize_path_sys;
 use crate::util::providers::SystemProvider;
+use crate::util::truncate_safe_in_place;
 
 const MAX_READ_SIZE: u32 = 250 * 1024;
+const TRUNCATED_SUFFIX: &str = "...truncated";
+/// Appended to the content of files that were read to the end without being truncated.
+const END_OF_FILE_FOOTER: &str = "\n=== end of file ===";
+/// Number of bytes at the start of a file that are checked for null bytes to detect binary files.
+const BINARY_DETECTION_BYTES: u64 = 8 * 1024;
+/// Default max size of a binary file that is returned as a hex dump.
+const DEFAULT_MAX_HEX_DUMP_BYTES: u64 = 4 * 1024;
+/// Upper bound of [FsRead::max_hex_dump_bytes], since a hex dump is about four times the size of
+/// the file.
+const MAX_HEX_DUMP_BYTES: u64 = 32 * 1024;
+/// Number of bytes shown on each line of a hex dump.
+const HEX_DUMP_LINE_BYTES: usize = 16;
 
 const FS_READ_TOOL_DESCRIPTION: &str = r#"
 A tool for viewing file contents.
@@ -39,8 +72,15 @@ WHEN TO USE THIS TOOL:
 
 HOW TO USE:
 - Provide the path to the file you want to view
+- To read several files in one call, provide one entry in ops per file. The files are read in parallel, and each is returned with its own header
 - Optionally specify an offset to start reading from a specific line
 - Optionally specify a limit to control how many lines are read
+- Alternatively, specify start_line and/or end_line to read an inclusive, 1-indexed range of lines. The header then shows the range read, e.g. `=== path (lines 100-200 of 10000, M bytes) ===`
+- Optionally specify a git_object SHA of a commit to read the file as of that commit, or of a blob the file pointed to in its history
+- Optionally specify include_pattern and/or exclude_pattern regexes to only return matching lines
+- Set include_checksum to true to also return the SHA-256 digest and size of each file's raw bytes, e.g. to verify a file that was just written
+- The content of each file is preceded by a `=== path (N lines, M bytes) ===` header, and followed by a `=== end of file ===` footer if the file was read to the end. Set suppress_header to true to omit them
+- Provide an s3://bucket/key or gcs://bucket/key path to read an object from cloud storage, if supported by this build
 - Do not use this for directories, use the ls tool instead
 
 FEATURES:
@@ -49,13 +89,81 @@ FEATURES:
 
 LIMITATIONS:
 - Maximum file size is 250KB
-- Cannot display binary files or images
+- Binary files up to max_hex_dump_bytes (4KB by default, at most 32KB) are returned as a hex dump, larger binary files cannot be read. Use imageRead for images
 
 TIPS:
 - Read multiple files in one go if you know you want to read more than one file
 - Dont use limit and offset for small files
 "#;
 
+const FS_READ_TOOL_DESCRIPTION_ZH_CN: &str = r#"
+用于查看文件内容的工具。
+
+何时使用此工具：
+- 需要读取特定文件的内容时使用
+- 适用于查看源代码、配置文件或日志文件
+- 非常适合查看基于文本的文件格式
+
+使用方法：
+- 提供要查看的文件路径
+- 要在一次调用中读取多个文件，请在 ops 中为每个文件提供一项。这些文件会被并行读取，并且每个文件都带有各自的标题
+- 可选地指定 offset，从特定行开始读取
+- 可选地指定 limit，控制读取的行数
+- 或者，指定 start_line 和/或 end_line 以读取从 1 开始编号的闭区间行范围。此时标题会显示读取的范围，例如 `=== path (lines 100-200 of 10000, M bytes) ===`
+- 可选地指定 git_object，即某个提交的 SHA（读取该提交中的文件），或该文件历史中某个 blob 的 SHA
+- 可选地指定 include_pattern 和/或 exclude_pattern 正则表达式，仅返回匹配的行
+- 将 include_checksum 设置为 true 可同时返回每个文件原始字节的 SHA-256 摘要和大小，例如用于验证刚写入的文件
+- 每个文件的内容前有 `=== path (N lines, M bytes) ===` 标题；如果读取到文件末尾，内容后还有 `=== end of file ===` 结尾。将 suppress_header 设置为 true 可省略它们
+- 提供 s3://bucket/key 或 gcs://bucket/key 路径以从云存储读取对象（如果当前构建支持）
+- 不要用于目录，请改用 ls 工具
+
+功能：
+- 可以使用 offset 参数从文件的任意位置读取
+- 通过限制读取的行数来处理大文件
+
+限制：
+- 最大文件大小为 250KB
+- 不超过 max_hex_dump_bytes（默认 4KB，最大 32KB）的二进制文件以十六进制转储形式返回，更大的二进制文件无法读取。图像请使用 imageRead
+
+提示：
+- 如果需要读取多个文件，请一次性读取
+- 小文件不要使用 limit 和 offset
+"#;
+
+const FS_READ_TOOL_DESCRIPTION_JA: &str = r#"
+ファイルの内容を表示するためのツールです。
+
+このツールを使用する場面:
+- 特定のファイルの内容を読み取る必要がある場合に使用します
+- ソースコード、設定ファイル、ログファイルの確認に役立ちます
+- テキストベースのファイル形式の確認に最適です
+
+使い方:
+- 表示したいファイルのパスを指定します
+- 1 回の呼び出しで複数のファイルを読み取るには、ファイルごとに ops に 1 つずつ指定します。ファイルは並列に読み取られ、それぞれに個別のヘッダーが付きます
+- 必要に応じて offset を指定し、特定の行から読み取りを開始します
+- 必要に応じて limit を指定し、読み取る行数を制御します
+- または、start_line や end_line を指定して、1 始まりの行範囲 (両端を含む) を読み取ります。この場合、ヘッダーには `=== path (lines 100-200 of 10000, M bytes) ===` のように読み取った範囲が表示されます
+- 必要に応じて git_object にコミットの SHA（そのコミット時点のファイルを読み取る）、またはファイルの履歴に含まれる blob の SHA を指定します
+- 必要に応じて include_pattern や exclude_pattern の正規表現を指定し、一致する行のみを返します
+- include_checksum を true に設定すると、各ファイルの生のバイトの SHA-256 ダイジェストとサイズも返します。書き込んだ直後のファイルの検証などに使用します
+- 各ファイルの内容の前には `=== path (N lines, M bytes) ===` というヘッダーが付き、ファイルの末尾まで読み取った場合は後ろに `=== end of file ===` というフッターが付きます。これらを省略するには suppress_header を true に設定します
+- クラウドストレージからオブジェクトを読み取るには s3://bucket/key または gcs://bucket/key のパスを指定します（このビルドでサポートされている場合）
+- ディレクトリには使用せず、代わりに ls ツールを使用してください
+
+機能:
+- offset パラメーターを使用してファイルの任意の位置から読み取れます
+- 読み取る行数を制限することで大きなファイルを扱えます
+
+制限事項:
+- 最大ファイルサイズは 250KB です
+- max_hex_dump_bytes (デフォルトは 4KB、最大 32KB) 以下のバイナリファイルは 16 進ダンプとして返され、それより大きいバイナリファイルは読み取れません。画像には imageRead を使用してください
+
+ヒント:
+- 複数のファイルを読む場合は、一度にまとめて読み取ってください
+- 小さなファイルには limit と offset を使用しないでください
+"#;
+
 // TODO - migrate from JsonSchema, it's not very configurable and prone to breaking changes in the
 // generated structure.
 const FS_READ_SCHEMA: &str = "";
@@ -69,6 +177,14 @@ impl BuiltInToolTrait for FsRead {
         FS_READ_TOOL_DESCRIPTION.into()
     }
 
+    fn description_for_locale(locale: &str) -> std::borrow::Cow<'static, str> {
+        match ToolLocale::parse(locale) {
+            Some(ToolLocale::ZhCn) => FS_READ_TOOL_DESCRIPTION_ZH_CN.into(),
+            Some(ToolLocale::Ja) => FS_READ_TOOL_DESCRIPTION_JA.into(),
+            None => Self::description(),
+        }
+    }
+
     fn input_schema() -> std::borrow::Cow<'static, str> {
         FS_READ_SCHEMA.into()
     }
@@ -77,18 +193,84 @@ impl BuiltInToolTrait for FsRead {
 /// A tool for reading files
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FsRead {
+    /// Files to read. Multiple files are read in parallel.
     pub ops: Vec<FsReadOp>,
+    /// Maximum number of bytes to return across all files
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    /// Case-sensitive regex. If set, only lines matching the pattern are returned
+    #[serde(default)]
+    pub include_pattern: Option<String>,
+    /// Case-sensitive regex. If set, lines matching the pattern are not returned
+    #[serde(default)]
+    pub exclude_pattern: Option<String>,
+    /// Whether to omit the header and footer around the content of each file
+    #[serde(default)]
+    pub suppress_header: Option<bool>,
+    /// Binary files up to this many bytes are returned as a hex dump. Defaults to 4096, and is
+    /// capped at 32768
+    #[serde(default)]
+    pub max_hex_dump_bytes: Option<u64>,
+    /// Whether to return the SHA-256 digest and size of the raw bytes of each file
+    #[serde(default)]
+    pub include_checksum: Option<bool>,
 }
 
 impl FsRead {
+    /// Returns the paths accessed by the tool, including the git repositories read from.
+    pub fn paths(&self) -> Vec<&str> {
+        self.ops
+            .iter()
+            .flat_map(|op| std::iter::once(op.path.as_str()).chain(op.repo_path.as_deref()))
+            .collect()
+    }
+
     pub fn tool_schema() -> serde_json::Value {
         let schema = schema_for!(Self);
         serde_json::to_value(schema).expect("creating tool schema should not fail")
     }
 
+    fn line_filter(&self) -> Result<LineFilter, String> {
+        LineFilter::new(self.include_pattern.as_deref(), self.exclude_pattern.as_deref())
+    }
+
+    /// [FsRead::max_hex_dump_bytes], clamped to [MAX_HEX_DUMP_BYTES].
+    fn max_hex_dump_bytes(&self) -> u64 {
+        self.max_hex_dump_bytes
+            .unwrap_or(DEFAULT_MAX_HEX_DUMP_BYTES)
+            .min(MAX_HEX_DUMP_BYTES)
+    }
+
     pub async fn validate<P: SystemProvider>(&self, provider: &P) -> Result<(), String> {
         let mut errors = Vec::new();
+        if let Err(err) = self.line_filter() {
+            errors.push(err);
+        }
         for op in &self.ops {
+            if let Err(err) = op.check_line_range() {
+                errors.push(err);
+                continue;
+            }
+            match CloudUri::parse(&op.path) {
+                Ok(Some(_)) if !cloud_storage::CLOUD_STORAGE_ENABLED => {
+                    errors.push("Reading from cloud storage requires the cloud-storage feature".to_string());
+                    continue;
+                },
+                // Objects are checked for existence when fetched.
+                Ok(Some(_)) => continue,
+                Ok(None) => (),
+                Err(err) => {
+                    errors.push(err.to_string());
+                    continue;
+                },
+            }
+            if let Some(sha) = &op.git_object {
+                if sha.len() != 40 || !sha.chars().all(|c| c.is_ascii_hexdigit()) {
+                    errors.push(format!("'{}' is not a 40 character hex SHA", sha));
+                }
+                // The file is read from the git object store, so it doesn't need to exist.
+                continue;
+            }
             let path = PathBuf::from(canonicalize_path_sys(&op.path, provider).map_err(|e| e.to_string())?);
             if !path.exists() {
                 errors.push(format!("'{}' does not exist", path.to_string_lossy()));
@@ -104,6 +286,12 @@ impl FsRead {
             };
             if !file_md.is_file() {
                 errors.push(format!("'{}' is not a file", path.to_string_lossy()));
+                continue;
+            }
+            if op.has_line_range() {
+                if let Err(err) = op.validate_line_range(&path).await {
+                    errors.push(err);
+                }
             }
         }
         if !errors.is_empty() {
@@ -113,15 +301,156 @@ impl FsRead {
         }
     }
 
-    pub async fn execute<P: SystemProvider>(&self, provider: &P) -> ToolExecutionResult {
-        let mut results = Vec::new();
+    pub async fn execute<P: SystemProvider>(
+        &self,
+        provider: &P,
+        cancel_token: &CancellationToken,
+    ) -> ToolExecutionResult {
+        let filter = Arc::new(self.line_filter()?);
+        let mut join_set = JoinSet::new();
+        let mut results = (0..self.ops.len()).map(|_| None).collect::<Vec<_>>();
+        let cloud_config = Arc::new(CloudStorageConfig::from_env(provider));
+        for (i, op) in self.ops.iter().enumerate() {
+            match CloudUri::parse(&op.path) {
+                Ok(Some(uri)) => {
+                    let op = op.clone();
+                    let cloud_config = Arc::clone(&cloud_config);
+                    let filter = Arc::clone(&filter);
+                    let cancel_token = cancel_token.clone();
+                    let max_bytes = self.max_bytes;
+                    let include_checksum = self.include_checksum.unwrap_or(false);
+                    join_set.spawn(async move {
+                        let res = op
+                            .execute_cloud(
+                                &cloud_config,
+                                &uri,
+                                max_bytes,
+                                LineCollector::new(&filter, op.limit()),
+                                include_checksum,
+                                &cancel_token,
+                            )
+                            .await;
+                        (i, res)
+                    });
+                    continue;
+                },
+                Ok(None) => (),
+                Err(err) => {
+                    results[i] = Some(Err(ToolExecutionError::Custom(err.to_string())));
+                    continue;
+                },
+            }
+            let repo_path = op
+                .repo_path
+                .as_ref()
+                .map(|p| canonicalize_path_sys(p, provider))
+                .transpose();
+            match (canonicalize_path_sys(&op.path, provider), repo_path) {
+                (Ok(path), Ok(repo_path)) => {
+                    let op = op.clone();
+                    let filter = Arc::clone(&filter);
+                    let cancel_token = cancel_token.clone();
+                    let max_hex_dump_bytes = self.max_hex_dump_bytes();
+                    let include_checksum = self.include_checksum.unwrap_or(false);
+                    join_set.spawn(async move {
+                        let lines = LineCollector::new(&filter, op.limit());
+                        let res = match &op.git_object {
+                            Some(sha) => {
+                                op.execute_git_object(
+                                    sha,
+                                    Path::new(&path),
+                                    repo_path.as_deref(),
+                                    lines,
+                                    include_checksum,
+                                )
+                                .await
+                            },
+                            None => {
+                                op.execute(
+                                    PathBuf::from(path),
+                                    lines,
+                                    max_hex_dump_bytes,
+                                    include_checksum,
+                                    &cancel_token,
+                                )
+                                .await
+                            },
+                        };
+                        (i, res)
+                    });
+                },
+                (Err(err), _) | (_, Err(err)) => results[i] = Some(Err(ToolExecutionError::Custom(err.to_string()))),
+            }
+        }
+        while let Some(res) = join_set.join_next().await {
+            let (i, res) = res.map_err(|e| ToolExecutionError::Custom(format!("failed to read file: {}", e)))?;
+            results[i] = Some(res);
+        }
+        if cancel_token.is_cancelled() {
+            return Err(ToolExecutionError::cancelled());
+        }
+
+        let is_multiple = self.ops.len() > 1;
+        let mut remaining_bytes = self.max_bytes.map(|v| v as usize);
+        let mut items = Vec::new();
         let mut errors = Vec::new();
-        for op in &self.ops {
-            match op.execute(provider).await {
-                Ok(res) => results.push(res),
-                Err(err) => errors.push((op.clone(), err)),
+        let (mut original_lines, mut filtered_lines) = (0, 0);
+        for (op, res) in self.ops.iter().zip(results) {
+            match res.expect("all ops should have completed") {
+                Ok(FsReadOpOutput {
+                    mut content,
+                    original_lines: original,
+                    filtered_lines: filtered,
+                    total_lines,
+                    is_complete,
+                    metadata,
+                    checksum,
+                }) => {
+                    original_lines += original;
+                    filtered_lines += filtered;
+                    if !self.suppress_header.unwrap_or(false) {
+                        let lines = match total_lines {
+                            Some(total) => {
+                                let start = op.skip() + 1;
+                                format!("lines {}-{} of {}", start, (start + original).saturating_sub(1), total)
+                            },
+                            None => format!("{} {}", filtered, if filtered == 1 { "line" } else { "lines" }),
+                        };
+                        content = format!(
+                            "=== {} ({}, {} {}) ===\n{}",
+                            op.path,
+                            lines,
+                            content.len(),
+                            if content.len() == 1 { "byte" } else { "bytes" },
+                            content
+                        );
+                        if is_complete {
+                            content.push_str(END_OF_FILE_FOOTER);
+                        }
+                    } else if is_multiple {
+                        content = format!("==> {} <==\n{}", op.path, content);
+                    }
+                    if let Some(remaining) = remaining_bytes.as_mut() {
+                        truncate_safe_in_place(&mut content, *remaining, TRUNCATED_SUFFIX);
+                        *remaining -= content.len();
+                    }
+                    items.push(ToolExecutionOutputItem::Text(content));
+                    if let Some(metadata) = metadata {
+                        items.push(ToolExecutionOutputItem::Json(metadata));
+                    }
+                    if let Some(checksum) = checksum {
+                        items.push(ToolExecutionOutputItem::Json(checksum));
+                    }
+                },
+                Err(err) => errors.push((op, err)),
             }
         }
+        if filter.is_active() {
+            items.push(ToolExecutionOutputItem::Json(serde_json::json!({
+                "original_lines": original_lines,
+                "filtered_lines": filtered_lines,
+            })));
+        }
         if !errors.is_empty() {
             let err_msg = errors
                 .into_iter()
@@ -130,63 +459,514 @@ impl FsRead {
                 .join(",");
             Err(ToolExecutionError::Custom(err_msg))
         } else {
-            Ok(ToolExecutionOutput::new(results))
+            Ok(ToolExecutionOutput::new(items))
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FsReadOp {
-    /// Path to the file
+    /// Path to the file, or an `s3://bucket/key` or `gcs://bucket/key` URI to read an object from
+    /// cloud storage
     pub path: String,
     /// Number of lines to read
     pub limit: Option<u32>,
     /// Line offset from the start of the file to start reading from
     pub offset: Option<u32>,
+    /// 1-indexed line to start reading from, inclusive. Cannot be combined with `offset` or
+    /// `limit`
+    pub start_line: Option<u32>,
+    /// 1-indexed line to stop reading at, inclusive. Cannot be combined with `offset` or `limit`
+    pub end_line: Option<u32>,
+    /// 40 character hex SHA of a git commit to read `path` as of, instead of the file on disk, e.g.
+    /// to compare against a historical version of the file. The SHA of a blob is also accepted if
+    /// `path` pointed to it in a commit reachable from `HEAD`
+    pub git_object: Option<String>,
+    /// Root of the git repository containing `git_object`. Defaults to the repository containing
+    /// `path`
+    pub repo_path: Option<String>,
 }
 
 impl FsReadOp {
-    async fn execute<P: SystemProvider>(&self, provider: &P) -> Result<ToolExecutionOutputItem, ToolExecutionError> {
-        let path = PathBuf::from(
-            canonicalize_path_sys(&self.path, provider).map_err(|e| ToolExecutionError::Custom(e.to_string()))?,
-        );
+    fn has_line_range(&self) -> bool {
+        self.start_line.is_some() || self.end_line.is_some()
+    }
+
+    /// Number of lines to skip from the start of the file.
+    fn skip(&self) -> usize {
+        match self.start_line {
+            Some(start) => start.saturating_sub(1) as usize,
+            None => self.offset.unwrap_or_default() as usize,
+        }
+    }
+
+    /// Maximum number of lines to read, after [FsReadOp::skip].
+    fn limit(&self) -> Option<u32> {
+        match self.end_line {
+            Some(end) => Some((end + 1).saturating_sub(self.start_line.unwrap_or(1))),
+            None if self.start_line.is_some() => None,
+            None => self.limit,
+        }
+    }
+
+    /// Checks that [FsReadOp::start_line] and [FsReadOp::end_line] form a valid range.
+    fn check_line_range(&self) -> Result<(), String> {
+        if !self.has_line_range() {
+            return Ok(());
+        }
+        if self.offset.is_some() || self.limit.is_some() {
+            return Err("start_line and end_line cannot be combined with offset or limit".to_string());
+        }
+        if self.start_line == Some(0) || self.end_line == Some(0) {
+            return Err("start_line and end_line are 1-indexed and must be greater than 0".to_string());
+        }
+        if let (Some(start), Some(end)) = (self.start_line, self.end_line) {
+            if start > end {
+                return Err(format!(
+                    "start_line ({}) must not be greater than end_line ({})",
+                    start, end
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that [FsReadOp::start_line] and [FsReadOp::end_line] are within the file at
+    /// `path`.
+    async fn validate_line_range(&self, path: &Path) -> Result<(), String> {
+        let total = count_lines(path)
+            .await
+            .map_err(|e| format!("Failed to read '{}': {}", path.to_string_lossy(), e))?;
+        for (name, line) in [("start_line", self.start_line), ("end_line", self.end_line)] {
+            if let Some(line) = line.filter(|&line| line as usize > total) {
+                return Err(format!(
+                    "{} ({}) exceeds the number of lines in '{}' ({})",
+                    name,
+                    line,
+                    path.to_string_lossy(),
+                    total
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the file at `path`, the canonicalized form of [FsReadOp::path].
+    async fn execute(
+        &self,
+        path: PathBuf,
+        mut lines: LineCollector<'_>,
+        max_hex_dump_bytes: u64,
+        include_checksum: bool,
+        cancel_token: &CancellationToken,
+    ) -> Result<FsReadOpOutput, ToolExecutionError> {
+        let read_err = |e| ToolExecutionError::from_io_result("failed to read", Some(&path), e);
+        let mut file = fs::File::open(&path).await.map_err(read_err)?;
+        let mut head = Vec::new();
+        (&mut file)
+            .take(BINARY_DETECTION_BYTES)
+            .read_to_end(&mut head)
+            .await
+            .map_err(read_err)?;
+        if head.contains_str(b"\0") {
+            let len = file.metadata().await.map_err(read_err)?.len();
+            if len > max_hex_dump_bytes {
+                return Err(ToolExecutionError::Custom(format!(
+                    "File appears to be binary ({} bytes); use `imageRead` if it is an image, or a different tool otherwise.",
+                    len
+                )));
+            }
+            file.read_to_end(&mut head).await.map_err(read_err)?;
+            for line in hex_dump(&head).skip(self.skip()) {
+                if !lines.push(line) {
+                    break;
+                }
+            }
+            let mut output = lines.finish();
+            if self.has_line_range() {
+                output.total_lines = Some(head.len().div_ceil(HEX_DUMP_LINE_BYTES));
+            }
+            if include_checksum {
+                output.checksum = Some(checksum(&head));
+            }
+            return Ok(output);
+        }
+        file.rewind().await.map_err(read_err)?;
 
         // TODO: add line numbers
-        let file_lines = LinesStream::new(
-            BufReader::new(
-                fs::File::open(&path)
-                    .await
-                    .map_err(|e| ToolExecutionError::io(format!("failed to read {}", path.to_string_lossy()), e))?,
-            )
-            .lines(),
-        );
-        let mut file_lines = file_lines
-            .enumerate()
-            .skip(self.offset.unwrap_or_default() as usize)
-            .take(self.limit.unwrap_or(u32::MAX) as usize);
-
-        let mut is_truncated = false;
-        let mut content = Vec::new();
-        while let Some((i, line)) = file_lines.next().await {
+        let file_lines = LinesStream::new(BufReader::new(file).lines());
+        let mut file_lines = file_lines.enumerate().skip(self.skip());
+
+        // When reading a line range, the rest of the file is read to count its lines.
+        let mut is_reading = true;
+        let mut total_lines = self.skip();
+        loop {
+            let next = tokio::select! {
+                biased;
+                _ = cancel_token.cancelled() => return Err(ToolExecutionError::cancelled()),
+                next = file_lines.next() => next,
+            };
+            let Some((i, line)) = next else {
+                break;
+            };
+            total_lines = i + 1;
             match line {
+                Ok(_) if !is_reading => (),
                 Ok(l) => {
-                    if content.len() as u32 > MAX_READ_SIZE {
-                        is_truncated = true;
-                        break;
+                    if !lines.push(l) {
+                        if !self.has_line_range() {
+                            break;
+                        }
+                        is_reading = false;
                     }
-                    content.push(l);
                 },
+                Err(_) if !is_reading => (),
                 Err(err) => {
-                    return Err(ToolExecutionError::io(format!("Failed to read line {}", i + 1,), err));
+                    return Err(ToolExecutionError::from_io_result(
+                        format!("Failed to read line {}", i + 1,),
+                        Some(&path),
+                        err,
+                    ));
                 },
             }
         }
 
-        let mut content = content.join("\n");
-        if is_truncated {
-            content.push_str("...truncated");
+        let mut output = lines.finish();
+        if self.has_line_range() {
+            output.total_lines = Some(total_lines);
+        }
+        if include_checksum {
+            // Lines are decoded as they are streamed, so the raw bytes are read separately.
+            output.checksum = Some(file_checksum(&path).await.map_err(read_err)?);
+        }
+        Ok(output)
+    }
+
+    /// Reads `path` as of the git object `sha` from the repository at `repo_path`, or the
+    /// repository containing `path` if not provided. See [read_git_blob].
+    async fn execute_git_object(
+        &self,
+        sha: &str,
+        path: &Path,
+        repo_path: Option<&str>,
+        mut lines: LineCollector<'_>,
+        include_checksum: bool,
+    ) -> Result<FsReadOpOutput, ToolExecutionError> {
+        // The file may no longer exist on disk, so find the closest existing directory to search
+        // for the repository from.
+        let repo_dir = match repo_path {
+            Some(repo_path) => PathBuf::from(repo_path),
+            None => path
+                .ancestors()
+                .skip(1)
+                .find(|p| p.is_dir())
+                .unwrap_or(path)
+                .to_path_buf(),
+        };
+        let oid = git2::Oid::from_str(sha).map_err(|e| ToolExecutionError::Custom(e.message().to_string()))?;
+        let path_buf = path.to_path_buf();
+        let content = tokio::task::spawn_blocking(move || read_git_blob(&repo_dir, &path_buf, oid))
+            .await
+            .map_err(|e| ToolExecutionError::Custom(format!("failed to read git object {}: {}", sha, e)))?
+            .map_err(|e| ToolExecutionError::Custom(format!("failed to read git object {}: {}", sha, e.message())))?;
+        let checksum = include_checksum.then(|| checksum(&content));
+        let blob = String::from_utf8(content)
+            .map_err(|e| ToolExecutionError::Custom(format!("git object {} is not valid UTF-8: {}", sha, e)))?;
+
+        for line in blob.lines().skip(self.skip()) {
+            if !lines.push(line.to_string()) {
+                break;
+            }
+        }
+        let mut output = lines.finish();
+        if self.has_line_range() {
+            output.total_lines = Some(blob.lines().count());
+        }
+        output.checksum = checksum;
+        Ok(output)
+    }
+
+    /// Reads the object at `uri` from cloud storage, downloading at most `max_bytes`.
+    async fn execute_cloud(
+        &self,
+        config: &CloudStorageConfig,
+        uri: &CloudUri,
+        max_bytes: Option<u64>,
+        mut lines: LineCollector<'_>,
+        include_checksum: bool,
+        cancel_token: &CancellationToken,
+    ) -> Result<FsReadOpOutput, ToolExecutionError> {
+        let object = tokio::select! {
+            biased;
+            _ = cancel_token.cancelled() => return Err(ToolExecutionError::cancelled()),
+            object = cloud_storage::get_object(config, uri, max_bytes) => object,
+        }
+        .map_err(|e| ToolExecutionError::Custom(e.to_string()))?;
+        let content = String::from_utf8_lossy(&object.content);
+        for line in content.lines().skip(self.skip()) {
+            if !lines.push(line.to_string()) {
+                break;
+            }
+        }
+        let mut output = lines.finish();
+        if self.has_line_range() {
+            output.total_lines = Some(content.lines().count());
+        }
+        output.metadata = Some(uri.metadata(object.etag.as_deref()));
+        if include_checksum {
+            output.checksum = Some(checksum(&object.content));
+        }
+        Ok(output)
+    }
+}
+
+/// Size of the buffer used to read files in chunks, for [file_checksum] and [count_lines].
+const READ_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Returns the SHA-256 hex digest and size of `bytes`, as returned by
+/// [FsRead::include_checksum].
+fn checksum(bytes: &[u8]) -> serde_json::Value {
+    checksum_json(Sha256::new_with_prefix(bytes), bytes.len() as u64)
+}
+
+/// Reads the content of `path` as of `oid` from the repository containing `repo_dir`.
+///
+/// `oid` is either a commit or tree, in which case the blob is resolved through the tree entry
+/// for `path`, or a blob, which must be the entry for `path` in a commit reachable from `HEAD`.
+/// Either way, only versions of `path` can be read, and not arbitrary objects in the repository.
+fn read_git_blob(repo_dir: &Path, path: &Path, oid: git2::Oid) -> Result<Vec<u8>, git2::Error> {
+    let repo = git2::Repository::discover(repo_dir)?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| git2::Error::from_str("cannot read files from a bare repository"))?;
+    // The path is canonicalized, so the working directory must be too for it to be a prefix.
+    let workdir = std::fs::canonicalize(workdir).map_err(|e| git2::Error::from_str(&e.to_string()))?;
+    let relative = path
+        .strip_prefix(&workdir)
+        .map_err(|e| git2::Error::from_str(&format!("'{}' is not in the repository: {}", path.to_string_lossy(), e)))?;
+
+    let object = repo.find_object(oid, None)?;
+    let blob = match object.kind() {
+        Some(git2::ObjectType::Blob) => {
+            let mut revwalk = repo.revwalk()?;
+            revwalk.push_head()?;
+            let mut found = false;
+            for commit in revwalk {
+                let tree = repo.find_commit(commit?)?.tree()?;
+                if tree.get_path(relative).is_ok_and(|entry| entry.id() == oid) {
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                return Err(git2::Error::from_str(&format!(
+                    "the blob is not a version of '{}'",
+                    relative.to_string_lossy()
+                )));
+            }
+            object.peel_to_blob()?
+        },
+        _ => object
+            .peel_to_tree()?
+            .get_path(relative)?
+            .to_object(&repo)?
+            .peel_to_blob()?,
+    };
+    Ok(blob.content().to_vec())
+}
+
+/// Same as [checksum] for the file at `path`, hashing it chunk by chunk rather than reading it
+/// into memory.
+async fn file_checksum(path: &Path) -> std::io::Result<serde_json::Value> {
+    let mut reader = BufReader::with_capacity(READ_CHUNK_BYTES, fs::File::open(path).await?);
+    let mut hasher = Sha256::new();
+    let mut size = 0;
+    loop {
+        let chunk = reader.fill_buf().await?;
+        if chunk.is_empty() {
+            break;
+        }
+        hasher.update(chunk);
+        let len = chunk.len();
+        size += len as u64;
+        reader.consume(len);
+    }
+    Ok(checksum_json(hasher, size))
+}
+
+/// Formats the digest of `hasher` along with the `size` of the hashed bytes.
+fn checksum_json(hasher: Sha256, size: u64) -> serde_json::Value {
+    let sha256 = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    serde_json::json!({
+        "sha256": sha256,
+        "size_bytes": size,
+    })
+}
+
+/// Returns the number of lines in the file at `path`, counted the same way as [str::lines]
+/// without reading the file into memory.
+async fn count_lines(path: &Path) -> std::io::Result<usize> {
+    let mut reader = BufReader::with_capacity(READ_CHUNK_BYTES, fs::File::open(path).await?);
+    let mut count = 0;
+    let mut last = None;
+    loop {
+        let chunk = reader.fill_buf().await?;
+        let Some(&byte) = chunk.last() else {
+            break;
+        };
+        count += chunk.iter().filter(|&&b| b == b'\n').count();
+        last = Some(byte);
+        let len = chunk.len();
+        reader.consume(len);
+    }
+    // The last line is only terminated by the end of the file.
+    if last.is_some_and(|b| b != b'\n') {
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// The content read by an [FsReadOp].
+#[derive(Debug)]
+struct FsReadOpOutput {
+    content: String,
+    /// Number of lines read before filtering by [FsRead::include_pattern] and
+    /// [FsRead::exclude_pattern]
+    original_lines: usize,
+    /// Number of lines kept after filtering
+    filtered_lines: usize,
+    /// Number of lines in the file, only counted when reading a line range
+    total_lines: Option<usize>,
+    /// See [checksum]
+    checksum: Option<serde_json::Value>,
+    /// Whether the content was read to the end of the file, without being truncated or limited by
+    /// [FsReadOp::limit]
+    is_complete: bool,
+    /// Where the content was read from, for objects in cloud storage
+    metadata: Option<serde_json::Value>,
+}
+
+/// The include and exclude patterns of an [FsRead].
+#[derive(Debug, Default)]
+struct LineFilter {
+    include: Option<Regex>,
+    exclude: Option<Regex>,
+}
+
+impl LineFilter {
+    fn new(include: Option<&str>, exclude: Option<&str>) -> Result<Self, String> {
+        let compile = |pattern: Option<&str>| {
+            pattern
+                .map(|p| Regex::new(p).map_err(|e| format!("Invalid regex pattern '{}': {}", p, e)))
+                .transpose()
+        };
+        Ok(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    fn is_active(&self) -> bool {
+        self.include.is_some() || self.exclude.is_some()
+    }
+
+    fn matches(&self, line: &str) -> bool {
+        self.include.as_ref().is_none_or(|r| r.is_match(line))
+            && !self.exclude.as_ref().is_some_and(|r| r.is_match(line))
+    }
+}
+
+/// Formats `bytes` like `xxd`, e.g. `00000000: 7f45 4c46 0201 0100 0000 0000 0000 0000
+/// .ELF............`
+fn hex_dump(bytes: &[u8]) -> impl Iterator<Item = String> + '_ {
+    bytes.chunks(HEX_DUMP_LINE_BYTES).enumerate().map(|(i, chunk)| {
+        let hex = chunk
+            .chunks(2)
+            .map(|pair| pair.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let ascii = chunk
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>();
+        // Pads the hex column of the last line so the ascii column stays aligned.
+        let width = HEX_DUMP_LINE_BYTES * 2 + HEX_DUMP_LINE_BYTES / 2 - 1;
+        format!(
+            "{:08x}: {:<width$}  {}",
+            i * HEX_DUMP_LINE_BYTES,
+            hex,
+            ascii,
+            width = width
+        )
+    })
+}
+
+/// Accumulates the lines read by an [FsReadOp], applying the [LineFilter], stopping after
+/// [FsReadOp::limit] lines, and truncating after [MAX_READ_SIZE] lines.
+struct LineCollector<'a> {
+    filter: &'a LineFilter,
+    limit: Option<u32>,
+    content: Vec<String>,
+    original_lines: usize,
+    is_limited: bool,
+    is_truncated: bool,
+}
+
+impl<'a> LineCollector<'a> {
+    fn new(filter: &'a LineFilter, limit: Option<u32>) -> Self {
+        Self {
+            filter,
+            limit,
+            content: Vec::new(),
+            original_lines: 0,
+            is_limited: false,
+            is_truncated: false,
+        }
+    }
+
+    /// Adds the next line, returning `false` once no more lines should be read.
+    fn push(&mut self, line: String) -> bool {
+        if self.limit.is_some_and(|limit| self.original_lines >= limit as usize) {
+            self.is_limited = true;
+            return false;
+        }
+        self.original_lines += 1;
+        if !self.filter.matches(&line) {
+            return true;
+        }
+        if self.content.len() as u32 > MAX_READ_SIZE {
+            self.is_truncated = true;
+            return false;
+        }
+        self.content.push(line);
+        true
+    }
+
+    fn finish(self) -> FsReadOpOutput {
+        let filtered_lines = self.content.len();
+        let mut content = self.content.join("\n");
+        if self.is_truncated {
+            content.push_str(TRUNCATED_SUFFIX);
+        }
+        FsReadOpOutput {
+            content,
+            original_lines: self.original_lines,
+            filtered_lines,
+            total_lines: None,
+            checksum: None,
+            is_complete: !self.is_limited && !self.is_truncated,
+            metadata: None,
         }
-        Ok(ToolExecutionOutputItem::Text(content))
     }
 }
 
@@ -210,17 +990,207 @@ mod tests {
                 path: test_base.join("test.txt").to_string_lossy().to_string(),
                 limit: None,
                 offset: None,
+                start_line: None,
+                end_line: None,
+                git_object: None,
+                repo_path: None,
             }],
+            max_bytes: None,
+            include_pattern: None,
+            exclude_pattern: None,
+            suppress_header: None,
+            max_hex_dump_bytes: None,
+            include_checksum: None,
         };
 
         assert!(tool.validate(&test_base).await.is_ok());
-        let result = tool.execute(&test_base).await.unwrap();
+        let result = tool.execute(&test_base, &CancellationToken::new()).await.unwrap();
         assert_eq!(result.items.len(), 1);
-        if let ToolExecutionOutputItem::Text(content) = &result.items[0] {
-            assert_eq!(content, "line1\nline2\nline3");
+        let expected = format!(
+            "=== {} (3 lines, 17 bytes) ===\nline1\nline2\nline3\n=== end of file ===",
+            test_base.join("test.txt").to_string_lossy()
+        );
+        assert_eq!(result.all_text(), expected);
+    }
+
+    fn read_op(test_base: &TestBase, path: &str) -> FsReadOp {
+        FsReadOp {
+            path: test_base.join(path).to_string_lossy().to_string(),
+            limit: None,
+            offset: None,
+            start_line: None,
+            end_line: None,
+            git_object: None,
+            repo_path: None,
         }
     }
 
+    #[tokio::test]
+    async fn test_fs_read_parallel_reads() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("a.txt", "a1\na2"))
+            .await
+            .with_file(("b.txt", "b1"))
+            .await
+            .with_file(("c.txt", "c1\nc2\nc3"))
+            .await;
+
+        let tool = FsRead {
+            ops: ["a.txt", "b.txt", "c.txt"]
+                .iter()
+                .map(|p| read_op(&test_base, p))
+                .collect(),
+            max_bytes: None,
+            include_pattern: None,
+            exclude_pattern: None,
+            suppress_header: None,
+            max_hex_dump_bytes: None,
+            include_checksum: None,
+        };
+        assert!(tool.validate(&test_base).await.is_ok());
+        let result = tool.execute(&test_base, &CancellationToken::new()).await.unwrap();
+        assert_eq!(result.items.len(), 3);
+        for (item, (path, content)) in
+            result
+                .items
+                .iter()
+                .zip([("a.txt", "a1\na2"), ("b.txt", "b1"), ("c.txt", "c1\nc2\nc3")])
+        {
+            let ToolExecutionOutputItem::Text(text) = item else {
+                panic!("expected text output");
+            };
+            let expected = format!(
+                "=== {} ({} {}, {} bytes) ===\n{}\n=== end of file ===",
+                test_base.join(path).to_string_lossy(),
+                content.lines().count(),
+                if content.lines().count() == 1 { "line" } else { "lines" },
+                content.len(),
+                content
+            );
+            assert_eq!(text, &expected);
+        }
+
+        // max_bytes applies to the aggregate output.
+        let total_bytes = |output: &ToolExecutionOutput| {
+            output
+                .items
+                .iter()
+                .map(|item| match item {
+                    ToolExecutionOutputItem::Text(text) => text.len() as u64,
+                    _ => 0,
+                })
+                .sum::<u64>()
+        };
+        let max_bytes = total_bytes(&result) - 4;
+        let tool = FsRead {
+            max_bytes: Some(max_bytes),
+            ..tool
+        };
+        let result = tool.execute(&test_base, &CancellationToken::new()).await.unwrap();
+        assert_eq!(result.items.len(), 3);
+        assert_eq!(total_bytes(&result), max_bytes);
+        assert!(result.all_text().ends_with(TRUNCATED_SUFFIX));
+    }
+
+    #[tokio::test]
+    async fn test_fs_read_git_object() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("repo/file.txt", "old1\nold2\nold3"))
+            .await
+            .with_file(("repo/other.txt", "other"))
+            .await;
+        let repo = test_base.join("repo");
+        let git_repo = git2::Repository::init(&repo).unwrap();
+        let mut index = git_repo.index().unwrap();
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+        let tree = git_repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("test", "test@example.com").unwrap();
+        let commit = git_repo
+            .commit(Some("HEAD"), &signature, &signature, "initial", &tree, &[])
+            .unwrap()
+            .to_string();
+        let blob = tree.get_path(Path::new("file.txt")).unwrap().id().to_string();
+        let other_blob = tree.get_path(Path::new("other.txt")).unwrap().id().to_string();
+        tokio::fs::write(repo.join("file.txt"), "new").await.unwrap();
+
+        let read = |path: &str, git_object: &str, repo_path: Option<String>| FsRead {
+            ops: vec![FsReadOp {
+                path: test_base.join(path).to_string_lossy().to_string(),
+                limit: Some(2),
+                offset: Some(1),
+                start_line: None,
+                end_line: None,
+                git_object: Some(git_object.to_string()),
+                repo_path,
+            }],
+            max_bytes: None,
+            include_pattern: None,
+            exclude_pattern: None,
+            suppress_header: Some(true),
+            max_hex_dump_bytes: None,
+            include_checksum: None,
+        };
+
+        for sha in [&commit, &blob] {
+            let tool = read("repo/file.txt", sha, None);
+            tool.validate(&test_base).await.unwrap();
+            let result = tool.execute(&test_base, &CancellationToken::new()).await.unwrap();
+            assert!(matches!(&result.items[0], ToolExecutionOutputItem::Text(t) if t == "old2\nold3"));
+        }
+
+        // Only versions of the path can be read, not arbitrary objects in the repository.
+        let tool = read("repo/file.txt", &other_blob, None);
+        let err = tool.execute(&test_base, &CancellationToken::new()).await.unwrap_err();
+        assert!(err.to_string().contains("not a version of"), "{}", err);
+        let tool = read("outside.txt", &commit, Some(repo.to_string_lossy().to_string()));
+        assert!(tool.execute(&test_base, &CancellationToken::new()).await.is_err());
+
+        // Files that no longer exist can be read, with the repository given explicitly.
+        tokio::fs::remove_file(repo.join("file.txt")).await.unwrap();
+        let tool = read("repo/file.txt", &commit, Some(repo.to_string_lossy().to_string()));
+        tool.validate(&test_base).await.unwrap();
+        let result = tool.execute(&test_base, &CancellationToken::new()).await.unwrap();
+        assert!(matches!(&result.items[0], ToolExecutionOutputItem::Text(t) if t == "old2\nold3"));
+
+        assert!(
+            read("repo/file.txt", "abc123", None)
+                .validate(&test_base)
+                .await
+                .is_err()
+        );
+        let missing = read("repo/file.txt", &"0".repeat(40), None);
+        assert!(missing.execute(&test_base, &CancellationToken::new()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fs_read_cancelled() {
+        let test_base = TestBase::new().await.with_file(("test.txt", "line1\nline2")).await;
+        let tool = FsRead {
+            ops: vec![FsReadOp {
+                path: test_base.join("test.txt").to_string_lossy().to_string(),
+                limit: None,
+                offset: None,
+                start_line: None,
+                end_line: None,
+                git_object: None,
+                repo_path: None,
+            }],
+            max_bytes: None,
+            include_pattern: None,
+            exclude_pattern: None,
+            suppress_header: Some(true),
+            max_hex_dump_bytes: None,
+            include_checksum: None,
+        };
+        let cancel_token = CancellationToken::new();
+        cancel_token.cancel();
+
+        let err = tool.execute(&test_base, &cancel_token).await.unwrap_err();
+        assert_eq!(err.to_string(), "cancelled by user");
+    }
+
     #[tokio::test]
     async fn test_fs_read_with_offset_and_limit() {
         let test_base = TestBase::new()
@@ -233,15 +1203,343 @@ mod tests {
                 path: test_base.join("test.txt").to_string_lossy().to_string(),
                 limit: Some(2),
                 offset: Some(1),
+                start_line: None,
+                end_line: None,
+                git_object: None,
+                repo_path: None,
             }],
+            max_bytes: None,
+            include_pattern: None,
+            exclude_pattern: None,
+            suppress_header: Some(true),
+            max_hex_dump_bytes: None,
+            include_checksum: None,
         };
 
-        let result = tool.execute(&test_base).await.unwrap();
+        let result = tool.execute(&test_base, &CancellationToken::new()).await.unwrap();
         if let ToolExecutionOutputItem::Text(content) = &result.items[0] {
             assert_eq!(content, "line2\nline3");
         }
     }
 
+    #[tokio::test]
+    async fn test_fs_read_header() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("a.txt", "line1\nline2\nline3"))
+            .await
+            .with_file(("b.txt", "x"))
+            .await;
+        let read = |path: &str, limit: Option<u32>, suppress_header: Option<bool>| FsRead {
+            ops: vec![FsReadOp {
+                limit,
+                ..read_op(&test_base, path)
+            }],
+            max_bytes: None,
+            include_pattern: None,
+            exclude_pattern: None,
+            suppress_header,
+            max_hex_dump_bytes: None,
+            include_checksum: None,
+        };
+        let test_base = &test_base;
+        let text = |tool: FsRead| async move {
+            tool.execute(test_base, &CancellationToken::new())
+                .await
+                .unwrap()
+                .all_text()
+                .replace(&*test_base.join("").to_string_lossy(), "")
+        };
+
+        assert_eq!(
+            text(read("b.txt", None, None)).await,
+            "=== b.txt (1 line, 1 byte) ===\nx\n=== end of file ==="
+        );
+        // Content stopped by the limit does not reach the end of the file.
+        assert_eq!(
+            text(read("a.txt", Some(2), None)).await,
+            "=== a.txt (2 lines, 11 bytes) ===\nline1\nline2"
+        );
+        assert_eq!(
+            text(read("a.txt", Some(3), None)).await,
+            "=== a.txt (3 lines, 17 bytes) ===\nline1\nline2\nline3\n=== end of file ==="
+        );
+        assert_eq!(text(read("a.txt", None, Some(true))).await, "line1\nline2\nline3");
+
+        // The footer is removed when truncated by max_bytes.
+        let full = read("a.txt", None, None)
+            .execute(test_base, &CancellationToken::new())
+            .await
+            .unwrap()
+            .all_text();
+        let tool = FsRead {
+            max_bytes: Some(full.len() as u64 - 4),
+            ..read("a.txt", None, None)
+        };
+        let truncated = text(tool).await;
+        assert!(
+            truncated.starts_with("=== a.txt (3 lines, 17 bytes) ==="),
+            "{}",
+            truncated
+        );
+        assert!(truncated.ends_with(TRUNCATED_SUFFIX), "{}", truncated);
+        assert!(!truncated.contains("end of file"), "{}", truncated);
+    }
+
+    #[tokio::test]
+    async fn test_fs_read_binary_file() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("small.bin", "\x7fELF\0\x01 hello, world!\n"))
+            .await
+            .with_file((
+                "large.bin",
+                "\0".repeat(DEFAULT_MAX_HEX_DUMP_BYTES as usize + 1).as_str(),
+            ))
+            .await;
+        let read = |path: &str| FsRead {
+            ops: vec![read_op(&test_base, path)],
+            max_bytes: None,
+            include_pattern: None,
+            exclude_pattern: None,
+            suppress_header: Some(true),
+            max_hex_dump_bytes: None,
+            include_checksum: None,
+        };
+
+        let result = read("small.bin")
+            .execute(&test_base, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            result.all_text(),
+            "00000000: 7f45 4c46 0001 2068 656c 6c6f 2c20 776f  .ELF.. hello, wo\n\
+             00000010: 726c 6421 0a                             rld!."
+        );
+
+        let err = read("large.bin")
+            .execute(&test_base, &CancellationToken::new())
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains(&format!(
+                "File appears to be binary ({} bytes)",
+                DEFAULT_MAX_HEX_DUMP_BYTES + 1
+            )),
+            "{}",
+            err
+        );
+        let tool = FsRead {
+            max_hex_dump_bytes: Some(DEFAULT_MAX_HEX_DUMP_BYTES + 1),
+            ..read("large.bin")
+        };
+        let result = tool.execute(&test_base, &CancellationToken::new()).await.unwrap();
+        assert!(result.all_text().starts_with("00000000: 0000 0000"));
+
+        // Larger limits are clamped.
+        let tool = FsRead {
+            max_hex_dump_bytes: Some(u64::MAX),
+            ..read("large.bin")
+        };
+        assert_eq!(tool.max_hex_dump_bytes(), MAX_HEX_DUMP_BYTES);
+    }
+
+    #[tokio::test]
+    async fn test_fs_read_checksum() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("a.txt", "line1\r\nline2\n"))
+            .await
+            .with_file(("b.bin", "\0\x01"))
+            .await;
+        let read = |include_checksum: Option<bool>| FsRead {
+            ops: vec![read_op(&test_base, "a.txt"), FsReadOp {
+                limit: Some(1),
+                ..read_op(&test_base, "b.bin")
+            }],
+            max_bytes: None,
+            include_pattern: None,
+            exclude_pattern: None,
+            suppress_header: Some(true),
+            max_hex_dump_bytes: None,
+            include_checksum,
+        };
+
+        let result = read(None).execute(&test_base, &CancellationToken::new()).await.unwrap();
+        assert_eq!(result.json_items().count(), 0);
+
+        // The checksum covers the raw bytes of the whole file, regardless of how it is read.
+        let result = read(Some(true))
+            .execute(&test_base, &CancellationToken::new())
+            .await
+            .unwrap();
+        let checksums = result.json_items().cloned().collect::<Vec<_>>();
+        assert_eq!(checksums, vec![
+            serde_json::json!({
+                "sha256": "6825eec65a7385d7b1e713ccc3ff37df54f90b38aed6edca93bddb734fad0b42",
+                "size_bytes": 13,
+            }),
+            serde_json::json!({
+                "sha256": "b413f47d13ee2fe6c845b2ee141af81de858df4ec549a58b7970bb96645bc8d2",
+                "size_bytes": 2,
+            }),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_read_in_chunks() {
+        let large = "line\n".repeat(READ_CHUNK_BYTES / 2);
+        let test_base = TestBase::new()
+            .await
+            .with_file(("empty.txt", ""))
+            .await
+            .with_file(("newline.txt", "\n"))
+            .await
+            .with_file(("unterminated.txt", "a\r\nb"))
+            .await
+            .with_file(("large.txt", large.clone()))
+            .await;
+
+        for (name, content) in [
+            ("empty.txt", ""),
+            ("newline.txt", "\n"),
+            ("unterminated.txt", "a\r\nb"),
+            ("large.txt", large.as_str()),
+        ] {
+            let path = test_base.join(name);
+            assert_eq!(count_lines(&path).await.unwrap(), content.lines().count(), "{}", name);
+            assert_eq!(
+                file_checksum(&path).await.unwrap(),
+                checksum(content.as_bytes()),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fs_read_line_range() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("test.txt", "line1\nline2\nline3\nline4\nline5"))
+            .await;
+        let read = |start_line: Option<u32>, end_line: Option<u32>| FsRead {
+            ops: vec![FsReadOp {
+                start_line,
+                end_line,
+                ..read_op(&test_base, "test.txt")
+            }],
+            max_bytes: None,
+            include_pattern: None,
+            exclude_pattern: None,
+            suppress_header: None,
+            max_hex_dump_bytes: None,
+            include_checksum: None,
+        };
+        let test_base = &test_base;
+        let text = |tool: FsRead| async move {
+            tool.validate(test_base).await.unwrap();
+            tool.execute(test_base, &CancellationToken::new())
+                .await
+                .unwrap()
+                .all_text()
+                .replace(&*test_base.join("").to_string_lossy(), "")
+        };
+
+        assert_eq!(
+            text(read(Some(2), Some(3))).await,
+            "=== test.txt (lines 2-3 of 5, 11 bytes) ===\nline2\nline3"
+        );
+        assert_eq!(
+            text(read(Some(4), None)).await,
+            "=== test.txt (lines 4-5 of 5, 11 bytes) ===\nline4\nline5\n=== end of file ==="
+        );
+        assert_eq!(
+            text(read(None, Some(1))).await,
+            "=== test.txt (lines 1-1 of 5, 5 bytes) ===\nline1"
+        );
+
+        for (start_line, end_line, expected) in [
+            (Some(3), Some(2), "must not be greater than end_line"),
+            (Some(0), None, "must be greater than 0"),
+            (Some(6), None, "start_line (6) exceeds the number of lines"),
+            (None, Some(10), "end_line (10) exceeds the number of lines"),
+        ] {
+            let err = read(start_line, end_line).validate(test_base).await.unwrap_err();
+            assert!(err.contains(expected), "{}", err);
+        }
+        let tool = FsRead {
+            ops: vec![FsReadOp {
+                offset: Some(1),
+                ..read(Some(1), None).ops.remove(0)
+            }],
+            ..read(None, None)
+        };
+        let err = tool.validate(test_base).await.unwrap_err();
+        assert!(err.contains("cannot be combined with offset"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_fs_read_line_filters() {
+        let test_base = TestBase::new()
+            .await
+            .with_file((
+                "test.log",
+                "INFO start
+DEBUG tick
+ERROR failed
+info lower
+DEBUG tock",
+            ))
+            .await;
+        let read = |include: Option<&str>, exclude: Option<&str>, offset: Option<u32>| FsRead {
+            ops: vec![FsReadOp {
+                path: test_base.join("test.log").to_string_lossy().to_string(),
+                limit: None,
+                offset,
+                start_line: None,
+                end_line: None,
+                git_object: None,
+                repo_path: None,
+            }],
+            max_bytes: None,
+            include_pattern: include.map(String::from),
+            exclude_pattern: exclude.map(String::from),
+            suppress_header: Some(true),
+            max_hex_dump_bytes: None,
+            include_checksum: None,
+        };
+        let test_base = &test_base;
+        let run = |tool: FsRead| async move {
+            let result = tool.execute(test_base, &CancellationToken::new()).await.unwrap();
+            (result.all_text(), result.json_items().next().cloned().unwrap())
+        };
+
+        // Include only, matched case-sensitively.
+        let (text, json) = run(read(Some("INFO|ERROR"), None, None)).await;
+        assert_eq!(text, "INFO start\nERROR failed");
+        assert_eq!(json, serde_json::json!({ "original_lines": 5, "filtered_lines": 2 }));
+
+        // Exclude only.
+        let (text, json) = run(read(None, Some("^DEBUG"), None)).await;
+        assert_eq!(text, "INFO start\nERROR failed\ninfo lower");
+        assert_eq!(json, serde_json::json!({ "original_lines": 5, "filtered_lines": 3 }));
+
+        // Both, applied after the offset.
+        let (text, json) = run(read(Some("(?i)info|DEBUG"), Some("tock"), Some(1))).await;
+        assert_eq!(text, "DEBUG tick\ninfo lower");
+        assert_eq!(json, serde_json::json!({ "original_lines": 4, "filtered_lines": 2 }));
+
+        // No metadata without filters.
+        let result = read(None, None, None)
+            .execute(test_base, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert!(result.json_items().next().is_none());
+
+        assert!(read(Some("("), None, None).validate(test_base).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_fs_read_multiple_files() {
         let test_base = TestBase::new()
@@ -257,16 +1555,30 @@ mod tests {
                     path: test_base.join("file1.txt").to_string_lossy().to_string(),
                     limit: None,
                     offset: None,
+                    start_line: None,
+                    end_line: None,
+                    git_object: None,
+                    repo_path: None,
                 },
                 FsReadOp {
                     path: test_base.join("file2.txt").to_string_lossy().to_string(),
                     limit: None,
                     offset: None,
+                    start_line: None,
+                    end_line: None,
+                    git_object: None,
+                    repo_path: None,
                 },
             ],
+            max_bytes: None,
+            include_pattern: None,
+            exclude_pattern: None,
+            suppress_header: Some(true),
+            max_hex_dump_bytes: None,
+            include_checksum: None,
         };
 
-        let result = tool.execute(&test_base).await.unwrap();
+        let result = tool.execute(&test_base, &CancellationToken::new()).await.unwrap();
         assert_eq!(result.items.len(), 2);
     }
 
@@ -278,7 +1590,17 @@ mod tests {
                 path: "/nonexistent/file.txt".to_string(),
                 limit: None,
                 offset: None,
+                start_line: None,
+                end_line: None,
+                git_object: None,
+                repo_path: None,
             }],
+            max_bytes: None,
+            include_pattern: None,
+            exclude_pattern: None,
+            suppress_header: Some(true),
+            max_hex_dump_bytes: None,
+            include_checksum: None,
         };
 
         assert!(tool.validate(&test_base).await.is_err());
@@ -293,9 +1615,111 @@ mod tests {
                 path: test_base.join("").to_string_lossy().to_string(),
                 limit: None,
                 offset: None,
+                start_line: None,
+                end_line: None,
+                git_object: None,
+                repo_path: None,
             }],
+            max_bytes: None,
+            include_pattern: None,
+            exclude_pattern: None,
+            suppress_header: Some(true),
+            max_hex_dump_bytes: None,
+            include_checksum: None,
         };
 
         assert!(tool.validate(&test_base).await.is_err());
     }
+
+    fn cloud_read(path: &str) -> FsRead {
+        FsRead {
+            ops: vec![FsReadOp {
+                path: path.to_string(),
+                limit: None,
+                offset: Some(1),
+                start_line: None,
+                end_line: None,
+                git_object: None,
+                repo_path: None,
+            }],
+            max_bytes: None,
+            include_pattern: None,
+            exclude_pattern: None,
+            suppress_header: Some(true),
+            max_hex_dump_bytes: None,
+            include_checksum: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fs_read_validate_cloud_path() {
+        let test_base = TestBase::new().await;
+        assert!(cloud_read("s3://bucket").validate(&test_base).await.is_err());
+        let res = cloud_read("s3://bucket/file.txt").validate(&test_base).await;
+        if cloud_storage::CLOUD_STORAGE_ENABLED {
+            res.unwrap();
+        } else {
+            assert!(res.unwrap_err().contains("cloud-storage feature"));
+        }
+    }
+
+    #[cfg(feature = "cloud-storage")]
+    #[tokio::test]
+    async fn test_fs_read_gcs_object() {
+        use crate::util::cloud_storage::test_server::{
+            MockResponse,
+            serve,
+        };
+
+        let (url, server) = serve(vec![MockResponse::new(
+            200,
+            Some("\"abc123\""),
+            "line 1\nline 2\nline 3",
+        )])
+        .await;
+        let test_base = TestBase::new().await.with_var("STORAGE_EMULATOR_HOST", url);
+        let output = cloud_read("gcs://my-bucket/dir/file.txt")
+            .execute(&test_base, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(output.all_text(), "line 2\nline 3");
+        assert_eq!(
+            output.json_items().next().unwrap(),
+            &serde_json::json!({
+                "source": "gcs",
+                "bucket": "my-bucket",
+                "key": "dir/file.txt",
+                "etag": "\"abc123\"",
+            })
+        );
+        let requests = server.await.unwrap();
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].path, "/storage/v1/b/my-bucket/o/dir%2Ffile%2Etxt?alt=media");
+    }
+
+    #[cfg(feature = "cloud-storage")]
+    #[tokio::test]
+    async fn test_fs_read_cloud_object_cancelled() {
+        // Connections are accepted by the OS but never responded to.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let test_base = TestBase::new().await.with_var("STORAGE_EMULATOR_HOST", url);
+
+        let cancel_token = CancellationToken::new();
+        tokio::spawn({
+            let cancel_token = cancel_token.clone();
+            async move {
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                cancel_token.cancel();
+            }
+        });
+        let err = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            cloud_read("gcs://my-bucket/file.txt").execute(&test_base, &cancel_token),
+        )
+        .await
+        .expect("read should stop once cancelled")
+        .unwrap_err();
+        assert_eq!(err.to_string(), "cancelled by user");
+    }
 }
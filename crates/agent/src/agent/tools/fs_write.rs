@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::path::{
     Path,
     PathBuf,
@@ -8,13 +10,28 @@ use serde::{
     Serialize,
 };
 use syntect::util::LinesWithEndings;
+use tokio::io::AsyncWriteExt as _;
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
 
 use super::{
     BuiltInToolName,
     BuiltInToolTrait,
+    ToolExample,
     ToolExecutionError,
+    ToolExecutionOutput,
+    ToolExecutionOutputItem,
     ToolExecutionResult,
+    ToolLocale,
 };
+use crate::agent::agent_config::definitions::FsWriteSettings;
+use crate::util::cloud_storage::{
+    self,
+    CloudProvider,
+    CloudStorageConfig,
+    CloudUri,
+};
+use crate::util::expand_template_vars;
 use crate::util::path::canonicalize_path_sys;
 use crate::util::providers::SystemProvider;
 
@@ -26,14 +43,80 @@ WHEN TO USE THIS TOOL:
 - Perfect for updating text-based file formats
 
 HOW TO USE:
-- Provide the path to the file you want to create or modify
-- Specify the operation to perform: one of `create`, `strReplace`, or `insert`
+- Provide the path to the file you want to create or modify. For `move`, provide the file to move as `source` instead
+- Specify the operation to perform: one of `create`, `strReplace`, `multiReplace`, `insert`, `regexReplace`, `delete`, or `move`
 - Use `create` to create a new file. Required parameter is `content`. Parent directories will be created if they are missing.
 - Use `strReplace` to replace and update the content of an existing file.
+- Use `multiReplace` to apply several `oldStr`/`newStr` `replacements` to an existing file at once. If any replacement fails, the file is left unmodified.
 - Use `insert` to insert content at a specific line, or append content to the end of a file.
+- Use `regexReplace` to replace matches of a regular expression `pattern` with `replacement`. Set `flags` to `g` to replace every match rather than only the first.
+- Use `delete` to delete a single file. Directories cannot be deleted.
+- Use `move` to rename or move the file at `source` to `destination`. Parent directories of `destination` will be created if they are missing. An existing file at `destination` is only overwritten if `overwrite` is true.
 
 TIPS:
 - To append content to the end of a file, use `insert` with no `insert_line`
+- To create or insert into a file that is not UTF-8, provide its `encoding`, e.g. `utf-16le` or `latin-1`
+- Set `lineEndings` to `lf` or `crlf` to match the line endings used by the file
+- Set `gitAdd` to true to stage the file in git after it is written
+- Set `backup` to true with `create` or `strReplace` to copy an existing file to `<path>.bak` before it is overwritten
+- To create a file from a template, use `{{VARIABLE_NAME}}` placeholders in `content` and provide their values in `variables`
+- Provide an s3://bucket/key path to `create` or `strReplace` an object in S3, if supported by this build. Set `contentType` to the MIME type of new objects
+"#;
+
+const FS_WRITE_TOOL_DESCRIPTION_ZH_CN: &str = r#"
+用于创建和编辑文本文件的工具。
+
+何时使用此工具：
+- 需要创建新文件或修改现有文件时使用
+- 非常适合更新基于文本的文件格式
+
+使用方法：
+- 提供要创建或修改的文件路径
+- 指定要执行的操作：`create`、`strReplace`、`multiReplace`、`insert`、`regexReplace`、`delete` 或 `move` 之一
+- 使用 `create` 创建新文件。必需参数为 `content`。缺失的父目录会被自动创建。
+- 使用 `strReplace` 替换并更新现有文件的内容。
+- 使用 `multiReplace` 一次性对现有文件应用多个 `oldStr`/`newStr` 替换（`replacements`）。如果任一替换失败，文件将保持不变。
+- 使用 `insert` 在特定行插入内容，或将内容追加到文件末尾。
+- 使用 `regexReplace` 将正则表达式 `pattern` 的匹配项替换为 `replacement`。将 `flags` 设置为 `g` 可替换所有匹配项，而不仅是第一个。
+- 使用 `delete` 删除单个文件。不能删除目录。
+- 使用 `move` 将 `source` 处的文件重命名或移动到 `destination`。缺失的 `destination` 父目录会被自动创建。仅当 `overwrite` 为 true 时，`destination` 处已有的文件才会被覆盖。
+
+提示：
+- 要将内容追加到文件末尾，请使用不带 `insert_line` 的 `insert`
+- 要创建非 UTF-8 文件或向其中插入内容，请提供其 `encoding`，例如 `utf-16le` 或 `latin-1`
+- 将 `lineEndings` 设置为 `lf` 或 `crlf`，以匹配文件所使用的换行符
+- 将 `gitAdd` 设置为 true，可在写入后将文件暂存到 git 中
+- 在 `create` 或 `strReplace` 中将 `backup` 设置为 true，可在覆盖现有文件之前将其复制到 `<path>.bak`
+- 要从模板创建文件，请在 `content` 中使用 `{{VARIABLE_NAME}}` 占位符，并在 `variables` 中提供其值
+- 提供 s3://bucket/key 路径以对 S3 中的对象执行 `create` 或 `strReplace`（如果当前构建支持）。将 `contentType` 设置为新对象的 MIME 类型
+"#;
+
+const FS_WRITE_TOOL_DESCRIPTION_JA: &str = r#"
+テキストファイルを作成および編集するためのツールです。
+
+このツールを使用する場面:
+- 新しいファイルを作成する場合や、既存のファイルを変更する場合に使用します
+- テキストベースのファイル形式の更新に最適です
+
+使い方:
+- 作成または変更するファイルのパスを指定します
+- 実行する操作を `create`、`strReplace`、`multiReplace`、`insert`、`regexReplace`、`delete`、`move` のいずれかで指定します
+- 新しいファイルを作成するには `create` を使用します。必須パラメーターは `content` です。親ディレクトリが存在しない場合は作成されます。
+- 既存のファイルの内容を置換して更新するには `strReplace` を使用します。
+- 既存のファイルに複数の `oldStr`/`newStr` の置換 (`replacements`) をまとめて適用するには `multiReplace` を使用します。いずれかの置換が失敗した場合、ファイルは変更されません。
+- 特定の行に内容を挿入する場合や、ファイルの末尾に内容を追加する場合は `insert` を使用します。
+- 正規表現 `pattern` に一致する箇所を `replacement` で置換するには `regexReplace` を使用します。最初の一致だけでなくすべての一致を置換するには `flags` に `g` を指定します。
+- 1 つのファイルを削除するには `delete` を使用します。ディレクトリは削除できません。
+- `source` のファイルの名前を変更する場合や `destination` に移動する場合は `move` を使用します。`destination` の親ディレクトリが存在しない場合は作成されます。`destination` に既存のファイルがある場合は、`overwrite` が true の場合のみ上書きされます。
+
+ヒント:
+- ファイルの末尾に内容を追加するには、`insert_line` を指定せずに `insert` を使用してください
+- UTF-8 以外のファイルを作成または挿入する場合は、`utf-16le` や `latin-1` などの `encoding` を指定してください
+- ファイルで使用されている改行コードに合わせるには、`lineEndings` を `lf` または `crlf` に設定してください
+- 書き込み後にファイルを git にステージするには、`gitAdd` を true に設定してください
+- 既存のファイルを上書きする前に `<path>.bak` にコピーするには、`create` または `strReplace` で `backup` を true に設定してください
+- テンプレートからファイルを作成するには、`content` で `{{VARIABLE_NAME}}` プレースホルダーを使用し、`variables` にその値を指定してください
+- S3 のオブジェクトに対して `create` または `strReplace` を実行するには、s3://bucket/key のパスを指定してください（このビルドでサポートされている場合）。新しいオブジェクトの MIME タイプは `contentType` で指定します
 "#;
 
 const FS_WRITE_SCHEMA: &str = r#"
@@ -45,18 +128,62 @@ const FS_WRITE_SCHEMA: &str = r#"
             "enum": [
                 "create",
                 "strReplace",
-                "insert"
+                "multiReplace",
+                "insert",
+                "regexReplace",
+                "delete",
+                "move"
             ],
-            "description": "The commands to run. Allowed options are: `create`, `strReplace`, `insert`"
+            "description": "The commands to run. Allowed options are: `create`, `strReplace`, `multiReplace`, `insert`, `regexReplace`, `delete`, `move`"
+        },
+        "source": {
+            "description": "Required parameter of `move` command. Path to the file to move.",
+            "type": "string"
+        },
+        "destination": {
+            "description": "Required parameter of `move` command. Path to move the file at `source` to.",
+            "type": "string"
+        },
+        "overwrite": {
+            "description": "Optional parameter of `move` command. Default is false. When true, an existing file at `destination` is overwritten.",
+            "type": "boolean"
         },
         "content": {
             "description": "Required parameter of `create` and `insert` commands.",
             "type": "string"
         },
+        "variables": {
+            "description": "Optional parameter of `create` command. Map of variable names to values, substituted for `{{VARIABLE_NAME}}` placeholders in `content`. Every placeholder must have a value.",
+            "type": "object",
+            "additionalProperties": {
+                "type": "string"
+            }
+        },
+        "contentType": {
+            "description": "Optional parameter of `create` command. The MIME type of the object when `path` is an s3://bucket/key URI, e.g. `application/json`.",
+            "type": "string"
+        },
+        "lineEndings": {
+            "description": "Optional parameter of `create` and `insert` commands. Normalizes all line endings of the written content to `lf` or `crlf`. Default is `auto`, which writes line endings as provided and uses the platform default for any added newline. For `insert`, the whole file is normalized.",
+            "type": "string",
+            "enum": [
+                "lf",
+                "crlf",
+                "auto"
+            ]
+        },
         "insertLine": {
             "description": "Optional parameter of `insert` command. Line is 0-indexed. `content` will be inserted at the provided line. If not provided, content will be inserted at the end of the file on a new line, inserting a newline at the end of the file if it is missing.",
             "type": "integer"
         },
+        "encoding": {
+            "description": "Optional parameter of `create` and `insert` commands. The text encoding of the file, e.g. `utf-16le` or `latin-1`. Defaults to `utf-8`.",
+            "type": "string"
+        },
+        "allowClamp": {
+            "description": "Optional parameter of `insert` command. Default is false. When true, an `insertLine` greater than the number of lines in the file inserts at the end of the file instead of returning an error.",
+            "type": "boolean"
+        },
         "newStr": {
             "description": "Required parameter of `strReplace` command containing the new string.",
             "type": "string"
@@ -69,18 +196,67 @@ const FS_WRITE_SCHEMA: &str = r#"
             "description": "Optional parameter of `strReplace` command. Default is false. When true, all instances of `oldStr` will be replaced with `newStr`.",
             "type": "boolean"
         },
+        "replacements": {
+            "description": "Required parameter of `multiReplace` command. The replacements to apply in order, each of which must match exactly one occurrence of `oldStr` in the file after the previous replacements are applied.",
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "oldStr": {
+                        "type": "string"
+                    },
+                    "newStr": {
+                        "type": "string"
+                    }
+                },
+                "required": [
+                    "oldStr",
+                    "newStr"
+                ]
+            }
+        },
+        "normalizeUnicode": {
+            "description": "Optional parameter of `strReplace` command. Default is false. When true, `oldStr` and the file content are compared after Unicode NFC normalization, so that composed and decomposed characters match each other.",
+            "type": "boolean"
+        },
+        "pattern": {
+            "description": "Required parameter of `regexReplace` command containing the regular expression to match in `path`.",
+            "type": "string"
+        },
+        "replacement": {
+            "description": "Required parameter of `regexReplace` command containing the replacement for each match. Capture groups can be referenced with `$1` or `${name}`.",
+            "type": "string"
+        },
+        "flags": {
+            "description": "Optional parameter of `regexReplace` command. Any combination of `g` (replace all matches instead of only the first), `i` (case-insensitive), `m` (`^` and `$` match at line boundaries), and `s` (`.` matches newlines), e.g. `gi`.",
+            "type": "string"
+        },
         "path": {
-            "description": "Path to the file",
+            "description": "Required parameter of all commands except `move`. Path to the file, or an s3://bucket/key URI for the `create` and `strReplace` commands",
             "type": "string"
+        },
+        "preview": {
+            "description": "Optional parameter of all commands. Default is false. When true, the file is not modified and the content that would be written (for `create`) or a diff of the changes (for all other commands) is returned instead.",
+            "type": "boolean"
+        },
+        "gitAdd": {
+            "description": "Optional parameter of all commands. Default is false. When true, the file is staged in its git repository after being written.",
+            "type": "boolean"
+        },
+        "backup": {
+            "description": "Optional parameter of `create` and `strReplace` commands. Default is false. When true, an existing file at `path` is copied to `<path>.bak` before being overwritten, replacing any previous backup.",
+            "type": "boolean"
         }
     },
     "required": [
-        "command",
-        "path"
+        "command"
     ]
 }
 "#;
 
+/// Size of the chunks written by `create`, between which cancellation is checked.
+const WRITE_CHUNK_SIZE: usize = 64 * 1024;
+
 #[cfg(unix)]
 const NEWLINE: &str = "\n";
 
@@ -96,9 +272,78 @@ impl BuiltInToolTrait for FsWrite {
         FS_WRITE_TOOL_DESCRIPTION.into()
     }
 
+    fn description_for_locale(locale: &str) -> std::borrow::Cow<'static, str> {
+        match ToolLocale::parse(locale) {
+            Some(ToolLocale::ZhCn) => FS_WRITE_TOOL_DESCRIPTION_ZH_CN.into(),
+            Some(ToolLocale::Ja) => FS_WRITE_TOOL_DESCRIPTION_JA.into(),
+            None => Self::description(),
+        }
+    }
+
     fn input_schema() -> std::borrow::Cow<'static, str> {
         FS_WRITE_SCHEMA.into()
     }
+
+    fn constraints() -> Vec<String> {
+        vec![
+            "Do not write through symlinks to modify files outside of the intended directory".to_string(),
+            "Do not create symlinks or hard links to sensitive files such as credentials or system configuration"
+                .to_string(),
+        ]
+    }
+
+    fn examples() -> Vec<ToolExample> {
+        vec![
+            ToolExample::new(
+                "Create a new file",
+                serde_json::json!({
+                    "command": "create",
+                    "path": "src/hello.py",
+                    "content": "print('hello')\n"
+                }),
+            ),
+            ToolExample::new(
+                "Replace every occurrence of a string in an existing file",
+                serde_json::json!({
+                    "command": "strReplace",
+                    "path": "README.md",
+                    "oldStr": "old_name",
+                    "newStr": "new_name",
+                    "replaceAll": true
+                }),
+            ),
+            ToolExample::new(
+                "Make several edits to an existing file at once",
+                serde_json::json!({
+                    "command": "multiReplace",
+                    "path": "src/config.rs",
+                    "replacements": [
+                        { "oldStr": "const TIMEOUT: u64 = 30;", "newStr": "const TIMEOUT: u64 = 60;" },
+                        { "oldStr": "const RETRIES: u32 = 3;", "newStr": "const RETRIES: u32 = 5;" }
+                    ]
+                }),
+            ),
+            ToolExample::new(
+                "Rename a function and all of its call sites using a regular expression",
+                serde_json::json!({
+                    "command": "regexReplace",
+                    "path": "src/lib.rs",
+                    "pattern": r"\bold_name\(",
+                    "replacement": "new_name(",
+                    "flags": "g"
+                }),
+            ),
+            ToolExample::new(
+                "Insert content after the first line of a file",
+                serde_json::json!({
+                    "command": "insert",
+                    "path": "notes.txt",
+                    "content": "A new second line",
+                    "insertLine": 1
+                }),
+            ),
+        ]
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -107,7 +352,11 @@ impl BuiltInToolTrait for FsWrite {
 pub enum FsWrite {
     Create(FileCreate),
     StrReplace(StrReplace),
+    MultiReplace(MultiStrReplace),
     Insert(Insert),
+    RegexReplace(RegexReplaceCmd),
+    Delete(FileDelete),
+    Move(FileMove),
 }
 
 impl FsWrite {
@@ -115,7 +364,20 @@ impl FsWrite {
         match self {
             FsWrite::Create(v) => &v.path,
             FsWrite::StrReplace(v) => &v.path,
+            FsWrite::MultiReplace(v) => &v.path,
             FsWrite::Insert(v) => &v.path,
+            FsWrite::RegexReplace(v) => &v.path,
+            FsWrite::Delete(v) => &v.path,
+            FsWrite::Move(v) => &v.source,
+        }
+    }
+
+    /// All of the paths modified by the command, i.e. [FsWrite::path] and the destination of a
+    /// move.
+    pub fn paths(&self) -> Vec<&str> {
+        match self {
+            FsWrite::Move(v) => vec![&v.source, &v.destination],
+            _ => vec![self.path()],
         }
     }
 
@@ -125,87 +387,622 @@ impl FsWrite {
         ))
     }
 
-    pub async fn validate<P: SystemProvider>(&self, provider: &P) -> Result<(), String> {
+    pub async fn validate<P: SystemProvider>(
+        &self,
+        provider: &P,
+        settings: Option<&FsWriteSettings>,
+    ) -> Result<(), String> {
         let mut errors = Vec::new();
 
         if self.path().is_empty() {
             errors.push("Path must not be empty".to_string());
         }
 
+        if let Some(encoding) = self.encoding() {
+            if let Err(err) = lookup_encoding(encoding) {
+                errors.push(err.to_string());
+            }
+        }
+
+        let is_cloud = match self.cloud_uri() {
+            Ok(Some(uri)) => {
+                if !cloud_storage::CLOUD_STORAGE_ENABLED {
+                    errors.push("Writing to cloud storage requires the cloud-storage feature".to_string());
+                } else if uri.provider != CloudProvider::S3 {
+                    errors.push(format!("Writing to {} is not supported", uri.provider));
+                } else if !matches!(self, FsWrite::Create(_) | FsWrite::StrReplace(_)) {
+                    errors.push(CLOUD_COMMAND_UNSUPPORTED.to_string());
+                } else if self.backup() {
+                    errors.push("Backups are not supported for cloud storage paths".to_string());
+                }
+                true
+            },
+            Ok(None) => false,
+            Err(err) => {
+                errors.push(err);
+                true
+            },
+        };
+
         match &self {
-            FsWrite::Create(_) => (),
+            FsWrite::Create(v) => {
+                if let Err(err) = v.content() {
+                    errors.push(err);
+                }
+            },
             FsWrite::StrReplace(_) => {
-                if !self.canonical_path(provider)?.exists() {
+                if !is_cloud && !self.canonical_path(provider)?.exists() {
                     errors.push(
                         "The provided path must exist in order to replace or insert contents into it".to_string(),
                     );
                 }
             },
+            FsWrite::MultiReplace(v) => {
+                if !is_cloud && !self.canonical_path(provider)?.exists() {
+                    errors.push("The provided path must exist in order to replace contents in it".to_string());
+                }
+                if v.replacements.is_empty() {
+                    errors.push("At least one replacement must be provided".to_string());
+                }
+                if v.replacements.iter().any(|r| r.old_str.is_empty()) {
+                    errors.push("oldStr of each replacement must not be empty".to_string());
+                }
+            },
             FsWrite::Insert(v) => {
                 if v.content.is_empty() {
                     errors.push("Content to insert must not be empty".to_string());
                 }
             },
+            FsWrite::RegexReplace(v) => {
+                if !is_cloud && !self.canonical_path(provider)?.exists() {
+                    errors.push("The provided path must exist in order to replace contents in it".to_string());
+                }
+                if let Err(err) = v.regex() {
+                    errors.push(err);
+                }
+            },
+            FsWrite::Delete(_) => {
+                if !is_cloud {
+                    let path = self.canonical_path(provider)?;
+                    if !path.exists() {
+                        errors.push("The provided path must exist in order to delete it".to_string());
+                    } else if path.is_dir() {
+                        errors.push("The provided path is a directory; only files can be deleted".to_string());
+                    }
+                }
+            },
+            FsWrite::Move(v) => {
+                if !is_cloud {
+                    let path = self.canonical_path(provider)?;
+                    if !path.exists() {
+                        errors.push("The provided path must exist in order to move it".to_string());
+                    } else if path.is_dir() {
+                        errors.push("The provided path is a directory; only files can be moved".to_string());
+                    }
+                    let destination = v.canonical_destination(provider)?;
+                    if destination.is_dir() {
+                        errors.push(
+                            "The destination is a directory; provide the full path of the moved file".to_string(),
+                        );
+                    } else if destination.exists() && !v.overwrite {
+                        errors.push("The destination already exists. Set overwrite=true to replace it".to_string());
+                    }
+                }
+            },
         }
 
         if !errors.is_empty() {
-            Err(errors.join("\n"))
-        } else {
-            Ok(())
+            return Err(errors.join("\n"));
+        }
+
+        if let Some(max_file_size) = settings.and_then(|s| s.max_file_size) {
+            let size = match &self {
+                FsWrite::Create(v) => v.content()?.len(),
+                FsWrite::Delete(_) | FsWrite::Move(_) => 0,
+                _ => self
+                    .make_context(provider)
+                    .await
+                    .map_err(|e| e.to_string())?
+                    .after
+                    .len(),
+            };
+            if size as u64 > max_file_size {
+                return Err(format!(
+                    "The file would have a size of {} bytes, which is greater than the max supported size of {} bytes",
+                    size, max_file_size
+                ));
+            }
         }
+        Ok(())
     }
 
-    pub async fn make_context(&self) -> eyre::Result<FsWriteContext> {
-        // TODO - return file diff context
-        Ok(FsWriteContext {
-            path: self.path().to_string(),
-        })
+    /// The cloud storage object that [FsWrite::path] refers to, if any.
+    fn cloud_uri(&self) -> Result<Option<CloudUri>, String> {
+        CloudUri::parse(self.path()).map_err(|e| e.to_string())
+    }
+
+    /// The text encoding of the file, if one was provided.
+    fn encoding(&self) -> Option<&str> {
+        match self {
+            FsWrite::Create(v) => v.encoding.as_deref(),
+            FsWrite::Insert(v) => v.encoding.as_deref(),
+            FsWrite::StrReplace(_)
+            | FsWrite::MultiReplace(_)
+            | FsWrite::RegexReplace(_)
+            | FsWrite::Delete(_)
+            | FsWrite::Move(_) => None,
+        }
+    }
+
+    pub async fn make_context<P: SystemProvider>(&self, provider: &P) -> eyre::Result<FsWriteContext> {
+        if let FsWrite::Move(v) = self {
+            return v.make_context(provider).await;
+        }
+
+        let mut backup_path = None;
+        let before = match self.cloud_uri().map_err(|e| eyre::eyre!(e))? {
+            Some(uri) => match cloud_storage::get_object(&CloudStorageConfig::from_env(provider), &uri, None).await {
+                Ok(object) => Some(decode_content(object.content, self.encoding())?),
+                Err(cloud_storage::CloudStorageError::NotFound { .. }) => None,
+                Err(err) => return Err(err.into()),
+            },
+            None => {
+                let path = self.canonical_path(provider).map_err(|e| eyre::eyre!(e))?;
+                let before = match tokio::fs::read(&path).await {
+                    Ok(bytes) => Some(decode_content(bytes, self.encoding())?),
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+                    Err(err) => return Err(err.into()),
+                };
+                if before.is_some() && self.backup() && !self.preview() {
+                    backup_path = Some(self::backup_path(&path).to_string_lossy().to_string());
+                }
+                before
+            },
+        };
+        let after = match &self {
+            FsWrite::Create(v) => v.content().map_err(|e| eyre::eyre!(e))?.into_owned(),
+            FsWrite::StrReplace(v) => v.new_content(before.as_deref().unwrap_or_default())?,
+            FsWrite::MultiReplace(v) => v.new_content(before.as_deref().unwrap_or_default())?.0,
+            FsWrite::Insert(v) => v.new_content(before.as_deref().unwrap_or_default())?,
+            FsWrite::RegexReplace(v) => v.new_content(before.as_deref().unwrap_or_default())?.0,
+            FsWrite::Delete(_) => String::new(),
+            FsWrite::Move(_) => unreachable!("handled above"),
+        };
+        let ctx = match &self {
+            FsWrite::Delete(_) => FsWriteContext::without_diff(self.path(), before, after),
+            _ => FsWriteContext::new(self.path(), before, after),
+        };
+        Ok(FsWriteContext { backup_path, ..ctx })
     }
 
     pub async fn execute<P: SystemProvider>(
         &self,
         _state: Option<&mut FsWriteState>,
         provider: &P,
+        cancel_token: &CancellationToken,
     ) -> ToolExecutionResult {
-        let path = self.canonical_path(provider).map_err(ToolExecutionError::Custom)?;
+        if let Some(uri) = self.cloud_uri()? {
+            let config = CloudStorageConfig::from_env(provider);
+            return match &self {
+                FsWrite::Create(v) => v.execute_cloud(&config, &uri).await,
+                FsWrite::StrReplace(v) => v.execute_cloud(&config, &uri, cancel_token).await,
+                FsWrite::MultiReplace(_)
+                | FsWrite::Insert(_)
+                | FsWrite::RegexReplace(_)
+                | FsWrite::Delete(_)
+                | FsWrite::Move(_) => Err(ToolExecutionError::Custom(CLOUD_COMMAND_UNSUPPORTED.to_string())),
+            };
+        }
 
-        match &self {
-            FsWrite::Create(v) => v.execute(path).await?,
-            FsWrite::StrReplace(v) => v.execute(path).await?,
-            FsWrite::Insert(v) => v.execute(path).await?,
+        let path = self.canonical_path(provider)?;
+
+        let mut output = match &self {
+            FsWrite::Create(v) => v.execute(&path, cancel_token).await,
+            FsWrite::StrReplace(v) => v.execute(&path, cancel_token).await,
+            FsWrite::MultiReplace(v) => v.execute(&path, cancel_token).await,
+            FsWrite::Insert(v) => v.execute(&path, cancel_token).await,
+            FsWrite::RegexReplace(v) => v.execute(&path, cancel_token).await,
+            FsWrite::Delete(v) => v.execute(&path).await,
+            FsWrite::Move(v) => v.execute(&path, provider).await,
+        }?;
+
+        if self.git_add() && !self.preview() {
+            let status = git_add(&path).await;
+            output.items.push(ToolExecutionOutputItem::Json(
+                serde_json::json!({ "git_status": status }),
+            ));
         }
+        Ok(output)
+    }
 
-        Ok(Default::default())
+    fn preview(&self) -> bool {
+        match self {
+            FsWrite::Create(v) => v.preview,
+            FsWrite::StrReplace(v) => v.preview,
+            FsWrite::MultiReplace(v) => v.preview,
+            FsWrite::Insert(v) => v.preview,
+            FsWrite::RegexReplace(v) => v.preview,
+            FsWrite::Delete(_) | FsWrite::Move(_) => None,
+        }
+        .unwrap_or(false)
+    }
+
+    fn backup(&self) -> bool {
+        match self {
+            FsWrite::Create(v) => v.backup,
+            FsWrite::StrReplace(v) => v.backup,
+            _ => None,
+        }
+        .unwrap_or(false)
+    }
+
+    fn git_add(&self) -> bool {
+        match self {
+            FsWrite::Create(v) => v.git_add,
+            FsWrite::StrReplace(v) => v.git_add,
+            FsWrite::MultiReplace(v) => v.git_add,
+            FsWrite::Insert(v) => v.git_add,
+            FsWrite::RegexReplace(v) => v.git_add,
+            FsWrite::Delete(_) | FsWrite::Move(_) => None,
+        }
+        .unwrap_or(false)
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The git status of a file after being staged by [git_add].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GitStatus {
+    /// The file in the working tree matches the index.
+    Staged,
+    /// The file in the working tree differs from the index.
+    Unstaged,
+    /// The file could not be staged, e.g. because it is not in a git repository.
+    Unknown,
+}
+
+/// Stages the file at `path` in the git repository containing it, returning its resulting
+/// status.
+async fn git_add(path: &Path) -> GitStatus {
+    let path = path.to_path_buf();
+    let result = tokio::task::spawn_blocking(move || stage_file(&path))
+        .await
+        .unwrap_or_else(|e| Err(git2::Error::from_str(&e.to_string())));
+    result.unwrap_or_else(|err| {
+        warn!(?err, "failed to stage file");
+        GitStatus::Unknown
+    })
+}
+
+/// Blocking implementation of [git_add].
+fn stage_file(path: &Path) -> Result<GitStatus, git2::Error> {
+    let dir = path.parent().unwrap_or(path);
+    let repo = git2::Repository::discover(dir)?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| git2::Error::from_str("cannot stage files in a bare repository"))?;
+    // The path is canonicalized, so the working directory must be too for it to be a prefix.
+    let workdir = std::fs::canonicalize(workdir).map_err(|e| git2::Error::from_str(&e.to_string()))?;
+    let relative = path
+        .strip_prefix(&workdir)
+        .map_err(|e| git2::Error::from_str(&format!("file is not in the repository's working directory: {}", e)))?;
+
+    let mut index = repo.index()?;
+    index.add_path(relative)?;
+    index.write()?;
+
+    let status = repo.status_file(relative)?;
+    if status.is_wt_new() || status.is_wt_modified() || status.is_wt_deleted() || status.is_wt_typechange() {
+        Ok(GitStatus::Unstaged)
+    } else {
+        Ok(GitStatus::Staged)
+    }
+}
+
+const CLOUD_COMMAND_UNSUPPORTED: &str = "Only the `create` and `strReplace` commands support cloud storage paths";
+
+const PREVIEW_PREFIX: &str = "[PREVIEW - file not written]";
+
+/// Creates the output returned in place of writing `would_write_bytes` bytes to a file.
+fn preview_output(text: String, would_write_bytes: usize) -> ToolExecutionOutput {
+    ToolExecutionOutput::new(vec![
+        ToolExecutionOutputItem::Text(format!("{}\n{}", PREVIEW_PREFIX, text)),
+        ToolExecutionOutputItem::Json(serde_json::json!({ "would_write_bytes": would_write_bytes })),
+    ])
+}
+
+/// Creates the preview output for a modification of the file at `path` from `old` to `new`.
+fn preview_diff_output(path: &Path, old: &str, new: &str) -> ToolExecutionOutput {
+    preview_output(unified_diff(&path.to_string_lossy(), old, new), new.len())
+}
+
+/// Returns a unified diff of the change to the file at `path` from `old` to `new`.
+fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .header(path, path)
+        .to_string()
+}
+
+/// Looks up the encoding for the given label, e.g. `utf-16le` or `latin-1`.
+fn lookup_encoding(label: &str) -> Result<&'static encoding_rs::Encoding, ToolExecutionError> {
+    encoding_rs::Encoding::for_label(label.as_bytes())
+        .or_else(|| encoding_rs::Encoding::for_label(label.replace('-', "").as_bytes()))
+        .ok_or_else(|| ToolExecutionError::Custom(format!("unknown encoding '{}'", label)))
+}
+
+/// Transcodes `content` into the given encoding, or returns it unchanged if no encoding is
+/// provided.
+fn encode_content<'a>(content: &'a str, encoding: Option<&str>) -> Result<Cow<'a, [u8]>, ToolExecutionError> {
+    let Some(label) = encoding else {
+        return Ok(content.as_bytes().into());
+    };
+    let encoding = lookup_encoding(label)?;
+    // encoding_rs only decodes UTF-16, so encode it ourselves.
+    if encoding == encoding_rs::UTF_16LE {
+        return Ok(content
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>()
+            .into());
+    }
+    if encoding == encoding_rs::UTF_16BE {
+        return Ok(content
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect::<Vec<_>>()
+            .into());
+    }
+    let (bytes, _, had_errors) = encoding.encode(content);
+    if had_errors {
+        return Err(ToolExecutionError::Custom(format!(
+            "content contains characters that cannot be encoded as {}",
+            encoding.name()
+        )));
+    }
+    Ok(bytes)
+}
+
+/// Decodes file contents from the given encoding, defaulting to UTF-8.
+fn decode_content(bytes: Vec<u8>, encoding: Option<&str>) -> Result<String, ToolExecutionError> {
+    let encoding = match encoding {
+        Some(label) => lookup_encoding(label)?,
+        None => encoding_rs::UTF_8,
+    };
+    if encoding == encoding_rs::UTF_8 {
+        return String::from_utf8(bytes)
+            .map_err(|e| ToolExecutionError::Custom(format!("file contents are not valid UTF-8: {}", e)));
+    }
+    encoding
+        .decode_without_bom_handling_and_without_replacement(&bytes)
+        .map(Cow::into_owned)
+        .ok_or_else(|| ToolExecutionError::Custom(format!("file contents are not valid {}", encoding.name())))
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FileCreate {
     path: String,
     content: String,
+    /// Encoding to write the content in, defaulting to UTF-8.
+    encoding: Option<String>,
+    /// Values for the `{{VARIABLE_NAME}}` placeholders in `content`.
+    variables: Option<HashMap<String, String>>,
+    /// MIME type of the object when writing to cloud storage.
+    content_type: Option<String>,
+    /// Line endings to normalize the content to.
+    line_endings: Option<LineEndings>,
+    /// When true, the content is returned instead of being written.
+    preview: Option<bool>,
+    /// Whether to stage the file in git after it is written.
+    git_add: Option<bool>,
+    /// Whether to copy an existing file to its [backup_path] before overwriting it.
+    backup: Option<bool>,
 }
 
 impl FileCreate {
-    async fn execute(&self, path: impl AsRef<Path>) -> Result<(), ToolExecutionError> {
+    /// The content to write, with any [FileCreate::variables] substituted.
+    fn content(&self) -> Result<Cow<'_, str>, String> {
+        let content = match &self.variables {
+            Some(variables) => expand_template_vars(&self.content, variables)
+                .map(Cow::Owned)
+                .map_err(|undefined| format!("Undefined template variables in content: {}", undefined.join(", ")))?,
+            None => Cow::Borrowed(self.content.as_str()),
+        };
+        Ok(self.line_endings.unwrap_or_default().normalize(content))
+    }
+
+    async fn execute(&self, path: impl AsRef<Path>, cancel_token: &CancellationToken) -> ToolExecutionResult {
         let path = path.as_ref();
 
-        if let Some(parent) = path.parent() {
-            if !parent.exists() {
-                tokio::fs::create_dir_all(parent).await.map_err(|e| {
-                    ToolExecutionError::io(format!("failed to create directory {}", parent.to_string_lossy()), e)
-                })?;
-            }
+        let text = self.content()?;
+        let content = encode_content(&text, self.encoding.as_deref())?;
+        if self.preview.unwrap_or(false) {
+            let len = content.len();
+            return Ok(preview_output(text.into_owned(), len));
         }
 
-        tokio::fs::write(path, &self.content)
+        let backup = match self.backup.unwrap_or(false) {
+            true => backup_file(path).await?,
+            false => None,
+        };
+        write_file(path, &content, cancel_token).await?;
+        Ok(backup
+            .map(|item| ToolExecutionOutput::new(vec![item]))
+            .unwrap_or_default())
+    }
+
+    /// Writes the content to the object at `uri` in cloud storage.
+    async fn execute_cloud(&self, config: &CloudStorageConfig, uri: &CloudUri) -> ToolExecutionResult {
+        let text = self.content()?;
+        let content = encode_content(&text, self.encoding.as_deref())?;
+        if self.preview.unwrap_or(false) {
+            let len = content.len();
+            return Ok(preview_output(text.into_owned(), len));
+        }
+
+        let etag = cloud_storage::put_object(config, uri, &content, self.content_type.as_deref(), None)
             .await
-            .map_err(|e| ToolExecutionError::io(format!("failed to write to {}", path.to_string_lossy()), e))?;
+            .map_err(|e| ToolExecutionError::Custom(e.to_string()))?;
+        Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Json(
+            uri.metadata(etag.as_deref()),
+        )]))
+    }
+}
 
-        Ok(())
+/// The line endings to write a file with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LineEndings {
+    Lf,
+    Crlf,
+    /// Line endings are written as provided, using the platform default where one is added.
+    #[default]
+    Auto,
+}
+
+impl LineEndings {
+    /// Converts every line ending in `content` to `self`.
+    fn normalize(self, content: Cow<'_, str>) -> Cow<'_, str> {
+        match self {
+            LineEndings::Auto => content,
+            LineEndings::Lf if content.contains("\r\n") => content.replace("\r\n", "\n").into(),
+            LineEndings::Lf => content,
+            LineEndings::Crlf => content.replace("\r\n", "\n").replace('\n', "\r\n").into(),
+        }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Writes `content` to the file at `path`, creating any missing parent directories.
+///
+/// The content is written to a temporary sibling file which then atomically replaces `path`, so
+/// that an existing file is never left partially written if the write is cancelled or fails, or
+/// the process exits.
+pub(super) async fn write_file(
+    path: &Path,
+    content: &[u8],
+    cancel_token: &CancellationToken,
+) -> Result<(), ToolExecutionError> {
+    if let Some(parent) = path.parent() {
+        if !parent.exists() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| ToolExecutionError::from_io_result("failed to create directory", Some(parent), e))?;
+        }
+    }
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| ToolExecutionError::Custom(format!("'{}' is not a file path", path.to_string_lossy())))?;
+    let tmp_path = path.with_file_name(format!(".{}.{}.tmp", file_name.to_string_lossy(), uuid::Uuid::new_v4()));
+    let mut guard = PartialFileGuard {
+        path: Some(tmp_path.clone()),
+    };
+    let map_err = |e| ToolExecutionError::from_io_result("failed to write", Some(path), e);
+    let mut file = tokio::fs::File::create(&tmp_path).await.map_err(map_err)?;
+    for chunk in content.chunks(WRITE_CHUNK_SIZE) {
+        tokio::select! {
+            biased;
+            _ = cancel_token.cancelled() => return Err(ToolExecutionError::cancelled()),
+            res = file.write_all(chunk) => res.map_err(map_err)?,
+        }
+    }
+    file.sync_all().await.map_err(map_err)?;
+    drop(file);
+
+    // Keep the permissions of the file being replaced, e.g. the executable bit of scripts.
+    if let Ok(metadata) = tokio::fs::metadata(path).await {
+        tokio::fs::set_permissions(&tmp_path, metadata.permissions())
+            .await
+            .map_err(map_err)?;
+    }
+    replace_file(&tmp_path, path).await.map_err(map_err)?;
+    guard.path = None;
+    Ok(())
+}
+
+/// Atomically replaces the file at `to` with the file at `from`.
+#[cfg(not(windows))]
+async fn replace_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    tokio::fs::rename(from, to).await
+}
+
+/// Atomically replaces the file at `to` with the file at `from`.
+#[cfg(windows)]
+async fn replace_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    use std::os::windows::ffi::OsStrExt as _;
+
+    use windows::Win32::Storage::FileSystem::{
+        MOVEFILE_REPLACE_EXISTING,
+        MOVEFILE_WRITE_THROUGH,
+        MoveFileExW,
+    };
+    use windows::core::PCWSTR;
+
+    let to_wide = |path: &Path| path.as_os_str().encode_wide().chain(Some(0)).collect::<Vec<u16>>();
+    let (from, to) = (to_wide(from), to_wide(to));
+    tokio::task::spawn_blocking(move || {
+        // SAFETY: both paths are null-terminated and outlive the call.
+        unsafe {
+            MoveFileExW(
+                PCWSTR(from.as_ptr()),
+                PCWSTR(to.as_ptr()),
+                MOVEFILE_REPLACE_EXISTING | MOVEFILE_WRITE_THROUGH,
+            )
+        }
+    })
+    .await
+    .map_err(std::io::Error::other)?
+    .map_err(std::io::Error::from)
+}
+
+/// Returns the path that the file at `path` is backed up to, i.e. `<path>.bak`.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+/// Copies the file at `path` to its [backup_path], replacing any previous backup. Returns the
+/// output item reporting the backup, or [None] if the file does not exist.
+///
+/// Failing to create the backup due to insufficient permissions is not an error, since the write
+/// itself may still succeed. A warning is returned as the output item instead.
+async fn backup_file(path: &Path) -> Result<Option<ToolExecutionOutputItem>, ToolExecutionError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let backup = backup_path(path);
+    match tokio::fs::copy(path, &backup).await {
+        Ok(_) => Ok(Some(ToolExecutionOutputItem::Json(
+            serde_json::json!({ "backup_path": backup.to_string_lossy() }),
+        ))),
+        Err(err) if err.kind() == std::io::ErrorKind::PermissionDenied => {
+            warn!(?path, ?backup, "failed to back up file");
+            Ok(Some(ToolExecutionOutputItem::Text(format!(
+                "Warning: failed to back up the file to {}: permission denied",
+                backup.to_string_lossy()
+            ))))
+        },
+        Err(err) => Err(ToolExecutionError::from_io_result("failed to back up", Some(path), err)),
+    }
+}
+
+/// Removes the file at `path` when dropped, used for cleaning up temporary files that were only
+/// partially written due to cancellation or errors.
+struct PartialFileGuard {
+    path: Option<PathBuf>,
+}
+
+impl Drop for PartialFileGuard {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StrReplace {
     path: String,
@@ -213,45 +1010,243 @@ pub struct StrReplace {
     new_str: String,
     #[serde(default)]
     replace_all: bool,
+    /// Whether `old_str` and the file content should be matched after NFC normalization. The file
+    /// content outside of the replaced ranges and `new_str` are written back unmodified.
+    normalize_unicode: Option<bool>,
+    /// When true, a diff of the replacement is returned instead of being written.
+    preview: Option<bool>,
+    /// Whether to stage the file in git after it is written.
+    git_add: Option<bool>,
+    /// Whether to copy the file to its [backup_path] before overwriting it.
+    backup: Option<bool>,
 }
 
 impl StrReplace {
-    async fn execute(&self, path: impl AsRef<Path>) -> Result<(), ToolExecutionError> {
+    async fn execute(&self, path: impl AsRef<Path>, cancel_token: &CancellationToken) -> ToolExecutionResult {
         let path = path.as_ref();
 
         let file = tokio::fs::read_to_string(path)
             .await
-            .map_err(|e| ToolExecutionError::io(format!("failed to read {}", path.to_string_lossy()), e))?;
+            .map_err(|e| ToolExecutionError::from_io_result("failed to read", Some(path), e))?;
+        if cancel_token.is_cancelled() {
+            return Err(ToolExecutionError::cancelled());
+        }
+
+        let new_file = self.new_content(&file)?;
+        if self.preview.unwrap_or(false) {
+            return Ok(preview_diff_output(path, &file, &new_file));
+        }
+
+        let backup = match self.backup.unwrap_or(false) {
+            true => backup_file(path).await?,
+            false => None,
+        };
+        write_file(path, new_file.as_bytes(), cancel_token).await?;
+
+        Ok(backup
+            .map(|item| ToolExecutionOutput::new(vec![item]))
+            .unwrap_or_default())
+    }
+
+    /// Applies the replacement to the object at `uri` in cloud storage. The object is only written
+    /// if it has not been modified since it was read.
+    async fn execute_cloud(
+        &self,
+        config: &CloudStorageConfig,
+        uri: &CloudUri,
+        cancel_token: &CancellationToken,
+    ) -> ToolExecutionResult {
+        let object = cloud_storage::get_object(config, uri, None)
+            .await
+            .map_err(|e| ToolExecutionError::Custom(e.to_string()))?;
+        let file = decode_content(object.content, None)?;
+        if cancel_token.is_cancelled() {
+            return Err(ToolExecutionError::cancelled());
+        }
+
+        let new_file = self.new_content(&file)?;
+        if self.preview.unwrap_or(false) {
+            return Ok(preview_output(
+                unified_diff(&uri.to_string(), &file, &new_file),
+                new_file.len(),
+            ));
+        }
 
-        let matches = file.match_indices(&self.old_str).collect::<Vec<_>>();
-        match matches.len() {
+        let etag = cloud_storage::put_object(
+            config,
+            uri,
+            new_file.as_bytes(),
+            object.content_type.as_deref(),
+            object.etag.as_deref(),
+        )
+        .await
+        .map_err(|e| ToolExecutionError::Custom(e.to_string()))?;
+        Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Json(
+            uri.metadata(etag.as_deref()),
+        )]))
+    }
+
+    /// Returns `file` with the replacement applied.
+    fn new_content(&self, file: &str) -> Result<String, ToolExecutionError> {
+        let matches = if self.normalize_unicode.unwrap_or(false) {
+            nfc_match_ranges(file, &self.old_str)
+        } else {
+            file.match_indices(&self.old_str)
+                .map(|(i, m)| i..i + m.len())
+                .collect::<Vec<_>>()
+        };
+        let new_file = match matches.len() {
             0 => {
                 return Err(ToolExecutionError::Custom(format!(
                     "no occurrences of \"{}\" were found",
                     &self.old_str
                 )));
             },
-            1 => {
-                let file = file.replacen(&self.old_str, &self.new_str, 1);
-                tokio::fs::write(path, file)
-                    .await
-                    .map_err(|e| ToolExecutionError::io(format!("failed to read {}", path.to_string_lossy()), e))?;
-            },
+            1 => replace_ranges(file, &matches, &self.new_str),
             x => {
                 if !self.replace_all {
                     return Err(ToolExecutionError::Custom(format!(
                         "{x} occurrences of old_str were found when only 1 is expected"
                     )));
                 }
-                let file = file.replace(&self.old_str, &self.new_str);
-                tokio::fs::write(path, file)
-                    .await
-                    .map_err(|e| ToolExecutionError::io(format!("failed to read {}", path.to_string_lossy()), e))?;
+                replace_ranges(file, &matches, &self.new_str)
             },
+        };
+        Ok(new_file)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiStrReplace {
+    path: String,
+    /// Replacements applied in order, each to the result of the previous ones.
+    replacements: Vec<StrReplacePair>,
+    /// When true, a diff of the replacements is returned instead of being written.
+    preview: Option<bool>,
+    /// Whether to stage the file in git after it is written.
+    git_add: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StrReplacePair {
+    old_str: String,
+    new_str: String,
+}
+
+impl MultiStrReplace {
+    async fn execute(&self, path: impl AsRef<Path>, cancel_token: &CancellationToken) -> ToolExecutionResult {
+        let path = path.as_ref();
+
+        let file = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| ToolExecutionError::from_io_result("failed to read", Some(path), e))?;
+        if cancel_token.is_cancelled() {
+            return Err(ToolExecutionError::cancelled());
         }
 
-        Ok(())
+        let (new_file, replacements) = self.new_content(&file)?;
+        if self.preview.unwrap_or(false) {
+            return Ok(preview_diff_output(path, &file, &new_file));
+        }
+
+        write_file(path, new_file.as_bytes(), cancel_token).await?;
+
+        Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Json(
+            serde_json::json!({ "replacements": replacements }),
+        )]))
+    }
+
+    /// Returns `file` with every replacement applied, along with the number of replacements made.
+    ///
+    /// Each `old_str` must match exactly once. If any replacement fails, an error listing every
+    /// failed replacement is returned and none of the replacements are kept.
+    fn new_content(&self, file: &str) -> Result<(String, usize), ToolExecutionError> {
+        let mut new_file = file.to_string();
+        let mut failures = Vec::new();
+        for (i, pair) in self.replacements.iter().enumerate() {
+            let matches = new_file
+                .match_indices(&pair.old_str)
+                .map(|(i, m)| i..i + m.len())
+                .collect::<Vec<_>>();
+            match matches.len() {
+                0 => failures.push(format!(
+                    "replacement {}: no occurrences of \"{}\" were found",
+                    i, pair.old_str
+                )),
+                1 => new_file = replace_ranges(&new_file, &matches, &pair.new_str),
+                x => failures.push(format!(
+                    "replacement {}: {} occurrences of \"{}\" were found when only 1 is expected",
+                    i, x, pair.old_str
+                )),
+            }
+        }
+        if !failures.is_empty() {
+            return Err(ToolExecutionError::Custom(format!(
+                "{} of {} replacements failed, no changes were made:\n{}",
+                failures.len(),
+                self.replacements.len(),
+                failures.join("\n")
+            )));
+        }
+        Ok((new_file, self.replacements.len()))
+    }
+}
+
+/// Replaces each of the given non-overlapping, ordered byte ranges of `content` with `new_str`.
+fn replace_ranges(content: &str, ranges: &[std::ops::Range<usize>], new_str: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut last = 0;
+    for range in ranges {
+        result.push_str(&content[last..range.start]);
+        result.push_str(new_str);
+        last = range.end;
+    }
+    result.push_str(&content[last..]);
+    result
+}
+
+/// Returns the byte ranges of `content` that match `pattern` when both are NFC normalized.
+///
+/// `content` is normalized in segments that each start at a character with a canonical combining
+/// class of 0, so that every match can be mapped back to a range of the original content. Matches
+/// that start or end in the middle of a segment (e.g. matching a base character without its
+/// combining mark) are ignored.
+fn nfc_match_ranges(content: &str, pattern: &str) -> Vec<std::ops::Range<usize>> {
+    use unicode_normalization::UnicodeNormalization;
+    use unicode_normalization::char::canonical_combining_class;
+
+    let pattern = pattern.nfc().collect::<String>();
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+
+    // Pairs of (normalized offset, original offset) at each segment boundary.
+    let mut boundaries = Vec::new();
+    let mut normalized = String::with_capacity(content.len());
+    let mut segment_start = 0;
+    for (i, c) in content.char_indices().skip(1) {
+        if canonical_combining_class(c) == 0 {
+            boundaries.push((normalized.len(), segment_start));
+            normalized.extend(content[segment_start..i].nfc());
+            segment_start = i;
+        }
     }
+    boundaries.push((normalized.len(), segment_start));
+    normalized.extend(content[segment_start..].nfc());
+    boundaries.push((normalized.len(), content.len()));
+
+    let to_original = |offset: usize| {
+        boundaries
+            .binary_search_by_key(&offset, |(n, _)| *n)
+            .ok()
+            .map(|i| boundaries[i].1)
+    };
+    normalized
+        .match_indices(&pattern)
+        .filter_map(|(i, m)| Some(to_original(i)?..to_original(i + m.len())?))
+        .collect()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -260,19 +1255,54 @@ pub struct Insert {
     path: String,
     content: String,
     insert_line: Option<u32>,
+    /// Whether an `insert_line` past the end of the file should be clamped to the file length
+    /// rather than returning an error.
+    allow_clamp: Option<bool>,
+    /// Encoding of the file, defaulting to UTF-8.
+    encoding: Option<String>,
+    /// Line endings to normalize the file to after inserting the content.
+    line_endings: Option<LineEndings>,
+    /// When true, a diff of the insertion is returned instead of being written.
+    preview: Option<bool>,
+    /// Whether to stage the file in git after it is written.
+    git_add: Option<bool>,
 }
 
 impl Insert {
-    async fn execute(&self, path: impl AsRef<Path>) -> Result<(), ToolExecutionError> {
+    async fn execute(&self, path: impl AsRef<Path>, cancel_token: &CancellationToken) -> ToolExecutionResult {
         let path = path.as_ref();
 
-        let mut file = tokio::fs::read_to_string(path)
+        let original = tokio::fs::read(path)
             .await
-            .map_err(|e| ToolExecutionError::io(format!("failed to read {}", path.to_string_lossy()), e))?;
+            .map_err(|e| ToolExecutionError::from_io_result("failed to read", Some(path), e))?;
+        let original = decode_content(original, self.encoding.as_deref())?;
+        if cancel_token.is_cancelled() {
+            return Err(ToolExecutionError::cancelled());
+        }
+
+        let file = self.new_content(&original)?;
+        if self.preview.unwrap_or(false) {
+            return Ok(preview_diff_output(path, &original, &file));
+        }
+
+        let content = encode_content(&file, self.encoding.as_deref())?;
+        write_file(path, &content, cancel_token).await?;
+
+        Ok(Default::default())
+    }
 
+    /// Returns `original` with the content inserted.
+    fn new_content(&self, original: &str) -> Result<String, ToolExecutionError> {
+        let mut file = original.to_string();
         let line_count = file.lines().count() as u32;
 
         if let Some(insert_line) = self.insert_line {
+            if insert_line > line_count && !self.allow_clamp.unwrap_or(false) {
+                return Err(ToolExecutionError::Custom(format!(
+                    "insert_line {} exceeds file length {}",
+                    insert_line, line_count
+                )));
+            }
             let insert_line = insert_line.clamp(0, line_count);
 
             // Get the index to insert at.
@@ -292,28 +1322,270 @@ impl Insert {
             }
             file.push_str(&self.content);
         }
-
-        tokio::fs::write(path, file)
-            .await
-            .map_err(|e| ToolExecutionError::io(format!("failed to write to {}", path.to_string_lossy()), e))?;
-
-        Ok(())
+        Ok(self
+            .line_endings
+            .unwrap_or_default()
+            .normalize(file.into())
+            .into_owned())
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct FsWriteContext {
+pub struct RegexReplaceCmd {
     path: String,
+    pattern: String,
+    /// Replacement for each match, which may reference capture groups with `$1` or `${name}`.
+    replacement: String,
+    /// sed-style flags, see [RegexReplaceCmd::regex].
+    flags: Option<String>,
+    /// When true, a diff of the replacement is returned instead of being written.
+    preview: Option<bool>,
+    /// Whether to stage the file in git after it is written.
+    git_add: Option<bool>,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct FsWriteState {
-    pub line_tracker: FileLineTracker,
-}
-
-/// Contains metadata for tracking user and agent contribution metrics for a given file for
+impl RegexReplaceCmd {
+    /// Compiles [RegexReplaceCmd::pattern] according to the flags: `i` for case-insensitive
+    /// matching, `m` for multi-line mode, and `s` for `.` to match newlines. `g` is handled by
+    /// [RegexReplaceCmd::new_content].
+    fn regex(&self) -> Result<regex::Regex, String> {
+        let mut builder = regex::RegexBuilder::new(&self.pattern);
+        for flag in self.flags.as_deref().unwrap_or_default().chars() {
+            match flag {
+                'g' => (),
+                'i' => {
+                    builder.case_insensitive(true);
+                },
+                'm' => {
+                    builder.multi_line(true);
+                },
+                's' => {
+                    builder.dot_matches_new_line(true);
+                },
+                other => return Err(format!("Unsupported regex flag '{}'", other)),
+            }
+        }
+        builder
+            .build()
+            .map_err(|e| format!("Invalid regex pattern '{}': {}", self.pattern, e))
+    }
+
+    fn is_global(&self) -> bool {
+        self.flags.as_deref().is_some_and(|f| f.contains('g'))
+    }
+
+    async fn execute(&self, path: impl AsRef<Path>, cancel_token: &CancellationToken) -> ToolExecutionResult {
+        let path = path.as_ref();
+
+        let file = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| ToolExecutionError::from_io_result("failed to read", Some(path), e))?;
+        if cancel_token.is_cancelled() {
+            return Err(ToolExecutionError::cancelled());
+        }
+
+        let (new_file, replacements) = self.new_content(&file)?;
+        if self.preview.unwrap_or(false) {
+            return Ok(preview_diff_output(path, &file, &new_file));
+        }
+
+        write_file(path, new_file.as_bytes(), cancel_token).await?;
+
+        Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Json(
+            serde_json::json!({ "replacements": replacements }),
+        )]))
+    }
+
+    /// Returns `file` with the replacements applied, along with the number of replacements made.
+    fn new_content(&self, file: &str) -> Result<(String, usize), ToolExecutionError> {
+        let regex = self.regex()?;
+        let replacements = if self.is_global() {
+            regex.find_iter(file).count()
+        } else {
+            regex.find(file).map_or(0, |_| 1)
+        };
+        if replacements == 0 {
+            return Err(ToolExecutionError::Custom(format!(
+                "no matches for pattern \"{}\" were found",
+                &self.pattern
+            )));
+        }
+        let new_file = if self.is_global() {
+            regex.replace_all(file, self.replacement.as_str())
+        } else {
+            regex.replace(file, self.replacement.as_str())
+        };
+        Ok((new_file.into_owned(), replacements))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDelete {
+    path: String,
+}
+
+impl FileDelete {
+    async fn execute(&self, path: impl AsRef<Path>) -> ToolExecutionResult {
+        let path = path.as_ref();
+
+        let metadata = tokio::fs::metadata(path)
+            .await
+            .map_err(|e| ToolExecutionError::from_io_result("failed to delete", Some(path), e))?;
+        // Checked explicitly since removing a directory fails with a different error on each
+        // platform.
+        if metadata.is_dir() {
+            return Err(ToolExecutionError::io(
+                "failed to delete: path is a directory",
+                Some(path),
+                std::io::ErrorKind::IsADirectory.into(),
+            ));
+        }
+
+        tokio::fs::remove_file(path)
+            .await
+            .map_err(|e| ToolExecutionError::from_io_result("failed to delete", Some(path), e))?;
+        Ok(Default::default())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMove {
+    #[serde(alias = "path")]
+    source: String,
+    destination: String,
+    /// Whether or not to replace an existing file at [FileMove::destination].
+    #[serde(default)]
+    overwrite: bool,
+}
+
+impl FileMove {
+    fn canonical_destination<P: SystemProvider>(&self, provider: &P) -> Result<PathBuf, String> {
+        Ok(PathBuf::from(
+            canonicalize_path_sys(&self.destination, provider).map_err(|e| e.to_string())?,
+        ))
+    }
+
+    /// Creates the context of the move as a write of the file's content to the destination.
+    async fn make_context<P: SystemProvider>(&self, provider: &P) -> eyre::Result<FsWriteContext> {
+        let source = canonicalize_path_sys(&self.source, provider)?;
+        let destination = self.canonical_destination(provider).map_err(|e| eyre::eyre!(e))?;
+        let before = match tokio::fs::read(&destination).await {
+            Ok(bytes) => Some(decode_content(bytes, None)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+        let after = decode_content(tokio::fs::read(&source).await?, None)?;
+        Ok(FsWriteContext::without_diff(&self.destination, before, after))
+    }
+
+    async fn execute<P: SystemProvider>(&self, path: impl AsRef<Path>, provider: &P) -> ToolExecutionResult {
+        let path = path.as_ref();
+        let destination = self.canonical_destination(provider)?;
+
+        if let Some(parent) = destination.parent() {
+            if !parent.exists() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| ToolExecutionError::from_io_result("failed to create directory", Some(parent), e))?;
+            }
+        }
+
+        let overwritten = destination.exists();
+        match tokio::fs::rename(path, &destination).await {
+            Ok(()) => (),
+            Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {
+                tokio::fs::copy(path, &destination)
+                    .await
+                    .map_err(|e| ToolExecutionError::from_io_result("failed to copy", Some(path), e))?;
+                tokio::fs::remove_file(path)
+                    .await
+                    .map_err(|e| ToolExecutionError::from_io_result("failed to delete", Some(path), e))?;
+            },
+            Err(err) => return Err(ToolExecutionError::from_io_result("failed to move", Some(path), err)),
+        }
+
+        if overwritten {
+            return Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Text(format!(
+                "Overwrote the existing file at {}",
+                destination.to_string_lossy()
+            ))]));
+        }
+        Ok(Default::default())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsWriteContext {
+    pub path: String,
+    /// Content of the file before the write, [None] if the file does not exist.
+    pub before: Option<String>,
+    /// Content of the file after the write.
+    pub after: String,
+    /// Unified diff from `before` to `after`, [None] for commands that aren't diffed, i.e.
+    /// `delete` and `move`.
+    pub diff: Option<String>,
+    /// Number of lines added and removed by the write, in that order. [None] if the write wasn't
+    /// diffed.
+    pub lines_changed: Option<(usize, usize)>,
+    /// Path that the file is backed up to before the write, if a backup was requested.
+    pub backup_path: Option<String>,
+}
+
+impl FsWriteContext {
+    pub fn new(path: impl Into<String>, before: Option<String>, after: String) -> Self {
+        let path = path.into();
+        let text_diff = similar::TextDiff::from_lines(before.as_deref().unwrap_or_default(), after.as_str());
+        let diff = text_diff.unified_diff().header(&path, &path).to_string();
+        let (mut added, mut removed) = (0, 0);
+        for change in text_diff.iter_all_changes() {
+            match change.tag() {
+                similar::ChangeTag::Insert => added += 1,
+                similar::ChangeTag::Delete => removed += 1,
+                similar::ChangeTag::Equal => (),
+            }
+        }
+        Self {
+            path,
+            before,
+            after,
+            diff: Some(diff),
+            lines_changed: Some((added, removed)),
+            backup_path: None,
+        }
+    }
+
+    /// Same as [FsWriteContext::new], except that no diff is computed.
+    pub fn without_diff(path: impl Into<String>, before: Option<String>, after: String) -> Self {
+        Self {
+            path: path.into(),
+            before,
+            after,
+            diff: None,
+            lines_changed: None,
+            backup_path: None,
+        }
+    }
+
+    /// Returns a terse description of the change, e.g. `"+3 -1 lines in src/main.rs"`.
+    pub fn diff_summary(&self) -> String {
+        match self.lines_changed {
+            Some((added, removed)) => format!("+{} -{} lines in {}", added, removed, self.path),
+            None => format!("No diff for {}", self.path),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsWriteState {
+    pub line_tracker: FileLineTracker,
+}
+
+/// Contains metadata for tracking user and agent contribution metrics for a given file for
 /// `fs_write` tool uses.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -365,24 +1637,263 @@ mod tests {
         let tool = FsWrite::Create(FileCreate {
             path: test_base.join("new.txt").to_string_lossy().to_string(),
             content: "hello world".to_string(),
+            ..Default::default()
         });
 
-        assert!(tool.validate(&test_base).await.is_ok());
-        assert!(tool.execute(None, &test_base).await.is_ok());
+        assert!(tool.validate(&test_base, None).await.is_ok());
+        assert!(tool.execute(None, &test_base, &CancellationToken::new()).await.is_ok());
 
         let content = tokio::fs::read_to_string(test_base.join("new.txt")).await.unwrap();
         assert_eq!(content, "hello world");
     }
 
+    #[tokio::test]
+    async fn test_validate_max_file_size() {
+        let test_base = TestBase::new().await.with_file(("test.txt", "hello world")).await;
+        let settings = FsWriteSettings {
+            max_file_size: Some(12),
+            ..Default::default()
+        };
+        let create = |content: &str| {
+            FsWrite::Create(FileCreate {
+                path: test_base.join("new.txt").to_string_lossy().to_string(),
+                content: content.to_string(),
+                ..Default::default()
+            })
+        };
+        let replace = |new_str: &str| {
+            FsWrite::StrReplace(StrReplace {
+                path: test_base.join("test.txt").to_string_lossy().to_string(),
+                old_str: "world".to_string(),
+                new_str: new_str.to_string(),
+                ..Default::default()
+            })
+        };
+
+        create("hello world")
+            .validate(&test_base, Some(&settings))
+            .await
+            .unwrap();
+        let err = create("hello world!!")
+            .validate(&test_base, Some(&settings))
+            .await
+            .unwrap_err();
+        assert!(err.contains("max supported size of 12 bytes"), "{}", err);
+        assert!(create("hello world!!").validate(&test_base, None).await.is_ok());
+
+        // The size is that of the file after the edit.
+        replace("rust").validate(&test_base, Some(&settings)).await.unwrap();
+        assert!(replace("everyone").validate(&test_base, Some(&settings)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_file_with_variables() {
+        let test_base = TestBase::new().await;
+        let create = |content: &str, variables: &[(&str, &str)]| {
+            FsWrite::Create(FileCreate {
+                path: test_base.join("config.toml").to_string_lossy().to_string(),
+                content: content.to_string(),
+                variables: Some(variables.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect()),
+                ..Default::default()
+            })
+        };
+
+        let tool = create(
+            "name = \"{{PROJECT_NAME}}\"\nversion = \"{{VERSION}}\"\nauthor = \"{{PROJECT_NAME}} team\"\n",
+            &[("PROJECT_NAME", "demo"), ("VERSION", "1.0.0"), ("UNUSED", "x")],
+        );
+        tool.validate(&test_base, None).await.unwrap();
+        tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert_eq!(
+            tokio::fs::read_to_string(test_base.join("config.toml")).await.unwrap(),
+            "name = \"demo\"\nversion = \"1.0.0\"\nauthor = \"demo team\"\n"
+        );
+
+        let tool = create("{{A}} {{MISSING}} {{B}} {{MISSING}}", &[("A", "a")]);
+        let err = tool.validate(&test_base, None).await.unwrap_err();
+        assert_eq!(err, "Undefined template variables in content: MISSING, B");
+        assert!(tool.execute(None, &test_base, &CancellationToken::new()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_line_endings() {
+        let test_base = TestBase::new().await;
+        let mixed = "a\r\nb\nc\r\n";
+        let create = |line_endings: Option<LineEndings>| {
+            FsWrite::Create(FileCreate {
+                path: test_base.join("file.txt").to_string_lossy().to_string(),
+                content: mixed.to_string(),
+                line_endings,
+                ..Default::default()
+            })
+        };
+        for (line_endings, expected) in [
+            (None, mixed),
+            (Some(LineEndings::Auto), mixed),
+            (Some(LineEndings::Lf), "a\nb\nc\n"),
+            (Some(LineEndings::Crlf), "a\r\nb\r\nc\r\n"),
+        ] {
+            create(line_endings)
+                .execute(None, &test_base, &CancellationToken::new())
+                .await
+                .unwrap();
+            assert_eq!(
+                tokio::fs::read_to_string(test_base.join("file.txt")).await.unwrap(),
+                expected,
+                "{:?}",
+                line_endings
+            );
+        }
+
+        // Inserting normalizes the whole file, including the existing content.
+        tokio::fs::write(test_base.join("file.txt"), "a\nb\r\n").await.unwrap();
+        let tool = FsWrite::Insert(Insert {
+            path: test_base.join("file.txt").to_string_lossy().to_string(),
+            content: "c\nd".to_string(),
+            insert_line: Some(1),
+            allow_clamp: None,
+            line_endings: Some(LineEndings::Crlf),
+            encoding: None,
+            preview: None,
+            git_add: None,
+        });
+        tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert_eq!(
+            tokio::fs::read_to_string(test_base.join("file.txt")).await.unwrap(),
+            "a\r\nc\r\nd\r\nb\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_file_cancelled() {
+        let test_base = TestBase::new().await;
+        let tool = FsWrite::Create(FileCreate {
+            path: test_base.join("new.txt").to_string_lossy().to_string(),
+            content: "hello world".to_string(),
+            ..Default::default()
+        });
+        let cancel_token = CancellationToken::new();
+        cancel_token.cancel();
+
+        let err = tool.execute(None, &test_base, &cancel_token).await.unwrap_err();
+        assert_eq!(err.to_string(), "cancelled by user");
+        assert!(!test_base.join("new.txt").exists(), "partial file should be removed");
+    }
+
+    #[tokio::test]
+    async fn test_str_replace_cancelled() {
+        let test_base = TestBase::new().await.with_file(("file.txt", "hello world")).await;
+        let tool = FsWrite::StrReplace(StrReplace {
+            path: test_base.join("file.txt").to_string_lossy().to_string(),
+            old_str: "hello".to_string(),
+            new_str: "goodbye".to_string(),
+            ..Default::default()
+        });
+        let cancel_token = CancellationToken::new();
+        cancel_token.cancel();
+
+        let err = tool.execute(None, &test_base, &cancel_token).await.unwrap_err();
+        assert_eq!(err.to_string(), "cancelled by user");
+        assert_eq!(
+            std::fs::read_to_string(test_base.join("file.txt")).unwrap(),
+            "hello world"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_edit_commands_cancelled() {
+        let test_base = TestBase::new().await.with_file(("file.txt", "hello world")).await;
+        let path = test_base.join("file.txt").to_string_lossy().to_string();
+        let cancel_token = CancellationToken::new();
+        cancel_token.cancel();
+
+        for tool in [
+            serde_json::json!({
+                "command": "multiReplace",
+                "path": path,
+                "replacements": [{ "oldStr": "hello", "newStr": "goodbye" }]
+            }),
+            serde_json::json!({
+                "command": "regexReplace",
+                "path": path,
+                "pattern": "w.rld",
+                "replacement": "there"
+            }),
+            serde_json::json!({
+                "command": "insert",
+                "path": path,
+                "content": "new line"
+            }),
+        ] {
+            let tool = serde_json::from_value::<FsWrite>(tool).unwrap();
+            let err = tool.execute(None, &test_base, &cancel_token).await.unwrap_err();
+            assert_eq!(err.to_string(), "cancelled by user");
+            assert_eq!(
+                std::fs::read_to_string(test_base.join("file.txt")).unwrap(),
+                "hello world"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_file_is_atomic() {
+        let test_base = TestBase::new().await.with_file(("existing.txt", "original")).await;
+        let path = test_base.join("existing.txt");
+        let create = |content: &str| {
+            FsWrite::Create(FileCreate {
+                path: path.to_string_lossy().to_string(),
+                content: content.to_string(),
+                ..Default::default()
+            })
+        };
+        let tmp_files = || {
+            std::fs::read_dir(test_base.join(""))
+                .unwrap()
+                .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+                .filter(|name| name.ends_with(".tmp"))
+                .collect::<Vec<_>>()
+        };
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        // A write that fails partway through leaves the existing file untouched.
+        let cancel_token = CancellationToken::new();
+        cancel_token.cancel();
+        let content = "x".repeat(WRITE_CHUNK_SIZE * 4);
+        let err = create(&content)
+            .execute(None, &test_base, &cancel_token)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "cancelled by user");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "original");
+        assert!(tmp_files().is_empty(), "temporary file should be removed");
+
+        create(&content)
+            .execute(None, &test_base, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+        assert!(tmp_files().is_empty());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o755);
+        }
+    }
+
     #[tokio::test]
     async fn test_create_file_with_parent_dirs() {
         let test_base = TestBase::new().await;
         let tool = FsWrite::Create(FileCreate {
             path: test_base.join("nested/dir/file.txt").to_string_lossy().to_string(),
             content: "nested content".to_string(),
+            ..Default::default()
         });
 
-        assert!(tool.execute(None, &test_base).await.is_ok());
+        assert!(tool.execute(None, &test_base, &CancellationToken::new()).await.is_ok());
 
         let content = tokio::fs::read_to_string(test_base.join("nested/dir/file.txt"))
             .await
@@ -398,10 +1909,10 @@ mod tests {
             path: test_base.join("test.txt").to_string_lossy().to_string(),
             old_str: "world".to_string(),
             new_str: "rust".to_string(),
-            replace_all: false,
+            ..Default::default()
         });
 
-        assert!(tool.execute(None, &test_base).await.is_ok());
+        assert!(tool.execute(None, &test_base, &CancellationToken::new()).await.is_ok());
 
         let content = tokio::fs::read_to_string(test_base.join("test.txt")).await.unwrap();
         assert_eq!(content, "hello rust");
@@ -416,9 +1927,10 @@ mod tests {
             old_str: "foo".to_string(),
             new_str: "baz".to_string(),
             replace_all: true,
+            ..Default::default()
         });
 
-        assert!(tool.execute(None, &test_base).await.is_ok());
+        assert!(tool.execute(None, &test_base, &CancellationToken::new()).await.is_ok());
 
         let content = tokio::fs::read_to_string(test_base.join("test.txt")).await.unwrap();
         assert_eq!(content, "baz bar baz");
@@ -432,10 +1944,106 @@ mod tests {
             path: test_base.join("test.txt").to_string_lossy().to_string(),
             old_str: "missing".to_string(),
             new_str: "replacement".to_string(),
-            replace_all: false,
+            ..Default::default()
+        });
+
+        assert!(tool.execute(None, &test_base, &CancellationToken::new()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_str_replace_normalize_unicode() {
+        // "café" with a decomposed "e" + combining acute accent, as commonly written on macOS.
+        let nfd = "Caf\u{0065}\u{0301} and cafe\u{0301}\n";
+        let test_base = TestBase::new().await.with_file(("test.txt", nfd)).await;
+        let path = test_base.join("test.txt").to_string_lossy().to_string();
+
+        let tool = FsWrite::StrReplace(StrReplace {
+            path: path.clone(),
+            old_str: "Caf\u{00e9}".to_string(),
+            new_str: "Bar".to_string(),
+            ..Default::default()
         });
+        assert!(
+            tool.execute(None, &test_base, &CancellationToken::new()).await.is_err(),
+            "NFC old_str should not match NFD content without normalization"
+        );
 
-        assert!(tool.execute(None, &test_base).await.is_err());
+        let tool = FsWrite::StrReplace(StrReplace {
+            path: path.clone(),
+            old_str: "Caf\u{00e9}".to_string(),
+            new_str: "Bar\u{0065}\u{0301}".to_string(),
+            normalize_unicode: Some(true),
+            ..Default::default()
+        });
+        tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        let content = tokio::fs::read_to_string(&path).await.unwrap();
+        // new_str and the remaining content are written back without normalization.
+        assert_eq!(content, "Bar\u{0065}\u{0301} and cafe\u{0301}\n");
+    }
+
+    #[tokio::test]
+    async fn test_multi_replace() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("test.txt", "alpha beta\ngamma beta\n"))
+            .await;
+        let path = test_base.join("test.txt").to_string_lossy().to_string();
+        let multi_replace = |pairs: &[(&str, &str)]| {
+            FsWrite::MultiReplace(MultiStrReplace {
+                path: path.clone(),
+                replacements: pairs
+                    .iter()
+                    .map(|&(old_str, new_str)| StrReplacePair {
+                        old_str: old_str.to_string(),
+                        new_str: new_str.to_string(),
+                    })
+                    .collect(),
+                preview: None,
+                git_add: None,
+            })
+        };
+
+        // Later replacements apply to the result of earlier ones.
+        let tool = multi_replace(&[("alpha beta", "alpha delta"), ("beta", "epsilon")]);
+        tool.validate(&test_base, None).await.unwrap();
+        let output = tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert_eq!(
+            output.json_items().next().unwrap(),
+            &serde_json::json!({ "replacements": 2 })
+        );
+        assert_eq!(
+            tokio::fs::read_to_string(&path).await.unwrap(),
+            "alpha delta\ngamma epsilon\n"
+        );
+
+        // A failed replacement leaves the file unmodified, and every failure is reported.
+        let tool = multi_replace(&[("gamma", "zeta"), ("missing", "x"), ("a", "b")]);
+        let err = tool
+            .execute(None, &test_base, &CancellationToken::new())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("2 of 3 replacements failed"), "{}", err);
+        assert!(err.contains("replacement 1: no occurrences"), "{}", err);
+        assert!(err.contains("replacement 2: "), "{}", err);
+        assert!(!err.contains("replacement 0"), "{}", err);
+        assert_eq!(
+            tokio::fs::read_to_string(&path).await.unwrap(),
+            "alpha delta\ngamma epsilon\n"
+        );
+
+        assert!(multi_replace(&[]).validate(&test_base, None).await.is_err());
+        assert!(multi_replace(&[("", "x")]).validate(&test_base, None).await.is_err());
+    }
+
+    #[test]
+    fn test_nfc_match_ranges() {
+        let content = "e\u{0301}x\u{00e9}e";
+        assert_eq!(nfc_match_ranges(content, "\u{00e9}"), vec![0..3, 4..6]);
+        assert_eq!(nfc_match_ranges(content, "e\u{0301}x"), vec![0..4]);
+        // A base character is not matched without its combining mark.
+        assert_eq!(nfc_match_ranges(content, "e"), vec![6..7]);
+        assert!(nfc_match_ranges(content, "").is_empty());
     }
 
     #[tokio::test]
@@ -449,14 +2057,339 @@ mod tests {
             path: test_base.join("test.txt").to_string_lossy().to_string(),
             content: "inserted".to_string(),
             insert_line: Some(1),
+            allow_clamp: None,
+            line_endings: None,
+            encoding: None,
+            preview: None,
+            git_add: None,
         });
 
-        assert!(tool.execute(None, &test_base).await.is_ok());
+        assert!(tool.execute(None, &test_base, &CancellationToken::new()).await.is_ok());
 
         let content = tokio::fs::read_to_string(test_base.join("test.txt")).await.unwrap();
         assert_eq!(content, "line1\ninserted\nline2\nline3");
     }
 
+    #[tokio::test]
+    async fn test_insert_line_out_of_bounds() {
+        let test_base = TestBase::new().await.with_file(("test.txt", "line1\nline2\n")).await;
+
+        let tool = FsWrite::Insert(Insert {
+            path: test_base.join("test.txt").to_string_lossy().to_string(),
+            content: "inserted".to_string(),
+            insert_line: Some(5),
+            allow_clamp: None,
+            line_endings: None,
+            encoding: None,
+            preview: None,
+            git_add: None,
+        });
+        let err = tool
+            .execute(None, &test_base, &CancellationToken::new())
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("insert_line 5 exceeds file length 2"),
+            "{}",
+            err
+        );
+        let content = tokio::fs::read_to_string(test_base.join("test.txt")).await.unwrap();
+        assert_eq!(content, "line1\nline2\n");
+
+        let tool = FsWrite::Insert(Insert {
+            path: test_base.join("test.txt").to_string_lossy().to_string(),
+            content: "inserted".to_string(),
+            insert_line: Some(5),
+            allow_clamp: Some(true),
+            line_endings: None,
+            encoding: None,
+            preview: None,
+            git_add: None,
+        });
+        assert!(tool.execute(None, &test_base, &CancellationToken::new()).await.is_ok());
+        let content = tokio::fs::read_to_string(test_base.join("test.txt")).await.unwrap();
+        assert_eq!(content, "line1\nline2\ninserted\n");
+    }
+
+    #[tokio::test]
+    async fn test_fs_write_encoding() {
+        let test_base = TestBase::new().await;
+        let path = test_base.join("file.txt").to_string_lossy().to_string();
+
+        let tool = FsWrite::Create(FileCreate {
+            path: path.clone(),
+            content: "héllo".to_string(),
+            encoding: Some("utf-16le".to_string()),
+            ..Default::default()
+        });
+        tool.validate(&test_base, None).await.unwrap();
+        tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        let expected = "héllo".encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<_>>();
+        assert_eq!(tokio::fs::read(&path).await.unwrap(), expected);
+
+        let tool = FsWrite::Create(FileCreate {
+            path: path.clone(),
+            content: "café\n".to_string(),
+            encoding: Some("latin-1".to_string()),
+            ..Default::default()
+        });
+        tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert_eq!(tokio::fs::read(&path).await.unwrap(), b"caf\xe9\n");
+
+        let tool = FsWrite::Insert(Insert {
+            path: path.clone(),
+            content: "naïve".to_string(),
+            insert_line: None,
+            allow_clamp: None,
+            line_endings: None,
+            encoding: Some("latin-1".to_string()),
+            preview: None,
+            git_add: None,
+        });
+        tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert_eq!(tokio::fs::read(&path).await.unwrap(), b"caf\xe9\nna\xefve");
+
+        // Unknown encodings and unencodable characters are rejected.
+        let tool = FsWrite::Create(FileCreate {
+            path: path.clone(),
+            content: "hello".to_string(),
+            encoding: Some("not-an-encoding".to_string()),
+            ..Default::default()
+        });
+        assert!(tool.validate(&test_base, None).await.is_err());
+        assert!(tool.execute(None, &test_base, &CancellationToken::new()).await.is_err());
+        let tool = FsWrite::Create(FileCreate {
+            path: path.clone(),
+            content: "日本".to_string(),
+            encoding: Some("latin-1".to_string()),
+            ..Default::default()
+        });
+        let err = tool
+            .execute(None, &test_base, &CancellationToken::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("cannot be encoded"), "{}", err);
+        assert_eq!(tokio::fs::read(&path).await.unwrap(), b"caf\xe9\nna\xefve");
+    }
+
+    #[tokio::test]
+    async fn test_fs_write_git_add() {
+        let test_base = TestBase::new().await.with_file(("repo/a.txt", "a\n")).await;
+        let repo = test_base.join("repo");
+        git2::Repository::init(&repo).unwrap();
+        let staged_paths = || {
+            let repo = git2::Repository::open(&repo).unwrap();
+            let index = repo.index().unwrap();
+            index
+                .iter()
+                .map(|entry| String::from_utf8(entry.path).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let git_status = |output: &ToolExecutionOutput| match output.items.last() {
+            Some(ToolExecutionOutputItem::Json(v)) => {
+                serde_json::from_value::<GitStatus>(v["git_status"].clone()).unwrap()
+            },
+            other => panic!("expected git status, got {:?}", other),
+        };
+
+        let tool = FsWrite::Create(FileCreate {
+            path: repo.join("b.txt").to_string_lossy().to_string(),
+            content: "b\n".to_string(),
+            git_add: Some(true),
+            ..Default::default()
+        });
+        let output = tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert_eq!(git_status(&output), GitStatus::Staged);
+
+        let tool = FsWrite::Insert(Insert {
+            path: repo.join("a.txt").to_string_lossy().to_string(),
+            content: "more".to_string(),
+            insert_line: None,
+            allow_clamp: None,
+            line_endings: None,
+            encoding: None,
+            preview: None,
+            git_add: Some(true),
+        });
+        let output = tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert_eq!(git_status(&output), GitStatus::Staged);
+        assert_eq!(staged_paths(), vec!["a.txt", "b.txt"]);
+
+        // Files outside of a repository can't be staged.
+        let tool = FsWrite::Create(FileCreate {
+            path: test_base.join("outside.txt").to_string_lossy().to_string(),
+            content: "c".to_string(),
+            git_add: Some(true),
+            ..Default::default()
+        });
+        let output = tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert_eq!(git_status(&output), GitStatus::Unknown);
+    }
+
+    #[tokio::test]
+    async fn test_regex_replace() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("test.txt", "Foo foo\nfoo FOO\n"))
+            .await;
+        let path = test_base.join("test.txt").to_string_lossy().to_string();
+        let regex_replace = |pattern: &str, replacement: &str, flags: Option<&str>| {
+            FsWrite::RegexReplace(RegexReplaceCmd {
+                path: path.clone(),
+                pattern: pattern.to_string(),
+                replacement: replacement.to_string(),
+                flags: flags.map(String::from),
+                preview: None,
+                git_add: None,
+            })
+        };
+
+        // Only the first match is replaced without the g flag.
+        let tool = regex_replace("foo", "bar", None);
+        tool.validate(&test_base, None).await.unwrap();
+        let output = tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert!(matches!(
+            &output.items[0],
+            ToolExecutionOutputItem::Json(v) if v["replacements"] == 1
+        ));
+        let content = tokio::fs::read_to_string(&path).await.unwrap();
+        assert_eq!(content, "Foo bar\nfoo FOO\n");
+
+        let tool = regex_replace(r"(f)(o+)", "${2}$1", Some("gi"));
+        let output = tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert!(matches!(
+            &output.items[0],
+            ToolExecutionOutputItem::Json(v) if v["replacements"] == 3
+        ));
+        let content = tokio::fs::read_to_string(&path).await.unwrap();
+        assert_eq!(content, "ooF bar\noof OOF\n");
+
+        let tool = regex_replace("^oo", "xx", Some("gm"));
+        tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        let content = tokio::fs::read_to_string(&path).await.unwrap();
+        assert_eq!(content, "xxF bar\nxxf OOF\n");
+
+        let tool = regex_replace("missing", "x", Some("g"));
+        assert!(tool.execute(None, &test_base, &CancellationToken::new()).await.is_err());
+
+        assert!(
+            regex_replace("(unclosed", "x", None)
+                .validate(&test_base, None)
+                .await
+                .is_err()
+        );
+        assert!(
+            regex_replace("foo", "x", Some("q"))
+                .validate(&test_base, None)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_fs_write_context_diff_summary() {
+        let cases = [
+            (None, "a\nb\nc\n", (3, 0), "+3 -0 lines in src/main.rs"),
+            (Some("a\nb\nc\n"), "a\nx\nc\n", (1, 1), "+1 -1 lines in src/main.rs"),
+            (Some("a\nb\nc\n"), "a\n", (0, 2), "+0 -2 lines in src/main.rs"),
+            (Some("a\nb\n"), "a\nb\nc\nd\ne\n", (3, 0), "+3 -0 lines in src/main.rs"),
+            (Some("a\nb\n"), "a\nb\n", (0, 0), "+0 -0 lines in src/main.rs"),
+            (Some("a\nb\n"), "", (0, 2), "+0 -2 lines in src/main.rs"),
+        ];
+        for (before, after, lines_changed, expected) in cases {
+            let ctx = FsWriteContext::new("src/main.rs", before.map(String::from), after.to_string());
+            assert_eq!(ctx.lines_changed, Some(lines_changed), "{:?} -> {:?}", before, after);
+            assert_eq!(ctx.diff_summary(), expected, "{:?} -> {:?}", before, after);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fs_write_make_context() {
+        let test_base = TestBase::new().await.with_file(("test.txt", "line1\nline2\n")).await;
+        let path = test_base.join("test.txt").to_string_lossy().to_string();
+
+        let tool = FsWrite::StrReplace(StrReplace {
+            path: path.clone(),
+            old_str: "line2".to_string(),
+            new_str: "replaced\nadded".to_string(),
+            ..Default::default()
+        });
+        let ctx = tool.make_context(&test_base).await.unwrap();
+        assert_eq!(ctx.before.as_deref(), Some("line1\nline2\n"));
+        assert_eq!(ctx.after, "line1\nreplaced\nadded\n");
+        let diff = ctx.diff.as_deref().unwrap();
+        assert!(diff.contains("-line2\n+replaced\n+added\n"), "{}", diff);
+        assert_eq!(ctx.diff_summary(), format!("+2 -1 lines in {}", path));
+
+        let tool = FsWrite::Create(FileCreate {
+            path: test_base.join("new.txt").to_string_lossy().to_string(),
+            content: "hello\n".to_string(),
+            ..Default::default()
+        });
+        let ctx = tool.make_context(&test_base).await.unwrap();
+        assert!(ctx.before.is_none());
+        assert!(ctx.diff_summary().starts_with("+1 -0 lines in "));
+
+        // Deletes aren't diffed.
+        let tool = FsWrite::Delete(FileDelete { path: path.clone() });
+        let ctx = tool.make_context(&test_base).await.unwrap();
+        assert!(ctx.diff.is_none());
+        assert!(ctx.lines_changed.is_none());
+        assert_eq!(ctx.diff_summary(), format!("No diff for {}", path));
+    }
+
+    #[tokio::test]
+    async fn test_fs_write_preview() {
+        let test_base = TestBase::new().await.with_file(("test.txt", "line1\nline2\n")).await;
+
+        let tool = FsWrite::Create(FileCreate {
+            path: test_base.join("new.txt").to_string_lossy().to_string(),
+            content: "hello world".to_string(),
+            preview: Some(true),
+            ..Default::default()
+        });
+        let output = tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert!(!test_base.join("new.txt").exists());
+        let [ToolExecutionOutputItem::Text(text), ToolExecutionOutputItem::Json(json)] = &output.items[..] else {
+            panic!("expected text and json output");
+        };
+        assert_eq!(text, &format!("{}\nhello world", PREVIEW_PREFIX));
+        assert_eq!(json, &serde_json::json!({ "would_write_bytes": 11 }));
+
+        let tool = FsWrite::StrReplace(StrReplace {
+            path: test_base.join("test.txt").to_string_lossy().to_string(),
+            old_str: "line2".to_string(),
+            new_str: "replaced".to_string(),
+            preview: Some(true),
+            ..Default::default()
+        });
+        let output = tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        let ToolExecutionOutputItem::Text(diff) = &output.items[0] else {
+            panic!("expected text output");
+        };
+        assert!(diff.starts_with(PREVIEW_PREFIX));
+        assert!(diff.contains("-line2\n+replaced\n"), "{}", diff);
+
+        let tool = FsWrite::Insert(Insert {
+            path: test_base.join("test.txt").to_string_lossy().to_string(),
+            content: "inserted".to_string(),
+            insert_line: Some(1),
+            allow_clamp: None,
+            line_endings: None,
+            encoding: None,
+            preview: Some(true),
+            git_add: None,
+        });
+        let output = tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        let ToolExecutionOutputItem::Text(diff) = &output.items[0] else {
+            panic!("expected text output");
+        };
+        assert!(diff.contains("+inserted\n"), "{}", diff);
+
+        let content = tokio::fs::read_to_string(test_base.join("test.txt")).await.unwrap();
+        assert_eq!(content, "line1\nline2\n");
+    }
+
     #[tokio::test]
     async fn test_insert_append() {
         let test_base = TestBase::new().await.with_file(("test.txt", "existing")).await;
@@ -465,9 +2398,14 @@ mod tests {
             path: test_base.join("test.txt").to_string_lossy().to_string(),
             content: "appended".to_string(),
             insert_line: None,
+            allow_clamp: None,
+            line_endings: None,
+            encoding: None,
+            preview: None,
+            git_add: None,
         });
 
-        assert!(tool.execute(None, &test_base).await.is_ok());
+        assert!(tool.execute(None, &test_base, &CancellationToken::new()).await.is_ok());
 
         let content = tokio::fs::read_to_string(test_base.join("test.txt")).await.unwrap();
         assert_eq!(content, "existing\nappended");
@@ -479,9 +2417,213 @@ mod tests {
         let tool = FsWrite::Create(FileCreate {
             path: "".to_string(),
             content: "content".to_string(),
+            ..Default::default()
         });
 
-        assert!(tool.validate(&test_base).await.is_err());
+        assert!(tool.validate(&test_base, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_delete_file() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("test.txt", "hello"))
+            .await
+            .with_file(("dir/nested.txt", "nested"))
+            .await;
+        let delete = |path: &str| {
+            FsWrite::Delete(FileDelete {
+                path: test_base.join(path).to_string_lossy().to_string(),
+            })
+        };
+
+        let tool = delete("test.txt");
+        tool.validate(&test_base, None).await.unwrap();
+        tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert!(!test_base.join("test.txt").exists());
+
+        let tool = delete("test.txt");
+        assert!(tool.validate(&test_base, None).await.is_err());
+        let err = tool
+            .execute(None, &test_base, &CancellationToken::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolExecutionError::Io { .. }), "{:?}", err);
+        assert!(
+            err.to_string().starts_with("failed to delete: path not found"),
+            "{}",
+            err
+        );
+
+        // Directories are never removed.
+        let tool = delete("dir");
+        assert!(tool.validate(&test_base, None).await.is_err());
+        let err = tool
+            .execute(None, &test_base, &CancellationToken::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolExecutionError::Io { .. }), "{:?}", err);
+        assert!(
+            err.to_string().starts_with("failed to delete: path is a directory"),
+            "{}",
+            err
+        );
+        assert!(test_base.join("dir/nested.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_move_file() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("a.txt", "alpha"))
+            .await
+            .with_file(("b.txt", "beta"))
+            .await;
+        let move_file = |source: &str, destination: &str| {
+            FsWrite::Move(FileMove {
+                source: test_base.join(source).to_string_lossy().to_string(),
+                destination: test_base.join(destination).to_string_lossy().to_string(),
+                overwrite: false,
+            })
+        };
+
+        // The context reflects the write to the destination.
+        let tool = move_file("a.txt", "nested/dir/c.txt");
+        tool.validate(&test_base, None).await.unwrap();
+        let ctx = tool.make_context(&test_base).await.unwrap();
+        assert_eq!(ctx.path, test_base.join("nested/dir/c.txt").to_string_lossy());
+        assert_eq!(ctx.before, None);
+        assert_eq!(ctx.after, "alpha");
+        assert!(ctx.diff.is_none());
+        let output = tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert_eq!(output.text_items().collect::<Vec<_>>(), [""]);
+        assert!(!test_base.join("a.txt").exists());
+        assert_eq!(
+            std::fs::read_to_string(test_base.join("nested/dir/c.txt")).unwrap(),
+            "alpha"
+        );
+
+        // Moving over an existing file requires overwrite, and is reported in the output.
+        let mut tool = move_file("nested/dir/c.txt", "b.txt");
+        let err = tool.validate(&test_base, None).await.unwrap_err();
+        assert!(err.contains("Set overwrite=true"), "{}", err);
+        if let FsWrite::Move(v) = &mut tool {
+            v.overwrite = true;
+        }
+        tool.validate(&test_base, None).await.unwrap();
+        let output = tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert!(
+            output
+                .text_items()
+                .next()
+                .unwrap()
+                .starts_with("Overwrote the existing file"),
+            "{:?}",
+            output
+        );
+        assert_eq!(std::fs::read_to_string(test_base.join("b.txt")).unwrap(), "alpha");
+
+        assert!(
+            move_file("missing.txt", "d.txt")
+                .validate(&test_base, None)
+                .await
+                .is_err()
+        );
+        assert!(move_file("nested", "d").validate(&test_base, None).await.is_err());
+        assert!(move_file("b.txt", "nested").validate(&test_base, None).await.is_err());
+        assert_eq!(move_file("b.txt", "d.txt").paths().len(), 2);
+
+        // `path` is accepted as an alias of `source`.
+        for key in ["source", "path"] {
+            let tool: FsWrite = serde_json::from_value(serde_json::json!({
+                "command": "move",
+                key: "b.txt",
+                "destination": "e.txt",
+            }))
+            .unwrap();
+            assert_eq!(tool.path(), "b.txt");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fs_write_backup() {
+        let test_base = TestBase::new().await.with_file(("test.txt", "one")).await;
+        let path = test_base.join("test.txt");
+        let backup = test_base.join("test.txt.bak");
+        let str_replace = |old_str: &str, new_str: &str| {
+            FsWrite::StrReplace(StrReplace {
+                path: path.to_string_lossy().to_string(),
+                old_str: old_str.to_string(),
+                new_str: new_str.to_string(),
+                backup: Some(true),
+                ..Default::default()
+            })
+        };
+
+        let tool = str_replace("one", "two");
+        let ctx = tool.make_context(&test_base).await.unwrap();
+        assert_eq!(ctx.backup_path, Some(backup.to_string_lossy().to_string()));
+        let output = tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert_eq!(
+            output.json_items().next().unwrap(),
+            &serde_json::json!({ "backup_path": backup.to_string_lossy() })
+        );
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "one");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "two");
+
+        // A failed replacement leaves the previous backup in place.
+        assert!(
+            str_replace("missing", "three")
+                .execute(None, &test_base, &CancellationToken::new())
+                .await
+                .is_err()
+        );
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "one");
+
+        // Previous backups are overwritten.
+        let tool = FsWrite::Create(FileCreate {
+            path: path.to_string_lossy().to_string(),
+            content: "three".to_string(),
+            backup: Some(true),
+            ..Default::default()
+        });
+        tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "two");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "three");
+
+        // New files are not backed up.
+        let new_path = test_base.join("new.txt");
+        let tool = FsWrite::Create(FileCreate {
+            path: new_path.to_string_lossy().to_string(),
+            content: "new".to_string(),
+            backup: Some(true),
+            ..Default::default()
+        });
+        assert_eq!(tool.make_context(&test_base).await.unwrap().backup_path, None);
+        tool.execute(None, &test_base, &CancellationToken::new()).await.unwrap();
+        assert!(!test_base.join("new.txt.bak").exists());
+
+        // Failing to create the backup due to permissions does not fail the write.
+        #[cfg(unix)]
+        if !nix::unistd::geteuid().is_root() {
+            use std::os::unix::fs::PermissionsExt as _;
+            std::fs::remove_file(&backup).unwrap();
+            let dir = test_base.join("");
+            std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o555)).unwrap();
+            let result = str_replace("three", "four")
+                .execute(None, &test_base, &CancellationToken::new())
+                .await;
+            std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+            let output = result.unwrap();
+            assert!(
+                output
+                    .text_items()
+                    .next()
+                    .unwrap()
+                    .starts_with("Warning: failed to back up")
+            );
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), "four");
+        }
     }
 
     #[tokio::test]
@@ -491,9 +2633,118 @@ mod tests {
             path: "/nonexistent/file.txt".to_string(),
             old_str: "old".to_string(),
             new_str: "new".to_string(),
-            replace_all: false,
+            ..Default::default()
         });
 
-        assert!(tool.validate(&test_base).await.is_err());
+        assert!(tool.validate(&test_base, None).await.is_err());
+    }
+
+    fn cloud_create(path: &str) -> FsWrite {
+        FsWrite::Create(FileCreate {
+            path: path.to_string(),
+            content: "{\"a\": 1}".to_string(),
+            content_type: Some("application/json".to_string()),
+            ..Default::default()
+        })
+    }
+
+    fn cloud_str_replace(path: &str) -> FsWrite {
+        FsWrite::StrReplace(StrReplace {
+            path: path.to_string(),
+            old_str: "world".to_string(),
+            new_str: "rust".to_string(),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_fs_write_validate_cloud_path() {
+        let test_base = TestBase::new().await;
+        let res = cloud_create("s3://bucket/file.json").validate(&test_base, None).await;
+        if cloud_storage::CLOUD_STORAGE_ENABLED {
+            res.unwrap();
+            // The object is checked for existence when it is read.
+            cloud_str_replace("s3://bucket/missing.txt")
+                .validate(&test_base, None)
+                .await
+                .unwrap();
+        } else {
+            assert!(res.unwrap_err().contains("cloud-storage feature"));
+        }
+        assert!(
+            cloud_create("gcs://bucket/file.json")
+                .validate(&test_base, None)
+                .await
+                .is_err()
+        );
+        assert!(cloud_create("s3://bucket").validate(&test_base, None).await.is_err());
+    }
+
+    #[cfg(feature = "cloud-storage")]
+    #[tokio::test]
+    async fn test_fs_write_s3_object() {
+        use crate::util::cloud_storage::test_server::{
+            MockResponse,
+            serve,
+        };
+
+        let (url, server) = serve(vec![
+            MockResponse::new(200, Some("\"v1\""), ""),
+            MockResponse::new(200, Some("\"v1\""), "hello world"),
+            MockResponse::new(200, Some("\"v2\""), ""),
+            MockResponse::new(200, Some("\"v2\""), "hello world"),
+            MockResponse::new(412, None, ""),
+        ])
+        .await;
+        let test_base = TestBase::new()
+            .await
+            .with_var("AWS_ENDPOINT_URL_S3", url)
+            .with_var("AWS_ACCESS_KEY_ID", "AKIDEXAMPLE")
+            .with_var("AWS_SECRET_ACCESS_KEY", "secret")
+            .with_var("AWS_REGION", "us-west-2");
+        let cancel_token = CancellationToken::new();
+
+        let output = cloud_create("s3://my-bucket/data/file.json")
+            .execute(None, &test_base, &cancel_token)
+            .await
+            .unwrap();
+        assert_eq!(
+            output.json_items().next().unwrap(),
+            &serde_json::json!({
+                "source": "s3",
+                "bucket": "my-bucket",
+                "key": "data/file.json",
+                "etag": "\"v1\"",
+            })
+        );
+
+        cloud_str_replace("s3://my-bucket/hello.txt")
+            .execute(None, &test_base, &cancel_token)
+            .await
+            .unwrap();
+
+        // The write fails if the object was modified after being read.
+        let err = cloud_str_replace("s3://my-bucket/hello.txt")
+            .execute(None, &test_base, &cancel_token)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("modified"), "{}", err);
+
+        let requests = server.await.unwrap();
+        let put = &requests[0];
+        assert_eq!(
+            (put.method.as_str(), put.path.as_str()),
+            ("PUT", "/my-bucket/data/file.json")
+        );
+        assert_eq!(put.header("content-type"), Some("application/json"));
+        assert_eq!(put.body, b"{\"a\": 1}");
+        assert!(put.header("authorization").unwrap().starts_with("AWS4-HMAC-SHA256"));
+
+        assert_eq!(requests[1].method, "GET");
+        let put = &requests[2];
+        assert_eq!(put.method, "PUT");
+        assert_eq!(put.header("if-match"), Some("\"v1\""));
+        assert_eq!(put.body, b"hello rust");
+        assert_eq!(requests[4].header("if-match"), Some("\"v2\""));
     }
 }
@@ -1,34 +1,140 @@
-#![allow(dead_code)]
+use std::borrow::Cow;
+use std::path::{
+    Path,
+    PathBuf,
+};
 
 use serde::{
     Deserialize,
     Serialize,
 };
+use tracing::warn;
+
+use super::{
+    BuiltInToolName,
+    BuiltInToolTrait,
+    ToolExample,
+    ToolExecutionError,
+    ToolExecutionOutput,
+    ToolExecutionOutputItem,
+    ToolExecutionResult,
+    ToolLocale,
+    ToolOutputSender,
+    send_output,
+};
+use crate::agent::util::glob::matches_any_pattern;
+use crate::util::path::canonicalize_path_sys;
+use crate::util::providers::SystemProvider;
 
 const GREP_TOOL_DESCRIPTION: &str = r#"
 A tool for searching file content.
+
+WHEN TO USE THIS TOOL:
+- Use instead of running the `grep` command to find the lines of files that match a pattern
+
+HOW TO USE:
+- Provide a regex pattern to search for
+- Optionally provide the path to a file or directory to search, defaulting to the current working directory
+- Directories are searched recursively unless recursive is set to false
+- Optionally provide a list of glob patterns in paths to only search matching files
+- Optionally set wholeWord to true to only match the pattern at word boundaries, like `grep -w`
+- Matches are returned as `path:line_number:line`
+
+LIMITATIONS:
+- Binary files, files larger than maxFileSizeBytes, symbolic links, and unreadable files are skipped
+- Hidden directories (including `.git`) and `node_modules` are not searched recursively, provide their path to search them
+- Only 1000 matches will be returned
+"#;
+
+const GREP_TOOL_DESCRIPTION_ZH_CN: &str = r#"
+用于搜索文件内容的工具。
+
+何时使用此工具：
+- 代替运行 `grep` 命令，用于查找文件中与模式匹配的行
+
+使用方法：
+- 提供要搜索的正则表达式模式
+- 可选地提供要搜索的文件或目录路径，默认为当前工作目录
+- 除非将 recursive 设置为 false，否则会递归搜索目录
+- 可选地在 paths 中提供 glob 模式列表，以仅搜索匹配的文件
+- 可选地将 wholeWord 设置为 true，以仅在单词边界处匹配模式，类似于 `grep -w`
+- 匹配结果以 `path:line_number:line` 的形式返回
+
+限制：
+- 会跳过二进制文件、大于 maxFileSizeBytes 的文件、符号链接以及无法读取的文件
+- 递归搜索时不会搜索隐藏目录（包括 `.git`）和 `node_modules`，如需搜索请提供其路径
+- 最多返回 1000 个匹配项
+"#;
+
+const GREP_TOOL_DESCRIPTION_JA: &str = r#"
+ファイルの内容を検索するためのツールです。
+
+このツールを使用する場面:
+- パターンに一致するファイルの行を探す際に、`grep` コマンドを実行する代わりに使用します
+
+使い方:
+- 検索する正規表現パターンを指定します
+- 必要に応じて検索するファイルまたはディレクトリのパスを指定します。デフォルトは現在の作業ディレクトリです
+- recursive を false に設定しない限り、ディレクトリは再帰的に検索されます
+- 必要に応じて paths に glob パターンのリストを指定し、一致するファイルのみを検索します
+- 必要に応じて wholeWord を true に設定し、`grep -w` のように単語境界でのみパターンに一致させます
+- 一致した行は `path:line_number:line` の形式で返されます
+
+制限事項:
+- バイナリファイル、maxFileSizeBytes より大きいファイル、シンボリックリンク、読み取れないファイルはスキップされます
+- 再帰検索では隠しディレクトリ (`.git` を含む) と `node_modules` は検索されません。検索するにはそのパスを指定します
+- 返される一致は最大 1000 件です
 "#;
 
 const GREP_SCHEMA: &str = r#"
 {
     "type": "object",
     "properties": {
-        "base": {
+        "pattern": {
             "type": "string",
-            "description": "Path to the directory to start the search from. Defaults to current working directory"
+            "description": "Regex to search files for"
         },
-        "pattern": {
-            "type": "integer",
-            "description": "Regex to search files for",
-            "default": 0
+        "path": {
+            "type": "string",
+            "description": "Path to the file or directory to search. Defaults to the current working directory"
+        },
+        "recursive": {
+            "type": "boolean",
+            "description": "Whether or not to search the subdirectories of path",
+            "default": true
         },
         "paths": {
             "type": "array",
-            "description": "List of file paths to search. Supports glob matching",
+            "description": "List of glob patterns, relative to path, of the files to search. Defaults to all files",
             "items": {
                 "type": "string",
                 "description": "Glob pattern"
             }
+        },
+        "skipBinary": {
+            "type": "boolean",
+            "description": "Whether or not to skip binary files",
+            "default": true
+        },
+        "includeBinary": {
+            "type": "boolean",
+            "description": "Whether or not to search binary files. Overrides skipBinary",
+            "default": false
+        },
+        "maxFileSizeBytes": {
+            "type": "integer",
+            "description": "Files larger than this size in bytes are skipped",
+            "default": 1048576
+        },
+        "wholeWord": {
+            "type": "boolean",
+            "description": "Whether or not to only match the pattern as a whole word, e.g. `foo` matches `foo bar` but not `foobar`",
+            "default": false
+        },
+        "lineOffset": {
+            "type": "integer",
+            "description": "Number added to each reported line number. Use when searching a partial read of a file so that line numbers are relative to the full file",
+            "default": 0
         }
     },
     "required": [
@@ -37,17 +143,633 @@ const GREP_SCHEMA: &str = r#"
 }
 "#;
 
-// impl BuiltInToolTrait for Grep {
-//     const DESCRIPTION: &str = GREP_TOOL_DESCRIPTION;
-//     const INPUT_SCHEMA: &str = GREP_SCHEMA;
-//     const NAME: BuiltInToolName = BuiltInToolName::Grep;
-// }
+impl BuiltInToolTrait for Grep {
+    fn name() -> BuiltInToolName {
+        BuiltInToolName::Grep
+    }
+
+    fn description() -> std::borrow::Cow<'static, str> {
+        GREP_TOOL_DESCRIPTION.into()
+    }
+
+    fn description_for_locale(locale: &str) -> std::borrow::Cow<'static, str> {
+        match ToolLocale::parse(locale) {
+            Some(ToolLocale::ZhCn) => GREP_TOOL_DESCRIPTION_ZH_CN.into(),
+            Some(ToolLocale::Ja) => GREP_TOOL_DESCRIPTION_JA.into(),
+            None => Self::description(),
+        }
+    }
+
+    fn input_schema() -> std::borrow::Cow<'static, str> {
+        GREP_SCHEMA.into()
+    }
+
+    fn examples() -> Vec<ToolExample> {
+        vec![ToolExample::new(
+            "Find the definition of a function in the Rust files of a directory",
+            serde_json::json!({ "pattern": r"fn parse_args\(", "path": "src", "paths": ["*.rs"] }),
+        )]
+    }
+}
+
+/// Number of bytes checked for a NUL byte when detecting binary files.
+const BINARY_DETECTION_BYTES: usize = 512;
+
+/// Default max size of a file that will be searched.
+const DEFAULT_MAX_FILE_SIZE_BYTES: u64 = 1024 * 1024;
+
+/// The max number of matching lines to send to the model.
+const MAX_GREP_MATCHES: usize = 1000;
+
+/// Directory names that are not searched when searching recursively, in addition to hidden
+/// directories. The model can still search them by providing their path explicitly.
+const PRUNED_DIRS: [&str; 1] = ["node_modules"];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Grep {
     pattern: String,
-    base: Option<String>,
-    paths: Option<String>,
+    /// File or directory to search. Defaults to the current working directory.
+    path: Option<String>,
+    /// Whether subdirectories of [Grep::path] are searched. Defaults to true.
+    recursive: Option<bool>,
+    /// Glob patterns, relative to [Grep::path], of the files to search.
+    paths: Option<Vec<String>>,
+    /// Whether binary files are skipped. Defaults to true.
+    skip_binary: Option<bool>,
+    /// Opts in to searching binary files, taking precedence over `skip_binary`.
+    include_binary: Option<bool>,
+    /// Files larger than this are skipped. Defaults to [DEFAULT_MAX_FILE_SIZE_BYTES].
+    max_file_size_bytes: Option<u64>,
+    /// Added to each reported line number, for content that starts partway through a file.
+    line_offset: Option<u32>,
+    /// Whether the pattern only matches at word boundaries. Defaults to false.
+    whole_word: Option<bool>,
+}
+
+/// A line matching the [Grep] pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GrepMatch {
+    /// 1-indexed line number, including [Grep::line_offset].
+    pub line_number: usize,
+    pub line: String,
 }
 
-impl Grep {}
+/// Files that were not searched, returned as the JSON summary item of the output.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GrepSummary {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_binary_files: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_large_files: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_symlinks: Vec<String>,
+    /// Files and directories that could not be read, e.g. due to insufficient permissions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_unreadable: Vec<String>,
+}
+
+impl GrepSummary {
+    fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+impl Grep {
+    /// [Grep::path], defaulting to the current working directory.
+    pub fn path(&self) -> &str {
+        self.path.as_deref().unwrap_or(".")
+    }
+
+    fn canonical_path<P: SystemProvider>(&self, provider: &P) -> Result<PathBuf, String> {
+        Ok(PathBuf::from(
+            canonicalize_path_sys(self.path(), provider).map_err(|e| e.to_string())?,
+        ))
+    }
+
+    fn recursive(&self) -> bool {
+        self.recursive.unwrap_or(true)
+    }
+
+    fn max_file_size_bytes(&self) -> u64 {
+        self.max_file_size_bytes.unwrap_or(DEFAULT_MAX_FILE_SIZE_BYTES)
+    }
+
+    fn skip_binary(&self) -> bool {
+        !self.include_binary.unwrap_or(false) && self.skip_binary.unwrap_or(true)
+    }
+
+    fn whole_word(&self) -> bool {
+        self.whole_word.unwrap_or(false)
+    }
+
+    pub async fn validate<P: SystemProvider>(&self, provider: &P) -> Result<(), String> {
+        let path = self.canonical_path(provider)?;
+        if tokio::fs::symlink_metadata(&path).await.is_err() {
+            return Err(format!("No file or directory at {} exists", path.to_string_lossy()));
+        }
+        if self.whole_word() && self.pattern.contains(r"\b") {
+            warn!(pattern = %self.pattern, "pattern already contains a word boundary, wholeWord adds another");
+        }
+        self.regex()?;
+        Ok(())
+    }
+
+    pub async fn execute<P: SystemProvider>(&self, provider: &P) -> ToolExecutionResult {
+        self.execute_inner(provider, None).await
+    }
+
+    /// Same as [Grep::execute], except that the matching lines of each file are sent through
+    /// `output` once the file is searched. The returned output only contains the items following
+    /// the matches.
+    pub async fn execute_stream<P: SystemProvider>(
+        &self,
+        provider: &P,
+        output: &ToolOutputSender,
+    ) -> ToolExecutionResult {
+        self.execute_inner(provider, Some(output)).await
+    }
+
+    async fn execute_inner<P: SystemProvider>(
+        &self,
+        provider: &P,
+        output: Option<&ToolOutputSender>,
+    ) -> ToolExecutionResult {
+        let root = self.canonical_path(provider)?;
+        let re = self.regex()?;
+
+        let mut summary = GrepSummary::default();
+        // Matching lines that have not been sent yet.
+        let mut lines = Vec::new();
+        let mut match_count = 0;
+        let mut truncated = false;
+        'files: for file in self.find_files(&root, &mut summary).await? {
+            let Some(content) = self.read_searchable_file(&file, &mut summary).await? else {
+                continue;
+            };
+            for m in self.search_content(&re, &content) {
+                if match_count >= MAX_GREP_MATCHES {
+                    truncated = true;
+                    break 'files;
+                }
+                match_count += 1;
+                lines.push(format!("{}:{}:{}", file.to_string_lossy(), m.line_number, m.line));
+            }
+            if let Some(output) = output {
+                send_lines(output, &mut lines).await;
+            }
+        }
+        if let Some(output) = output {
+            send_lines(output, &mut lines).await;
+        }
+
+        if !summary.skipped_binary_files.is_empty() {
+            warn!(files = ?summary.skipped_binary_files, "skipped binary files");
+        }
+        if match_count == 0 {
+            lines.push("No matches found".to_string());
+        } else if truncated {
+            lines.push(format!(
+                "Results were truncated to the first {} matches",
+                MAX_GREP_MATCHES
+            ));
+        }
+
+        let mut items = Vec::new();
+        if !lines.is_empty() {
+            items.push(ToolExecutionOutputItem::Text(lines.join("\n")));
+        }
+        if !summary.is_empty() {
+            let summary =
+                serde_json::to_value(summary).map_err(|e| format!("failed to serialize grep summary: {}", e))?;
+            items.push(ToolExecutionOutputItem::Json(summary));
+        }
+        Ok(ToolExecutionOutput::new(items))
+    }
+
+    /// Returns the files to search under `root`, sorted by path. Symbolic links are not followed,
+    /// so that a search cannot loop or leave `root`, and are recorded in `summary` instead.
+    ///
+    /// Hidden directories (including `.git`) and [PRUNED_DIRS] are not searched, and directories
+    /// under `root` that cannot be read are recorded in `summary`.
+    async fn find_files(&self, root: &Path, summary: &mut GrepSummary) -> Result<Vec<PathBuf>, ToolExecutionError> {
+        let md = tokio::fs::symlink_metadata(root)
+            .await
+            .map_err(|e| ToolExecutionError::from_io_result("failed to get metadata", Some(root), e))?;
+        if !md.is_dir() {
+            // A file given explicitly is searched even if it is a symbolic link.
+            return Ok(vec![root.to_path_buf()]);
+        }
+
+        let mut files = Vec::new();
+        let mut dirs = vec![root.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            let mut read_dir = match tokio::fs::read_dir(&dir).await {
+                Ok(read_dir) => read_dir,
+                Err(e) if dir == root => {
+                    return Err(ToolExecutionError::from_io_result(
+                        "failed to read directory",
+                        Some(&dir),
+                        e,
+                    ));
+                },
+                Err(e) => {
+                    warn!(?dir, ?e, "failed to read directory");
+                    summary.skipped_unreadable.push(dir.to_string_lossy().to_string());
+                    continue;
+                },
+            };
+            loop {
+                let entry = match read_dir.next_entry().await {
+                    Ok(Some(entry)) => entry,
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!(?dir, ?e, "failed to read directory");
+                        summary.skipped_unreadable.push(dir.to_string_lossy().to_string());
+                        break;
+                    },
+                };
+                let path = entry.path();
+                let Ok(file_type) = entry.file_type().await else {
+                    summary.skipped_unreadable.push(path.to_string_lossy().to_string());
+                    continue;
+                };
+                if file_type.is_symlink() {
+                    summary.skipped_symlinks.push(path.to_string_lossy().to_string());
+                } else if file_type.is_dir() {
+                    if self.recursive() && !is_pruned_dir(&path) {
+                        dirs.push(path);
+                    }
+                } else if self.matches_paths(root, &path) {
+                    files.push(path);
+                }
+            }
+        }
+        files.sort();
+        summary.skipped_symlinks.sort();
+        summary.skipped_unreadable.sort();
+        Ok(files)
+    }
+
+    /// Whether `path` matches [Grep::paths], either by its path relative to `root` or its file
+    /// name.
+    fn matches_paths(&self, root: &Path, path: &Path) -> bool {
+        let Some(patterns) = &self.paths else {
+            return true;
+        };
+        let relative = path.strip_prefix(root).unwrap_or(path).to_string_lossy();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        matches_any_pattern(patterns, &relative) || matches_any_pattern(patterns, &name)
+    }
+
+    /// Compiles the pattern, surrounded by word boundaries if [Grep::whole_word] is set.
+    fn regex(&self) -> Result<regex::Regex, String> {
+        let pattern = if self.whole_word() {
+            Cow::Owned(format!(r"\b(?:{})\b", self.pattern))
+        } else {
+            Cow::Borrowed(self.pattern.as_str())
+        };
+        regex::Regex::new(&pattern).map_err(|e| format!("invalid pattern '{}': {}", self.pattern, e))
+    }
+
+    /// Returns the lines of `content` that match `re`, compiled by [Grep::regex].
+    fn search_content(&self, re: &regex::Regex, content: &str) -> Vec<GrepMatch> {
+        let offset = self.line_offset.unwrap_or(0) as usize;
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| re.is_match(line))
+            .map(|(i, line)| GrepMatch {
+                line_number: i + 1 + offset,
+                line: line.to_string(),
+            })
+            .collect()
+    }
+
+    /// Reads the content of the file at `path` to be searched, returning `None` if the file
+    /// should be skipped. Skipped files, including those that cannot be read, are recorded in
+    /// `summary`.
+    async fn read_searchable_file(
+        &self,
+        path: impl AsRef<Path>,
+        summary: &mut GrepSummary,
+    ) -> Result<Option<String>, ToolExecutionError> {
+        let path = path.as_ref();
+        let content = match tokio::fs::metadata(path).await {
+            Ok(md) if md.len() > self.max_file_size_bytes() => {
+                summary.skipped_large_files.push(path.to_string_lossy().to_string());
+                return Ok(None);
+            },
+            Ok(_) => tokio::fs::read(path).await,
+            Err(e) => Err(e),
+        };
+        let content = match content {
+            Ok(content) => content,
+            Err(e) => {
+                warn!(?path, ?e, "failed to read file");
+                summary.skipped_unreadable.push(path.to_string_lossy().to_string());
+                return Ok(None);
+            },
+        };
+        if self.skip_binary() && is_binary(&content) {
+            summary.skipped_binary_files.push(path.to_string_lossy().to_string());
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&content).into_owned()))
+    }
+}
+
+/// Whether the directory at `path` should not be searched, i.e. it is hidden or one of
+/// [PRUNED_DIRS].
+fn is_pruned_dir(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.starts_with('.') || PRUNED_DIRS.contains(&name.as_ref())
+}
+
+/// A file is considered binary if it contains a NUL byte within the first
+/// [BINARY_DETECTION_BYTES] bytes.
+fn is_binary(content: &[u8]) -> bool {
+    content.iter().take(BINARY_DETECTION_BYTES).any(|b| *b == 0)
+}
+
+/// Sends the matching `lines` as a single text item, if there are any, clearing them.
+async fn send_lines(output: &ToolOutputSender, lines: &mut Vec<String>) {
+    if !lines.is_empty() {
+        send_output(output, ToolExecutionOutputItem::Text(lines.join("\n"))).await;
+        lines.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test::TestBase;
+
+    fn grep(args: serde_json::Value) -> Grep {
+        serde_json::from_value(args).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_grep_skip_binary_files() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("text.txt", "hello world"))
+            .await
+            .with_file(("binary.bin", "hello\0world"))
+            .await;
+
+        let tool = grep(serde_json::json!({ "pattern": "hello" }));
+        let mut summary = GrepSummary::default();
+        let text = tool
+            .read_searchable_file(test_base.join("text.txt"), &mut summary)
+            .await
+            .unwrap();
+        assert_eq!(text.as_deref(), Some("hello world"));
+        let binary = tool
+            .read_searchable_file(test_base.join("binary.bin"), &mut summary)
+            .await
+            .unwrap();
+        assert!(binary.is_none());
+        assert_eq!(summary.skipped_binary_files, vec![
+            test_base.join("binary.bin").to_string_lossy().to_string()
+        ]);
+        assert_eq!(
+            serde_json::to_value(&summary).unwrap(),
+            serde_json::json!({ "skipped_binary_files": [test_base.join("binary.bin").to_string_lossy()] })
+        );
+
+        for args in [
+            serde_json::json!({ "pattern": "hello", "skipBinary": false }),
+            serde_json::json!({ "pattern": "hello", "includeBinary": true }),
+        ] {
+            let tool = grep(args);
+            let mut summary = GrepSummary::default();
+            let binary = tool
+                .read_searchable_file(test_base.join("binary.bin"), &mut summary)
+                .await
+                .unwrap();
+            assert!(binary.is_some());
+            assert!(summary.skipped_binary_files.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grep_skip_large_files() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("small.txt", "a".repeat(10)))
+            .await
+            .with_file(("large.txt", "a".repeat(20)))
+            .await;
+
+        let tool = grep(serde_json::json!({ "pattern": "a", "maxFileSizeBytes": 15 }));
+        let mut summary = GrepSummary::default();
+        let small = tool
+            .read_searchable_file(test_base.join("small.txt"), &mut summary)
+            .await
+            .unwrap();
+        assert!(small.is_some());
+        let large = tool
+            .read_searchable_file(test_base.join("large.txt"), &mut summary)
+            .await
+            .unwrap();
+        assert!(large.is_none());
+        assert_eq!(summary.skipped_large_files, vec![
+            test_base.join("large.txt").to_string_lossy().to_string()
+        ]);
+
+        // Both files are below the default limit.
+        let tool = grep(serde_json::json!({ "pattern": "a" }));
+        let mut summary = GrepSummary::default();
+        let large = tool
+            .read_searchable_file(test_base.join("large.txt"), &mut summary)
+            .await
+            .unwrap();
+        assert!(large.is_some());
+        assert_eq!(summary, GrepSummary::default());
+    }
+
+    #[tokio::test]
+    async fn test_grep_whole_word() {
+        let content = "foo bar\nfoobar\nbar_foo\n(foo)\nbaz";
+        let lines = |tool: &Grep| {
+            tool.search_content(&tool.regex().unwrap(), content)
+                .into_iter()
+                .map(|m| m.line)
+                .collect::<Vec<_>>()
+        };
+
+        let tool = grep(serde_json::json!({ "pattern": "foo" }));
+        assert_eq!(lines(&tool), vec!["foo bar", "foobar", "bar_foo", "(foo)"]);
+
+        let tool = grep(serde_json::json!({ "pattern": "foo", "wholeWord": true }));
+        tool.validate(&TestBase::new().await).await.unwrap();
+        assert_eq!(lines(&tool), vec!["foo bar", "(foo)"]);
+
+        // Alternations are grouped so that the boundaries apply to every branch.
+        let tool = grep(serde_json::json!({ "pattern": "foo|baz", "wholeWord": true }));
+        assert_eq!(lines(&tool), vec!["foo bar", "(foo)", "baz"]);
+
+        // Patterns that already contain a word boundary are still accepted.
+        let tool = grep(serde_json::json!({ "pattern": "\\bfoo", "wholeWord": true }));
+        tool.validate(&TestBase::new().await).await.unwrap();
+        assert_eq!(lines(&tool), vec!["foo bar", "(foo)"]);
+
+        let tool = grep(serde_json::json!({ "pattern": "(", "wholeWord": true }));
+        assert!(tool.validate(&TestBase::new().await).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_grep_execute() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("root/a.rs", "fn main() {}\nlet x = 1;\nfn helper() {}\n"))
+            .await
+            .with_file(("root/notes.txt", "fn in a text file\n"))
+            .await
+            .with_file(("root/nested/b.rs", "fn nested() {}\n"))
+            .await
+            .with_file(("root/data.bin", "fn\0binary"))
+            .await
+            .with_file(("root/large.rs", format!("fn large() {{}}\n{}", "a".repeat(100))))
+            .await;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(test_base.join("root/a.rs"), test_base.join("root/link.rs")).unwrap();
+        let root = test_base.join("root");
+        let path = |p: &str| root.join(p).to_string_lossy().to_string();
+
+        let tool = grep(serde_json::json!({
+            "pattern": "^fn",
+            "path": root.to_string_lossy(),
+            "paths": ["*.rs"],
+            "maxFileSizeBytes": 64
+        }));
+        tool.validate(&test_base).await.unwrap();
+        let output = tool.execute(&test_base).await.unwrap();
+        assert_eq!(
+            output.all_text(),
+            [
+                format!("{}:1:fn main() {{}}", path("a.rs")),
+                format!("{}:3:fn helper() {{}}", path("a.rs")),
+                format!("{}:1:fn nested() {{}}", path("nested/b.rs")),
+            ]
+            .join("\n")
+        );
+        let summary: GrepSummary = serde_json::from_value(output.json_items().next().unwrap().clone()).unwrap();
+        assert_eq!(summary.skipped_large_files, vec![path("large.rs")]);
+        #[cfg(unix)]
+        assert_eq!(summary.skipped_symlinks, vec![path("link.rs")]);
+
+        // Binary files are skipped, and only the top-level directory is searched when not recursive.
+        let tool = grep(serde_json::json!({ "pattern": "^fn", "path": root.to_string_lossy(), "recursive": false }));
+        let output = tool.execute(&test_base).await.unwrap();
+        let text = output.all_text();
+        assert!(
+            text.contains(&format!("{}:1:fn in a text file", path("notes.txt"))),
+            "{}",
+            text
+        );
+        assert!(!text.contains("nested"), "{}", text);
+        assert!(!text.contains("data.bin"), "{}", text);
+        let summary: GrepSummary = serde_json::from_value(output.json_items().next().unwrap().clone()).unwrap();
+        assert_eq!(summary.skipped_binary_files, vec![path("data.bin")]);
+
+        // A symbolic link given as the path is resolved and its target is searched.
+        #[cfg(unix)]
+        {
+            let tool = grep(serde_json::json!({ "pattern": "helper", "path": path("link.rs") }));
+            let output = tool.execute(&test_base).await.unwrap();
+            assert_eq!(output.all_text(), format!("{}:3:fn helper() {{}}", path("a.rs")));
+        }
+
+        let tool = grep(serde_json::json!({ "pattern": "missing", "path": root.to_string_lossy() }));
+        assert_eq!(tool.execute(&test_base).await.unwrap().all_text(), "No matches found");
+
+        // Each file's matches are sent as it is searched, leaving only the summary.
+        let tool = grep(serde_json::json!({ "pattern": "^fn", "path": root.to_string_lossy(), "paths": ["*.rs"] }));
+        let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+        let output = tool.execute_stream(&test_base, &tx).await.unwrap();
+        drop(tx);
+        assert_eq!(output.item_count_by_type().text, 0);
+        let mut sent = Vec::new();
+        while let Some(item) = rx.recv().await {
+            let Ok(ToolExecutionOutputItem::Text(text)) = item else {
+                panic!("expected text items");
+            };
+            sent.push(text);
+        }
+        assert_eq!(sent.len(), 3, "{:?}", sent);
+        assert!(
+            sent[0].starts_with(&path("a.rs")) && sent[0].lines().count() == 2,
+            "{:?}",
+            sent
+        );
+
+        let tool = grep(serde_json::json!({ "pattern": "fn", "path": path("missing") }));
+        assert!(tool.validate(&test_base).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_grep_skips_pruned_and_unreadable() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("root/a.txt", "needle\n"))
+            .await
+            .with_file(("root/.git/config", "needle\n"))
+            .await
+            .with_file(("root/.cache/b.txt", "needle\n"))
+            .await
+            .with_file(("root/node_modules/pkg/index.js", "needle\n"))
+            .await;
+        let root = test_base.join("root");
+        let path = |p: &str| root.join(p).to_string_lossy().to_string();
+        // Opening a socket for reading fails, even when running as root.
+        #[cfg(unix)]
+        let _listener = std::os::unix::net::UnixListener::bind(root.join("socket")).unwrap();
+
+        let tool = grep(serde_json::json!({ "pattern": "needle", "path": root.to_string_lossy() }));
+        let output = tool.execute(&test_base).await.unwrap();
+        assert_eq!(output.all_text(), format!("{}:1:needle", path("a.txt")));
+        #[cfg(unix)]
+        {
+            let summary: GrepSummary = serde_json::from_value(output.json_items().next().unwrap().clone()).unwrap();
+            assert_eq!(summary.skipped_unreadable, vec![path("socket")]);
+        }
+
+        // Pruned directories are searched when given explicitly.
+        let tool = grep(serde_json::json!({ "pattern": "needle", "path": path("node_modules") }));
+        let output = tool.execute(&test_base).await.unwrap();
+        assert_eq!(
+            output.all_text(),
+            format!("{}:1:needle", path("node_modules/pkg/index.js"))
+        );
+    }
+
+    #[test]
+    fn test_grep_line_offset() {
+        let file = (1..=300).map(|i| format!("line {}", i)).collect::<Vec<_>>();
+        // Lines 100-200 of the file, as returned by a partial read.
+        let partial = file[99..200].join("\n");
+
+        let tool = grep(serde_json::json!({ "pattern": "^line 1[05]0$" }));
+        let matches = tool.search_content(&tool.regex().unwrap(), &partial);
+        assert_eq!(
+            matches.iter().map(|m| m.line_number).collect::<Vec<_>>(),
+            vec![1, 51],
+            "without an offset, line numbers are relative to the partial content"
+        );
+
+        let tool = grep(serde_json::json!({ "pattern": "^line 1[05]0$", "lineOffset": 99 }));
+        let matches = tool.search_content(&tool.regex().unwrap(), &partial);
+        assert_eq!(matches, vec![
+            GrepMatch {
+                line_number: 100,
+                line: "line 100".to_string(),
+            },
+            GrepMatch {
+                line_number: 150,
+                line: "line 150".to_string(),
+            },
+        ]);
+    }
+}
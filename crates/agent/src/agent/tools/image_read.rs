@@ -4,6 +4,7 @@ use std::path::{
     PathBuf,
 };
 use std::str::FromStr as _;
+use std::time::Duration;
 
 use serde::{
     Deserialize,
@@ -18,13 +19,17 @@ use super::{
     ToolExecutionOutput,
     ToolExecutionOutputItem,
     ToolExecutionResult,
+    ToolLocale,
 };
 use crate::agent::agent_loop::types::{
     ImageBlock,
     ImageFormat,
     ImageSource,
 };
-use crate::agent::consts::MAX_IMAGE_SIZE_BYTES;
+use crate::agent::consts::{
+    MAX_IMAGE_DIMENSION_PX,
+    MAX_IMAGE_SIZE_BYTES,
+};
 use crate::agent::util::path::canonicalize_path;
 
 const IMAGE_READ_TOOL_DESCRIPTION: &str = r#"
@@ -32,16 +37,71 @@ A tool for reading images.
 
 WHEN TO USE THIS TOOL:
 - Use when you want to read a file that you know is a supported image
+- Use when you want to view an image referenced by an http or https URL, e.g. in documentation
 
 HOW TO USE:
-- Provide a list of paths to images you want to read
+- Provide a list of paths or http(s) URLs of images you want to read
+- Optionally provide convertTo to convert the images to another format, e.g. `jpeg` to reduce the size of a large PNG
 
 FEATURES:
 - Able to read the following image formats: {IMAGE_FORMATS}
 - Can read multiple images in one go
+- Can optionally extract text from images using OCR, e.g. to read the text in screenshots
+- Returns the capture time, camera make and model, and GPS coordinates of JPEG photos when available
 
 LIMITATIONS:
 - Maximum supported image size is 10 MB
+- Maximum supported image width and height is {MAX_IMAGE_DIMENSION_PX} pixels
+- Downloading an image from a URL times out after 30 seconds
+- TIFF images are converted to PNG unless convertTo is provided
+"#;
+
+const IMAGE_READ_TOOL_DESCRIPTION_ZH_CN: &str = r#"
+用于读取图像的工具。
+
+何时使用此工具：
+- 需要读取已知为受支持图像格式的文件时使用
+- 需要查看通过 http 或 https URL 引用的图像（例如文档中的图像）时使用
+
+使用方法：
+- 提供要读取的图像路径或 http(s) URL 列表
+- 可选地提供 convertTo，将图像转换为其他格式，例如使用 `jpeg` 减小大型 PNG 的体积
+
+功能：
+- 可以读取以下图像格式：{IMAGE_FORMATS}
+- 可以一次读取多张图像
+- 可以选择使用 OCR 从图像中提取文本，例如读取屏幕截图中的文字
+- 如果可用，会返回 JPEG 照片的拍摄时间、相机品牌和型号以及 GPS 坐标
+
+限制：
+- 支持的最大图像大小为 10 MB
+- 支持的最大图像宽度和高度为 {MAX_IMAGE_DIMENSION_PX} 像素
+- 从 URL 下载图像的超时时间为 30 秒
+- 除非提供了 convertTo，否则 TIFF 图像会被转换为 PNG
+"#;
+
+const IMAGE_READ_TOOL_DESCRIPTION_JA: &str = r#"
+画像を読み取るためのツールです。
+
+このツールを使用する場面:
+- サポートされている画像であることがわかっているファイルを読み取る場合に使用します
+- ドキュメントなどで http または https の URL によって参照されている画像を表示する場合に使用します
+
+使い方:
+- 読み取りたい画像のパスまたは http(s) URL のリストを指定します
+- 必要に応じて convertTo を指定し、画像を別の形式に変換します (例: 大きな PNG のサイズを小さくするには `jpeg`)
+
+機能:
+- 次の画像形式を読み取れます: {IMAGE_FORMATS}
+- 複数の画像を一度に読み取れます
+- 必要に応じて OCR を使用して画像からテキストを抽出できます (例: スクリーンショット内のテキストの読み取り)
+- JPEG 写真の撮影日時、カメラのメーカーとモデル、GPS 座標が含まれている場合は、それらも返します
+
+制限事項:
+- サポートされる最大画像サイズは 10 MB です
+- サポートされる画像の最大幅と最大高さは {MAX_IMAGE_DIMENSION_PX} ピクセルです
+- URL からの画像のダウンロードは 30 秒でタイムアウトします
+- convertTo が指定されていない場合、TIFF 画像は PNG に変換されます
 "#;
 
 const IMAGE_READ_SCHEMA: &str = r#"
@@ -53,8 +113,18 @@ const IMAGE_READ_SCHEMA: &str = r#"
             "description": "List of paths to images to read",
             "items": {
                 "type": "string",
-                "description": "Path to an image"
+                "description": "Path to an image, or an http:// or https:// URL to download it from"
             }
+        },
+        "extractText": {
+            "type": "boolean",
+            "description": "Whether or not to extract text from the images using OCR",
+            "default": false
+        },
+        "convertTo": {
+            "type": "string",
+            "enum": ["gif", "jpeg", "png", "webp"],
+            "description": "Format to convert the images to before returning them. JPEG is lossy and drops transparency, while PNG and WebP are lossless"
         }
     },
     "required": [
@@ -69,7 +139,15 @@ impl BuiltInToolTrait for ImageRead {
     }
 
     fn description() -> std::borrow::Cow<'static, str> {
-        make_tool_description().into()
+        make_tool_description(IMAGE_READ_TOOL_DESCRIPTION).into()
+    }
+
+    fn description_for_locale(locale: &str) -> std::borrow::Cow<'static, str> {
+        match ToolLocale::parse(locale) {
+            Some(ToolLocale::ZhCn) => make_tool_description(IMAGE_READ_TOOL_DESCRIPTION_ZH_CN).into(),
+            Some(ToolLocale::Ja) => make_tool_description(IMAGE_READ_TOOL_DESCRIPTION_JA).into(),
+            None => Self::description(),
+        }
     }
 
     fn input_schema() -> std::borrow::Cow<'static, str> {
@@ -77,24 +155,44 @@ impl BuiltInToolTrait for ImageRead {
     }
 }
 
-fn make_tool_description() -> String {
+fn make_tool_description(template: &str) -> String {
     let supported_formats = ImageFormat::iter()
         .map(|v| v.to_string())
         .collect::<Vec<_>>()
         .join(", ");
-    IMAGE_READ_TOOL_DESCRIPTION.replace("{IMAGE_FORMATS}", &supported_formats)
+    template
+        .replace("{IMAGE_FORMATS}", &supported_formats)
+        .replace("{MAX_IMAGE_DIMENSION_PX}", &MAX_IMAGE_DIMENSION_PX.to_string())
 }
 
+/// How long to wait for an image to be downloaded from a URL.
+const IMAGE_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ImageRead {
     pub paths: Vec<String>,
+    /// Whether to run OCR on each image, returning the extracted text alongside the image.
+    pub extract_text: Option<bool>,
+    /// Format to re-encode each image to before it is returned.
+    pub convert_to: Option<ImageFormat>,
 }
 
 impl ImageRead {
     pub async fn validate(&self) -> Result<(), String> {
+        if self.extract_text() && !cfg!(feature = "ocr") {
+            return Err("Text extraction is not supported by this build".to_string());
+        }
+        if self.convert_to == Some(ImageFormat::Tiff) {
+            return Err("Images cannot be converted to TIFF".to_string());
+        }
         let paths = self.processed_paths()?;
         let mut errors = Vec::new();
+        for url in self.paths.iter().filter(|p| is_url(p)) {
+            if let Err(err) = url::Url::parse(url) {
+                errors.push(format!("'{}' is not a valid URL: {}", url, err));
+            }
+        }
         for path in &paths {
             if !is_supported_image_type(path) {
                 errors.push(format!("'{}' is not a supported image type", path.to_string_lossy()));
@@ -134,10 +232,50 @@ impl ImageRead {
     pub async fn execute(&self) -> ToolExecutionResult {
         let mut results = Vec::new();
         let mut errors = Vec::new();
-        let paths = self.processed_paths()?;
-        for path in paths {
-            match read_image(path).await {
-                Ok(block) => results.push(ToolExecutionOutputItem::Image(block)),
+        for path in &self.paths {
+            let (path, block) = if is_url(path) {
+                (path.clone(), read_image_url(path).await)
+            } else {
+                let path = process_image_path(path)?;
+                let block = read_image(&path).await;
+                (path.to_string_lossy().to_string(), block)
+            };
+            // Check the dimensions before converting, since conversion fully decodes the image.
+            let block = block.and_then(|block| {
+                check_image_dimensions(&block).map_err(|e| format!("image at {} {}", path, e))?;
+                Ok(block)
+            });
+            // Conversion drops the metadata, so it is read from the original image.
+            let exif = block.as_ref().ok().and_then(read_exif);
+            let convert_to = block.as_ref().ok().and_then(|block| self.convert_to(block.format));
+            let block = match (block, convert_to) {
+                (Ok(block), Some(format)) => convert_image(block, format)
+                    .await
+                    .map_err(|e| format!("failed to convert {} to {}: {}", path, format, e)),
+                (res, _) => res,
+            };
+            match block {
+                Ok(block) => {
+                    let ocr_result = if self.extract_text() {
+                        Some(extract_text(block.source.bytes().to_vec()).await)
+                    } else {
+                        None
+                    };
+                    results.push(ToolExecutionOutputItem::Image(block));
+                    if let Some(exif) = exif {
+                        results.push(ToolExecutionOutputItem::Json(serde_json::json!({ "exif": exif })));
+                    }
+                    match ocr_result {
+                        Some(Ok(ocr)) => {
+                            results.push(ToolExecutionOutputItem::Text(ocr.text));
+                            results.push(ToolExecutionOutputItem::Json(
+                                serde_json::json!({ "confidence": ocr.confidence }),
+                            ));
+                        },
+                        Some(Err(err)) => errors.push(format!("failed to extract text from {}: {}", path, err)),
+                        None => (),
+                    }
+                },
                 // Validate step should prevent errors from cropping up here.
                 Err(err) => errors.push(err),
             }
@@ -149,14 +287,26 @@ impl ImageRead {
         }
     }
 
-    fn processed_paths(&self) -> Result<Vec<PathBuf>, String> {
-        let mut paths = Vec::new();
-        for path in &self.paths {
-            let path = canonicalize_path(path).map_err(|e| format!("failed to process path {}: {}", path, e))?;
-            let path = pre_process_image_path(&path);
-            paths.push(PathBuf::from(path));
+    fn extract_text(&self) -> bool {
+        self.extract_text.unwrap_or(false)
+    }
+
+    /// The format to convert an image in `format` to. TIFF images are converted to PNG by default,
+    /// since they cannot be sent to the model.
+    fn convert_to(&self, format: ImageFormat) -> Option<ImageFormat> {
+        match format {
+            ImageFormat::Tiff => self.convert_to.or(Some(ImageFormat::Png)),
+            _ => self.convert_to,
         }
-        Ok(paths)
+    }
+
+    /// The local paths to read, excluding URLs.
+    fn processed_paths(&self) -> Result<Vec<PathBuf>, String> {
+        self.paths
+            .iter()
+            .filter(|path| !is_url(path))
+            .map(|path| process_image_path(path))
+            .collect()
     }
 }
 
@@ -199,6 +349,267 @@ pub async fn read_image(path: impl AsRef<Path>) -> Result<ImageBlock, String> {
     })
 }
 
+fn process_image_path(path: &str) -> Result<PathBuf, String> {
+    let path = canonicalize_path(path).map_err(|e| format!("failed to process path {}: {}", path, e))?;
+    Ok(PathBuf::from(pre_process_image_path(&path)))
+}
+
+/// Whether or not `path` should be downloaded rather than read from the file system.
+fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Downloads an image from the given URL if it is a supported image type and within the size
+/// limits of the API, returning a human and model friendly error message otherwise.
+///
+/// The format is taken from the `Content-Type` header, falling back to the image content and then
+/// the extension of the URL.
+pub async fn read_image_url(url: &str) -> Result<ImageBlock, String> {
+    let client = reqwest::Client::builder()
+        .timeout(IMAGE_DOWNLOAD_TIMEOUT)
+        .build()
+        .map_err(|e| format!("failed to create HTTP client: {}", e))?;
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("failed to download image from {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "failed to download image from {}: server responded with {}",
+            url,
+            response.status()
+        ));
+    }
+
+    let too_large = |size: u64| {
+        format!(
+            "image at {} has size {} bytes, but the max supported size is {}",
+            url, size, MAX_IMAGE_SIZE_BYTES
+        )
+    };
+    // Check the advertised size before downloading anything.
+    if let Some(size) = response.content_length() {
+        if size > MAX_IMAGE_SIZE_BYTES {
+            return Err(too_large(size));
+        }
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+
+    let mut content = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("failed to download image from {}: {}", url, e))?
+    {
+        content.extend_from_slice(&chunk);
+        if content.len() as u64 > MAX_IMAGE_SIZE_BYTES {
+            return Err(too_large(content.len() as u64));
+        }
+    }
+
+    let format = content_type
+        .as_deref()
+        .and_then(|v| v.split(';').next())
+        .and_then(|v| v.trim().strip_prefix("image/"))
+        .and_then(|v| ImageFormat::from_str(v).ok())
+        .or_else(|| match image::guess_format(&content) {
+            Ok(image::ImageFormat::Gif) => Some(ImageFormat::Gif),
+            Ok(image::ImageFormat::Jpeg) => Some(ImageFormat::Jpeg),
+            Ok(image::ImageFormat::Png) => Some(ImageFormat::Png),
+            Ok(image::ImageFormat::WebP) => Some(ImageFormat::Webp),
+            Ok(image::ImageFormat::Tiff) => Some(ImageFormat::Tiff),
+            _ => None,
+        })
+        .or_else(|| {
+            let path = url::Url::parse(url).ok()?.path().to_string();
+            let extension = Path::new(&path).extension()?.to_string_lossy().to_lowercase();
+            ImageFormat::from_str(&extension).ok()
+        })
+        .ok_or_else(|| {
+            format!(
+                "image at {} is not a supported format (content type: {})",
+                url,
+                content_type.as_deref().unwrap_or("unknown")
+            )
+        })?;
+
+    Ok(ImageBlock {
+        format,
+        source: ImageSource::Url {
+            url: url.to_string(),
+            bytes: content,
+        },
+    })
+}
+
+/// Returns an error containing the dimensions of the image if its width or height is greater than
+/// [MAX_IMAGE_DIMENSION_PX]. Only the image header is read.
+fn check_image_dimensions(block: &ImageBlock) -> Result<(), String> {
+    let (width, height) = image::ImageReader::with_format(
+        std::io::Cursor::new(block.source.bytes()),
+        image_crate_format(block.format),
+    )
+    .into_dimensions()
+    .map_err(|e| format!("could not be read: {}", e))?;
+    if width > MAX_IMAGE_DIMENSION_PX || height > MAX_IMAGE_DIMENSION_PX {
+        return Err(format!(
+            "has dimensions {}x{} px, but the max supported width and height is {} px",
+            width, height, MAX_IMAGE_DIMENSION_PX
+        ));
+    }
+    Ok(())
+}
+
+/// Reads the capture time, camera make and model, and GPS coordinates from the EXIF metadata of a
+/// JPEG image, returning [None] if the image has no such metadata or it cannot be parsed.
+///
+/// GPS coordinates are converted to signed decimal degrees.
+fn read_exif(block: &ImageBlock) -> Option<serde_json::Value> {
+    use exif::{
+        In,
+        Tag,
+        Value,
+    };
+
+    if block.format != ImageFormat::Jpeg {
+        return None;
+    }
+    let exif = exif::Reader::new()
+        .read_from_container(&mut std::io::Cursor::new(block.source.bytes()))
+        .ok()?;
+    let ascii = |tag: Tag| match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(values) => {
+            let value = String::from_utf8_lossy(values.first()?).trim().to_string();
+            (!value.is_empty()).then_some(value)
+        },
+        _ => None,
+    };
+    let coordinate = |tag: Tag, ref_tag: Tag, negative_ref: &str| {
+        let Value::Rational(dms) = &exif.get_field(tag, In::PRIMARY)?.value else {
+            return None;
+        };
+        let degrees = dms
+            .iter()
+            .zip([1.0, 60.0, 3600.0])
+            .map(|(v, divisor)| v.to_f64() / divisor)
+            .sum::<f64>();
+        Some(if ascii(ref_tag).as_deref() == Some(negative_ref) {
+            -degrees
+        } else {
+            degrees
+        })
+    };
+
+    let mut fields = serde_json::Map::new();
+    for (key, value) in [
+        (
+            "date_time",
+            ascii(Tag::DateTimeOriginal)
+                .or_else(|| ascii(Tag::DateTime))
+                .map(Into::into),
+        ),
+        ("make", ascii(Tag::Make).map(Into::into)),
+        ("model", ascii(Tag::Model).map(Into::into)),
+        (
+            "gps_latitude",
+            coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef, "S").map(Into::into),
+        ),
+        (
+            "gps_longitude",
+            coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef, "W").map(Into::into),
+        ),
+    ] {
+        if let Some(value) = value {
+            fields.insert(key.to_string(), value);
+        }
+    }
+    (!fields.is_empty()).then_some(fields.into())
+}
+
+/// Decodes the image and re-encodes it as `format`, returning the image unchanged if it is
+/// already in that format.
+async fn convert_image(block: ImageBlock, format: ImageFormat) -> Result<ImageBlock, String> {
+    if block.format == format {
+        return Ok(block);
+    }
+    let (url, bytes) = match block.source {
+        ImageSource::Bytes(bytes) => (None, bytes),
+        ImageSource::Url { url, bytes } => (Some(url), bytes),
+    };
+    let source_format = image_crate_format(block.format);
+    let converted = tokio::task::spawn_blocking(move || {
+        let mut image = image::load_from_memory_with_format(&bytes, source_format).map_err(|e| e.to_string())?;
+        // JPEG does not support transparency.
+        if format == ImageFormat::Jpeg {
+            image = image::DynamicImage::ImageRgb8(image.to_rgb8());
+        }
+        let mut converted = std::io::Cursor::new(Vec::new());
+        image
+            .write_to(&mut converted, image_crate_format(format))
+            .map_err(|e| e.to_string())?;
+        Ok::<_, String>(converted.into_inner())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    if converted.len() as u64 > MAX_IMAGE_SIZE_BYTES {
+        return Err(format!(
+            "converted image has size {} bytes, but the max supported size is {}",
+            converted.len(),
+            MAX_IMAGE_SIZE_BYTES
+        ));
+    }
+    let source = match url {
+        Some(url) => ImageSource::Url { url, bytes: converted },
+        None => ImageSource::Bytes(converted),
+    };
+    Ok(ImageBlock { format, source })
+}
+
+fn image_crate_format(format: ImageFormat) -> image::ImageFormat {
+    match format {
+        ImageFormat::Gif => image::ImageFormat::Gif,
+        ImageFormat::Jpeg => image::ImageFormat::Jpeg,
+        ImageFormat::Png => image::ImageFormat::Png,
+        ImageFormat::Webp => image::ImageFormat::WebP,
+        ImageFormat::Tiff => image::ImageFormat::Tiff,
+    }
+}
+
+/// Text extracted from an image using OCR.
+#[derive(Debug, Clone)]
+struct OcrResult {
+    text: String,
+    /// Mean confidence of the recognized text, between 0 and 1.
+    confidence: f64,
+}
+
+/// Extracts text from the given encoded image using Tesseract.
+#[cfg(feature = "ocr")]
+async fn extract_text(image: Vec<u8>) -> Result<OcrResult, String> {
+    tokio::task::spawn_blocking(move || {
+        let mut tess = leptess::LepTess::new(None, "eng").map_err(|e| e.to_string())?;
+        tess.set_image_from_mem(&image).map_err(|e| e.to_string())?;
+        let text = tess.get_utf8_text().map_err(|e| e.to_string())?;
+        Ok(OcrResult {
+            text: text.trim().to_string(),
+            confidence: f64::from(tess.mean_text_conf()) / 100.0,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(not(feature = "ocr"))]
+async fn extract_text(_image: Vec<u8>) -> Result<OcrResult, String> {
+    Err("text extraction is not supported by this build".to_string())
+}
+
 /// Macos screenshots insert a NNBSP character rather than a space between the timestamp and AM/PM
 /// part. An example of a screenshot name is: /path-to/Screenshot 2025-03-13 at 1.46.32 PM.png
 ///
@@ -258,6 +669,8 @@ mod tests {
 
         let tool = ImageRead {
             paths: vec![test_base.join("test.png").to_string_lossy().to_string()],
+            extract_text: None,
+            convert_to: None,
         };
 
         assert!(tool.validate().await.is_ok());
@@ -269,6 +682,269 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_convert_image() {
+        // A semi-transparent gradient, encoded as PNG.
+        let image = image::RgbaImage::from_fn(16, 16, |x, y| image::Rgba([(x * 16) as u8, (y * 16) as u8, 128, 200]));
+        let mut png = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut png, image::ImageFormat::Png).unwrap();
+        let test_base = TestBase::new().await.with_file(("test.png", png.into_inner())).await;
+
+        let mut tool = ImageRead {
+            paths: vec![test_base.join("test.png").to_string_lossy().to_string()],
+            extract_text: None,
+            convert_to: Some(ImageFormat::Jpeg),
+        };
+        let result = tool.execute().await.unwrap();
+        let [ToolExecutionOutputItem::Image(block)] = &result.items[..] else {
+            panic!("expected a single image");
+        };
+        let bytes = block.source.bytes();
+        assert_eq!(block.format, ImageFormat::Jpeg);
+        assert!(!bytes.is_empty());
+        assert_eq!(image::guess_format(bytes).unwrap(), image::ImageFormat::Jpeg);
+        let decoded = image::load_from_memory(bytes).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (16, 16));
+
+        tool.convert_to = Some(ImageFormat::Webp);
+        let result = tool.execute().await.unwrap();
+        let [ToolExecutionOutputItem::Image(block)] = &result.items[..] else {
+            panic!("expected a single image");
+        };
+        let bytes = block.source.bytes();
+        assert_eq!(block.format, ImageFormat::Webp);
+        assert_eq!(image::load_from_memory(bytes).unwrap().to_rgba8(), image);
+    }
+
+    #[tokio::test]
+    async fn test_read_image_url() {
+        let mut server = mockito::Server::new_async().await;
+        let _typed = server
+            .mock("GET", "/typed")
+            .with_header("content-type", "image/png")
+            .with_body(create_test_png())
+            .create_async()
+            .await;
+        let _untyped = server
+            .mock("GET", "/untyped")
+            .with_body(create_test_png())
+            .create_async()
+            .await;
+        let _missing = server.mock("GET", "/missing.png").with_status(404).create_async().await;
+        let _large = server
+            .mock("GET", "/large.png")
+            .with_header("content-type", "image/png")
+            .with_body(vec![0; MAX_IMAGE_SIZE_BYTES as usize + 1])
+            .create_async()
+            .await;
+        let _text = server
+            .mock("GET", "/text")
+            .with_header("content-type", "text/plain")
+            .with_body("not an image")
+            .create_async()
+            .await;
+        let url = |path: &str| format!("{}{}", server.url(), path);
+
+        let tool = ImageRead {
+            paths: vec![url("/typed"), url("/untyped")],
+            extract_text: None,
+            convert_to: None,
+        };
+        assert!(tool.validate().await.is_ok());
+        let result = tool.execute().await.unwrap();
+        assert_eq!(result.items.len(), 2);
+        for (item, path) in result.items.iter().zip(["/typed", "/untyped"]) {
+            let ToolExecutionOutputItem::Image(block) = item else {
+                panic!("expected an image");
+            };
+            assert_eq!(block.format, ImageFormat::Png);
+            assert!(
+                matches!(&block.source, ImageSource::Url { url: u, bytes } if *u == url(path) && *bytes == create_test_png())
+            );
+        }
+
+        for (path, expected) in [
+            ("/missing.png", "404"),
+            ("/large.png", "max supported size"),
+            ("/text", "not a supported format"),
+        ] {
+            let err = read_image_url(&url(path)).await.unwrap_err();
+            assert!(err.contains(expected), "{}", err);
+        }
+
+        let tool = ImageRead {
+            paths: vec!["http://[invalid".to_string()],
+            extract_text: None,
+            convert_to: None,
+        };
+        assert!(tool.validate().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_image_dimensions() {
+        let encode = |width: u32, height: u32| {
+            let mut png = std::io::Cursor::new(Vec::new());
+            image::GrayImage::new(width, height)
+                .write_to(&mut png, image::ImageFormat::Png)
+                .unwrap();
+            png.into_inner()
+        };
+        let test_base = TestBase::new()
+            .await
+            .with_file(("max.png", encode(MAX_IMAGE_DIMENSION_PX, 1)))
+            .await
+            .with_file(("wide.png", encode(MAX_IMAGE_DIMENSION_PX + 1, 1)))
+            .await
+            .with_file(("tall.png", encode(1, MAX_IMAGE_DIMENSION_PX + 1)))
+            .await;
+        let tool = |name: &str| ImageRead {
+            paths: vec![test_base.join(name).to_string_lossy().to_string()],
+            extract_text: None,
+            convert_to: None,
+        };
+
+        assert!(tool("max.png").execute().await.is_ok());
+        for (name, dimensions) in [("wide.png", "8001x1"), ("tall.png", "1x8001")] {
+            let Err(ToolExecutionError::Custom(err)) = tool(name).execute().await else {
+                panic!("expected {} to be rejected", name);
+            };
+            assert!(err.contains(dimensions), "{}", err);
+            assert!(err.contains("8000 px"), "{}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_exif() {
+        use exif::{
+            Field,
+            In,
+            Rational,
+            Tag,
+            Value,
+        };
+
+        let ascii = |tag: Tag, value: &str| Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![value.as_bytes().to_vec()]),
+        };
+        let dms = |tag: Tag, d: u32, m: u32, s: u32| Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Rational(vec![
+                Rational::from((d, 1)),
+                Rational::from((m, 1)),
+                Rational::from((s, 1)),
+            ]),
+        };
+        let fields = [
+            ascii(Tag::DateTime, "2024:05:01 12:30:00"),
+            ascii(Tag::Make, "Canon"),
+            ascii(Tag::Model, "EOS R5"),
+            dms(Tag::GPSLatitude, 47, 36, 36),
+            ascii(Tag::GPSLatitudeRef, "N"),
+            dms(Tag::GPSLongitude, 122, 19, 48),
+            ascii(Tag::GPSLongitudeRef, "W"),
+        ];
+        let mut writer = exif::experimental::Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut tiff = std::io::Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+        let tiff = tiff.into_inner();
+
+        // Insert the EXIF data as an APP1 segment directly after the JPEG SOI marker.
+        let mut plain = std::io::Cursor::new(Vec::new());
+        image::RgbImage::new(4, 4)
+            .write_to(&mut plain, image::ImageFormat::Jpeg)
+            .unwrap();
+        let plain = plain.into_inner();
+        let mut jpeg = plain[..2].to_vec();
+        jpeg.extend_from_slice(&[0xff, 0xe1]);
+        jpeg.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\0\0");
+        jpeg.extend_from_slice(&tiff);
+        jpeg.extend_from_slice(&plain[2..]);
+
+        let test_base = TestBase::new()
+            .await
+            .with_file(("photo.jpg", jpeg))
+            .await
+            .with_file(("plain.jpg", plain))
+            .await;
+        let tool = |name: &str| ImageRead {
+            paths: vec![test_base.join(name).to_string_lossy().to_string()],
+            extract_text: None,
+            convert_to: Some(ImageFormat::Png),
+        };
+
+        let result = tool("photo.jpg").execute().await.unwrap();
+        let [ToolExecutionOutputItem::Image(_), ToolExecutionOutputItem::Json(json)] = &result.items[..] else {
+            panic!("expected an image followed by exif metadata: {:?}", result.items);
+        };
+        let exif = &json["exif"];
+        assert_eq!(exif["date_time"], "2024:05:01 12:30:00");
+        assert_eq!(exif["make"], "Canon");
+        assert_eq!(exif["model"], "EOS R5");
+        assert!(
+            (exif["gps_latitude"].as_f64().unwrap() - 47.61).abs() < 1e-9,
+            "{}",
+            exif
+        );
+        assert!(
+            (exif["gps_longitude"].as_f64().unwrap() + 122.33).abs() < 1e-9,
+            "{}",
+            exif
+        );
+
+        // Images without metadata only return the image.
+        let result = tool("plain.jpg").execute().await.unwrap();
+        assert_eq!(result.items.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_tiff() {
+        let image = image::RgbImage::from_fn(4, 4, |x, y| image::Rgb([(x * 64) as u8, (y * 64) as u8, 0]));
+        let mut tiff = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut tiff, image::ImageFormat::Tiff).unwrap();
+        let tiff = tiff.into_inner();
+        let test_base = TestBase::new()
+            .await
+            .with_file(("test.tif", tiff.clone()))
+            .await
+            .with_file(("test.tiff", tiff.clone()))
+            .await;
+
+        for name in ["test.tif", "test.tiff"] {
+            let path = test_base.join(name);
+            assert!(is_supported_image_type(&path));
+            let block = read_image(&path).await.unwrap();
+            assert_eq!(block.format, ImageFormat::Tiff);
+            assert_eq!(block.source.bytes(), tiff);
+        }
+
+        // TIFF images are converted to PNG before being returned.
+        let tool = ImageRead {
+            paths: vec![test_base.join("test.tif").to_string_lossy().to_string()],
+            extract_text: None,
+            convert_to: None,
+        };
+        assert!(tool.validate().await.is_ok());
+        let result = tool.execute().await.unwrap();
+        let [ToolExecutionOutputItem::Image(block)] = &result.items[..] else {
+            panic!("expected a single image");
+        };
+        assert_eq!(block.format, ImageFormat::Png);
+        assert_eq!(image::load_from_memory(block.source.bytes()).unwrap().to_rgb8(), image);
+
+        let tool = ImageRead {
+            convert_to: Some(ImageFormat::Tiff),
+            ..tool
+        };
+        assert!(tool.validate().await.is_err());
+    }
+
     #[tokio::test]
     async fn test_read_multiple_images() {
         let test_base = TestBase::new()
@@ -283,18 +959,37 @@ mod tests {
                 test_base.join("image1.png").to_string_lossy().to_string(),
                 test_base.join("image2.png").to_string_lossy().to_string(),
             ],
+            extract_text: None,
+            convert_to: None,
         };
 
         let result = tool.execute().await.unwrap();
         assert_eq!(result.items.len(), 2);
     }
 
+    #[tokio::test]
+    #[cfg(not(feature = "ocr"))]
+    async fn test_validate_extract_text_unsupported() {
+        let test_base = TestBase::new().await.with_file(("test.png", create_test_png())).await;
+
+        let tool = ImageRead {
+            paths: vec![test_base.join("test.png").to_string_lossy().to_string()],
+            extract_text: Some(true),
+            convert_to: None,
+        };
+
+        assert!(tool.validate().await.is_err());
+        assert!(tool.execute().await.is_err());
+    }
+
     #[tokio::test]
     async fn test_validate_unsupported_format() {
         let test_base = TestBase::new().await.with_file(("test.txt", "not an image")).await;
 
         let tool = ImageRead {
             paths: vec![test_base.join("test.txt").to_string_lossy().to_string()],
+            extract_text: None,
+            convert_to: None,
         };
 
         assert!(tool.validate().await.is_err());
@@ -304,6 +999,8 @@ mod tests {
     async fn test_validate_nonexistent_file() {
         let tool = ImageRead {
             paths: vec!["/nonexistent/image.png".to_string()],
+            extract_text: None,
+            convert_to: None,
         };
 
         assert!(tool.validate().await.is_err());
@@ -315,6 +1012,8 @@ mod tests {
 
         let tool = ImageRead {
             paths: vec![test_base.join("").to_string_lossy().to_string()],
+            extract_text: None,
+            convert_to: None,
         };
 
         assert!(tool.validate().await.is_err());
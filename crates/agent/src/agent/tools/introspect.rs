@@ -1,7 +1,222 @@
+use std::path::Path;
+
 use serde::{
     Deserialize,
     Serialize,
 };
+use strum::IntoEnumIterator;
+
+use super::{
+    BuiltInToolName,
+    BuiltInToolTrait,
+    ToolExecutionError,
+    ToolExecutionOutput,
+    ToolExecutionOutputItem,
+    ToolExecutionResult,
+    ToolLocale,
+};
+use crate::agent::agent_config::parse::CanonicalToolName;
+use crate::util::providers::SystemProvider;
+
+const INTROSPECT_TOOL_DESCRIPTION: &str = r#"
+A tool for inspecting the environment the agent is running in.
+
+WHEN TO USE THIS TOOL:
+- Use to check which operating system, working directory, and environment variables are available before running commands
+- Use to check which built-in tools are enabled, e.g. after a tool use fails because the tool is disabled
+
+HOW TO USE:
+- Takes no arguments
+- Returns the OS name and version, current working directory, environment variable names, built-in tools and whether each is enabled, free disk space of the working directory's volume, and the target triple of the agent
+
+LIMITATIONS:
+- Only the names of environment variables are returned, not their values
+"#;
+
+const INTROSPECT_TOOL_DESCRIPTION_ZH_CN: &str = r#"
+用于检查代理运行环境的工具。
+
+何时使用此工具：
+- 在运行命令之前，用于检查可用的操作系统、工作目录和环境变量
+- 用于检查启用了哪些内置工具，例如在因工具被禁用而导致工具使用失败之后
+
+使用方法：
+- 不需要任何参数
+- 返回操作系统名称和版本、当前工作目录、环境变量名称、内置工具及其是否启用、工作目录所在卷的可用磁盘空间，以及代理的目标三元组
+
+限制：
+- 只返回环境变量的名称，不返回其值
+"#;
+
+const INTROSPECT_TOOL_DESCRIPTION_JA: &str = r#"
+エージェントが実行されている環境を調べるためのツールです。
+
+このツールを使用する場面:
+- コマンドを実行する前に、利用可能な OS、作業ディレクトリ、環境変数を確認する場合に使用します
+- ツールが無効なためにツールの使用が失敗した後など、有効になっている組み込みツールを確認する場合に使用します
+
+使い方:
+- 引数はありません
+- OS の名前とバージョン、現在の作業ディレクトリ、環境変数名、組み込みツールとそれぞれが有効かどうか、作業ディレクトリのボリュームの空きディスク容量、エージェントのターゲットトリプルを返します
+
+制限事項:
+- 環境変数は名前のみが返され、値は返されません
+"#;
+
+const INTROSPECT_SCHEMA: &str = r#"
+{
+    "type": "object",
+    "properties": {}
+}
+"#;
+
+/// Target triple of the agent, set by the build script.
+const TARGET_TRIPLE: &str = env!("TARGET");
+
+impl BuiltInToolTrait for Introspect {
+    fn name() -> BuiltInToolName {
+        BuiltInToolName::Introspect
+    }
+
+    fn description() -> std::borrow::Cow<'static, str> {
+        INTROSPECT_TOOL_DESCRIPTION.into()
+    }
+
+    fn description_for_locale(locale: &str) -> std::borrow::Cow<'static, str> {
+        match ToolLocale::parse(locale) {
+            Some(ToolLocale::ZhCn) => INTROSPECT_TOOL_DESCRIPTION_ZH_CN.into(),
+            Some(ToolLocale::Ja) => INTROSPECT_TOOL_DESCRIPTION_JA.into(),
+            None => Self::description(),
+        }
+    }
+
+    fn input_schema() -> std::borrow::Cow<'static, str> {
+        INTROSPECT_SCHEMA.into()
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Introspect {}
+
+/// The environment information returned by [Introspect].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct IntrospectOutput {
+    pub os: OsInfo,
+    pub cwd: String,
+    /// Sorted names of the environment variables of the agent process.
+    pub env_var_names: Vec<String>,
+    pub built_in_tools: Vec<BuiltInToolStatus>,
+    /// Bytes available on the volume containing [IntrospectOutput::cwd], if known.
+    pub free_disk_bytes: Option<u64>,
+    pub target: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct OsInfo {
+    /// Name of the OS family, e.g. `linux` or `macos`.
+    pub name: String,
+    /// Name of the distribution, e.g. `Ubuntu`, if known.
+    pub distribution: Option<String>,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct BuiltInToolStatus {
+    pub name: BuiltInToolName,
+    pub enabled: bool,
+}
+
+impl Introspect {
+    /// Gathers information about the environment. `enabled_tools` are the tools available to the
+    /// agent.
+    pub async fn execute<P: SystemProvider>(
+        &self,
+        provider: &P,
+        enabled_tools: &[CanonicalToolName],
+    ) -> ToolExecutionResult {
+        let cwd = provider
+            .cwd()
+            .map_err(|e| ToolExecutionError::from_io_result("failed to get the current directory", None, e))?;
+
+        let mut env_var_names = std::env::vars_os()
+            .map(|(name, _)| name.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        env_var_names.sort();
+
+        let built_in_tools = BuiltInToolName::iter()
+            .map(|name| BuiltInToolStatus {
+                enabled: enabled_tools.contains(&CanonicalToolName::BuiltIn(name.clone())),
+                name,
+            })
+            .collect();
+
+        let output = IntrospectOutput {
+            os: OsInfo {
+                name: std::env::consts::OS.to_string(),
+                distribution: sysinfo::System::name(),
+                version: sysinfo::System::os_version(),
+            },
+            free_disk_bytes: free_disk_bytes(&cwd),
+            cwd: cwd.to_string_lossy().to_string(),
+            env_var_names,
+            built_in_tools,
+            target: TARGET_TRIPLE.to_string(),
+        };
+        let output = serde_json::to_value(output).map_err(|e| format!("failed to serialize output: {}", e))?;
+        Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Json(output)]))
+    }
+}
+
+/// Returns the available space of the disk with the longest mount point containing `path`.
+fn free_disk_bytes(path: &Path) -> Option<u64> {
+    let path = std::fs::canonicalize(path).ok()?;
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::providers::CwdProvider as _;
+    use crate::util::test::TestBase;
+
+    #[tokio::test]
+    async fn test_introspect() {
+        let test_base = TestBase::new().await;
+        let enabled = [CanonicalToolName::BuiltIn(BuiltInToolName::FsRead)];
+        let output = Introspect {}.execute(&test_base, &enabled).await.unwrap();
+        let output: IntrospectOutput = serde_json::from_value(output.json_items().next().unwrap().clone()).unwrap();
+
+        assert_eq!(output.os.name, std::env::consts::OS);
+        assert_eq!(output.cwd, test_base.cwd().unwrap().to_string_lossy());
+        assert_eq!(output.target, TARGET_TRIPLE);
+        assert!(output.env_var_names.is_sorted());
+        assert!(output.env_var_names.iter().any(|name| name == "PATH"));
+        assert_eq!(output.built_in_tools.len(), BuiltInToolName::iter().count());
+        for status in &output.built_in_tools {
+            assert_eq!(
+                status.enabled,
+                status.name == BuiltInToolName::FsRead,
+                "{}",
+                status.name
+            );
+        }
+        #[cfg(target_os = "linux")]
+        assert!(output.free_disk_bytes.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_introspect_env_var_values_are_not_returned() {
+        let output = Introspect {}.execute(&TestBase::new().await, &[]).await.unwrap();
+        let path = std::env::var("PATH").unwrap();
+        assert!(!output.json_items().next().unwrap().to_string().contains(&path));
+    }
+}
@@ -1,15 +1,26 @@
-use std::collections::VecDeque;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
 use std::fs::Metadata;
 use std::path::{
     Path,
     PathBuf,
 };
+use std::sync::Arc;
 
+use ignore::Match;
+use ignore::gitignore::{
+    Gitignore,
+    GitignoreBuilder,
+};
 use serde::{
     Deserialize,
     Serialize,
 };
 use tokio::fs::DirEntry;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 use tracing::{
     debug,
     trace,
@@ -19,13 +30,19 @@ use tracing::{
 use super::{
     BuiltInToolName,
     BuiltInToolTrait,
+    ToolExample,
     ToolExecutionResult,
+    ToolLocale,
 };
+use crate::agent::agent_config::definitions::LsSettings;
 use crate::agent::tools::{
     ToolExecutionOutput,
     ToolExecutionOutputItem,
+    ToolOutputSender,
+    send_output,
 };
 use crate::agent::util::glob::matches_any_pattern;
+use crate::util::format_bytes;
 use crate::util::path::canonicalize_path_sys;
 use crate::util::providers::SystemProvider;
 
@@ -36,12 +53,65 @@ HOW TO USE:
 - Provide the path to the directory you want to view
 - Optionally provide a depth to recursively list directory contents
 - Optionally provide a list of glob patterns to exclude files and directories from being searched
+- Optionally set showHidden to true to list hidden entries, whose names start with `.`. `.git` directories are never listed
+- Optionally set showInodes to true to prefix each entry with its inode number, to tell whether two paths are hard links to the same file. Not supported on Windows
+- Optionally set respectGitignore to true to skip entries excluded by .gitignore files
+- Optionally set groupBy to "extension" or "type" to group the listed entries under section headers
+- Optionally set sortBy to "name", "size", "modified", or "type" to choose how the entries of each directory are sorted, and sortDesc to reverse the order. Entries are sorted by most recently modified by default
+- Optionally set filterType to "file", "directory", or "symlink" to only list entries of that type, e.g. to list only subdirectories
+- Optionally set minSizeBytes and/or maxSizeBytes to only list files within a size range, e.g. to find large log files. Directories are always listed
+- Optionally set outputFormat to "json" to get the entries as an array of objects with path, type, size_bytes, modified_unix, and permissions fields, instead of parsing the text listing
+- Optionally set outputFormat to "tree" to show only the names of a recursive listing as a tree, which is the easiest way to understand a project's layout
 
 LIMITATIONS:
-- Only 1000 entries will be returned
+- Only 1000 entries will be returned. The listing ends with a `Total:` line giving the number of files and directories listed, their total size, and how many entries were omitted
 - Directories containing over 10000 entries will be truncated
 "#;
 
+const LS_TOOL_DESCRIPTION_ZH_CN: &str = r#"
+用于列出目录内容的工具。
+
+使用方法：
+- 提供要查看的目录路径
+- 可选地提供 depth，以递归列出目录内容
+- 可选地提供 glob 模式列表，以排除不需要搜索的文件和目录
+- 可选地将 showHidden 设置为 true，以列出名称以 `.` 开头的隐藏条目。`.git` 目录永远不会被列出
+- 可选地将 showInodes 设置为 true，在每个条目前显示其 inode 编号，以判断两个路径是否为同一文件的硬链接。Windows 上不支持
+- 可选地将 respectGitignore 设置为 true，以跳过被 .gitignore 文件排除的条目
+- 可选地将 groupBy 设置为 "extension" 或 "type"，以在分节标题下对列出的条目进行分组
+- 可选地将 sortBy 设置为 "name"、"size"、"modified" 或 "type"，以选择每个目录中条目的排序方式，并使用 sortDesc 反转顺序。默认按最近修改时间排序
+- 可选地将 filterType 设置为 "file"、"directory" 或 "symlink"，仅列出该类型的条目，例如只列出子目录
+- 可选地设置 minSizeBytes 和/或 maxSizeBytes，仅列出大小在指定范围内的文件，例如查找较大的日志文件。目录始终会被列出
+- 可选地将 outputFormat 设置为 "json"，以对象数组的形式获取条目（包含 path、type、size_bytes、modified_unix 和 permissions 字段），而无需解析文本列表
+- 可选地将 outputFormat 设置为 "tree"，以树形结构仅显示递归列表中的名称，这是了解项目布局最简单的方式
+
+限制：
+- 最多返回 1000 个条目。列表以 `Total:` 行结尾，给出所列文件和目录的数量、总大小以及被省略的条目数
+- 包含超过 10000 个条目的目录将被截断
+"#;
+
+const LS_TOOL_DESCRIPTION_JA: &str = r#"
+ディレクトリの内容を一覧表示するためのツールです。
+
+使い方:
+- 表示したいディレクトリのパスを指定します
+- 必要に応じて depth を指定し、ディレクトリの内容を再帰的に一覧表示します
+- 必要に応じて glob パターンのリストを指定し、検索対象からファイルやディレクトリを除外します
+- 必要に応じて showHidden を true に設定し、名前が `.` で始まる隠しエントリを一覧表示します。`.git` ディレクトリは一覧表示されません
+- 必要に応じて showInodes を true に設定すると、各エントリの先頭に inode 番号が表示され、2 つのパスが同じファイルへのハードリンクかどうかを判断できます。Windows ではサポートされていません
+- 必要に応じて respectGitignore を true に設定し、.gitignore ファイルで除外されたエントリをスキップします
+- 必要に応じて groupBy を "extension" または "type" に設定し、一覧のエントリをセクション見出しごとにグループ化します
+- 必要に応じて sortBy を "name"、"size"、"modified"、"type" のいずれかに設定して各ディレクトリのエントリの並べ替え方法を選択し、sortDesc で順序を逆にします。デフォルトでは最終更新日時の新しい順に並べ替えられます
+- 必要に応じて filterType を "file"、"directory"、"symlink" のいずれかに設定すると、サブディレクトリのみなど、その種類のエントリのみを一覧表示できます
+- 必要に応じて minSizeBytes や maxSizeBytes を設定すると、大きなログファイルを探す場合などに、指定したサイズ範囲内のファイルのみを一覧表示できます。ディレクトリは常に一覧表示されます
+- 必要に応じて outputFormat を "json" に設定すると、テキストの一覧を解析する代わりに、path、type、size_bytes、modified_unix、permissions フィールドを持つオブジェクトの配列としてエントリを取得できます
+- 必要に応じて outputFormat を "tree" に設定すると、再帰的な一覧の名前のみをツリー形式で表示します。プロジェクトの構成を把握する最も簡単な方法です
+
+制限事項:
+- 返されるエントリは最大 1000 件です。一覧は `Total:` 行で終わり、一覧表示されたファイルとディレクトリの数、合計サイズ、および省略されたエントリ数が示されます
+- 10000 件を超えるエントリを含むディレクトリは切り詰められます
+"#;
+
 const LS_SCHEMA: &str = r#"
 {
     "type": "object",
@@ -62,6 +132,72 @@ const LS_SCHEMA: &str = r#"
                 "type": "string",
                 "description": "Glob pattern to ignore"
             }
+        },
+        "showHidden": {
+            "type": "boolean",
+            "description": "Whether or not to list hidden entries, whose names start with `.`, and recurse into hidden directories. `.git` directories are always excluded, regardless of this flag, to avoid clutter",
+            "default": false
+        },
+        "followMounts": {
+            "type": "boolean",
+            "description": "Whether or not to recurse into directories that are mount points, e.g. network shares",
+            "default": false
+        },
+        "showUsageSummary": {
+            "type": "boolean",
+            "description": "Whether or not to include the total size and number of files and directories listed",
+            "default": false
+        },
+        "showInodes": {
+            "type": "boolean",
+            "description": "Whether or not to prefix each entry with its inode number, like `ls -i`, to tell whether two paths are hard links to the same file. Ignored on Windows",
+            "default": false
+        },
+        "treeFormat": {
+            "type": "boolean",
+            "description": "Deprecated: use outputFormat \"tree\" instead. Setting this to true is the same as setting outputFormat to \"tree\", and is ignored when outputFormat is set",
+            "default": false
+        },
+        "respectGitignore": {
+            "type": "boolean",
+            "description": "Whether or not to skip entries excluded by .gitignore files in the listed directories",
+            "default": false
+        },
+        "groupBy": {
+            "type": "string",
+            "enum": ["extension", "type", "none"],
+            "description": "How to group the listed entries. \"extension\" groups entries by file extension, and \"type\" lists directories, then files, then symlinks. Ignored when outputFormat is \"json\" or \"tree\"",
+            "default": "none"
+        },
+        "sortBy": {
+            "type": "string",
+            "enum": ["name", "size", "modified", "type"],
+            "description": "How to sort the entries of each directory. \"type\" lists directories, then files, then symlinks, each sorted by name",
+            "default": "modified"
+        },
+        "sortDesc": {
+            "type": "boolean",
+            "description": "Whether or not to sort in descending order. Defaults to true when sorting by modified time, so the most recently modified entries are listed first, and false otherwise"
+        },
+        "filterType": {
+            "type": "string",
+            "enum": ["file", "directory", "symlink", "all"],
+            "description": "Only list entries of this type. Directories are still searched through when depth is greater than 0, and are kept in the tree when outputFormat is \"tree\" so that nested entries can be shown",
+            "default": "all"
+        },
+        "minSizeBytes": {
+            "type": "integer",
+            "description": "Only list files that are at least this many bytes in size. Symlinks are filtered by the size of their target. Ignored for directories, which are always listed"
+        },
+        "maxSizeBytes": {
+            "type": "integer",
+            "description": "Only list files that are at most this many bytes in size. Symlinks are filtered by the size of their target. Ignored for directories, which are always listed"
+        },
+        "outputFormat": {
+            "type": "string",
+            "enum": ["text", "json", "tree"],
+            "description": "Format of the listed entries. \"json\" returns an array of objects with path, type (\"file\", \"directory\", or \"symlink\"), size_bytes, modified_unix, and permissions (e.g. \"rwxr-xr-x\", null on Windows) fields, in listed order. \"tree\" draws the names of the listed entries as a tree, with directories ending in `/` and truncated directories ending in `... (N more entries)`. groupBy is ignored when \"json\" or \"tree\" is used",
+            "default": "text"
         }
     },
     "required": [
@@ -72,12 +208,17 @@ const LS_SCHEMA: &str = r#"
 
 /// Directory names to not search through when performing recursive directory listings.
 ///
-/// The model would have to explicitly search these directories if it wants to.
-const IGNORE_PATTERNS: [&str; 7] = ["node_modules", "bin", "build", "dist", "out", ".cache", ".git"];
+/// The model would have to explicitly search these directories if it wants to. Hidden entries
+/// (e.g. `.cache`, `.env`) are excluded separately unless `show_hidden` is true.
+const IGNORE_PATTERNS: [&str; 5] = ["node_modules", "bin", "build", "dist", "out"];
 
-// The max number of entry listing results to send to the model.
+// The max number of entry listing results to send to the model, unless configured with
+// [LsSettings::max_entries].
 const MAX_LS_ENTRIES: usize = 1000;
 
+/// The maximum amount of directories that will be read concurrently.
+const MAX_CONCURRENT_DIR_READS: usize = 8;
+
 /// The maximum amount of entries that will be read within a given directory.
 const MAX_ENTRY_COUNT_PER_DIR: usize = 10_000;
 
@@ -90,16 +231,169 @@ impl BuiltInToolTrait for Ls {
         LS_TOOL_DESCRIPTION.into()
     }
 
+    fn description_for_locale(locale: &str) -> std::borrow::Cow<'static, str> {
+        match ToolLocale::parse(locale) {
+            Some(ToolLocale::ZhCn) => LS_TOOL_DESCRIPTION_ZH_CN.into(),
+            Some(ToolLocale::Ja) => LS_TOOL_DESCRIPTION_JA.into(),
+            None => Self::description(),
+        }
+    }
+
     fn input_schema() -> std::borrow::Cow<'static, str> {
         LS_SCHEMA.into()
     }
+
+    fn constraints() -> Vec<String> {
+        vec![
+            "Do not use relative path traversal (e.g. `../..`) to list directories above the filesystem root or the user's workspace unless explicitly asked".to_string(),
+        ]
+    }
+
+    fn examples() -> Vec<ToolExample> {
+        vec![
+            ToolExample::new("List the current directory", serde_json::json!({ "path": "." })),
+            ToolExample::new(
+                "Recursively list a directory two levels deep, skipping test files",
+                serde_json::json!({ "path": "src", "depth": 2, "ignore": ["*_test.rs"] }),
+            ),
+        ]
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Ls {
     pub path: String,
     pub depth: Option<usize>,
     pub ignore: Option<Vec<String>>,
+    pub follow_mounts: Option<bool>,
+    pub show_hidden: Option<bool>,
+    pub show_usage_summary: Option<bool>,
+    /// Deprecated alias of [LsOutputFormat::Tree], used when [Ls::output_format] is not set.
+    pub tree_format: Option<bool>,
+    pub respect_gitignore: Option<bool>,
+    pub group_by: Option<LsGroupBy>,
+    pub sort_by: Option<LsSortKey>,
+    /// Whether to sort in descending order. Defaults to true for [LsSortKey::Modified], and false
+    /// otherwise.
+    pub sort_desc: Option<bool>,
+    pub output_format: Option<LsOutputFormat>,
+    pub min_size_bytes: Option<u64>,
+    pub max_size_bytes: Option<u64>,
+    pub filter_type: Option<LsEntryType>,
+    /// Whether to prefix each entry with its inode number. Only supported on Unix.
+    pub show_inodes: Option<bool>,
+}
+
+/// Type of an entry listed by [Ls], used to filter the listing with [Ls::filter_type].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LsEntryType {
+    File,
+    Directory,
+    Symlink,
+    #[default]
+    All,
+}
+
+impl LsEntryType {
+    fn of(md: &Metadata) -> Self {
+        match format_ftype(md) {
+            'd' => Self::Directory,
+            'l' => Self::Symlink,
+            _ => Self::File,
+        }
+    }
+
+    fn matches(self, md: &Metadata) -> bool {
+        self == Self::All || self == Self::of(md)
+    }
+}
+
+/// Format of the entries listed by [Ls].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LsOutputFormat {
+    /// Lines in the style of `ls -l`.
+    #[default]
+    Text,
+    /// A [ToolExecutionOutputItem::Json] array with an object for each entry, in listed order.
+    Json,
+    /// Entry names drawn as a tree with box-drawing characters, like `tree`.
+    Tree,
+}
+
+/// How the entries of each directory listed by [Ls] are sorted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LsSortKey {
+    /// Sort entries by file name.
+    Name,
+    /// Sort entries by size in bytes.
+    Size,
+    /// Sort entries by last modified time.
+    #[default]
+    Modified,
+    /// Sort directories, then files, then symlinks, each by file name.
+    Type,
+}
+
+impl LsSortKey {
+    /// Sorts `entries` by this key, in descending order if `desc` is set.
+    fn sort(self, entries: &mut [Entry], desc: bool) {
+        let name = |entry: &Entry| entry.path.file_name().map(|name| name.to_os_string());
+        let type_rank = |entry: &Entry| match &entry.metadata {
+            md if md.is_dir() => 0,
+            md if md.is_symlink() => 2,
+            _ => 1,
+        };
+        match self {
+            LsSortKey::Name => entries.sort_by_key(name),
+            LsSortKey::Size => entries.sort_by_key(|entry| entry.metadata.len()),
+            LsSortKey::Modified => entries.sort_by_key(|entry| entry.last_modified),
+            LsSortKey::Type => entries.sort_by_key(|entry| (type_rank(entry), name(entry))),
+        }
+        if desc {
+            entries.reverse();
+        }
+    }
+}
+
+/// How the entries listed by [Ls] are grouped. Grouping only applies to [LsOutputFormat::Text].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LsGroupBy {
+    /// Group entries by their file extension, sorted by extension.
+    Extension,
+    /// Group directories, then files, then symlinks.
+    Type,
+    #[default]
+    None,
+}
+
+/// Disk usage of the entries returned by [Ls], included in the output if
+/// [Ls::show_usage_summary] is set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LsUsageSummary {
+    /// Sum of the file sizes
+    pub total_apparent_bytes: u64,
+    /// Sum of the space allocated on disk, accounting for block alignment
+    pub total_disk_bytes: u64,
+    pub file_count: u64,
+    pub dir_count: u64,
+}
+
+impl LsUsageSummary {
+    fn add(&mut self, md: &Metadata) {
+        self.total_apparent_bytes += md.len();
+        self.total_disk_bytes += disk_usage(md);
+        if md.is_dir() {
+            self.dir_count += 1;
+        } else if md.is_file() {
+            self.file_count += 1;
+        }
+    }
 }
 
 impl Ls {
@@ -123,11 +417,53 @@ impl Ls {
         {
             return Err(format!("Path is not a directory: {}", path.to_string_lossy()));
         }
+        if let (Some(min), Some(max)) = (self.min_size_bytes, self.max_size_bytes) {
+            if min > max {
+                return Err(format!(
+                    "minSizeBytes ({}) must not be greater than maxSizeBytes ({})",
+                    min, max
+                ));
+            }
+        }
         Ok(())
     }
 
-    pub async fn execute<P: SystemProvider>(&self, provider: &P) -> ToolExecutionResult {
+    pub async fn execute<P: SystemProvider>(&self, provider: &P, settings: Option<&LsSettings>) -> ToolExecutionResult {
+        self.execute_with_concurrency(provider, settings, MAX_CONCURRENT_DIR_READS)
+            .await
+    }
+
+    /// Same as [Self::execute], except that the entries of a plain listing are sent through
+    /// `output` as each depth level is read. Returns the output items that were not sent.
+    pub async fn execute_stream<P: SystemProvider>(
+        &self,
+        provider: &P,
+        settings: Option<&LsSettings>,
+        output: &ToolOutputSender,
+    ) -> ToolExecutionResult {
+        self.execute_inner(provider, settings, MAX_CONCURRENT_DIR_READS, Some(output))
+            .await
+    }
+
+    /// Same as [Self::execute], reading at most `max_concurrency` directories in parallel.
+    pub async fn execute_with_concurrency<P: SystemProvider>(
+        &self,
+        provider: &P,
+        settings: Option<&LsSettings>,
+        max_concurrency: usize,
+    ) -> ToolExecutionResult {
+        self.execute_inner(provider, settings, max_concurrency, None).await
+    }
+
+    async fn execute_inner<P: SystemProvider>(
+        &self,
+        provider: &P,
+        settings: Option<&LsSettings>,
+        max_concurrency: usize,
+        output: Option<&ToolOutputSender>,
+    ) -> ToolExecutionResult {
         let path = self.canonical_path(provider)?;
+        let max_entries = settings.and_then(|s| s.max_entries).unwrap_or(MAX_LS_ENTRIES);
         let max_depth = self.depth();
         debug!(?path, max_depth, "Reading directory at path with depth");
 
@@ -135,6 +471,31 @@ impl Ls {
         let mut prefix = Vec::new();
         // Directory listing results
         let mut result = Vec::new();
+        // Entries in the order they were listed, used to build the flat format.
+        let mut listed_entries = Vec::new();
+        // Only plain listings are sent while listing, since other formats depend on every entry.
+        let output =
+            output.filter(|_| self.output_format() == LsOutputFormat::Text && self.group_by() == LsGroupBy::None);
+        // Number of listed entries that have been sent through `output`.
+        let mut sent_entries = 0;
+        let mut listed = 0;
+        // Number of entries left unexamined because the listing was truncated.
+        let mut omitted = 0;
+        let mut omitted_more = false;
+        let mut usage = LsUsageSummary::default();
+        // Entries grouped by their parent directory, used to build the tree format.
+        let mut children: HashMap<PathBuf, Vec<Entry>> = HashMap::new();
+        // Directories whose listing was truncated, with the number of entries left out and whether
+        // or not there are more entries than were read.
+        let mut truncated_dirs: HashMap<PathBuf, (usize, bool)> = HashMap::new();
+        // Rules from outside of the listing, extended with the .gitignore files found while listing.
+        let mut gitignores = GitignoreRules::new(&path);
+        // Listed directories that are excluded by a .gitignore file, along with their contents.
+        let mut gitignored_dirs: HashSet<PathBuf> = HashSet::new();
+        if gitignores.is_dir_ignored(&path) {
+            gitignored_dirs.insert(path.clone());
+        }
+        let mut gitignore_excluded_count = 0;
 
         #[cfg(unix)]
         {
@@ -142,84 +503,174 @@ impl Ls {
             prefix.push(format!("User id: {}", user_id));
         }
 
-        let mut dir_queue = VecDeque::new();
-        dir_queue.push_back((path.clone(), 0));
-        while let Some((dir_path, depth)) = dir_queue.pop_front() {
-            if depth > max_depth {
-                break;
-            }
-
-            let mut read_dir = tokio::fs::read_dir(&dir_path)
+        let root_dev = device_id(
+            &tokio::fs::metadata(&path)
                 .await
-                .map_err(|e| format!("failed to read directory path '{}': {}", dir_path.to_string_lossy(), e))?;
+                .map_err(|e| format!("failed to get metadata for {}: {}", path.to_string_lossy(), e))?,
+        );
 
-            let mut entries = Vec::new();
-            let mut exceeded_threshold = false;
+        let ignore = Arc::new(self.ignore.clone().unwrap_or_default());
+        let (sort_by, sort_desc) = (self.sort_by(), self.sort_desc());
+        let semaphore = Arc::new(Semaphore::new(max_concurrency.max(1)));
 
-            let mut i = 0;
-            while let Some(ent) = read_dir
-                .next_entry()
-                .await
-                .map_err(|e| format!("failed to get next entry: {}", e))?
-            {
-                // Ignore the entry if it matches one of the ignore arguments.
-                let entry_path = ent.path();
-                if self.matches_ignore_patterns(&entry_path) {
-                    trace!("ignoring file: {}", entry_path.to_string_lossy());
-                    continue;
-                }
+        // Directories are read in parallel one depth level at a time, and then processed in the
+        // order they were discovered so that the output is the same as a sequential breadth-first
+        // traversal.
+        let mut level = vec![path.clone()];
+        let mut depth = 0;
+        while !level.is_empty() && depth <= max_depth {
+            let mut join_set = JoinSet::new();
+            for (i, dir_path) in level.iter().cloned().enumerate() {
+                let ignore = Arc::clone(&ignore);
+                let semaphore = Arc::clone(&semaphore);
+                join_set.spawn(async move {
+                    let _permit = semaphore.acquire_owned().await;
+                    (
+                        i,
+                        read_dir_entries(&dir_path, &ignore, root_dev, sort_by, sort_desc).await,
+                    )
+                });
+            }
 
-                entries.push(Entry::new(ent).await?);
-                i += 1;
-                if i > MAX_ENTRY_COUNT_PER_DIR {
-                    exceeded_threshold = true;
-                }
+            let mut level_results = (0..level.len()).map(|_| None).collect::<Vec<_>>();
+            while let Some(res) = join_set.join_next().await {
+                let (i, entries) = res.map_err(|e| format!("failed to read directory: {}", e))?;
+                level_results[i] = Some(entries);
             }
 
-            entries.sort_by_key(|ent| ent.last_modified);
-            entries.reverse();
+            let mut next_level = Vec::new();
+            for (dir_path, entries) in level.iter().zip(level_results) {
+                let (entries, exceeded_threshold) = entries.expect("all directories should have been read")?;
 
-            // Finally, handle results
-            for entry in &entries {
-                result.push(entry.to_long_format());
-
-                // Break if we've exceeded the Ls result threshold.
-                if result.len() > MAX_LS_ENTRIES {
-                    prefix.push(format!(
-                        "Directory at {} was truncated (has total {}{} entries)",
-                        dir_path.to_string_lossy(),
-                        entries.len(),
-                        if exceeded_threshold { "+" } else { "" }
-                    ));
-                    break;
+                if entries
+                    .iter()
+                    .any(|e| e.path.file_name() == Some(".gitignore".as_ref()))
+                {
+                    gitignores.add_dir(dir_path);
                 }
 
-                // Otherwise, continue searching
-                if entry.metadata.is_dir() {
-                    // Exclude the directory from being searched if it is a commonly ignored
-                    // directory.
-                    if matches_any_pattern(IGNORE_PATTERNS, entry.path.to_string_lossy()) {
+                // Finally, handle results
+                for (i, entry) in entries.iter().enumerate() {
+                    if self.is_hidden(entry) || !self.is_within_size_range(entry).await {
                         continue;
                     }
-                    dir_queue.push_back((entry.path.clone(), depth + 1));
+                    if !gitignores.is_empty()
+                        && (gitignored_dirs.contains(dir_path)
+                            || gitignores.is_ignored(&entry.path, entry.metadata.is_dir()))
+                    {
+                        gitignore_excluded_count += 1;
+                        if self.respect_gitignore() {
+                            continue;
+                        }
+                        if entry.metadata.is_dir() {
+                            gitignored_dirs.insert(entry.path.clone());
+                        }
+                    }
+
+                    // Directories that don't match the type filter are still searched through,
+                    // and kept in the tree so that their matching descendants can be shown.
+                    let is_tree = self.output_format() == LsOutputFormat::Tree;
+                    if self.filter_type().matches(&entry.metadata) || (is_tree && entry.metadata.is_dir()) {
+                        if is_tree {
+                            children.entry(dir_path.clone()).or_default().push(entry.clone());
+                        } else {
+                            listed_entries.push(entry.clone());
+                        }
+                        usage.add(&entry.metadata);
+                        listed += 1;
+
+                        // Break if we've exceeded the Ls result threshold.
+                        if listed > max_entries {
+                            prefix.push(format!(
+                                "Directory at {} was truncated (has total {}{} entries)",
+                                dir_path.to_string_lossy(),
+                                entries.len(),
+                                if exceeded_threshold { "+" } else { "" }
+                            ));
+                            omitted += entries.len() - i - 1;
+                            omitted_more |= exceeded_threshold;
+                            truncated_dirs.insert(dir_path.clone(), (entries.len() - i - 1, exceeded_threshold));
+                            break;
+                        }
+                    }
+
+                    // Otherwise, continue searching
+                    if entry.metadata.is_dir() && !self.is_excluded_from_recursion(entry) {
+                        next_level.push(entry.path.clone());
+                    }
+                }
+                if exceeded_threshold {
+                    truncated_dirs.entry(dir_path.clone()).or_insert((0, true));
+                }
+            }
+
+            if let Some(output) = output {
+                if listed_entries.len() > sent_entries {
+                    let mut lines = Vec::new();
+                    format_grouped(
+                        &listed_entries[sent_entries..],
+                        LsGroupBy::None,
+                        self.show_inodes(),
+                        &mut lines,
+                    );
+                    send_output(output, ToolExecutionOutputItem::Text(lines.join("\n"))).await;
+                    sent_entries = listed_entries.len();
                 }
             }
+
+            level = next_level;
+            depth += 1;
         }
 
-        let prefix = prefix.join("\n");
-        let result = result.join("\n");
-        Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Text(format!(
-            "{}\n{}",
-            prefix, result
-        ))]))
-    }
+        let mut total = format!(
+            "Total: {} files, {} directories, {} bytes ({})",
+            usage.file_count,
+            usage.dir_count,
+            usage.total_apparent_bytes,
+            format_bytes(usage.total_apparent_bytes)
+        );
+        if omitted > 0 || omitted_more {
+            total.push_str(&format!(
+                "; {}{} entries omitted due to truncation",
+                omitted,
+                if omitted_more { "+" } else { "" }
+            ));
+        }
 
-    fn matches_ignore_patterns(&self, path: impl AsRef<Path>) -> bool {
-        let path = path.as_ref().to_string_lossy();
-        match &self.ignore {
-            Some(patterns) => matches_any_pattern(patterns, path),
-            None => false,
+        let mut items = Vec::new();
+        if self.output_format() == LsOutputFormat::Json {
+            prefix.push(total);
+            items.push(ToolExecutionOutputItem::Text(prefix.join("\n")));
+            items.push(ToolExecutionOutputItem::Json(
+                listed_entries.iter().map(Entry::to_json).collect(),
+            ));
+        } else {
+            if self.output_format() == LsOutputFormat::Tree {
+                result = vec![path.to_string_lossy().to_string()];
+                format_tree(&path, &children, &truncated_dirs, "", &mut result);
+            } else {
+                format_grouped(
+                    &listed_entries[sent_entries..],
+                    self.group_by(),
+                    self.show_inodes(),
+                    &mut result,
+                );
+            }
+            result.push(total);
+            let prefix = prefix.join("\n");
+            let result = result.join("\n");
+            items.push(ToolExecutionOutputItem::Text(format!("{}\n{}", prefix, result)));
+        }
+        if self.show_usage_summary.unwrap_or(false) {
+            let usage = serde_json::to_value(usage).map_err(|e| format!("failed to serialize usage summary: {}", e))?;
+            items.push(ToolExecutionOutputItem::Json(usage));
+        }
+        if !gitignores.is_empty() {
+            items.push(ToolExecutionOutputItem::Json(serde_json::json!({
+                "gitignore_excluded_count": gitignore_excluded_count
+            })));
         }
+        Ok(ToolExecutionOutput::new(items))
     }
 
     fn canonical_path<P: SystemProvider>(&self, provider: &P) -> Result<PathBuf, String> {
@@ -231,6 +682,313 @@ impl Ls {
     fn depth(&self) -> usize {
         self.depth.unwrap_or(Self::DEFAULT_DEPTH)
     }
+
+    fn follow_mounts(&self) -> bool {
+        self.follow_mounts.unwrap_or(false)
+    }
+
+    fn show_hidden(&self) -> bool {
+        self.show_hidden.unwrap_or(false)
+    }
+
+    fn respect_gitignore(&self) -> bool {
+        self.respect_gitignore.unwrap_or(false)
+    }
+
+    fn group_by(&self) -> LsGroupBy {
+        self.group_by.unwrap_or_default()
+    }
+
+    fn sort_by(&self) -> LsSortKey {
+        self.sort_by.unwrap_or_default()
+    }
+
+    fn show_inodes(&self) -> bool {
+        self.show_inodes.unwrap_or(false)
+    }
+
+    fn filter_type(&self) -> LsEntryType {
+        self.filter_type.unwrap_or_default()
+    }
+
+    fn output_format(&self) -> LsOutputFormat {
+        match (self.output_format, self.tree_format) {
+            (Some(format), _) => format,
+            (None, Some(true)) => LsOutputFormat::Tree,
+            (None, _) => LsOutputFormat::default(),
+        }
+    }
+
+    fn sort_desc(&self) -> bool {
+        self.sort_desc.unwrap_or(self.sort_by() == LsSortKey::Modified)
+    }
+
+    /// Whether or not `entry` should be left out of the listing. `.git` directories are always
+    /// hidden, and other entries starting with `.` are hidden unless [Ls::show_hidden] is set.
+    fn is_hidden(&self, entry: &Entry) -> bool {
+        let name = entry.path.file_name().unwrap_or_default();
+        if name == ".git" {
+            return true;
+        }
+        name.to_string_lossy().starts_with('.') && !self.show_hidden()
+    }
+
+    /// Whether or not `entry` passes the [Ls::min_size_bytes] and [Ls::max_size_bytes] filters.
+    /// Directories are always included, and symlinks are checked against the size of their
+    /// target.
+    async fn is_within_size_range(&self, entry: &Entry) -> bool {
+        if self.min_size_bytes.is_none() && self.max_size_bytes.is_none() {
+            return true;
+        }
+        let size = if entry.metadata.is_symlink() {
+            match tokio::fs::metadata(&entry.path).await {
+                Ok(md) if md.is_dir() => return true,
+                Ok(md) => md.len(),
+                // Broken symlinks have no size to compare against.
+                Err(_) => return false,
+            }
+        } else if entry.metadata.is_dir() {
+            return true;
+        } else {
+            entry.metadata.len()
+        };
+        self.min_size_bytes.is_none_or(|min| size >= min) && self.max_size_bytes.is_none_or(|max| size <= max)
+    }
+
+    /// Whether or not the directory given by `entry` should not be searched through when
+    /// performing a recursive listing.
+    fn is_excluded_from_recursion(&self, entry: &Entry) -> bool {
+        let name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+        // Commonly ignored directories
+        if matches_any_pattern(IGNORE_PATTERNS, &name) {
+            return true;
+        }
+        // Avoid accidentally walking into slow network shares.
+        entry.is_mount_point && !self.follow_mounts()
+    }
+}
+
+/// Reads the entries of the directory at `dir_path`, sorted by `sort_by`.
+///
+/// Also returns whether or not the directory contains more than [MAX_ENTRY_COUNT_PER_DIR]
+/// entries.
+async fn read_dir_entries(
+    dir_path: &Path,
+    ignore: &[String],
+    root_dev: Option<u64>,
+    sort_by: LsSortKey,
+    sort_desc: bool,
+) -> Result<(Vec<Entry>, bool), String> {
+    let mut read_dir = tokio::fs::read_dir(&dir_path)
+        .await
+        .map_err(|e| format!("failed to read directory path '{}': {}", dir_path.to_string_lossy(), e))?;
+
+    let mut entries = Vec::new();
+    let mut exceeded_threshold = false;
+
+    let mut i = 0;
+    while let Some(ent) = read_dir
+        .next_entry()
+        .await
+        .map_err(|e| format!("failed to get next entry: {}", e))?
+    {
+        // Ignore the entry if it matches one of the ignore arguments.
+        let entry_path = ent.path();
+        if matches_any_pattern(ignore, entry_path.to_string_lossy()) {
+            trace!("ignoring file: {}", entry_path.to_string_lossy());
+            continue;
+        }
+
+        entries.push(Entry::new(ent, root_dev).await?);
+        i += 1;
+        if i > MAX_ENTRY_COUNT_PER_DIR {
+            exceeded_threshold = true;
+        }
+    }
+
+    sort_by.sort(&mut entries, sort_desc);
+
+    Ok((entries, exceeded_threshold))
+}
+
+/// The gitignore rules that apply to a listing, matched with git's precedence: the .gitignore
+/// files in an entry's parent directories, closest first, then the repository's
+/// `.git/info/exclude`, then the user's global excludes file.
+#[derive(Debug, Default)]
+struct GitignoreRules {
+    /// Root of the git repository containing the listing, if any.
+    repo_root: Option<PathBuf>,
+    /// Rules of each .gitignore file, keyed by the directory containing it.
+    dirs: HashMap<PathBuf, Gitignore>,
+    /// Rules from `.git/info/exclude` and the global excludes file, in order of precedence.
+    repo: Vec<Gitignore>,
+}
+
+impl GitignoreRules {
+    /// Loads the rules that apply to the entries under `root` from outside of it. If `root` is in
+    /// a git repository, these are the .gitignore files between the repository root and `root`,
+    /// along with the repository and global excludes.
+    fn new(root: &Path) -> Self {
+        let mut rules = Self::default();
+        let Some(repo_root) = root.ancestors().find(|dir| dir.join(".git").exists()) else {
+            return rules;
+        };
+        rules.repo_root = Some(repo_root.to_path_buf());
+        for dir in root.ancestors().skip(1).take_while(|dir| dir.starts_with(repo_root)) {
+            if dir.join(".gitignore").is_file() {
+                rules.add_dir(dir);
+            }
+        }
+
+        let mut exclude = GitignoreBuilder::new(repo_root);
+        if let Some(err) = exclude.add(repo_root.join(".git").join("info").join("exclude")) {
+            debug!(
+                ?err,
+                "failed to read .git/info/exclude in {}",
+                repo_root.to_string_lossy()
+            );
+        }
+        match exclude.build() {
+            Ok(exclude) => rules.repo.push(exclude),
+            Err(err) => warn!(
+                ?err,
+                "failed to parse .git/info/exclude in {}",
+                repo_root.to_string_lossy()
+            ),
+        }
+        let (global, err) = GitignoreBuilder::new(repo_root).build_global();
+        if let Some(err) = err {
+            warn!(?err, "failed to read the global git excludes file");
+        }
+        rules.repo.push(global);
+        rules
+    }
+
+    /// Adds the rules of the .gitignore file in `dir`. Invalid patterns are skipped.
+    fn add_dir(&mut self, dir: &Path) {
+        let (gitignore, err) = Gitignore::new(dir.join(".gitignore"));
+        if let Some(err) = err {
+            warn!(?err, "failed to read .gitignore in {}", dir.to_string_lossy());
+        }
+        self.dirs.insert(dir.to_path_buf(), gitignore);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.dirs.values().chain(&self.repo).all(Gitignore::is_empty)
+    }
+
+    /// Whether or not `path` is excluded, not accounting for whether any of its parent
+    /// directories are excluded.
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let dir_rules = path.ancestors().skip(1).filter_map(|dir| self.dirs.get(dir));
+        for gitignore in dir_rules.chain(&self.repo) {
+            match gitignore.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => (),
+            }
+        }
+        false
+    }
+
+    /// Whether or not `dir` or any of its parent directories within the repository are excluded.
+    fn is_dir_ignored(&self, dir: &Path) -> bool {
+        let Some(repo_root) = &self.repo_root else {
+            return false;
+        };
+        dir.ancestors()
+            .take_while(|dir| dir.starts_with(repo_root) && dir != repo_root)
+            .any(|dir| self.is_ignored(dir, true))
+    }
+}
+
+/// Appends the names of the entries of `dir` and its listed subdirectories to `lines` for
+/// [LsOutputFormat::Tree], with each nested level prefixed by `prefix`. Directories in
+/// `truncated_dirs` end with a `... (N more entries)` line.
+fn format_tree(
+    dir: &Path,
+    children: &HashMap<PathBuf, Vec<Entry>>,
+    truncated_dirs: &HashMap<PathBuf, (usize, bool)>,
+    prefix: &str,
+    lines: &mut Vec<String>,
+) {
+    let entries = children.get(dir).map(Vec::as_slice).unwrap_or_default();
+    let truncated = truncated_dirs.get(dir);
+    let count = entries.len() + usize::from(truncated.is_some());
+    for (i, entry) in entries.iter().enumerate() {
+        let is_last = i + 1 == count;
+        lines.push(format!(
+            "{}{}{}",
+            prefix,
+            if is_last { "└── " } else { "├── " },
+            entry.tree_name()
+        ));
+        let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+        format_tree(&entry.path, children, truncated_dirs, &child_prefix, lines);
+    }
+    match truncated {
+        Some((0, _)) => lines.push(format!("{}└── ... (more entries)", prefix)),
+        Some((n, more)) => lines.push(format!(
+            "{}└── ... ({}{} more entries)",
+            prefix,
+            n,
+            if *more { "+" } else { "" }
+        )),
+        None => (),
+    }
+}
+
+/// Appends `entries` to `lines`, preceded by a `--- <group> ---` header for each group if
+/// `group_by` is not [LsGroupBy::None]. Entries keep their listed order within a group.
+fn format_grouped(entries: &[Entry], group_by: LsGroupBy, show_inodes: bool, lines: &mut Vec<String>) {
+    if group_by == LsGroupBy::None {
+        lines.extend(entries.iter().map(|entry| entry.to_long_format(show_inodes)));
+        return;
+    }
+
+    let group_of = |entry: &Entry| -> (u8, String) {
+        match group_by {
+            LsGroupBy::Extension => match entry.path.extension() {
+                Some(ext) => (0, format!(".{}", ext.to_string_lossy())),
+                None => (1, "(no extension)".to_string()),
+            },
+            LsGroupBy::Type if entry.metadata.is_dir() => (0, "directories".to_string()),
+            LsGroupBy::Type if entry.metadata.is_symlink() => (2, "symlinks".to_string()),
+            LsGroupBy::Type => (1, "files".to_string()),
+            LsGroupBy::None => (0, String::new()),
+        }
+    };
+
+    let mut grouped = entries.iter().map(|entry| (group_of(entry), entry)).collect::<Vec<_>>();
+    grouped.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for group in grouped.chunk_by(|(a, _), (b, _)| a == b) {
+        lines.push(format!("--- {} ---", (group[0].0).1));
+        lines.extend(group.iter().map(|(_, entry)| entry.to_long_format(show_inodes)));
+    }
+}
+
+/// Returns the id of the device containing the file, if supported by the platform.
+#[cfg(unix)]
+fn device_id(md: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(md.dev())
+}
+
+#[cfg(windows)]
+fn device_id(_md: &Metadata) -> Option<u64> {
+    None
+}
+
+/// Returns the number of bytes allocated on disk for the file, equivalent to `du`.
+#[cfg(unix)]
+fn disk_usage(md: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    md.blocks() * 512
+}
+
+#[cfg(windows)]
+fn disk_usage(md: &Metadata) -> u64 {
+    md.len()
 }
 
 #[derive(Debug, Clone)]
@@ -239,10 +997,12 @@ struct Entry {
     metadata: Metadata,
     /// Seconds since UNIX Epoch
     last_modified: u64,
+    /// Whether or not the entry is on a different device than the listed root directory
+    is_mount_point: bool,
 }
 
 impl Entry {
-    async fn new(ent: DirEntry) -> Result<Self, String> {
+    async fn new(ent: DirEntry, root_dev: Option<u64>) -> Result<Self, String> {
         let entry_path = ent.path();
 
         let metadata = ent
@@ -269,15 +1029,22 @@ impl Entry {
             })?
             .as_secs();
 
+        let is_mount_point = match (root_dev, device_id(&metadata)) {
+            (Some(root), Some(dev)) => metadata.is_dir() && root != dev,
+            _ => false,
+        };
+
         Ok(Self {
             path: entry_path,
             metadata,
             last_modified,
+            is_mount_point,
         })
     }
 
+    /// Formats the entry like `ls -l`, or `ls -li` if `show_inodes` is true.
     #[cfg(unix)]
-    fn to_long_format(&self) -> String {
+    fn to_long_format(&self, show_inodes: bool) -> String {
         use std::os::unix::fs::{
             MetadataExt,
             PermissionsExt,
@@ -295,20 +1062,27 @@ impl Entry {
             .unwrap();
 
         format!(
-            "{}{} {} {} {} {} {} {}",
+            "{}{}{}{} {} {} {} {} {} {}",
+            if show_inodes {
+                format!("{} ", self.metadata.ino())
+            } else {
+                String::new()
+            },
             format_ftype(&self.metadata),
             formatted_mode,
+            if self.is_mount_point { "M" } else { "" },
             self.metadata.nlink(),
             self.metadata.uid(),
             self.metadata.gid(),
             self.metadata.size(),
             formatted_date,
-            self.path.to_string_lossy()
+            self.display_name()
         )
     }
 
+    /// Inodes are not shown on Windows, where file indexes are not stable.
     #[cfg(windows)]
-    fn to_long_format(&self) -> String {
+    fn to_long_format(&self, _show_inodes: bool) -> String {
         let datetime = time::OffsetDateTime::from_unix_timestamp(self.last_modified as i64).unwrap();
         let formatted_date = datetime
             .format(time::macros::format_description!(
@@ -321,9 +1095,64 @@ impl Entry {
             format_ftype(&self.metadata),
             self.metadata.len(),
             formatted_date,
-            self.path.to_string_lossy()
+            self.display_name()
         )
     }
+
+    /// Formats the entry for [LsOutputFormat::Json].
+    fn to_json(&self) -> serde_json::Value {
+        #[cfg(unix)]
+        let permissions = {
+            use std::os::unix::fs::PermissionsExt;
+            Some(
+                format_mode(self.metadata.permissions().mode())
+                    .into_iter()
+                    .collect::<String>(),
+            )
+        };
+        #[cfg(windows)]
+        let permissions: Option<String> = None;
+
+        serde_json::json!({
+            "path": self.path.to_string_lossy(),
+            "type": LsEntryType::of(&self.metadata),
+            "size_bytes": self.metadata.len(),
+            "modified_unix": self.last_modified,
+            "permissions": permissions,
+        })
+    }
+
+    /// The full path of the entry. Symlinks are followed by ` -> <target>`, and ` [dangling]` if
+    /// the target does not exist.
+    fn display_name(&self) -> String {
+        format!("{}{}", self.path.to_string_lossy(), self.symlink_target())
+    }
+
+    /// The file name of the entry for [LsOutputFormat::Tree], ending with `/` for directories.
+    fn tree_name(&self) -> String {
+        let name = self.path.file_name().unwrap_or_default().to_string_lossy();
+        if self.metadata.is_dir() {
+            format!("{}/", name)
+        } else {
+            format!("{}{}", name, self.symlink_target())
+        }
+    }
+
+    /// ` -> <target>` for symlinks, followed by ` [dangling]` if the target does not exist, and
+    /// empty otherwise.
+    fn symlink_target(&self) -> String {
+        if !self.metadata.is_symlink() {
+            return String::new();
+        }
+        match std::fs::read_link(&self.path) {
+            Ok(target) if self.path.exists() => format!(" -> {}", target.to_string_lossy()),
+            Ok(target) => format!(" -> {} [dangling]", target.to_string_lossy()),
+            Err(err) => {
+                warn!(?err, "failed to read symlink target of {}", self.path.to_string_lossy());
+                String::new()
+            },
+        }
+    }
 }
 
 fn format_ftype(md: &Metadata) -> char {
@@ -382,6 +1211,716 @@ mod tests {
         assert_mode!(0o641, "rw-r----x");
     }
 
+    #[tokio::test]
+    async fn test_ls_usage_summary() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("root/file1.txt", "a".repeat(10)))
+            .await
+            .with_file(("root/dir/file2.txt", "b".repeat(5000)))
+            .await;
+
+        let mut tool = Ls {
+            path: test_base.join("root").to_string_lossy().to_string(),
+            depth: Some(1),
+            ..Default::default()
+        };
+        let result = tool.execute(&test_base, None).await.unwrap();
+        assert_eq!(result.items.len(), 1, "usage summary should be opt-in");
+
+        tool.show_usage_summary = Some(true);
+        let result = tool.execute(&test_base, None).await.unwrap();
+        let [ToolExecutionOutputItem::Text(_), ToolExecutionOutputItem::Json(usage)] = &result.items[..] else {
+            panic!("expected text and json output");
+        };
+        let usage: LsUsageSummary = serde_json::from_value(usage.clone()).unwrap();
+        assert_eq!(usage.file_count, 2);
+        assert_eq!(usage.dir_count, 1);
+        assert!(usage.total_apparent_bytes >= 5010);
+        #[cfg(unix)]
+        assert_eq!(usage.total_disk_bytes % 512, 0);
+    }
+
+    #[tokio::test]
+    async fn test_ls_tree_format() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("root/a.txt", "a"))
+            .await
+            .with_file(("root/dir/sub/b.txt", "b"))
+            .await;
+        let root = test_base.join("root").to_string_lossy().to_string();
+
+        let tool = Ls {
+            path: root.clone(),
+            depth: Some(2),
+            sort_by: Some(LsSortKey::Name),
+            tree_format: Some(true),
+            ..Default::default()
+        };
+        let content = tool.execute(&test_base, None).await.unwrap().all_text();
+        let lines = content
+            .lines()
+            .skip_while(|line| *line != root)
+            .take_while(|line| !line.starts_with("Total: "))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [&*root, "├── a.txt", "└── dir/", "    └── sub/", "        └── b.txt"],
+            "{}",
+            content
+        );
+
+        // treeFormat is an alias of the tree output format.
+        let tree_output = Ls {
+            tree_format: None,
+            output_format: Some(LsOutputFormat::Tree),
+            ..tool.clone()
+        };
+        assert_eq!(tree_output.execute(&test_base, None).await.unwrap().all_text(), content);
+
+        // An explicit output format takes precedence.
+        let text_output = Ls {
+            output_format: Some(LsOutputFormat::Text),
+            ..tool
+        };
+        let content = text_output.execute(&test_base, None).await.unwrap().all_text();
+        assert!(!content.contains("── "), "{}", content);
+    }
+
+    #[tokio::test]
+    async fn test_ls_group_by() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("root/b.txt", "b"))
+            .await
+            .with_file(("root/a.rs", "a"))
+            .await
+            .with_file(("root/Makefile", "all:"))
+            .await
+            .with_file(("root/c.rs", "c"))
+            .await
+            .with_file(("root/dir/nested.txt", "nested"))
+            .await;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(test_base.join("root/b.txt"), test_base.join("root/link")).unwrap();
+        let root = test_base.join("root").to_string_lossy().to_string();
+
+        let lines_after_headers = |content: &str| {
+            content
+                .lines()
+                .skip_while(|line| !line.starts_with("--- "))
+                .take_while(|line| !line.starts_with("Total: "))
+                .map(|line| {
+                    line.split(" -> ")
+                        .next()
+                        .unwrap()
+                        .rsplit('/')
+                        .next()
+                        .unwrap()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let tool = Ls {
+            path: root.clone(),
+            group_by: Some(LsGroupBy::Extension),
+            ..Default::default()
+        };
+        let content = tool.execute(&test_base, None).await.unwrap().all_text();
+        let lines = lines_after_headers(&content);
+        let header_idx = |header: &str| lines.iter().position(|l| l == header).expect(header);
+        let (rs, txt, none) = (
+            header_idx("--- .rs ---"),
+            header_idx("--- .txt ---"),
+            header_idx("--- (no extension) ---"),
+        );
+        assert!(rs < txt && txt < none, "{}", content);
+        assert_eq!(txt - rs, 3, "{}", content);
+        assert!(lines[rs + 1..txt].iter().all(|l| l.ends_with(".rs")), "{}", content);
+        assert_eq!(lines[txt + 1], "b.txt", "{}", content);
+        assert!(lines[none + 1..].iter().any(|l| l == "Makefile"), "{}", content);
+
+        let tool = Ls {
+            path: root,
+            group_by: Some(LsGroupBy::Type),
+            ..Default::default()
+        };
+        let content = tool.execute(&test_base, None).await.unwrap().all_text();
+        let lines = lines_after_headers(&content);
+        assert_eq!(lines[0], "--- directories ---", "{}", content);
+        assert_eq!(lines[1], "dir", "{}", content);
+        assert_eq!(lines[2], "--- files ---", "{}", content);
+        assert!(lines[3..7].iter().all(|l| !l.starts_with("---")), "{}", content);
+        #[cfg(unix)]
+        assert_eq!(&lines[7..], ["--- symlinks ---", "link"], "{}", content);
+    }
+
+    #[tokio::test]
+    async fn test_ls_sort_by() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("root/b.txt", "bb"))
+            .await
+            .with_file(("root/a.txt", "aaa"))
+            .await
+            .with_file(("root/c.txt", "c"))
+            .await
+            .with_file(("root/dir/nested.txt", "nested"))
+            .await;
+        let root = test_base.join("root");
+        let set_modified = |name: &str, secs: u64| {
+            let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+            std::fs::File::options()
+                .write(true)
+                .open(root.join(name))
+                .unwrap()
+                .set_modified(time)
+                .unwrap();
+        };
+        set_modified("a.txt", 3_000);
+        set_modified("b.txt", 1_000);
+        set_modified("c.txt", 2_000);
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.join("b.txt"), root.join("0-link")).unwrap();
+
+        let test_base = &test_base;
+        let names = |sort_by: Option<LsSortKey>, sort_desc: Option<bool>| {
+            let tool = Ls {
+                path: root.to_string_lossy().to_string(),
+                sort_by,
+                sort_desc,
+                ..Default::default()
+            };
+            async move {
+                tool.execute(test_base, None)
+                    .await
+                    .unwrap()
+                    .all_text()
+                    .lines()
+                    .skip(1)
+                    .map(|line| {
+                        line.split(" -> ")
+                            .next()
+                            .unwrap()
+                            .rsplit('/')
+                            .next()
+                            .unwrap()
+                            .to_string()
+                    })
+                    .filter(|name| name.ends_with(".txt"))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(names(None, None).await, ["a.txt", "c.txt", "b.txt"]);
+        assert_eq!(names(Some(LsSortKey::Modified), Some(false)).await, [
+            "b.txt", "c.txt", "a.txt"
+        ]);
+        assert_eq!(names(Some(LsSortKey::Name), None).await, ["a.txt", "b.txt", "c.txt"]);
+        assert_eq!(names(Some(LsSortKey::Name), Some(true)).await, [
+            "c.txt", "b.txt", "a.txt"
+        ]);
+        assert_eq!(names(Some(LsSortKey::Size), None).await, ["c.txt", "b.txt", "a.txt"]);
+        assert_eq!(names(Some(LsSortKey::Size), Some(true)).await, [
+            "a.txt", "b.txt", "c.txt"
+        ]);
+
+        let tool = Ls {
+            path: root.to_string_lossy().to_string(),
+            sort_by: Some(LsSortKey::Type),
+            ..Default::default()
+        };
+        let content = tool.execute(test_base, None).await.unwrap().all_text();
+        let lines = content
+            .lines()
+            .skip(1)
+            .take_while(|line| !line.starts_with("Total: "))
+            .map(|line| line.split(" -> ").next().unwrap().rsplit('/').next().unwrap())
+            .collect::<Vec<_>>();
+        let mut expected = vec!["dir", "a.txt", "b.txt", "c.txt"];
+        if cfg!(unix) {
+            expected.push("0-link");
+        }
+        assert_eq!(lines, expected, "{}", content);
+    }
+
+    #[tokio::test]
+    async fn test_ls_json_output() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("root/a.txt", "hello"))
+            .await
+            .with_file(("root/dir/nested.txt", "nested"))
+            .await;
+        let root = test_base.join("root");
+        let modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+        std::fs::File::options()
+            .write(true)
+            .open(root.join("a.txt"))
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        #[cfg(unix)]
+        std::fs::set_permissions(root.join("a.txt"), std::os::unix::fs::PermissionsExt::from_mode(0o640)).unwrap();
+
+        let tool = Ls {
+            path: root.to_string_lossy().to_string(),
+            depth: Some(1),
+            // Ignored for JSON output.
+            tree_format: Some(true),
+            sort_by: Some(LsSortKey::Name),
+            output_format: Some(LsOutputFormat::Json),
+            ..Default::default()
+        };
+        let result = tool.execute(&test_base, None).await.unwrap();
+        let entries = result.json_items().next().unwrap().as_array().unwrap().clone();
+        let paths = entries
+            .iter()
+            .map(|e| e["path"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        let expected = ["a.txt", "dir", "dir/nested.txt"].map(|p| root.join(p).to_string_lossy().to_string());
+        assert_eq!(paths, expected);
+
+        assert_eq!(entries[0]["type"], "file");
+        assert_eq!(entries[0]["size_bytes"], 5);
+        assert_eq!(entries[0]["modified_unix"], 1_000_000);
+        #[cfg(unix)]
+        assert_eq!(entries[0]["permissions"], "rw-r-----");
+        assert_eq!(entries[1]["type"], "directory");
+        assert!(!result.all_text().contains("a.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_ls_size_filter() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("root/small.txt", "a"))
+            .await
+            .with_file(("root/medium.txt", "a".repeat(100)))
+            .await
+            .with_file(("root/large.log", "a".repeat(1000)))
+            .await
+            .with_file(("root/dir/nested.log", "a".repeat(1000)))
+            .await;
+        let root = test_base.join("root");
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.join("large.log"), root.join("link")).unwrap();
+
+        let names = |min_size_bytes: Option<u64>, max_size_bytes: Option<u64>| {
+            let test_base = &test_base;
+            let root = &root;
+            async move {
+                let tool = Ls {
+                    path: root.to_string_lossy().to_string(),
+                    depth: Some(1),
+                    sort_by: Some(LsSortKey::Name),
+                    output_format: Some(LsOutputFormat::Json),
+                    min_size_bytes,
+                    max_size_bytes,
+                    ..Default::default()
+                };
+                let result = tool.execute(test_base, None).await.unwrap();
+                result
+                    .json_items()
+                    .next()
+                    .unwrap()
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|e| {
+                        std::path::Path::new(e["path"].as_str().unwrap())
+                            .strip_prefix(root)
+                            .unwrap()
+                            .to_string_lossy()
+                            .to_string()
+                    })
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        #[cfg(unix)]
+        {
+            assert_eq!(names(Some(500), None).await, vec![
+                "dir",
+                "large.log",
+                "link",
+                "dir/nested.log"
+            ]);
+            assert_eq!(names(None, Some(500)).await, vec!["dir", "medium.txt", "small.txt"]);
+        }
+        assert_eq!(names(Some(50), Some(500)).await, vec!["dir", "medium.txt"]);
+
+        let tool = Ls {
+            path: root.to_string_lossy().to_string(),
+            min_size_bytes: Some(10),
+            max_size_bytes: Some(1),
+            ..Default::default()
+        };
+        assert!(tool.validate(&test_base).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ls_filter_type() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("root/a.txt", "a"))
+            .await
+            .with_file(("root/dir/nested.txt", "nested"))
+            .await
+            .with_file(("root/dir/subdir/deep.txt", "deep"))
+            .await;
+        let root = test_base.join("root");
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.join("a.txt"), root.join("link")).unwrap();
+
+        let test_base = &test_base;
+        let entries = |filter_type: LsEntryType| {
+            let tool = Ls {
+                path: root.to_string_lossy().to_string(),
+                depth: Some(2),
+                sort_by: Some(LsSortKey::Name),
+                output_format: Some(LsOutputFormat::Json),
+                filter_type: Some(filter_type),
+                ..Default::default()
+            };
+            let root = &root;
+            async move {
+                let result = tool.execute(test_base, None).await.unwrap();
+                result
+                    .json_items()
+                    .next()
+                    .unwrap()
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|e| {
+                        let path = std::path::Path::new(e["path"].as_str().unwrap())
+                            .strip_prefix(root)
+                            .unwrap()
+                            .to_string_lossy()
+                            .to_string();
+                        (path, e["type"].as_str().unwrap().to_string())
+                    })
+                    .collect::<Vec<_>>()
+            }
+        };
+        let entry = |path: &str, ty: &str| (path.to_string(), ty.to_string());
+
+        assert_eq!(entries(LsEntryType::Directory).await, vec![
+            entry("dir", "directory"),
+            entry("dir/subdir", "directory")
+        ]);
+        assert_eq!(entries(LsEntryType::File).await, vec![
+            entry("a.txt", "file"),
+            entry("dir/nested.txt", "file"),
+            entry("dir/subdir/deep.txt", "file")
+        ]);
+        #[cfg(unix)]
+        {
+            assert_eq!(entries(LsEntryType::Symlink).await, vec![entry("link", "symlink")]);
+            assert_eq!(entries(LsEntryType::All).await.len(), 6);
+        }
+
+        // Directories are kept in the tree so that nested files can be shown.
+        let tool = Ls {
+            path: root.to_string_lossy().to_string(),
+            depth: Some(2),
+            tree_format: Some(true),
+            filter_type: Some(LsEntryType::File),
+            ..Default::default()
+        };
+        let text = tool.execute(test_base, None).await.unwrap().all_text();
+        assert!(text.contains("subdir"), "{}", text);
+        assert!(text.contains("deep.txt"), "{}", text);
+        assert!(!text.contains("link"), "{}", text);
+    }
+
+    #[tokio::test]
+    async fn test_ls_total_summary() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("root/a.txt", "a".repeat(1000)))
+            .await
+            .with_file(("root/b.txt", "b".repeat(1048)))
+            .await;
+        let root = test_base.join("root");
+        std::fs::create_dir(root.join("dir")).unwrap();
+
+        let tool = Ls {
+            path: root.to_string_lossy().to_string(),
+            filter_type: Some(LsEntryType::File),
+            ..Default::default()
+        };
+        let content = tool.execute(&test_base, None).await.unwrap().all_text();
+        assert_eq!(
+            content.lines().last().unwrap(),
+            "Total: 2 files, 0 directories, 2048 bytes (2.0 KB)",
+            "{}",
+            content
+        );
+
+        // Entries left out of a truncated listing are counted.
+        let many = test_base.join("many");
+        std::fs::create_dir(&many).unwrap();
+        for i in 0..MAX_LS_ENTRIES + 5 {
+            std::fs::write(many.join(format!("{}.txt", i)), "").unwrap();
+        }
+        let tool = Ls {
+            path: many.to_string_lossy().to_string(),
+            output_format: Some(LsOutputFormat::Json),
+            ..Default::default()
+        };
+        let result = tool.execute(&test_base, None).await.unwrap();
+        let total = result.all_text().lines().last().unwrap().to_string();
+        assert_eq!(
+            total,
+            "Total: 1001 files, 0 directories, 0 bytes (0 B); 4 entries omitted due to truncation"
+        );
+
+        // The limit can be configured in the tool settings.
+        let settings = LsSettings { max_entries: Some(10) };
+        let result = tool.execute(&test_base, Some(&settings)).await.unwrap();
+        let total = result.all_text().lines().last().unwrap().to_string();
+        assert_eq!(
+            total,
+            "Total: 11 files, 0 directories, 0 bytes (0 B); 994 entries omitted due to truncation"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_ls_symlink_target() {
+        let test_base = TestBase::new().await.with_file(("root/a.txt", "a")).await;
+        let root = test_base.join("root");
+        std::os::unix::fs::symlink(root.join("a.txt"), root.join("link")).unwrap();
+        std::os::unix::fs::symlink("missing.txt", root.join("dangling")).unwrap();
+
+        let tool = Ls {
+            path: root.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let content = tool.execute(&test_base, None).await.unwrap().all_text();
+        let line = |name: &str| {
+            let suffix = format!("{} ->", root.join(name).to_string_lossy());
+            content.lines().find(|l| l.contains(&suffix)).unwrap().to_string()
+        };
+        assert!(
+            line("link").ends_with(&format!(" -> {}", root.join("a.txt").to_string_lossy())),
+            "{}",
+            content
+        );
+        assert!(line("dangling").ends_with(" -> missing.txt [dangling]"), "{}", content);
+        assert!(
+            content
+                .lines()
+                .any(|l| l.ends_with(&*root.join("a.txt").to_string_lossy())),
+            "{}",
+            content
+        );
+
+        let tool = Ls {
+            path: root.to_string_lossy().to_string(),
+            tree_format: Some(true),
+            ..Default::default()
+        };
+        let content = tool.execute(&test_base, None).await.unwrap().all_text();
+        assert!(content.contains("── dangling -> missing.txt [dangling]"), "{}", content);
+    }
+
+    #[tokio::test]
+    async fn test_ls_tree_output() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("root/Cargo.toml", ""))
+            .await
+            .with_file(("root/src/main.rs", ""))
+            .await
+            .with_file(("root/src/lib/tool.rs", ""))
+            .await
+            .with_file(("root/target/debug/out", ""))
+            .await;
+        let root = test_base.join("root");
+
+        let tool = Ls {
+            path: root.to_string_lossy().to_string(),
+            depth: Some(5),
+            ignore: Some(vec!["**/target".to_string()]),
+            sort_by: Some(LsSortKey::Name),
+            output_format: Some(LsOutputFormat::Tree),
+            ..Default::default()
+        };
+        let content = tool.execute(&test_base, None).await.unwrap().all_text();
+        let lines = content
+            .lines()
+            .skip_while(|line| *line != root.to_string_lossy())
+            .take_while(|line| !line.starts_with("Total: "))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                &*root.to_string_lossy(),
+                "├── Cargo.toml",
+                "└── src/",
+                "    ├── lib/",
+                "    │   └── tool.rs",
+                "    └── main.rs",
+            ],
+            "{}",
+            content
+        );
+
+        // Depth is respected.
+        let tool = Ls { depth: Some(0), ..tool };
+        let content = tool.execute(&test_base, None).await.unwrap().all_text();
+        assert!(content.contains("└── src/"), "{}", content);
+        assert!(!content.contains("main.rs"), "{}", content);
+
+        // Truncated directories show how many entries were left out.
+        let many = test_base.join("many");
+        std::fs::create_dir(&many).unwrap();
+        for i in 0..MAX_LS_ENTRIES + 5 {
+            std::fs::write(many.join(format!("{}.txt", i)), "").unwrap();
+        }
+        let tool = Ls {
+            path: many.to_string_lossy().to_string(),
+            output_format: Some(LsOutputFormat::Tree),
+            ..Default::default()
+        };
+        let content = tool.execute(&test_base, None).await.unwrap().all_text();
+        let lines = content.lines().collect::<Vec<_>>();
+        assert_eq!(lines[lines.len() - 2], "└── ... (4 more entries)", "{}", content);
+        assert!(lines[lines.len() - 3].starts_with("├── "), "{}", content);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_ls_show_inodes() {
+        use std::os::unix::fs::MetadataExt;
+
+        let test_base = TestBase::new().await.with_file(("root/a.txt", "a")).await;
+        let root = test_base.join("root");
+        std::fs::hard_link(root.join("a.txt"), root.join("b.txt")).unwrap();
+        let ino = std::fs::metadata(root.join("a.txt")).unwrap().ino();
+
+        let mut tool = Ls {
+            path: root.to_string_lossy().to_string(),
+            show_inodes: Some(true),
+            ..Default::default()
+        };
+        let content = tool.execute(&test_base, None).await.unwrap().all_text();
+        for name in ["a.txt", "b.txt"] {
+            let line = content.lines().find(|l| l.ends_with(name)).unwrap();
+            assert!(line.starts_with(&format!("{} -rw", ino)), "{}", content);
+        }
+
+        tool.show_inodes = None;
+        let content = tool.execute(&test_base, None).await.unwrap().all_text();
+        let line = content.lines().find(|l| l.ends_with("a.txt")).unwrap();
+        assert!(line.starts_with("-rw"), "{}", content);
+    }
+
+    #[tokio::test]
+    async fn test_ls_gitignore() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("root/.gitignore", "*.log\n!keep.log\ntarget/\n/top.txt\n"))
+            .await
+            .with_file(("root/main.rs", "fn main() {}"))
+            .await
+            .with_file(("root/debug.log", "log"))
+            .await
+            .with_file(("root/keep.log", "log"))
+            .await
+            .with_file(("root/top.txt", "top"))
+            .await
+            .with_file(("root/target/out.bin", "bin"))
+            .await
+            .with_file(("root/src/top.txt", "nested"))
+            .await
+            .with_file(("root/src/trace.log", "log"))
+            .await;
+
+        let mut tool = Ls {
+            path: test_base.join("root").to_string_lossy().to_string(),
+            depth: Some(1),
+            ..Default::default()
+        };
+        let excluded_count =
+            |result: &ToolExecutionOutput| result.json_items().next().unwrap()["gitignore_excluded_count"].clone();
+
+        // debug.log, top.txt, target, target/out.bin, and src/trace.log
+        let result = tool.execute(&test_base, None).await.unwrap();
+        assert_eq!(excluded_count(&result), 5);
+        let content = result.all_text();
+        assert!(content.contains("debug.log"));
+        assert!(content.contains("out.bin"));
+
+        // Ignored directories are skipped, so their contents are not counted.
+        tool.respect_gitignore = Some(true);
+        let result = tool.execute(&test_base, None).await.unwrap();
+        assert_eq!(excluded_count(&result), 4);
+        let content = result.all_text();
+        for name in ["main.rs", "keep.log", "src/top.txt"] {
+            assert!(content.contains(name), "expected {} in {}", name, content);
+        }
+        // .gitignore files are applied even though they are hidden.
+        for name in ["debug.log", "root/top.txt", "target", "trace.log", ".gitignore"] {
+            assert!(!content.contains(name), "unexpected {} in {}", name, content);
+        }
+
+        // No summary is included without a .gitignore file.
+        tool.path = test_base.join("root/src").to_string_lossy().to_string();
+        let result = tool.execute(&test_base, None).await.unwrap();
+        assert!(result.json_items().next().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_ls_gitignore_repo() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("repo/.git/info/exclude", "*.tmp\n"))
+            .await
+            .with_file(("repo/.gitignore", "**/gen/*.rs\n\\#notes\nbuild/\n"))
+            .await
+            .with_file(("repo/src/main.rs", "fn main() {}"))
+            .await
+            .with_file(("repo/src/gen/api.rs", "generated"))
+            .await
+            .with_file(("repo/src/#notes", "notes"))
+            .await
+            .with_file(("repo/src/scratch.tmp", "tmp"))
+            .await
+            .with_file(("repo/build/out.rs", "out"))
+            .await;
+
+        // Rules from the .gitignore above the listed directory and .git/info/exclude apply.
+        let mut tool = Ls {
+            path: test_base.join("repo/src").to_string_lossy().to_string(),
+            depth: Some(1),
+            respect_gitignore: Some(true),
+            ..Default::default()
+        };
+        let result = tool.execute(&test_base, None).await.unwrap();
+        let content = result.all_text();
+        assert!(content.contains("main.rs"), "{}", content);
+        for name in ["api.rs", "#notes", "scratch.tmp"] {
+            assert!(!content.contains(name), "unexpected {} in {}", name, content);
+        }
+        assert_eq!(
+            result.json_items().next().unwrap()["gitignore_excluded_count"],
+            3,
+            "{}",
+            content
+        );
+
+        // Everything in an excluded directory is excluded.
+        tool.path = test_base.join("repo/build").to_string_lossy().to_string();
+        let result = tool.execute(&test_base, None).await.unwrap();
+        assert!(!result.all_text().contains("out.rs"), "{}", result.all_text());
+    }
+
     #[tokio::test]
     async fn test_ls_basic_directory() {
         let test_base = TestBase::new()
@@ -395,10 +1934,11 @@ mod tests {
             path: test_base.join("").to_string_lossy().to_string(),
             depth: None,
             ignore: None,
+            ..Default::default()
         };
 
         assert!(tool.validate(&test_base).await.is_ok());
-        let result = tool.execute(&test_base).await.unwrap();
+        let result = tool.execute(&test_base, None).await.unwrap();
         assert_eq!(result.items.len(), 1);
 
         if let ToolExecutionOutputItem::Text(content) = &result.items[0] {
@@ -420,9 +1960,10 @@ mod tests {
             path: test_base.join("").to_string_lossy().to_string(),
             depth: Some(1),
             ignore: None,
+            ..Default::default()
         };
 
-        let result = tool.execute(&test_base).await.unwrap();
+        let result = tool.execute(&test_base, None).await.unwrap();
 
         if let ToolExecutionOutputItem::Text(content) = &result.items[0] {
             assert!(content.contains("root.txt"));
@@ -431,6 +1972,72 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_ls_parallel_matches_sequential() {
+        let mut test_base = TestBase::new().await;
+        for i in 0..10 {
+            for j in 0..5 {
+                test_base = test_base
+                    .with_file((format!("dir{}/nested{}/file{}.txt", i, j, j), "content"))
+                    .await;
+            }
+        }
+
+        let tool = Ls {
+            path: test_base.join("").to_string_lossy().to_string(),
+            depth: Some(3),
+            ..Default::default()
+        };
+
+        let sequential = tool.execute_with_concurrency(&test_base, None, 1).await.unwrap();
+        let parallel = tool.execute(&test_base, None).await.unwrap();
+        assert_eq!(
+            sequential.text_items().collect::<Vec<_>>(),
+            parallel.text_items().collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ls_recursive_hidden_directories() {
+        let test_base = TestBase::new()
+            .await
+            .with_file((".hidden/secret.txt", "secret"))
+            .await
+            .with_file((".env", "KEY=value"))
+            .await
+            .with_file((".git/HEAD", "ref: refs/heads/main"))
+            .await
+            .with_file(("node_modules/dep/index.js", "dep"))
+            .await
+            .with_file(("visible/file.txt", "visible"))
+            .await;
+
+        let mut tool = Ls {
+            path: test_base.join("").to_string_lossy().to_string(),
+            depth: Some(2),
+            ..Default::default()
+        };
+
+        let result = tool.execute(&test_base, None).await.unwrap();
+        let content = result.all_text();
+        assert!(!content.contains(".hidden"));
+        assert!(!content.contains(".env"));
+        assert!(!content.contains("secret.txt"));
+        assert!(content.contains("node_modules"));
+        assert!(!content.contains("index.js"));
+        assert!(content.contains("file.txt"));
+
+        tool.show_hidden = Some(true);
+        let result = tool.execute(&test_base, None).await.unwrap();
+        let content = result.all_text();
+        assert!(content.contains(".hidden"));
+        assert!(content.contains(".env"));
+        assert!(content.contains("secret.txt"));
+        assert!(!content.contains("index.js"));
+        assert!(!content.contains(".git"));
+        assert!(!content.contains("HEAD"));
+    }
+
     #[tokio::test]
     async fn test_ls_with_ignore_patterns() {
         let test_base = TestBase::new()
@@ -444,9 +2051,10 @@ mod tests {
             path: test_base.join("").to_string_lossy().to_string(),
             depth: None,
             ignore: Some(vec!["*.log".to_string()]),
+            ..Default::default()
         };
 
-        let result = tool.execute(&test_base).await.unwrap();
+        let result = tool.execute(&test_base, None).await.unwrap();
 
         if let ToolExecutionOutputItem::Text(content) = &result.items[0] {
             assert!(content.contains("keep.txt"));
@@ -454,6 +2062,26 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn test_ls_mount_points() {
+        // /proc is always mounted as a separate filesystem on linux.
+        let tool = Ls {
+            path: "/".to_string(),
+            ..Default::default()
+        };
+        let result = tool.execute(&TestBase::new().await, None).await.unwrap();
+        let ToolExecutionOutputItem::Text(content) = &result.items[0] else {
+            panic!("expected text output");
+        };
+        let proc_line = content.lines().find(|l| l.ends_with(" /proc")).unwrap();
+        assert!(
+            proc_line.split_whitespace().next().unwrap().ends_with('M'),
+            "{}",
+            proc_line
+        );
+    }
+
     #[tokio::test]
     async fn test_ls_validate_nonexistent_directory() {
         let test_base = TestBase::new().await;
@@ -461,6 +2089,7 @@ mod tests {
             path: "/nonexistent/directory".to_string(),
             depth: None,
             ignore: None,
+            ..Default::default()
         };
 
         assert!(tool.validate(&test_base).await.is_err());
@@ -474,6 +2103,7 @@ mod tests {
             path: test_base.join("file.txt").to_string_lossy().to_string(),
             depth: None,
             ignore: None,
+            ..Default::default()
         };
 
         assert!(tool.validate(&test_base).await.is_err());
@@ -2,7 +2,9 @@ use serde::{
     Deserialize,
     Serialize,
 };
+use tracing::warn;
 
+use super::ToolParseErrorKind;
 use crate::agent::agent_config::parse::CanonicalToolName;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,4 +23,76 @@ impl McpTool {
             tool_name: self.tool_name.clone(),
         }
     }
+
+    /// Validates [Self::params] against `schema`, the input schema of the tool as returned by the
+    /// MCP server.
+    ///
+    /// Validation is skipped if the server returned a schema that is not itself valid.
+    pub fn validate_params(&self, schema: &serde_json::Value) -> Result<(), ToolParseErrorKind> {
+        let validator = match jsonschema::validator_for(schema) {
+            Ok(validator) => validator,
+            Err(err) => {
+                warn!(?err, server_name = %self.server_name, tool_name = %self.tool_name, "invalid input schema for mcp tool, skipping validation");
+                return Ok(());
+            },
+        };
+        let params = serde_json::Value::Object(self.params.clone().unwrap_or_default());
+        validator.validate(&params).map_err(ToolParseErrorKind::schema_failure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_params() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string" },
+                "limit": { "type": "integer", "minimum": 1 }
+            },
+            "required": ["query"]
+        });
+        let tool = |params: serde_json::Value| McpTool {
+            tool_name: "search".to_string(),
+            server_name: "docs".to_string(),
+            params: params.as_object().cloned(),
+        };
+
+        tool(serde_json::json!({ "query": "rust", "limit": 5 }))
+            .validate_params(&schema)
+            .unwrap();
+
+        for params in [
+            serde_json::json!({ "limit": 5 }),
+            serde_json::json!({ "query": 1 }),
+            serde_json::json!({ "query": "rust", "limit": 0 }),
+        ] {
+            let err = tool(params.clone()).validate_params(&schema).unwrap_err();
+            assert!(
+                matches!(err, ToolParseErrorKind::SchemaFailure(_)),
+                "expected a schema failure for {}, got {:?}",
+                params,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_params_invalid_schema() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "not-a-type" }
+            }
+        });
+        let tool = McpTool {
+            tool_name: "search".to_string(),
+            server_name: "docs".to_string(),
+            params: serde_json::json!({ "query": "rust" }).as_object().cloned(),
+        };
+        tool.validate_params(&schema).unwrap();
+    }
 }
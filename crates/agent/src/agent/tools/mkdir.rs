@@ -1,19 +1,30 @@
-#![allow(dead_code)]
-
-use std::path::PathBuf;
+use std::path::{
+    Component,
+    Path,
+    PathBuf,
+};
 
 use serde::{
     Deserialize,
     Serialize,
 };
+use tracing::warn;
 
+use super::chmod::parse_octal_mode;
 use super::{
+    BuiltInToolName,
+    BuiltInToolTrait,
+    ToolExample,
     ToolExecutionError,
+    ToolExecutionOutput,
+    ToolExecutionOutputItem,
     ToolExecutionResult,
+    ToolLocale,
 };
-use crate::agent::util::path::canonicalize_path;
+use crate::agent::util::path::canonicalize_path_sys;
+use crate::agent::util::providers::SystemProvider;
 
-pub const MKDIR_TOOL_DESCRIPTION: &str = r#"
+const MKDIR_TOOL_DESCRIPTION: &str = r#"
 A tool for creating directories.
 
 WHEN TO USE THIS TOOL:
@@ -21,7 +32,44 @@ WHEN TO USE THIS TOOL:
 
 HOW TO USE:
 - Provide the path for the directory to be created
-- Parent directories will be created if they don't already exist
+- Parent directories will be created if they don't already exist, unless parents is false
+- Optionally provide the permission bits of the created directories as an octal mode string, e.g. "755" (Unix only)
+- The canonical path of the directory is returned, which can be used in subsequent tool uses
+
+TIPS:
+- Creating a directory that already exists succeeds without modifying it
+"#;
+
+const MKDIR_TOOL_DESCRIPTION_ZH_CN: &str = r#"
+用于创建目录的工具。
+
+何时使用此工具：
+- 需要创建目录时使用
+
+使用方法：
+- 提供要创建的目录路径
+- 除非将 parents 设置为 false，否则会创建不存在的父目录
+- 可选地以八进制字符串形式通过 mode 提供所创建目录的权限位，例如 "755"（仅限 Unix）
+- 返回目录的规范路径，可在后续的工具使用中使用
+
+提示：
+- 创建已存在的目录会成功，且不会修改该目录
+"#;
+
+const MKDIR_TOOL_DESCRIPTION_JA: &str = r#"
+ディレクトリを作成するためのツールです。
+
+このツールを使用する場面:
+- ディレクトリを作成する必要がある場合に使用します
+
+使い方:
+- 作成するディレクトリのパスを指定します
+- parents を false に設定しない限り、存在しない親ディレクトリも作成されます
+- 必要に応じて、作成するディレクトリのパーミッションビットを 8 進数の文字列として mode で指定します。例: "755" (Unix のみ)
+- ディレクトリの正規パスが返されるため、後続のツール使用で利用できます
+
+ヒント:
+- 既に存在するディレクトリを作成しても成功し、そのディレクトリは変更されません
 "#;
 
 const MKDIR_SCHEMA: &str = r#"
@@ -31,6 +79,15 @@ const MKDIR_SCHEMA: &str = r#"
         "path": {
             "description": "Path to the directory",
             "type": "string"
+        },
+        "parents": {
+            "description": "Whether or not to create missing parent directories",
+            "type": "boolean",
+            "default": true
+        },
+        "mode": {
+            "description": "Permission bits of the created directories as an octal string, e.g. \"755\" or \"0700\". Subject to the umask. Unix only",
+            "type": "string"
         }
     },
     "required": [
@@ -39,41 +96,200 @@ const MKDIR_SCHEMA: &str = r#"
 }
 "#;
 
+impl BuiltInToolTrait for Mkdir {
+    fn name() -> BuiltInToolName {
+        BuiltInToolName::Mkdir
+    }
+
+    fn description() -> std::borrow::Cow<'static, str> {
+        MKDIR_TOOL_DESCRIPTION.into()
+    }
+
+    fn description_for_locale(locale: &str) -> std::borrow::Cow<'static, str> {
+        match ToolLocale::parse(locale) {
+            Some(ToolLocale::ZhCn) => MKDIR_TOOL_DESCRIPTION_ZH_CN.into(),
+            Some(ToolLocale::Ja) => MKDIR_TOOL_DESCRIPTION_JA.into(),
+            None => Self::description(),
+        }
+    }
+
+    fn input_schema() -> std::borrow::Cow<'static, str> {
+        MKDIR_SCHEMA.into()
+    }
+
+    fn examples() -> Vec<ToolExample> {
+        vec![ToolExample::new(
+            "Create a directory along with any missing parents",
+            serde_json::json!({ "path": "src/generated/models" }),
+        )]
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Mkdir {
-    path: String,
+    pub path: String,
+    parents: Option<bool>,
+    /// Octal permission bits of the created directories, e.g. `755`. Only supported on Unix.
+    mode: Option<String>,
 }
 
 impl Mkdir {
-    fn canonical_path(&self) -> Result<PathBuf, String> {
-        Ok(PathBuf::from(canonicalize_path(&self.path).map_err(|e| e.to_string())?))
+    fn canonical_path<P: SystemProvider>(&self, provider: &P) -> Result<PathBuf, String> {
+        Ok(PathBuf::from(
+            canonicalize_path_sys(&self.path, provider).map_err(|e| e.to_string())?,
+        ))
     }
 
-    pub async fn validate(&self) -> Result<(), String> {
+    fn parents(&self) -> bool {
+        self.parents.unwrap_or(true)
+    }
+
+    pub async fn validate<P: SystemProvider>(&self, provider: &P) -> Result<(), String> {
         if self.path.is_empty() {
             return Err("Path must not be empty".to_string());
         }
+        if self.path.contains('\0') {
+            return Err("Path must not contain null bytes".to_string());
+        }
+        if let Some(mode) = &self.mode {
+            if !cfg!(unix) {
+                return Err("mode is only supported on Unix".to_string());
+            }
+            parse_octal_mode(mode)?;
+        }
+
+        let path = self.canonical_path(provider)?;
+
+        // Relative components must not be used to escape the working directory.
+        let has_relative_components = Path::new(&self.path)
+            .components()
+            .any(|c| matches!(c, Component::CurDir | Component::ParentDir));
+        if has_relative_components {
+            let cwd = provider
+                .cwd()
+                .map_err(|e| format!("failed to get the current directory: {}", e))?;
+            let root =
+                PathBuf::from(canonicalize_path_sys(cwd.to_string_lossy(), provider).map_err(|e| e.to_string())?);
+            if !path.starts_with(&root) {
+                return Err(format!(
+                    "{} resolves to {}, which is outside of {}",
+                    self.path,
+                    path.to_string_lossy(),
+                    root.to_string_lossy()
+                ));
+            }
+        }
 
-        let path = self.canonical_path()?;
         if path.exists() {
-            let Ok(file_md) = tokio::fs::symlink_metadata(&path).await else {
-                return Err(format!("A file at {} already exists", self.path));
-            };
-            if file_md.is_dir() {
-                return Err(format!("A directory at {} already exists", self.path));
-            } else {
+            if !path.is_dir() {
                 return Err(format!("A file at {} already exists", self.path));
             }
+            warn!(path = %path.to_string_lossy(), "directory already exists");
         }
 
         Ok(())
     }
 
-    pub async fn execute(&self) -> ToolExecutionResult {
-        let path = self.canonical_path()?;
-        tokio::fs::create_dir_all(&path)
+    /// Creates the directory, returning its canonical path. An existing directory is left as is.
+    pub async fn execute<P: SystemProvider>(&self, provider: &P) -> ToolExecutionResult {
+        let path = self.canonical_path(provider)?;
+        if !path.is_dir() {
+            let mut builder = tokio::fs::DirBuilder::new();
+            builder.recursive(self.parents());
+            #[cfg(unix)]
+            if let Some(mode) = &self.mode {
+                builder.mode(parse_octal_mode(mode)?);
+            }
+            builder
+                .create(&path)
+                .await
+                .map_err(|e| ToolExecutionError::from_io_result("failed to create directory", Some(&path), e))?;
+        }
+        let path = tokio::fs::canonicalize(&path)
             .await
-            .map_err(|e| ToolExecutionError::io(format!("failed to create directory {}", path.to_string_lossy()), e))?;
-        Ok(Default::default())
+            .map_err(|e| ToolExecutionError::from_io_result("failed to canonicalize", Some(&path), e))?;
+        Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Text(
+            path.to_string_lossy().to_string(),
+        )]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test::TestBase;
+
+    fn mkdir(path: impl AsRef<Path>, parents: Option<bool>) -> Mkdir {
+        Mkdir {
+            path: path.as_ref().to_string_lossy().to_string(),
+            parents,
+            mode: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mkdir_parents() {
+        let test_base = TestBase::new().await;
+
+        let tool = mkdir(test_base.join("a/b/c"), None);
+        tool.validate(&test_base).await.unwrap();
+        let output = tool.execute(&test_base).await.unwrap();
+        assert!(test_base.join("a/b/c").is_dir());
+        assert_eq!(
+            output.all_text(),
+            std::fs::canonicalize(test_base.join("a/b/c"))
+                .unwrap()
+                .to_string_lossy()
+        );
+
+        // Creating an existing directory succeeds.
+        tool.validate(&test_base).await.unwrap();
+        tool.execute(&test_base).await.unwrap();
+
+        // Only the final component is created when parents is false.
+        let tool = mkdir(test_base.join("a/b/d"), Some(false));
+        tool.validate(&test_base).await.unwrap();
+        tool.execute(&test_base).await.unwrap();
+        assert!(test_base.join("a/b/d").is_dir());
+
+        let tool = mkdir(test_base.join("missing/dir"), Some(false));
+        tool.validate(&test_base).await.unwrap();
+        assert!(tool.execute(&test_base).await.is_err());
+        assert!(!test_base.join("missing").exists());
+    }
+
+    #[tokio::test]
+    async fn test_mkdir_validate() {
+        let test_base = TestBase::new().await.with_file(("file.txt", "hello")).await;
+
+        assert!(mkdir("", None).validate(&test_base).await.is_err());
+        assert!(mkdir("bad\0name", None).validate(&test_base).await.is_err());
+        assert!(mkdir("file.txt", None).validate(&test_base).await.is_err());
+        assert!(mkdir("../outside", None).validate(&test_base).await.is_err());
+        assert!(mkdir("./a/../../outside", None).validate(&test_base).await.is_err());
+
+        mkdir("./a/../b", None).validate(&test_base).await.unwrap();
+        let mut tool = mkdir("dir", None);
+        for mode in ["10000", "999", "u+x", "493"] {
+            tool.mode = Some(mode.to_string());
+            assert!(tool.validate(&test_base).await.is_err(), "{}", mode);
+        }
+        mkdir(test_base.join("new"), None).validate(&test_base).await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_mkdir_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let test_base = TestBase::new().await;
+        let mut tool = mkdir(test_base.join("private/nested"), None);
+        tool.mode = Some("0700".to_string());
+        tool.validate(&test_base).await.unwrap();
+        tool.execute(&test_base).await.unwrap();
+        for dir in ["private", "private/nested"] {
+            let mode = std::fs::metadata(test_base.join(dir)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700, "{}", dir);
+        }
     }
 }
@@ -1,9 +1,35 @@
+//! Built-in tools and tool execution.
+//!
+//! # Tool use purpose
+//!
+//! Every tool spec sent to the model includes an extra optional string argument named
+//! [TOOL_USE_PURPOSE_FIELD_NAME]. Built-in tools include it in their input schema through
+//! [merge_base_schema], and all other tools have it added by
+//! [crate::agent::tool_utils::add_tool_use_purpose_arg]. The
+//! model sets it to a brief explanation of why it is making the tool use, e.g. "Check which tests
+//! exist before adding a new one". The purpose is not part of the tool's own arguments - it is
+//! removed from the arguments in [Tool::parse] before the tool is deserialized, and is only used
+//! to give the user context when displaying or approving the tool use.
+//!
+//! When present, the purpose must be non-empty and at most [MAX_TOOL_USE_PURPOSE_LEN]
+//! characters.
+//!
+//! # Tool schema version
+//!
+//! The input schema of every built-in tool is annotated with its [BuiltInToolTrait::version]
+//! under the top-level [TOOL_VERSION_FIELD_NAME] key. It is metadata only, and not an argument
+//! of the tool. If a tool use does include it, e.g. from a conversation that was recorded with
+//! the version, [Tool::parse] removes it, logs a warning if it differs from the current version,
+//! and migrates the remaining arguments.
+
 #[cfg(unix)]
 pub mod execute_cmd;
 #[cfg(windows)]
 #[path = "execute_cmd_windows.rs"]
 pub mod execute_cmd;
 
+pub mod chmod;
+pub mod chown;
 pub mod fs_read;
 pub mod fs_write;
 pub mod grep;
@@ -12,11 +38,23 @@ pub mod introspect;
 pub mod ls;
 pub mod mcp;
 pub mod mkdir;
+pub mod render_template;
 pub mod rm;
+pub mod spell_check;
+pub mod stat;
 
 use std::borrow::Cow;
-use std::sync::Arc;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::sync::{
+    Arc,
+    OnceLock,
+};
 
+use chmod::Chmod;
+use chown::Chown;
 use execute_cmd::ExecuteCmd;
 use fs_read::FsRead;
 use fs_write::{
@@ -30,23 +68,53 @@ use introspect::Introspect;
 use ls::Ls;
 use mcp::McpTool;
 use mkdir::Mkdir;
+use render_template::RenderTemplate;
+use rm::Rm;
 use schemars::JsonSchema;
 use serde::{
     Deserialize,
     Serialize,
 };
+use sha2::{
+    Digest as _,
+    Sha256,
+};
+use spell_check::SpellCheck;
+use stat::Stat;
 use strum::IntoEnumIterator;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
 use super::agent_config::parse::CanonicalToolName;
 use super::agent_loop::types::ToolUseBlock;
-use super::consts::TOOL_USE_PURPOSE_FIELD_NAME;
+use super::consts::{
+    MAX_TOOL_USE_PURPOSE_LEN,
+    TOOL_USE_PURPOSE_FIELD_DESCRIPTION,
+    TOOL_USE_PURPOSE_FIELD_NAME,
+    TOOL_VERSION_FIELD_NAME,
+};
 use super::protocol::AgentError;
 use crate::agent::agent_loop::types::{
     ImageBlock,
     ToolSpec,
 };
+use crate::agent::util::error::{
+    ErrorContext as _,
+    UtilError,
+};
+use crate::agent::util::providers::SystemProvider;
+use crate::agent::util::{
+    parse_shell_command,
+    quote_shell_arg,
+    truncate_safe_in_place,
+};
 
-fn generate_tool_spec_from_json_schema<T>() -> ToolSpec
+/// Generates a tool spec from the [JsonSchema] implementation of `T`.
+///
+/// If `use_defs` is false, all subschemas are inlined into the generated schema. Otherwise,
+/// shared subschemas are referenced from a top-level `$defs` object, reducing the schema size
+/// for types with repeated or recursive subschemas.
+fn generate_tool_spec_from_json_schema<T>(use_defs: bool) -> ToolSpec
 where
     T: JsonSchema + BuiltInToolTrait,
 {
@@ -54,7 +122,7 @@ where
     use schemars::generate::SchemaSettings;
 
     let generator = SchemaGenerator::new(SchemaSettings::default().with(|s| {
-        s.inline_subschemas = true;
+        s.inline_subschemas = !use_defs;
     }));
     let mut input_schema = generator
         .into_root_schema_for::<T>()
@@ -64,23 +132,68 @@ where
         .clone();
     input_schema.remove("$schema");
     input_schema.remove("description");
+    merge_base_schema(&mut input_schema, T::version());
 
     ToolSpec {
         name: T::name().to_string(),
-        description: T::description().to_string(),
+        description: tool_description::<T>(None),
         input_schema,
     }
 }
 
+/// Returns the schema of the [TOOL_USE_PURPOSE_FIELD_NAME] argument.
+pub fn tool_use_purpose_schema() -> serde_json::Value {
+    serde_json::json!({
+        "description": TOOL_USE_PURPOSE_FIELD_DESCRIPTION,
+        "type": "string"
+    })
+}
+
+/// Merges the properties shared by every built-in tool into `input_schema`, and annotates it with
+/// the schema `version`. Properties already defined by the tool are left as is.
+fn merge_base_schema(input_schema: &mut serde_json::Map<String, serde_json::Value>, version: u32) {
+    input_schema.insert(TOOL_VERSION_FIELD_NAME.to_string(), version.into());
+    let base_properties = [(TOOL_USE_PURPOSE_FIELD_NAME, tool_use_purpose_schema())];
+    let Some(properties) = input_schema
+        .entry("properties")
+        .or_insert_with(|| serde_json::Value::Object(Default::default()))
+        .as_object_mut()
+    else {
+        return;
+    };
+    for (name, schema) in base_properties {
+        properties.entry(name).or_insert(schema);
+    }
+}
+
+/// Returns the description of `T` with its constraints appended as a `CONSTRAINTS:` section.
+fn tool_description<T: BuiltInToolTrait>(locale: Option<&str>) -> String {
+    let mut description = match locale {
+        Some(locale) => T::description_for_locale(locale).to_string(),
+        None => T::description().to_string(),
+    };
+    let constraints = T::constraints();
+    if !constraints.is_empty() {
+        description.push_str("\nCONSTRAINTS:\n");
+        for constraint in constraints {
+            description.push_str(&format!("- {}\n", constraint));
+        }
+    }
+    description
+}
+
 fn generate_tool_spec_from_trait<T>() -> ToolSpec
 where
     T: BuiltInToolTrait,
 {
+    let mut input_schema: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(T::input_schema().to_string().as_str()).expect("built-in tool specs should not fail");
+    merge_base_schema(&mut input_schema, T::version());
+
     ToolSpec {
         name: T::name().to_string(),
-        description: T::description().to_string(),
-        input_schema: serde_json::from_str(T::input_schema().to_string().as_str())
-            .expect("built-in tool specs should not fail"),
+        description: tool_description::<T>(None),
+        input_schema,
     }
 }
 
@@ -105,12 +218,204 @@ pub enum BuiltInToolName {
     ExecuteCmd,
     ImageRead,
     Ls,
+    SpellCheck,
+    Chmod,
+    Chown,
+    Stat,
+    RenderTemplate,
+    Grep,
+    Mkdir,
+    Rm,
+    Introspect,
 }
 
 trait BuiltInToolTrait {
     fn name() -> BuiltInToolName;
     fn description() -> Cow<'static, str>;
     fn input_schema() -> Cow<'static, str>;
+
+    /// The description of the tool translated for the given locale. Falls back to
+    /// [BuiltInToolTrait::description] for unsupported locales.
+    fn description_for_locale(locale: &str) -> Cow<'static, str> {
+        let _ = locale;
+        Self::description()
+    }
+
+    /// Rules the model must not violate when using the tool. These are always included in the
+    /// tool description.
+    fn constraints() -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Example tool uses that can be provided to the model alongside the tool description.
+    fn examples() -> Vec<ToolExample> {
+        Vec::new()
+    }
+
+    /// Version of the tool's input schema, included in the tool spec as
+    /// [TOOL_VERSION_FIELD_NAME]. Must be incremented on incompatible schema changes.
+    fn version() -> u32 {
+        1
+    }
+
+    /// Migrations for upgrading arguments written against older versions of the input schema.
+    fn migrations() -> Vec<ToolSchemaMigration> {
+        Vec::new()
+    }
+}
+
+/// Upgrades tool arguments from one version of a tool's input schema to another.
+pub struct ToolSchemaMigration {
+    pub from: u32,
+    pub to: u32,
+    pub migrate: Box<dyn Fn(serde_json::Value) -> serde_json::Value + Send + Sync>,
+}
+
+impl std::fmt::Debug for ToolSchemaMigration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolSchemaMigration")
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Locales with translated built-in tool descriptions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolLocale {
+    /// Simplified Chinese
+    ZhCn,
+    /// Japanese
+    Ja,
+}
+
+impl ToolLocale {
+    /// Parses a locale tag such as "zh-CN", "zh_Hans", or "ja-JP", returning [None] for
+    /// unsupported locales.
+    pub fn parse(locale: &str) -> Option<Self> {
+        let locale = locale.trim().to_lowercase().replace('_', "-");
+        let mut parts = locale.split('-');
+        match (parts.next(), parts.next()) {
+            (Some("zh"), None | Some("cn" | "hans" | "sg")) => Some(Self::ZhCn),
+            (Some("ja"), _) => Some(Self::Ja),
+            _ => None,
+        }
+    }
+}
+
+/// Applies `migrations` in sequence to upgrade `args` from version `from` to version `to`.
+///
+/// Returns [None] if there is no chain of migrations between the two versions.
+pub fn migrate_tool_args(
+    migrations: &[ToolSchemaMigration],
+    from: u32,
+    to: u32,
+    mut args: serde_json::Value,
+) -> Option<serde_json::Value> {
+    let mut version = from;
+    // Bounded by the number of migrations to guard against cycles.
+    for _ in 0..migrations.len() {
+        if version == to {
+            break;
+        }
+        let migration = migrations.iter().find(|m| m.from == version)?;
+        args = (migration.migrate)(args);
+        version = migration.to;
+    }
+    (version == to).then_some(args)
+}
+
+/// An example invocation of a tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolExample {
+    /// Example tool input, conforming to the tool's input schema
+    pub input: serde_json::Value,
+    /// What the example does
+    pub description: String,
+}
+
+impl ToolExample {
+    fn new(description: impl Into<String>, input: serde_json::Value) -> Self {
+        Self {
+            input,
+            description: description.into(),
+        }
+    }
+}
+
+/// Formats `examples` as an `EXAMPLES:` section to append to a tool description.
+pub fn format_tool_examples(examples: &[ToolExample]) -> String {
+    let mut section = "\nEXAMPLES:\n".to_string();
+    for example in examples {
+        section.push_str(&format!("- {}\n  {}\n", example.description, example.input));
+    }
+    section
+}
+
+/// Removes the [TOOL_VERSION_FIELD_NAME] from `args`, migrating them to the current version of
+/// the tool's input schema if they were written against a different version.
+fn migrate_built_in_args(name: &BuiltInToolName, args: serde_json::Value) -> serde_json::Value {
+    migrate_versioned_args(name, BuiltInTool::version(name), &BuiltInTool::migrations(name), args)
+}
+
+/// Same as [migrate_built_in_args], for a tool at version `current` with the given `migrations`.
+fn migrate_versioned_args(
+    name: &BuiltInToolName,
+    current: u32,
+    migrations: &[ToolSchemaMigration],
+    mut args: serde_json::Value,
+) -> serde_json::Value {
+    let Some(version) = args
+        .as_object_mut()
+        .and_then(|obj| obj.remove(TOOL_VERSION_FIELD_NAME))
+        .and_then(|v| v.as_u64())
+        .and_then(|v| u32::try_from(v).ok())
+    else {
+        return args;
+    };
+    if version == current {
+        return args;
+    }
+    tracing::warn!(%name, version, current, "received tool use for a different version of the tool schema");
+    match migrate_tool_args(migrations, version, current, args.clone()) {
+        Some(migrated) => migrated,
+        None => {
+            tracing::warn!(%name, version, current, "no migration found for tool schema version");
+            args
+        },
+    }
+}
+
+/// Recursively sorts the keys of all objects in `value`.
+fn canonicalize_json(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries = map.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            serde_json::Value::Object(entries.into_iter().map(|(k, v)| (k, canonicalize_json(v))).collect())
+        },
+        serde_json::Value::Array(values) => {
+            serde_json::Value::Array(values.into_iter().map(canonicalize_json).collect())
+        },
+        other => other,
+    }
+}
+
+fn validate_tool_use_purpose(purpose: &str) -> Result<(), ToolParseErrorKind> {
+    if purpose.trim().is_empty() {
+        return Err(ToolParseErrorKind::InvalidArgs(format!(
+            "{} must not be empty",
+            TOOL_USE_PURPOSE_FIELD_NAME
+        )));
+    }
+    let len = purpose.chars().count();
+    if len > MAX_TOOL_USE_PURPOSE_LEN {
+        return Err(ToolParseErrorKind::InvalidArgs(format!(
+            "{} must be at most {} characters, found {}",
+            TOOL_USE_PURPOSE_FIELD_NAME, MAX_TOOL_USE_PURPOSE_LEN, len
+        )));
+    }
+    Ok(())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -125,11 +430,16 @@ impl Tool {
             obj.remove(TOOL_USE_PURPOSE_FIELD_NAME)
                 .and_then(|v| v.as_str().map(String::from))
         });
+        if let Some(purpose) = &tool_use_purpose {
+            validate_tool_use_purpose(purpose)?;
+        }
 
         let kind = match name {
-            CanonicalToolName::BuiltIn(name) => match BuiltInTool::from_parts(name, args) {
-                Ok(tool) => ToolKind::BuiltIn(tool),
-                Err(err) => return Err(err),
+            CanonicalToolName::BuiltIn(name) => {
+                match BuiltInTool::from_parts(name, migrate_built_in_args(name, args)) {
+                    Ok(tool) => ToolKind::BuiltIn(tool),
+                    Err(err) => return Err(err),
+                }
             },
             CanonicalToolName::Mcp { server_name, tool_name } => match args.as_object() {
                 Some(params) => ToolKind::Mcp(McpTool {
@@ -158,6 +468,23 @@ impl Tool {
         &self.kind
     }
 
+    /// Returns the model-provided reason for this tool use, if any.
+    pub fn purpose(&self) -> Option<&str> {
+        self.tool_use_purpose.as_deref()
+    }
+
+    /// Returns a SHA-256 hex digest of the tool kind and arguments, excluding the
+    /// [Tool::purpose]. Identical tool calls produce identical hashes regardless of argument key
+    /// order.
+    pub fn compute_input_hash(&self) -> String {
+        let value = serde_json::to_value(&self.kind).expect("tool kind should serialize to json");
+        let json = canonicalize_json(value).to_string();
+        Sha256::digest(json.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
     pub fn canonical_tool_name(&self) -> CanonicalToolName {
         self.kind.canonical_tool_name()
     }
@@ -177,8 +504,61 @@ impl Tool {
         self.kind.mcp_tool_name()
     }
 
-    pub async fn get_context(&self) -> Option<ToolContext> {
-        self.kind.get_context().await
+    pub async fn get_context<P: SystemProvider>(&self, provider: &P) -> Option<ToolContext> {
+        self.kind.get_context(provider).await
+    }
+
+    /// Returns a short, human-readable description of the tool use for displaying to the user,
+    /// if the tool supports one.
+    pub fn preview(&self) -> Option<String> {
+        self.kind.preview()
+    }
+
+    /// Executes the tool on a background task, returning a stream of the output items as they
+    /// are produced.
+    ///
+    /// Long-running tools send their output while executing:
+    /// - [ExecuteCmd] sends the stdout of the command as text items of complete lines, which are
+    ///   then left out of the final JSON item.
+    /// - [Grep] sends the matching lines of each file as a text item once the file is searched.
+    /// - [Ls] sends the entries of each depth level as a text item for plain listings, i.e. without
+    ///   a tree, grouping, or JSON format.
+    ///
+    /// Other tools send their output once they finish executing. Dropping the returned stream
+    /// cancels the execution. MCP tools are not supported since they are executed through the
+    /// agent's MCP manager.
+    pub fn execute_stream<P: SystemProvider + Clone>(&self, provider: &P) -> ToolExecutionOutputStream {
+        let (tx, stream) = tool_output_channel();
+        let kind = self.kind.clone();
+        let provider = provider.clone();
+        tokio::spawn(async move {
+            let ToolKind::BuiltIn(tool) = kind else {
+                let err = ToolExecutionError::Custom("streaming is not supported for MCP tools".to_string());
+                let _ = tx.send(Err(err)).await;
+                return;
+            };
+            let cancel_token = CancellationToken::new();
+            let res = tokio::select! {
+                res = tool.execute_stream(&provider, &cancel_token, &tx) => res,
+                _ = tx.closed() => {
+                    cancel_token.cancel();
+                    return;
+                },
+            };
+            match res {
+                Ok(output) => {
+                    for item in output.items {
+                        if tx.send(Ok(item)).await.is_err() {
+                            return;
+                        }
+                    }
+                },
+                Err(err) => {
+                    let _ = tx.send(Err(err)).await;
+                },
+            }
+        });
+        stream
     }
 }
 
@@ -220,18 +600,46 @@ impl ToolKind {
         }
     }
 
-    pub async fn get_context(&self) -> Option<ToolContext> {
+    pub async fn get_context<P: SystemProvider>(&self, provider: &P) -> Option<ToolContext> {
         match self {
             ToolKind::BuiltIn(t) => match t {
                 BuiltInTool::FileRead(_) => None,
-                BuiltInTool::FileWrite(fw) => fw.make_context().await.ok().map(ToolContext::FileWrite),
+                BuiltInTool::FileWrite(fw) => fw.make_context(provider).await.ok().map(ToolContext::FileWrite),
                 _ => None,
             },
             ToolKind::Mcp(_) => None,
         }
     }
+
+    pub fn preview(&self) -> Option<String> {
+        match self {
+            ToolKind::BuiltIn(BuiltInTool::ExecuteCmd(t)) => {
+                // Normalizes quoting and whitespace, e.g. `cargo  test "--lib"` -> `cargo test --lib`.
+                // Commands with operators or expansions are shown as is, since quoting them would
+                // show a different command from the one that runs.
+                let commands = t.commands().into_iter().map(|command| {
+                    if command.contains(SHELL_SPECIAL_CHARS) {
+                        return command.trim().to_string();
+                    }
+                    let (program, args) = parse_shell_command(command);
+                    std::iter::once(program)
+                        .chain(args)
+                        .map(|arg| quote_shell_arg(&arg))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                });
+                Some(commands.collect::<Vec<_>>().join(" | "))
+            },
+            _ => None,
+        }
+    }
 }
 
+/// Characters with a special meaning to the shell outside of quotes, other than whitespace.
+const SHELL_SPECIAL_CHARS: &[char] = &[
+    '$', '`', ';', '&', '|', '<', '>', '(', ')', '{', '}', '*', '?', '[', '~', '#', '!', '\n',
+];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BuiltInTool {
     FileRead(FsRead),
@@ -242,6 +650,12 @@ pub enum BuiltInTool {
     ImageRead(ImageRead),
     ExecuteCmd(ExecuteCmd),
     Introspect(Introspect),
+    SpellCheck(SpellCheck),
+    Chmod(Chmod),
+    Chown(Chown),
+    Stat(Stat),
+    RenderTemplate(RenderTemplate),
+    Rm(Rm),
     /// TODO
     SpawnSubagent,
 }
@@ -264,16 +678,168 @@ impl BuiltInTool {
             BuiltInToolName::Ls => serde_json::from_value::<Ls>(args)
                 .map(Self::Ls)
                 .map_err(ToolParseErrorKind::schema_failure),
+            BuiltInToolName::SpellCheck => serde_json::from_value::<SpellCheck>(args)
+                .map(Self::SpellCheck)
+                .map_err(ToolParseErrorKind::schema_failure),
+            BuiltInToolName::Chmod => serde_json::from_value::<Chmod>(args)
+                .map(Self::Chmod)
+                .map_err(ToolParseErrorKind::schema_failure),
+            BuiltInToolName::Chown => serde_json::from_value::<Chown>(args)
+                .map(Self::Chown)
+                .map_err(ToolParseErrorKind::schema_failure),
+            BuiltInToolName::Stat => serde_json::from_value::<Stat>(args)
+                .map(Self::Stat)
+                .map_err(ToolParseErrorKind::schema_failure),
+            BuiltInToolName::RenderTemplate => serde_json::from_value::<RenderTemplate>(args)
+                .map(Self::RenderTemplate)
+                .map_err(ToolParseErrorKind::schema_failure),
+            BuiltInToolName::Grep => serde_json::from_value::<Grep>(args)
+                .map(Self::Grep)
+                .map_err(ToolParseErrorKind::schema_failure),
+            BuiltInToolName::Mkdir => serde_json::from_value::<Mkdir>(args)
+                .map(Self::Mkdir)
+                .map_err(ToolParseErrorKind::schema_failure),
+            BuiltInToolName::Rm => serde_json::from_value::<Rm>(args)
+                .map(Self::Rm)
+                .map_err(ToolParseErrorKind::schema_failure),
+            BuiltInToolName::Introspect => serde_json::from_value::<Introspect>(args)
+                .map(Self::Introspect)
+                .map_err(ToolParseErrorKind::schema_failure),
         }
     }
 
     pub fn generate_tool_spec(name: &BuiltInToolName) -> ToolSpec {
         match name {
-            BuiltInToolName::FsRead => generate_tool_spec_from_json_schema::<FsRead>(),
+            BuiltInToolName::FsRead => generate_tool_spec_from_json_schema::<FsRead>(false),
             BuiltInToolName::FsWrite => generate_tool_spec_from_trait::<FsWrite>(),
             BuiltInToolName::ExecuteCmd => generate_tool_spec_from_trait::<ExecuteCmd>(),
             BuiltInToolName::ImageRead => generate_tool_spec_from_trait::<ImageRead>(),
             BuiltInToolName::Ls => generate_tool_spec_from_trait::<Ls>(),
+            BuiltInToolName::SpellCheck => generate_tool_spec_from_trait::<SpellCheck>(),
+            BuiltInToolName::Chmod => generate_tool_spec_from_trait::<Chmod>(),
+            BuiltInToolName::Chown => generate_tool_spec_from_trait::<Chown>(),
+            BuiltInToolName::Stat => generate_tool_spec_from_trait::<Stat>(),
+            BuiltInToolName::RenderTemplate => generate_tool_spec_from_trait::<RenderTemplate>(),
+            BuiltInToolName::Grep => generate_tool_spec_from_trait::<Grep>(),
+            BuiltInToolName::Mkdir => generate_tool_spec_from_trait::<Mkdir>(),
+            BuiltInToolName::Rm => generate_tool_spec_from_trait::<Rm>(),
+            BuiltInToolName::Introspect => generate_tool_spec_from_trait::<Introspect>(),
+        }
+    }
+
+    pub fn version(name: &BuiltInToolName) -> u32 {
+        match name {
+            BuiltInToolName::FsRead => FsRead::version(),
+            BuiltInToolName::FsWrite => FsWrite::version(),
+            BuiltInToolName::ExecuteCmd => ExecuteCmd::version(),
+            BuiltInToolName::ImageRead => ImageRead::version(),
+            BuiltInToolName::Ls => Ls::version(),
+            BuiltInToolName::SpellCheck => SpellCheck::version(),
+            BuiltInToolName::Chmod => Chmod::version(),
+            BuiltInToolName::Chown => Chown::version(),
+            BuiltInToolName::Stat => Stat::version(),
+            BuiltInToolName::RenderTemplate => RenderTemplate::version(),
+            BuiltInToolName::Grep => Grep::version(),
+            BuiltInToolName::Mkdir => Mkdir::version(),
+            BuiltInToolName::Rm => Rm::version(),
+            BuiltInToolName::Introspect => Introspect::version(),
+        }
+    }
+
+    pub fn migrations(name: &BuiltInToolName) -> Vec<ToolSchemaMigration> {
+        match name {
+            BuiltInToolName::FsRead => FsRead::migrations(),
+            BuiltInToolName::FsWrite => FsWrite::migrations(),
+            BuiltInToolName::ExecuteCmd => ExecuteCmd::migrations(),
+            BuiltInToolName::ImageRead => ImageRead::migrations(),
+            BuiltInToolName::Ls => Ls::migrations(),
+            BuiltInToolName::SpellCheck => SpellCheck::migrations(),
+            BuiltInToolName::Chmod => Chmod::migrations(),
+            BuiltInToolName::Chown => Chown::migrations(),
+            BuiltInToolName::Stat => Stat::migrations(),
+            BuiltInToolName::RenderTemplate => RenderTemplate::migrations(),
+            BuiltInToolName::Grep => Grep::migrations(),
+            BuiltInToolName::Mkdir => Mkdir::migrations(),
+            BuiltInToolName::Rm => Rm::migrations(),
+            BuiltInToolName::Introspect => Introspect::migrations(),
+        }
+    }
+
+    /// Returns the tool description for the given locale, including any constraints.
+    pub fn localized_description(name: &BuiltInToolName, locale: &str) -> String {
+        let locale = Some(locale);
+        match name {
+            BuiltInToolName::FsRead => tool_description::<FsRead>(locale),
+            BuiltInToolName::FsWrite => tool_description::<FsWrite>(locale),
+            BuiltInToolName::ExecuteCmd => tool_description::<ExecuteCmd>(locale),
+            BuiltInToolName::ImageRead => tool_description::<ImageRead>(locale),
+            BuiltInToolName::Ls => tool_description::<Ls>(locale),
+            BuiltInToolName::SpellCheck => tool_description::<SpellCheck>(locale),
+            BuiltInToolName::Chmod => tool_description::<Chmod>(locale),
+            BuiltInToolName::Chown => tool_description::<Chown>(locale),
+            BuiltInToolName::Stat => tool_description::<Stat>(locale),
+            BuiltInToolName::RenderTemplate => tool_description::<RenderTemplate>(locale),
+            BuiltInToolName::Grep => tool_description::<Grep>(locale),
+            BuiltInToolName::Mkdir => tool_description::<Mkdir>(locale),
+            BuiltInToolName::Rm => tool_description::<Rm>(locale),
+            BuiltInToolName::Introspect => tool_description::<Introspect>(locale),
+        }
+    }
+
+    pub fn examples(name: &BuiltInToolName) -> Vec<ToolExample> {
+        match name {
+            BuiltInToolName::FsRead => FsRead::examples(),
+            BuiltInToolName::FsWrite => FsWrite::examples(),
+            BuiltInToolName::ExecuteCmd => ExecuteCmd::examples(),
+            BuiltInToolName::ImageRead => ImageRead::examples(),
+            BuiltInToolName::Ls => Ls::examples(),
+            BuiltInToolName::SpellCheck => SpellCheck::examples(),
+            BuiltInToolName::Chmod => Chmod::examples(),
+            BuiltInToolName::Chown => Chown::examples(),
+            BuiltInToolName::Stat => Stat::examples(),
+            BuiltInToolName::RenderTemplate => RenderTemplate::examples(),
+            BuiltInToolName::Grep => Grep::examples(),
+            BuiltInToolName::Mkdir => Mkdir::examples(),
+            BuiltInToolName::Rm => Rm::examples(),
+            BuiltInToolName::Introspect => Introspect::examples(),
+        }
+    }
+
+    /// Executes tools that do not require any state from the agent.
+    async fn execute<P: SystemProvider>(self, provider: &P, cancel_token: &CancellationToken) -> ToolExecutionResult {
+        match self {
+            BuiltInTool::FileRead(t) => t.execute(provider, cancel_token).await,
+            BuiltInTool::FileWrite(t) => t.execute(None, provider, cancel_token).await,
+            BuiltInTool::ExecuteCmd(t) => t.execute(provider).await,
+            BuiltInTool::ImageRead(t) => t.execute().await,
+            BuiltInTool::Ls(t) => t.execute(provider, None).await,
+            BuiltInTool::SpellCheck(t) => t.execute(provider).await,
+            BuiltInTool::Chmod(t) => t.execute(provider).await,
+            BuiltInTool::Chown(t) => t.execute(provider).await,
+            BuiltInTool::Stat(t) => t.execute(provider).await,
+            BuiltInTool::RenderTemplate(t) => t.execute(provider, cancel_token).await,
+            BuiltInTool::Grep(t) => t.execute(provider).await,
+            BuiltInTool::Mkdir(t) => t.execute(provider).await,
+            BuiltInTool::Rm(t) => t.execute(provider, None).await,
+            // Without an agent, every built-in tool can be executed.
+            BuiltInTool::Introspect(t) => t.execute(provider, &built_in_tool_names()).await,
+            BuiltInTool::SpawnSubagent => Err(ToolExecutionError::Custom("unimplemented".to_string())),
+        }
+    }
+
+    /// Same as [BuiltInTool::execute], except that tools supporting it send their output through
+    /// `output` while executing. Returns the output items that were not sent.
+    async fn execute_stream<P: SystemProvider>(
+        self,
+        provider: &P,
+        cancel_token: &CancellationToken,
+        output: &ToolOutputSender,
+    ) -> ToolExecutionResult {
+        match self {
+            BuiltInTool::ExecuteCmd(t) => t.execute_stream(provider, output).await,
+            BuiltInTool::Ls(t) => t.execute_stream(provider, None, output).await,
+            BuiltInTool::Grep(t) => t.execute_stream(provider, output).await,
+            tool => tool.execute(provider, cancel_token).await,
         }
     }
 
@@ -281,12 +847,18 @@ impl BuiltInTool {
         match self {
             BuiltInTool::FileRead(_) => BuiltInToolName::FsRead,
             BuiltInTool::FileWrite(_) => BuiltInToolName::FsWrite,
-            BuiltInTool::Grep(_) => panic!("unimplemented"),
+            BuiltInTool::Grep(_) => BuiltInToolName::Grep,
             BuiltInTool::Ls(_) => BuiltInToolName::Ls,
-            BuiltInTool::Mkdir(_) => panic!("unimplemented"),
+            BuiltInTool::Mkdir(_) => BuiltInToolName::Mkdir,
             BuiltInTool::ImageRead(_) => BuiltInToolName::ImageRead,
             BuiltInTool::ExecuteCmd(_) => BuiltInToolName::ExecuteCmd,
-            BuiltInTool::Introspect(_) => panic!("unimplemented"),
+            BuiltInTool::Introspect(_) => BuiltInToolName::Introspect,
+            BuiltInTool::SpellCheck(_) => BuiltInToolName::SpellCheck,
+            BuiltInTool::Chmod(_) => BuiltInToolName::Chmod,
+            BuiltInTool::Chown(_) => BuiltInToolName::Chown,
+            BuiltInTool::Stat(_) => BuiltInToolName::Stat,
+            BuiltInTool::RenderTemplate(_) => BuiltInToolName::RenderTemplate,
+            BuiltInTool::Rm(_) => BuiltInToolName::Rm,
             BuiltInTool::SpawnSubagent => panic!("unimplemented"),
         }
     }
@@ -295,12 +867,18 @@ impl BuiltInTool {
         match self {
             BuiltInTool::FileRead(_) => BuiltInToolName::FsRead.into(),
             BuiltInTool::FileWrite(_) => BuiltInToolName::FsWrite.into(),
-            BuiltInTool::Grep(_) => panic!("unimplemented"),
+            BuiltInTool::Grep(_) => BuiltInToolName::Grep.into(),
             BuiltInTool::Ls(_) => BuiltInToolName::Ls.into(),
-            BuiltInTool::Mkdir(_) => panic!("unimplemented"),
+            BuiltInTool::Mkdir(_) => BuiltInToolName::Mkdir.into(),
             BuiltInTool::ImageRead(_) => BuiltInToolName::ImageRead.into(),
             BuiltInTool::ExecuteCmd(_) => BuiltInToolName::ExecuteCmd.into(),
-            BuiltInTool::Introspect(_) => panic!("unimplemented"),
+            BuiltInTool::Introspect(_) => BuiltInToolName::Introspect.into(),
+            BuiltInTool::SpellCheck(_) => BuiltInToolName::SpellCheck.into(),
+            BuiltInTool::Chmod(_) => BuiltInToolName::Chmod.into(),
+            BuiltInTool::Chown(_) => BuiltInToolName::Chown.into(),
+            BuiltInTool::Stat(_) => BuiltInToolName::Stat.into(),
+            BuiltInTool::RenderTemplate(_) => BuiltInToolName::RenderTemplate.into(),
+            BuiltInTool::Rm(_) => BuiltInToolName::Rm.into(),
             BuiltInTool::SpawnSubagent => panic!("unimplemented"),
         }
     }
@@ -310,6 +888,22 @@ pub fn built_in_tool_names() -> Vec<CanonicalToolName> {
     BuiltInToolName::iter().map(CanonicalToolName::BuiltIn).collect()
 }
 
+/// Returns the tool specs for all built-in tools, sorted by name.
+///
+/// The specs are only generated once, so subsequent calls are cheap.
+pub fn generate_all_tool_specs() -> Vec<ToolSpec> {
+    static TOOL_SPECS: OnceLock<Vec<ToolSpec>> = OnceLock::new();
+    TOOL_SPECS
+        .get_or_init(|| {
+            let mut specs = BuiltInToolName::iter()
+                .map(|name| BuiltInTool::generate_tool_spec(&name))
+                .collect::<Vec<_>>();
+            specs.sort_by(|a, b| a.name.cmp(&b.name));
+            specs
+        })
+        .clone()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ToolContext {
     FileRead,
@@ -338,6 +932,151 @@ impl ToolExecutionOutput {
     pub fn new(items: Vec<ToolExecutionOutputItem>) -> Self {
         Self { items }
     }
+
+    /// Returns a new output containing only the items for which `pred` returns `true`.
+    ///
+    /// Useful for stripping item kinds that a consumer does not support, e.g. removing images
+    /// before sending to a text-only model.
+    pub fn filter_items<F: Fn(&ToolExecutionOutputItem) -> bool>(self, pred: F) -> Self {
+        Self {
+            items: self.items.into_iter().filter(|item| pred(item)).collect(),
+        }
+    }
+
+    /// Iterates over the content of all [ToolExecutionOutputItem::Text] items.
+    pub fn text_items(&self) -> impl Iterator<Item = &str> {
+        self.items.iter().filter_map(|item| match item {
+            ToolExecutionOutputItem::Text(s) => Some(s.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Joins the content of all [ToolExecutionOutputItem::Text] items with `separator`.
+    pub fn join_text_items(&self, separator: &str) -> String {
+        self.text_items().collect::<Vec<_>>().join(separator)
+    }
+
+    /// Joins the content of all [ToolExecutionOutputItem::Text] items with newlines.
+    pub fn all_text(&self) -> String {
+        self.join_text_items("\n")
+    }
+
+    /// Iterates over the content of all [ToolExecutionOutputItem::Json] items.
+    pub fn json_items(&self) -> impl Iterator<Item = &serde_json::Value> {
+        self.items.iter().filter_map(|item| match item {
+            ToolExecutionOutputItem::Json(v) => Some(v),
+            _ => None,
+        })
+    }
+
+    /// Truncates each [ToolExecutionOutputItem::Text] item independently to at most `max_bytes`,
+    /// appending `suffix` to any item that was truncated.
+    pub fn truncate_text_items(&mut self, max_bytes: usize, suffix: &str) {
+        for item in &mut self.items {
+            if let ToolExecutionOutputItem::Text(s) = item {
+                truncate_safe_in_place(s, max_bytes, suffix);
+            }
+        }
+    }
+
+    /// Truncates [ToolExecutionOutputItem::Text] items such that their combined length is at
+    /// most `max_bytes`.
+    ///
+    /// Items are kept in order - the item that exceeds the budget is truncated with `suffix`
+    /// appended, and all text items after it are cleared.
+    pub fn truncate_total(&mut self, max_bytes: usize, suffix: &str) {
+        let mut remaining = max_bytes;
+        for item in &mut self.items {
+            if let ToolExecutionOutputItem::Text(s) = item {
+                truncate_safe_in_place(s, remaining, suffix);
+                remaining -= s.len();
+            }
+        }
+    }
+
+    /// Returns the number of items of each kind contained in this output.
+    pub fn item_count_by_type(&self) -> ToolOutputTypeCounts {
+        let mut counts = ToolOutputTypeCounts::default();
+        for item in &self.items {
+            match item {
+                ToolExecutionOutputItem::Text(_) => counts.text += 1,
+                ToolExecutionOutputItem::Json(_) => counts.json += 1,
+                ToolExecutionOutputItem::Image(_) => counts.image += 1,
+            }
+        }
+        counts
+    }
+
+    /// Returns the sum of the byte lengths of all [ToolExecutionOutputItem::Text] items.
+    pub fn total_text_bytes(&self) -> usize {
+        self.items
+            .iter()
+            .map(|item| match item {
+                ToolExecutionOutputItem::Text(s) => s.len(),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Returns the sum of the byte lengths of all [ToolExecutionOutputItem::Image] items.
+    pub fn total_image_bytes(&self) -> usize {
+        self.items
+            .iter()
+            .map(|item| match item {
+                ToolExecutionOutputItem::Image(block) => block.source.bytes().len(),
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+/// The max number of output items buffered by a [ToolExecutionOutputStream].
+const TOOL_OUTPUT_STREAM_CAPACITY: usize = 16;
+
+/// Sends the output items of a tool while it is executing. See [Tool::execute_stream].
+pub type ToolOutputSender = mpsc::Sender<Result<ToolExecutionOutputItem, ToolExecutionError>>;
+
+/// Creates a channel for streaming the output items of a tool while it is executing.
+pub fn tool_output_channel() -> (ToolOutputSender, ToolExecutionOutputStream) {
+    let (tx, rx) = mpsc::channel(TOOL_OUTPUT_STREAM_CAPACITY);
+    (tx, ToolExecutionOutputStream(rx))
+}
+
+/// Sends an output item of an executing tool. A closed stream is ignored, since dropping the
+/// stream cancels the execution.
+async fn send_output(output: &ToolOutputSender, item: ToolExecutionOutputItem) {
+    let _ = output.send(Ok(item)).await;
+}
+
+/// Output items of a tool execution, received as they are produced. See [Tool::execute_stream].
+///
+/// If the tool fails, the error is the last message received.
+#[derive(Debug)]
+pub struct ToolExecutionOutputStream(pub mpsc::Receiver<Result<ToolExecutionOutputItem, ToolExecutionError>>);
+
+impl ToolExecutionOutputStream {
+    /// Receives the next output item, returning [None] once the tool has finished executing.
+    pub async fn next(&mut self) -> Option<Result<ToolExecutionOutputItem, ToolExecutionError>> {
+        self.0.recv().await
+    }
+
+    /// Waits for the tool to finish executing, collecting all of the streamed items. For callers
+    /// that do not support streaming.
+    pub async fn collect(mut self) -> ToolExecutionResult {
+        let mut items = Vec::new();
+        while let Some(item) = self.next().await {
+            items.push(item?);
+        }
+        Ok(ToolExecutionOutput::new(items))
+    }
+}
+
+/// Number of items of each kind in a [ToolExecutionOutput].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolOutputTypeCounts {
+    pub text: usize,
+    pub json: usize,
+    pub image: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -359,10 +1098,50 @@ pub struct ToolState {
     pub file_write: Option<FsWriteState>,
 }
 
+impl ToolState {
+    /// Writes the state to `path` as JSON.
+    ///
+    /// The state is first written to a temporary file in the same directory which is then renamed
+    /// to `path`, so an interrupted save never leaves a partially written file behind.
+    pub async fn serialize_to_disk(&self, path: impl AsRef<Path>) -> Result<(), UtilError> {
+        let path = path.as_ref();
+        let content = serde_json::to_vec_pretty(self).context("failed to serialize tool state")?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("failed to create directory {}", parent.to_string_lossy()))?;
+        }
+
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(format!(".{}.tmp", std::process::id()));
+        let tmp_path = PathBuf::from(tmp_path);
+        tokio::fs::write(&tmp_path, content)
+            .await
+            .with_context(|| format!("failed to write {}", tmp_path.to_string_lossy()))?;
+        if let Err(err) = tokio::fs::rename(&tmp_path, path).await {
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            return Err(err).with_context(|| format!("failed to rename to {}", path.to_string_lossy()));
+        }
+        Ok(())
+    }
+
+    /// Reads state previously written with [ToolState::serialize_to_disk].
+    pub async fn deserialize_from_disk(path: impl AsRef<Path>) -> Result<Self, UtilError> {
+        let path = path.as_ref();
+        let content = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("failed to read {}", path.to_string_lossy()))?;
+        serde_json::from_slice(&content).with_context(|| format!("failed to deserialize {}", path.to_string_lossy()))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ToolExecutionError {
     Io {
         context: String,
+        /// The path of the file the operation failed on, if any.
+        #[serde(default)]
+        path: Option<PathBuf>,
         #[serde(skip)]
         source: Option<Arc<std::io::Error>>,
     },
@@ -375,11 +1154,20 @@ impl From<String> for ToolExecutionError {
     }
 }
 
+impl From<std::io::Error> for ToolExecutionError {
+    fn from(value: std::io::Error) -> Self {
+        Self::io("io error", None, value)
+    }
+}
+
 impl std::fmt::Display for ToolExecutionError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ToolExecutionError::Io { context, source } => {
+            ToolExecutionError::Io { context, path, source } => {
                 write!(f, "{}", context)?;
+                if let Some(p) = path {
+                    write!(f, " at {}", p.to_string_lossy())?;
+                }
                 if let Some(s) = source {
                     write!(f, ": {}", s)?;
                 }
@@ -411,12 +1199,31 @@ impl std::error::Error for ToolExecutionError {
 }
 
 impl ToolExecutionError {
-    pub fn io(context: impl Into<String>, source: std::io::Error) -> Self {
+    pub fn io(context: impl Into<String>, path: Option<&Path>, source: std::io::Error) -> Self {
         Self::Io {
             context: context.into(),
+            path: path.map(Path::to_path_buf),
             source: Some(Arc::new(source)),
         }
     }
+
+    /// Error returned by tools that were cancelled during execution.
+    pub fn cancelled() -> Self {
+        Self::Custom("cancelled by user".to_string())
+    }
+
+    /// Creates an [ToolExecutionError::Io], adding a more specific message to `context` for
+    /// common [std::io::ErrorKind]s.
+    pub fn from_io_result<C: Into<String>>(context: C, path: Option<&Path>, err: std::io::Error) -> Self {
+        let context = context.into();
+        let reason = match err.kind() {
+            std::io::ErrorKind::NotFound => "path not found",
+            std::io::ErrorKind::PermissionDenied => "permission denied",
+            std::io::ErrorKind::AlreadyExists => "file already exists",
+            _ => return Self::io(context, path, err),
+        };
+        Self::io(format!("{}: {}", context, reason), path, err)
+    }
 }
 
 #[derive(Debug, Clone, thiserror::Error)]
@@ -467,6 +1274,7 @@ impl ToolParseErrorKind {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent::agent_loop::types::ImageSource;
 
     #[test]
     fn test_tool_schemas() {
@@ -480,4 +1288,503 @@ mod tests {
     fn test_built_in_tools() {
         built_in_tool_names();
     }
+
+    #[test]
+    fn test_from_io_result() {
+        use std::io::{
+            Error,
+            ErrorKind,
+        };
+
+        let cases = [
+            (ErrorKind::NotFound, "failed to read: path not found"),
+            (ErrorKind::PermissionDenied, "failed to read: permission denied"),
+            (ErrorKind::AlreadyExists, "failed to read: file already exists"),
+            (ErrorKind::Other, "failed to read"),
+        ];
+        for (kind, expected) in cases {
+            let err = ToolExecutionError::from_io_result("failed to read", None, Error::new(kind, "oops"));
+            let ToolExecutionError::Io { context, source, .. } = &err else {
+                panic!("expected an io error, instead found: {:?}", err);
+            };
+            assert_eq!(context, expected);
+            assert_eq!(source.as_ref().unwrap().kind(), kind);
+            assert_eq!(err.to_string(), format!("{}: oops", expected));
+        }
+    }
+
+    #[test]
+    fn test_io_error_display_includes_path() {
+        use std::io::{
+            Error,
+            ErrorKind,
+        };
+
+        let path = PathBuf::from("/path/to/file.txt");
+        let err = ToolExecutionError::io("failed to read", Some(&path), Error::other("oops"));
+        assert_eq!(err.to_string(), "failed to read at /path/to/file.txt: oops");
+
+        let err =
+            ToolExecutionError::from_io_result("failed to read", Some(&path), Error::new(ErrorKind::NotFound, "oops"));
+        let ToolExecutionError::Io { path: err_path, .. } = &err else {
+            panic!("expected an io error, instead found: {:?}", err);
+        };
+        assert_eq!(err_path.as_ref(), Some(&path));
+        assert_eq!(
+            err.to_string(),
+            "failed to read: path not found at /path/to/file.txt: oops"
+        );
+
+        let err = ToolExecutionError::io("failed to read", None, Error::other("oops"));
+        assert_eq!(err.to_string(), "failed to read: oops");
+
+        let err = ToolExecutionError::from(Error::other("oops"));
+        assert!(matches!(err, ToolExecutionError::Io { path: None, .. }), "{:?}", err);
+        assert_eq!(err.to_string(), "io error: oops");
+    }
+
+    #[test]
+    fn test_tool_constraints_in_spec() {
+        for name in [
+            BuiltInToolName::ExecuteCmd,
+            BuiltInToolName::FsWrite,
+            BuiltInToolName::Ls,
+        ] {
+            let spec = BuiltInTool::generate_tool_spec(&name);
+            assert!(
+                spec.description.contains("CONSTRAINTS:"),
+                "{} is missing constraints",
+                name
+            );
+        }
+        let spec = BuiltInTool::generate_tool_spec(&BuiltInToolName::FsRead);
+        assert!(!spec.description.contains("CONSTRAINTS:"));
+    }
+
+    #[test]
+    fn test_tool_examples_match_schema() {
+        for name in BuiltInToolName::iter() {
+            let spec = BuiltInTool::generate_tool_spec(&name);
+            let validator = jsonschema::validator_for(&serde_json::Value::Object(spec.input_schema)).unwrap();
+            for example in BuiltInTool::examples(&name) {
+                let errors = validator
+                    .iter_errors(&example.input)
+                    .map(|err| err.to_string())
+                    .collect::<Vec<_>>();
+                assert!(
+                    errors.is_empty(),
+                    "example for {} should match the input schema: {:?}",
+                    name,
+                    errors
+                );
+                BuiltInTool::from_parts(&name, example.input.clone())
+                    .unwrap_or_else(|err| panic!("example for {} should parse: {:?}", name, err));
+            }
+        }
+        assert!(!BuiltInTool::examples(&BuiltInToolName::FsWrite).is_empty());
+        assert!(!BuiltInTool::examples(&BuiltInToolName::Ls).is_empty());
+        assert!(!BuiltInTool::examples(&BuiltInToolName::ExecuteCmd).is_empty());
+    }
+
+    #[test]
+    fn test_generate_tool_spec_use_defs() {
+        let inlined = generate_tool_spec_from_json_schema::<FsRead>(false);
+        assert!(!inlined.input_schema.contains_key("$defs"));
+
+        let with_defs = generate_tool_spec_from_json_schema::<FsRead>(true);
+        assert!(with_defs.input_schema.contains_key("$defs"));
+        assert!(!with_defs.input_schema.contains_key("$schema"));
+        assert!(!with_defs.input_schema.contains_key("description"));
+    }
+
+    #[test]
+    fn test_generate_all_tool_specs() {
+        let specs = generate_all_tool_specs();
+        assert_eq!(specs.len(), BuiltInToolName::iter().count());
+        assert!(specs.windows(2).all(|w| w[0].name <= w[1].name));
+    }
+
+    #[test]
+    fn test_tool_spec_version() {
+        for spec in generate_all_tool_specs() {
+            assert_eq!(
+                spec.input_schema.get(TOOL_VERSION_FIELD_NAME),
+                Some(&serde_json::json!(1)),
+                "{} should include the schema version",
+                spec.name
+            );
+            assert!(
+                spec.input_schema["properties"].get(TOOL_VERSION_FIELD_NAME).is_none(),
+                "{} should not include the schema version as an argument",
+                spec.name
+            );
+        }
+
+        // The version is stripped before parsing, if present.
+        let name = CanonicalToolName::BuiltIn(BuiltInToolName::Stat);
+        Tool::parse(&name, serde_json::json!({ "path": "/tmp", TOOL_VERSION_FIELD_NAME: 1 })).unwrap();
+        Tool::parse(&name, serde_json::json!({ "path": "/tmp", TOOL_VERSION_FIELD_NAME: 0 })).unwrap();
+    }
+
+    #[test]
+    fn test_tool_spec_purpose_field() {
+        for spec in generate_all_tool_specs() {
+            let properties = spec.input_schema.get("properties").and_then(|p| p.as_object()).unwrap();
+            assert_eq!(
+                properties.get(TOOL_USE_PURPOSE_FIELD_NAME),
+                Some(&tool_use_purpose_schema()),
+                "{} should include the tool use purpose field",
+                spec.name
+            );
+            let required = spec.input_schema.get("required").and_then(|r| r.as_array());
+            assert!(
+                !required.is_some_and(|r| r.contains(&TOOL_USE_PURPOSE_FIELD_NAME.into())),
+                "{} should not require the tool use purpose field",
+                spec.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_compute_input_hash() {
+        let ls = CanonicalToolName::BuiltIn(BuiltInToolName::Ls);
+        let hash =
+            |name: &CanonicalToolName, args: serde_json::Value| Tool::parse(name, args).unwrap().compute_input_hash();
+
+        let a = hash(&ls, serde_json::json!({ "path": "/tmp", "depth": 1 }));
+        assert_eq!(a.len(), 64);
+        assert_eq!(a, hash(&ls, serde_json::json!({ "depth": 1, "path": "/tmp" })));
+        assert_eq!(
+            a,
+            hash(
+                &ls,
+                serde_json::json!({ "path": "/tmp", "depth": 1, TOOL_USE_PURPOSE_FIELD_NAME: "list tmp" })
+            )
+        );
+        assert_ne!(a, hash(&ls, serde_json::json!({ "path": "/tmp", "depth": 2 })));
+
+        let mcp = CanonicalToolName::Mcp {
+            server_name: "docs".to_string(),
+            tool_name: "search".to_string(),
+        };
+        assert_eq!(
+            hash(
+                &mcp,
+                serde_json::json!({ "query": "rust", "filters": { "lang": "en", "year": 2024 } })
+            ),
+            hash(
+                &mcp,
+                serde_json::json!({ "filters": { "year": 2024, "lang": "en" }, "query": "rust" })
+            )
+        );
+        assert_ne!(
+            hash(&mcp, serde_json::json!({ "query": "rust" })),
+            hash(&mcp, serde_json::json!({ "query": "go" }))
+        );
+    }
+
+    #[test]
+    fn test_tool_preview() {
+        let name = CanonicalToolName::BuiltIn(BuiltInToolName::ExecuteCmd);
+        for (command, expected) in [
+            (
+                "cargo test --test integration_tests",
+                "cargo test --test integration_tests",
+            ),
+            (
+                "cargo   test  \"--test\" 'integration_tests'",
+                "cargo test --test integration_tests",
+            ),
+            ("git commit -m \"a message\"", "git commit -m 'a message'"),
+            // Operators and expansions are kept as is.
+            ("rm -rf $HOME", "rm -rf $HOME"),
+            ("cargo build  && cargo test ", "cargo build  && cargo test"),
+            ("echo \"$(date)\"", "echo \"$(date)\""),
+        ] {
+            let tool = Tool::parse(&name, serde_json::json!({ "command": command })).unwrap();
+            assert_eq!(tool.preview().as_deref(), Some(expected));
+        }
+
+        #[cfg(unix)]
+        {
+            let tool = Tool::parse(
+                &name,
+                serde_json::json!({ "command": "cat  log.txt", "pipeTo": { "command": "grep \"an error\"" } }),
+            )
+            .unwrap();
+            assert_eq!(tool.preview().as_deref(), Some("cat log.txt | grep 'an error'"));
+        }
+
+        let tool = Tool::parse(
+            &CanonicalToolName::BuiltIn(BuiltInToolName::Stat),
+            serde_json::json!({ "path": "/tmp" }),
+        )
+        .unwrap();
+        assert!(tool.preview().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tool_execute_stream() {
+        let test_base = crate::util::test::TestBase::new()
+            .await
+            .with_file(("dir/a.txt", "a"))
+            .await
+            .with_file(("dir/sub/b.txt", "b"))
+            .await;
+        // Paths are absolute, so a real provider can be cloned onto the execution task.
+        let provider = crate::util::providers::RealProvider;
+        let ls = CanonicalToolName::BuiltIn(BuiltInToolName::Ls);
+        let path = test_base.join("dir").to_string_lossy().to_string();
+        let tool = Tool::parse(
+            &ls,
+            serde_json::json!({ "path": path, "depth": 1, "showUsageSummary": true }),
+        )
+        .unwrap();
+
+        // Each depth level is sent before the total.
+        let mut stream = tool.execute_stream(&provider);
+        assert!(
+            matches!(stream.next().await, Some(Ok(ToolExecutionOutputItem::Text(t))) if t.contains("a.txt") && !t.contains("b.txt"))
+        );
+        assert!(matches!(stream.next().await, Some(Ok(ToolExecutionOutputItem::Text(t))) if t.contains("b.txt")));
+        assert!(matches!(stream.next().await, Some(Ok(ToolExecutionOutputItem::Text(t))) if t.contains("Total:")));
+        assert!(matches!(
+            stream.next().await,
+            Some(Ok(ToolExecutionOutputItem::Json(_)))
+        ));
+        assert!(stream.next().await.is_none());
+
+        let output = tool.execute_stream(&provider).collect().await.unwrap();
+        assert_eq!(output.item_count_by_type(), ToolOutputTypeCounts {
+            text: 3,
+            json: 1,
+            image: 0
+        });
+
+        let path = test_base.join("missing").to_string_lossy().to_string();
+        let tool = Tool::parse(&ls, serde_json::json!({ "path": path })).unwrap();
+        assert!(tool.execute_stream(&provider).collect().await.is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_tool_execute_stream_incremental() {
+        let provider = crate::util::providers::RealProvider;
+        let tool = Tool::parse(
+            &CanonicalToolName::BuiltIn(BuiltInToolName::ExecuteCmd),
+            serde_json::json!({ "command": "echo first; sleep 5; echo second" }),
+        )
+        .unwrap();
+
+        // The first line arrives while the command is still running.
+        let mut stream = tool.execute_stream(&provider);
+        let first = tokio::time::timeout(std::time::Duration::from_secs(3), stream.next())
+            .await
+            .expect("first line should be sent before the command exits");
+        assert!(matches!(first, Some(Ok(ToolExecutionOutputItem::Text(t))) if t == "first\n"));
+        drop(stream);
+
+        let tool = Tool::parse(
+            &CanonicalToolName::BuiltIn(BuiltInToolName::ExecuteCmd),
+            serde_json::json!({ "command": "echo first; echo second >&2" }),
+        )
+        .unwrap();
+        let output = tool.execute_stream(&provider).collect().await.unwrap();
+        assert!(matches!(&output.items[0], ToolExecutionOutputItem::Text(t) if t == "first\n"));
+        let ToolExecutionOutputItem::Json(result) = &output.items[1] else {
+            panic!("expected the command result");
+        };
+        assert!(result.get("stdout").is_none());
+        assert_eq!(result["stderr"], "second\n");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_tool_execute_stream_drop_kills_command() {
+        let test_base = crate::util::test::TestBase::new().await;
+        let marker = test_base.join("marker");
+        let tool = Tool::parse(
+            &CanonicalToolName::BuiltIn(BuiltInToolName::ExecuteCmd),
+            serde_json::json!({ "command": format!("echo started; sleep 1; touch {}", marker.display()) }),
+        )
+        .unwrap();
+
+        let mut stream = tool.execute_stream(&crate::util::providers::RealProvider);
+        assert!(matches!(stream.next().await, Some(Ok(ToolExecutionOutputItem::Text(t))) if t == "started\n"));
+        drop(stream);
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        assert!(
+            !marker.exists(),
+            "the command should be killed once the stream is dropped"
+        );
+    }
+
+    #[test]
+    fn test_tool_locale_parse() {
+        for (locale, expected) in [
+            ("zh-CN", Some(ToolLocale::ZhCn)),
+            ("zh_cn", Some(ToolLocale::ZhCn)),
+            ("zh-Hans", Some(ToolLocale::ZhCn)),
+            ("zh", Some(ToolLocale::ZhCn)),
+            ("ja", Some(ToolLocale::Ja)),
+            ("ja-JP", Some(ToolLocale::Ja)),
+            ("zh-TW", None),
+            ("en-US", None),
+            ("", None),
+        ] {
+            assert_eq!(ToolLocale::parse(locale), expected, "{}", locale);
+        }
+    }
+
+    #[test]
+    fn test_localized_description() {
+        for name in BuiltInToolName::iter() {
+            let english = BuiltInTool::generate_tool_spec(&name).description;
+            assert_eq!(BuiltInTool::localized_description(&name, "en-US"), english);
+            for locale in ["zh-CN", "ja"] {
+                let localized = BuiltInTool::localized_description(&name, locale);
+                assert_ne!(localized, english, "{} should be translated for {}", name, locale);
+                assert!(!localized.contains("{IMAGE_FORMATS}"));
+            }
+        }
+        assert!(BuiltInTool::localized_description(&BuiltInToolName::Ls, "ja").contains("ディレクトリ"));
+        assert!(BuiltInTool::localized_description(&BuiltInToolName::Ls, "zh-CN").contains("目录"));
+    }
+
+    #[test]
+    fn test_migrate_tool_args() {
+        let migrations = vec![
+            ToolSchemaMigration {
+                from: 1,
+                to: 2,
+                migrate: Box::new(|mut args| {
+                    let path = args["file"].take();
+                    serde_json::json!({ "path": path })
+                }),
+            },
+            ToolSchemaMigration {
+                from: 2,
+                to: 3,
+                migrate: Box::new(|mut args| {
+                    args["recursive"] = false.into();
+                    args
+                }),
+            },
+        ];
+        let args = serde_json::json!({ "file": "a.txt" });
+        assert_eq!(
+            migrate_tool_args(&migrations, 1, 3, args.clone()),
+            Some(serde_json::json!({ "path": "a.txt", "recursive": false }))
+        );
+        assert_eq!(migrate_tool_args(&migrations, 2, 2, args.clone()), Some(args.clone()));
+        assert_eq!(migrate_tool_args(&migrations, 3, 1, args.clone()), None);
+        assert_eq!(migrate_tool_args(&[], 1, 2, args), None);
+
+        // Arguments sent with an older version are migrated, and the version is removed.
+        let name = BuiltInToolName::Ls;
+        assert_eq!(
+            migrate_versioned_args(
+                &name,
+                3,
+                &migrations,
+                serde_json::json!({ "file": "a.txt", TOOL_VERSION_FIELD_NAME: 1 })
+            ),
+            serde_json::json!({ "path": "a.txt", "recursive": false })
+        );
+        assert_eq!(
+            migrate_versioned_args(
+                &name,
+                3,
+                &migrations,
+                serde_json::json!({ "path": "a.txt", TOOL_VERSION_FIELD_NAME: 3 })
+            ),
+            serde_json::json!({ "path": "a.txt" })
+        );
+        // Arguments without a version are left as is.
+        assert_eq!(
+            migrate_versioned_args(&name, 3, &migrations, serde_json::json!({ "file": "a.txt" })),
+            serde_json::json!({ "file": "a.txt" })
+        );
+    }
+
+    #[test]
+    fn test_tool_parse_purpose() {
+        let name = CanonicalToolName::BuiltIn(BuiltInToolName::Stat);
+        let tool = Tool::parse(
+            &name,
+            serde_json::json!({ "path": "/tmp", TOOL_USE_PURPOSE_FIELD_NAME: "Check the directory exists" }),
+        )
+        .unwrap();
+        assert_eq!(tool.purpose(), Some("Check the directory exists"));
+
+        let tool = Tool::parse(&name, serde_json::json!({ "path": "/tmp" })).unwrap();
+        assert_eq!(tool.purpose(), None);
+
+        for purpose in [" ".to_string(), "a".repeat(MAX_TOOL_USE_PURPOSE_LEN + 1)] {
+            let err = Tool::parse(
+                &name,
+                serde_json::json!({ "path": "/tmp", TOOL_USE_PURPOSE_FIELD_NAME: purpose }),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ToolParseErrorKind::InvalidArgs(_)), "{:?}", err);
+        }
+    }
+
+    #[test]
+    fn test_tool_execution_output_join_text_items() {
+        let output = ToolExecutionOutput::new(vec![
+            ToolExecutionOutputItem::Text("hello".to_string()),
+            ToolExecutionOutputItem::Json(serde_json::json!({ "a": 1 })),
+            ToolExecutionOutputItem::Text("world".to_string()),
+        ]);
+        assert_eq!(output.join_text_items(", "), "hello, world");
+        assert_eq!(output.all_text(), "hello\nworld");
+        assert_eq!(ToolExecutionOutput::default().all_text(), "");
+    }
+
+    #[test]
+    fn test_tool_execution_output_stats() {
+        let output = ToolExecutionOutput::new(vec![
+            ToolExecutionOutputItem::Text("hello".to_string()),
+            ToolExecutionOutputItem::Text("world!".to_string()),
+            ToolExecutionOutputItem::Json(serde_json::json!({ "a": 1 })),
+            ToolExecutionOutputItem::Image(ImageBlock {
+                format: crate::agent::agent_loop::types::ImageFormat::Png,
+                source: ImageSource::Bytes(vec![0; 16]),
+            }),
+        ]);
+
+        assert_eq!(output.item_count_by_type(), ToolOutputTypeCounts {
+            text: 2,
+            json: 1,
+            image: 1,
+        });
+        assert_eq!(output.total_text_bytes(), 11);
+        assert_eq!(output.total_image_bytes(), 16);
+
+        assert_eq!(output.text_items().collect::<Vec<_>>(), vec!["hello", "world!"]);
+        assert_eq!(output.json_items().collect::<Vec<_>>(), vec![
+            &serde_json::json!({ "a": 1 })
+        ]);
+
+        let mut truncated = output.clone();
+        truncated.truncate_text_items(4, "..");
+        assert_eq!(truncated.text_items().collect::<Vec<_>>(), vec!["he..", "wo.."]);
+
+        let mut truncated = output.clone();
+        truncated.truncate_total(8, "..");
+        assert_eq!(truncated.text_items().collect::<Vec<_>>(), vec!["hello", "w.."]);
+        assert_eq!(truncated.total_text_bytes(), 8);
+
+        let mut truncated = output.clone();
+        truncated.truncate_total(4, "..");
+        assert_eq!(truncated.text_items().collect::<Vec<_>>(), vec!["he..", ""]);
+
+        let output = output.filter_items(|item| !matches!(item, ToolExecutionOutputItem::Image(_)));
+        assert_eq!(output.item_count_by_type(), ToolOutputTypeCounts {
+            text: 2,
+            json: 1,
+            image: 0,
+        });
+    }
 }
@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use tera::{
+    Context,
+    Tera,
+};
+use tokio_util::sync::CancellationToken;
+
+use super::fs_write::write_file;
+use super::{
+    BuiltInToolName,
+    BuiltInToolTrait,
+    ToolExample,
+    ToolExecutionError,
+    ToolExecutionOutput,
+    ToolExecutionOutputItem,
+    ToolExecutionResult,
+    ToolLocale,
+};
+use crate::util::path::canonicalize_path_sys;
+use crate::util::providers::SystemProvider;
+
+const RENDER_TEMPLATE_TOOL_DESCRIPTION: &str = r#"
+A tool for rendering a template file with a set of variables and writing the result to a file.
+
+WHEN TO USE THIS TOOL:
+- Use when you need to generate one or more files from a shared template, e.g. configuration files or boilerplate code
+
+HOW TO USE:
+- Provide the path to the template, the path to write the rendered output to, and the variables to render the template with
+- Templates use Jinja2 syntax, as implemented by Tera
+- Values are inserted with `{{ name }}`, and nested values with `{{ user.name }}`
+- Filters can be applied to values, e.g. `{{ name | upper }}` or `{{ port | default(value=8080) }}`
+- Conditionals are written as `{% if enabled %}...{% else %}...{% endif %}`
+- Loops are written as `{% for item in items %}{{ item }}{% endfor %}`
+
+TIPS:
+- Literal template syntax can be written inside `{% raw %}...{% endraw %}`
+- Rendering fails if the template references a variable that is not provided
+- Missing parent directories of the output path will be created
+"#;
+
+const RENDER_TEMPLATE_TOOL_DESCRIPTION_ZH_CN: &str = r#"
+用于使用一组变量渲染模板文件并将结果写入文件的工具。
+
+何时使用此工具：
+- 需要从共享模板生成一个或多个文件时使用，例如配置文件或样板代码
+
+使用方法：
+- 提供模板路径、渲染结果的写入路径以及用于渲染模板的变量
+- 模板使用 Tera 实现的 Jinja2 语法
+- 使用 `{{ name }}` 插入值，使用 `{{ user.name }}` 插入嵌套值
+- 可以对值应用过滤器，例如 `{{ name | upper }}` 或 `{{ port | default(value=8080) }}`
+- 条件语句写作 `{% if enabled %}...{% else %}...{% endif %}`
+- 循环写作 `{% for item in items %}{{ item }}{% endfor %}`
+
+提示：
+- 字面的模板语法可以写在 `{% raw %}...{% endraw %}` 中
+- 如果模板引用了未提供的变量，渲染将失败
+- 输出路径中缺失的父目录会被自动创建
+"#;
+
+const RENDER_TEMPLATE_TOOL_DESCRIPTION_JA: &str = r#"
+テンプレートファイルを変数でレンダリングし、結果をファイルに書き込むためのツールです。
+
+このツールを使用する場面:
+- 設定ファイルや定型コードなど、共通のテンプレートから 1 つ以上のファイルを生成する場合に使用します
+
+使い方:
+- テンプレートのパス、レンダリング結果の書き込み先パス、テンプレートのレンダリングに使用する変数を指定します
+- テンプレートには Tera によって実装された Jinja2 構文を使用します
+- 値は `{{ name }}`、ネストされた値は `{{ user.name }}` で挿入します
+- `{{ name | upper }}` や `{{ port | default(value=8080) }}` のように値にフィルターを適用できます
+- 条件分岐は `{% if enabled %}...{% else %}...{% endif %}` と記述します
+- ループは `{% for item in items %}{{ item }}{% endfor %}` と記述します
+
+ヒント:
+- リテラルのテンプレート構文は `{% raw %}...{% endraw %}` の中に記述できます
+- 提供されていない変数をテンプレートが参照している場合、レンダリングは失敗します
+- 出力パスの親ディレクトリが存在しない場合は作成されます
+"#;
+
+const RENDER_TEMPLATE_SCHEMA: &str = r#"
+{
+    "type": "object",
+    "properties": {
+        "template_path": {
+            "description": "Path to the template file",
+            "type": "string"
+        },
+        "output_path": {
+            "description": "Path to write the rendered template to. Existing files are overwritten",
+            "type": "string"
+        },
+        "variables": {
+            "description": "Values to render the template with, referenced by name in the template",
+            "type": "object",
+            "additionalProperties": true
+        }
+    },
+    "required": [
+        "template_path",
+        "output_path"
+    ]
+}
+"#;
+
+/// Name of the template registered with [Tera], used for error messages.
+const TEMPLATE_NAME: &str = "template";
+
+impl BuiltInToolTrait for RenderTemplate {
+    fn name() -> BuiltInToolName {
+        BuiltInToolName::RenderTemplate
+    }
+
+    fn description() -> std::borrow::Cow<'static, str> {
+        RENDER_TEMPLATE_TOOL_DESCRIPTION.into()
+    }
+
+    fn description_for_locale(locale: &str) -> std::borrow::Cow<'static, str> {
+        match ToolLocale::parse(locale) {
+            Some(ToolLocale::ZhCn) => RENDER_TEMPLATE_TOOL_DESCRIPTION_ZH_CN.into(),
+            Some(ToolLocale::Ja) => RENDER_TEMPLATE_TOOL_DESCRIPTION_JA.into(),
+            None => Self::description(),
+        }
+    }
+
+    fn input_schema() -> std::borrow::Cow<'static, str> {
+        RENDER_TEMPLATE_SCHEMA.into()
+    }
+
+    fn examples() -> Vec<ToolExample> {
+        vec![ToolExample::new(
+            "Generate a config file for a service",
+            serde_json::json!({
+                "template_path": "templates/service.toml.tmpl",
+                "output_path": "config/api.toml",
+                "variables": { "name": "api", "port": 8080, "debug": false }
+            }),
+        )]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderTemplate {
+    pub template_path: String,
+    pub output_path: String,
+    #[serde(default)]
+    pub variables: HashMap<String, serde_json::Value>,
+}
+
+impl RenderTemplate {
+    fn canonical_path<P: SystemProvider>(path: &str, provider: &P) -> Result<PathBuf, String> {
+        Ok(PathBuf::from(
+            canonicalize_path_sys(path, provider).map_err(|e| e.to_string())?,
+        ))
+    }
+
+    pub async fn validate<P: SystemProvider>(&self, provider: &P) -> Result<(), String> {
+        if self.output_path.is_empty() {
+            return Err("Output path must not be empty".to_string());
+        }
+        let template_path = Self::canonical_path(&self.template_path, provider)?;
+        let template = tokio::fs::read_to_string(&template_path)
+            .await
+            .map_err(|e| format!("Failed to read template '{}': {}", self.template_path, e))?;
+        compile(&template).map(|_| ())
+    }
+
+    pub async fn execute<P: SystemProvider>(
+        &self,
+        provider: &P,
+        cancel_token: &CancellationToken,
+    ) -> ToolExecutionResult {
+        let template_path = Self::canonical_path(&self.template_path, provider)?;
+        let output_path = Self::canonical_path(&self.output_path, provider)?;
+
+        let template = tokio::fs::read_to_string(&template_path)
+            .await
+            .map_err(|e| ToolExecutionError::from_io_result("failed to read template", Some(&template_path), e))?;
+        let context = Context::from_serialize(&self.variables)
+            .map_err(|e| ToolExecutionError::Custom(format!("Invalid template variables: {}", e)))?;
+        let rendered = compile(&template)?
+            .render(TEMPLATE_NAME, &context)
+            .map_err(|e| ToolExecutionError::Custom(format!("Failed to render template: {}", error_chain(&e))))?;
+
+        write_file(&output_path, rendered.as_bytes(), cancel_token).await?;
+        Ok(ToolExecutionOutput::new(vec![
+            ToolExecutionOutputItem::Text(format!(
+                "Rendered {} to {}",
+                template_path.to_string_lossy(),
+                output_path.to_string_lossy()
+            )),
+            ToolExecutionOutputItem::Json(serde_json::json!({ "rendered_bytes": rendered.len() })),
+        ]))
+    }
+}
+
+/// Compiles `template`, returning an error if its syntax is invalid. Values are inserted without
+/// HTML escaping since templates are not necessarily HTML.
+///
+/// Tera's built-in `get_env` function is disabled, since it would otherwise allow templates to
+/// write the agent's environment (including credentials) to the output file.
+fn compile(template: &str) -> Result<Tera, String> {
+    let mut tera = Tera::default();
+    tera.autoescape_on(vec![]);
+    tera.register_function("get_env", |_: &HashMap<String, tera::Value>| {
+        Err(tera::Error::msg("get_env is not available in templates"))
+    });
+    tera.add_raw_template(TEMPLATE_NAME, template)
+        .map_err(|e| format!("Invalid template syntax: {}", error_chain(&e)))?;
+    Ok(tera)
+}
+
+/// Formats `err` along with its sources, since [tera::Error] only describes the failing template
+/// at the top level.
+fn error_chain(err: &tera::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = std::error::Error::source(err);
+    while let Some(err) = source {
+        message.push_str(": ");
+        message.push_str(&err.to_string());
+        source = err.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test::TestBase;
+
+    fn render_template(variables: serde_json::Value) -> RenderTemplate {
+        RenderTemplate {
+            template_path: "service.tmpl".to_string(),
+            output_path: "out/service.toml".to_string(),
+            variables: serde_json::from_value(variables).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_render_template() {
+        let test_base = TestBase::new()
+            .await
+            .with_file((
+                "service.tmpl",
+                "name = \"{{ name }}\"\n{% if debug %}debug = true\n{% endif %}{% for port in ports %}port = {{ port }}\n{% endfor %}{{ name | upper }}",
+            ))
+            .await;
+
+        let tool = render_template(serde_json::json!({ "name": "a<b>", "debug": true, "ports": [80, 443] }));
+        tool.validate(&test_base).await.unwrap();
+        let output = tool.execute(&test_base, &CancellationToken::new()).await.unwrap();
+
+        let expected = "name = \"a<b>\"\ndebug = true\nport = 80\nport = 443\nA<B>";
+        assert_eq!(
+            tokio::fs::read_to_string(test_base.join("out/service.toml"))
+                .await
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            output.json_items().next().unwrap(),
+            &serde_json::json!({ "rendered_bytes": expected.len() })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_template_validate() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("service.tmpl", "{% if debug %}unterminated"))
+            .await;
+        let err = render_template(serde_json::json!({}))
+            .validate(&test_base)
+            .await
+            .unwrap_err();
+        assert!(err.contains("Invalid template syntax"), "{}", err);
+
+        let mut tool = render_template(serde_json::json!({}));
+        tool.template_path = "missing.tmpl".to_string();
+        assert!(tool.validate(&test_base).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_render_template_missing_variable() {
+        let test_base = TestBase::new().await.with_file(("service.tmpl", "{{ name }}")).await;
+        let tool = render_template(serde_json::json!({}));
+        tool.validate(&test_base).await.unwrap();
+        assert!(tool.execute(&test_base, &CancellationToken::new()).await.is_err());
+        assert!(!test_base.join("out/service.toml").exists());
+    }
+
+    #[tokio::test]
+    async fn test_render_template_get_env_disabled() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("service.tmpl", "{{ get_env(name=\"HOME\") }}"))
+            .await;
+        let tool = render_template(serde_json::json!({}));
+        let err = tool.execute(&test_base, &CancellationToken::new()).await.unwrap_err();
+        assert!(err.to_string().contains("get_env is not available"), "{}", err);
+        assert!(!test_base.join("out/service.toml").exists());
+    }
+}
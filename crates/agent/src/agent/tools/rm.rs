@@ -1,6 +1,7 @@
-#![allow(dead_code)]
-
-use std::path::PathBuf;
+use std::path::{
+    Path,
+    PathBuf,
+};
 
 use serde::{
     Deserialize,
@@ -8,23 +9,80 @@ use serde::{
 };
 
 use super::{
+    BuiltInToolName,
+    BuiltInToolTrait,
+    ToolExample,
     ToolExecutionError,
+    ToolExecutionOutput,
+    ToolExecutionOutputItem,
     ToolExecutionResult,
+    ToolLocale,
 };
-use crate::agent::util::path::canonicalize_path;
+use crate::agent::agent_config::definitions::RmSettings;
+use crate::agent::util::path::{
+    canonicalize_parent_sys,
+    canonicalize_path_sys,
+};
+use crate::agent::util::providers::SystemProvider;
 
-pub const RM_TOOL_DESCRIPTION: &str = r#"
+const RM_TOOL_DESCRIPTION: &str = r#"
 A tool for removing files and directories.
 
 WHEN TO USE THIS TOOL:
 - Use when you need to remove files or directories
 
 HOW TO USE:
-- Provide the path for the directory to be created
-- Parent directories will be created if they don't already exist
+- Provide the path to the file or directory to remove, or set glob to true to remove every path matched by a glob pattern
+- Set recursive to true to remove directories along with their contents
+- Removing a non-empty directory, or more than 10 paths matched by a glob pattern, requires setting confirmNonEmpty to true
+- Set dryRun to true to list the paths that would be removed without removing them
+
+LIMITATIONS:
+- The filesystem root and paths outside of the current working directory cannot be removed
 
 TIPS:
-- Use the ls tool
+- Use the ls tool to check the contents of a directory before removing it
+- Use dryRun to check which paths a glob pattern matches before removing them
+"#;
+
+const RM_TOOL_DESCRIPTION_ZH_CN: &str = r#"
+用于删除文件和目录的工具。
+
+何时使用此工具：
+- 需要删除文件或目录时使用
+
+使用方法：
+- 提供要删除的文件或目录的路径，或将 glob 设置为 true 以删除 glob 模式匹配的所有路径
+- 将 recursive 设置为 true，以连同内容一起删除目录
+- 删除非空目录，或删除 glob 模式匹配的超过 10 个路径时，需要将 confirmNonEmpty 设置为 true
+- 将 dryRun 设置为 true，可列出将被删除的路径而不实际删除
+
+限制：
+- 无法删除文件系统根目录以及当前工作目录之外的路径
+
+提示：
+- 删除目录前，请使用 ls 工具检查其内容
+- 删除前，请使用 dryRun 检查 glob 模式匹配了哪些路径
+"#;
+
+const RM_TOOL_DESCRIPTION_JA: &str = r#"
+ファイルやディレクトリを削除するためのツールです。
+
+このツールを使用する場面:
+- ファイルやディレクトリを削除する必要がある場合に使用します
+
+使い方:
+- 削除するファイルまたはディレクトリのパスを指定します。glob パターンに一致するすべてのパスを削除するには glob を true に設定します
+- ディレクトリをその内容ごと削除するには recursive を true に設定します
+- 空でないディレクトリ、または glob パターンに一致する 10 件を超えるパスを削除するには、confirmNonEmpty を true に設定する必要があります
+- 削除せずに削除対象のパスを一覧表示するには dryRun を true に設定します
+
+制限事項:
+- ファイルシステムのルートと、現在の作業ディレクトリの外にあるパスは削除できません
+
+ヒント:
+- ディレクトリを削除する前に、ls ツールでその内容を確認してください
+- 削除する前に、dryRun で glob パターンに一致するパスを確認してください
 "#;
 
 const RM_SCHEMA: &str = r#"
@@ -32,8 +90,28 @@ const RM_SCHEMA: &str = r#"
     "type": "object",
     "properties": {
         "path": {
-            "description": "Path to the file or directory",
+            "description": "Path to the file or directory, or a glob pattern if glob is true",
             "type": "string"
+        },
+        "glob": {
+            "description": "Whether or not path is a glob pattern matching the paths to remove",
+            "type": "boolean",
+            "default": false
+        },
+        "recursive": {
+            "description": "Whether or not to remove directories and their contents",
+            "type": "boolean",
+            "default": false
+        },
+        "confirmNonEmpty": {
+            "description": "Must be true to remove non-empty directories, or more than 10 paths matched by a glob pattern",
+            "type": "boolean",
+            "default": false
+        },
+        "dryRun": {
+            "description": "Whether or not to only list the paths that would be removed, without removing them",
+            "type": "boolean",
+            "default": false
         }
     },
     "required": [
@@ -42,41 +120,467 @@ const RM_SCHEMA: &str = r#"
 }
 "#;
 
+/// The max number of paths a glob pattern can match before requiring confirmation.
+const MAX_UNCONFIRMED_GLOB_MATCHES: usize = 10;
+
+impl BuiltInToolTrait for Rm {
+    fn name() -> BuiltInToolName {
+        BuiltInToolName::Rm
+    }
+
+    fn description() -> std::borrow::Cow<'static, str> {
+        RM_TOOL_DESCRIPTION.into()
+    }
+
+    fn description_for_locale(locale: &str) -> std::borrow::Cow<'static, str> {
+        match ToolLocale::parse(locale) {
+            Some(ToolLocale::ZhCn) => RM_TOOL_DESCRIPTION_ZH_CN.into(),
+            Some(ToolLocale::Ja) => RM_TOOL_DESCRIPTION_JA.into(),
+            None => Self::description(),
+        }
+    }
+
+    fn input_schema() -> std::borrow::Cow<'static, str> {
+        RM_SCHEMA.into()
+    }
+
+    fn examples() -> Vec<ToolExample> {
+        vec![ToolExample::new(
+            "List the log files that would be removed by a glob pattern",
+            serde_json::json!({ "path": "logs/*.log", "glob": true, "dryRun": true }),
+        )]
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Rm {
-    path: String,
+    pub path: String,
+    /// Whether [Rm::path] is a glob pattern. Otherwise, glob metacharacters such as `[` are
+    /// matched literally.
+    #[serde(default)]
+    glob: bool,
+    #[serde(default)]
+    recursive: bool,
+    /// Required to remove non-empty directories, or more than [MAX_UNCONFIRMED_GLOB_MATCHES]
+    /// paths matched by a glob pattern.
+    confirm_non_empty: Option<bool>,
+    /// When true, the paths that would be removed are returned instead of being removed.
+    #[serde(default)]
+    dry_run: bool,
 }
 
 impl Rm {
-    fn canonical_path(&self) -> Result<PathBuf, String> {
-        Ok(PathBuf::from(canonicalize_path(&self.path).map_err(|e| e.to_string())?))
+    fn confirm_non_empty(&self) -> bool {
+        self.confirm_non_empty.unwrap_or(false)
     }
 
-    pub async fn validate(&self) -> Result<(), String> {
+    fn is_glob(&self) -> bool {
+        self.glob
+    }
+
+    pub async fn validate<P: SystemProvider>(&self, provider: &P, settings: Option<&RmSettings>) -> Result<(), String> {
         if self.path.is_empty() {
             return Err("Path must not be empty".to_string());
         }
 
-        let path = self.canonical_path()?;
-        if path.exists() {
-            let Ok(file_md) = tokio::fs::symlink_metadata(&path).await else {
-                return Err(format!("A file at {} already exists", self.path));
-            };
-            if file_md.is_dir() {
-                return Err(format!("A directory at {} already exists", self.path));
-            } else {
-                return Err(format!("A file at {} already exists", self.path));
+        // A symlink at the final component is not resolved, so that the link is removed rather
+        // than its target.
+        let path = PathBuf::from(canonicalize_parent_sys(&self.path, provider).map_err(|e| e.to_string())?);
+        if path.parent().is_none() {
+            return Err("The filesystem root cannot be removed".to_string());
+        }
+        check_inside_base_dir(&self.path, &path, &base_dir(provider, settings)?)?;
+        if self.is_glob() {
+            glob::Pattern::new(&path.to_string_lossy()).map_err(|e| format!("Invalid glob pattern: {}", e))?;
+        } else if tokio::fs::symlink_metadata(&path).await.is_err() {
+            if self.path.contains(['*', '?', '[']) {
+                return Err(format!(
+                    "No file or directory at {} exists. Set glob=true to match it as a glob pattern",
+                    self.path
+                ));
             }
+            return Err(format!("No file or directory at {} exists", self.path));
         }
 
         Ok(())
     }
 
-    pub async fn execute(&self) -> ToolExecutionResult {
-        let path = self.canonical_path()?;
-        tokio::fs::create_dir_all(&path)
+    pub async fn execute<P: SystemProvider>(&self, provider: &P, settings: Option<&RmSettings>) -> ToolExecutionResult {
+        let paths = self.resolve_paths(provider, settings)?;
+        // The confirmation guards don't apply to dry runs, so that they can be used to check what
+        // would be removed.
+        let confirmed = self.confirm_non_empty() || self.dry_run;
+        if self.is_glob() && paths.len() > MAX_UNCONFIRMED_GLOB_MATCHES && !confirmed {
+            return Err(ToolExecutionError::Custom(format!(
+                "Glob pattern matches {} paths. Set confirmNonEmpty=true to proceed",
+                paths.len()
+            )));
+        }
+
+        // Check every path before removing anything so that a failed guard doesn't leave a
+        // partial removal behind.
+        let mut to_remove = Vec::new();
+        for path in paths {
+            let md = tokio::fs::symlink_metadata(&path)
+                .await
+                .map_err(|e| ToolExecutionError::from_io_result("failed to get metadata", Some(&path), e))?;
+            if md.is_dir() {
+                if !self.recursive {
+                    return Err(ToolExecutionError::Custom(format!(
+                        "{} is a directory. Set recursive=true to remove it",
+                        path.to_string_lossy()
+                    )));
+                }
+                if !confirmed && !is_empty_dir(&path).await? {
+                    return Err(ToolExecutionError::Custom(
+                        "Directory is non-empty. Set confirmNonEmpty=true to proceed".to_string(),
+                    ));
+                }
+            }
+            to_remove.push((path, md.is_dir()));
+        }
+
+        if self.dry_run {
+            let mut lines = vec![format!(
+                "Would remove {} {}",
+                to_remove.len(),
+                if to_remove.len() == 1 { "path" } else { "paths" }
+            )];
+            lines.extend(to_remove.iter().map(|(path, _)| path.to_string_lossy().to_string()));
+            return Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Text(
+                lines.join("\n"),
+            )]));
+        }
+
+        for (path, is_dir) in &to_remove {
+            let res = if *is_dir {
+                tokio::fs::remove_dir_all(path).await
+            } else {
+                tokio::fs::remove_file(path).await
+            };
+            res.map_err(|e| ToolExecutionError::from_io_result("failed to remove", Some(path), e))?;
+        }
+
+        Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Text(format!(
+            "Removed {} {}",
+            to_remove.len(),
+            if to_remove.len() == 1 { "path" } else { "paths" }
+        ))]))
+    }
+
+    /// Returns the paths to remove, expanding [Rm::path] if it is a glob pattern.
+    ///
+    /// Glob patterns follow symlinked directories, so every match is canonicalized and checked
+    /// against the base directory again.
+    fn resolve_paths<P: SystemProvider>(
+        &self,
+        provider: &P,
+        settings: Option<&RmSettings>,
+    ) -> Result<Vec<PathBuf>, ToolExecutionError> {
+        let path = PathBuf::from(canonicalize_parent_sys(&self.path, provider).map_err(|e| e.to_string())?);
+        let base_dir = base_dir(provider, settings)?;
+        if !self.is_glob() {
+            check_inside_base_dir(&self.path, &path, &base_dir)?;
+            return Ok(vec![path]);
+        }
+        let mut paths = Vec::new();
+        for entry in glob::glob(&path.to_string_lossy())
+            .map_err(|e| ToolExecutionError::Custom(format!("Invalid glob pattern: {}", e)))?
+        {
+            let entry = entry.map_err(|e| ToolExecutionError::Custom(e.to_string()))?;
+            let entry = entry.to_string_lossy();
+            let path = PathBuf::from(canonicalize_parent_sys(&entry, provider).map_err(|e| e.to_string())?);
+            check_inside_base_dir(&entry, &path, &base_dir)?;
+            paths.push(path);
+        }
+        if paths.is_empty() {
+            return Err(ToolExecutionError::Custom(format!(
+                "No paths matched pattern {}",
+                self.path
+            )));
+        }
+        Ok(paths)
+    }
+}
+
+/// Checks that `path`, the canonical form of `input`, is inside of but not equal to `base_dir`.
+fn check_inside_base_dir(input: &str, path: &Path, base_dir: &Path) -> Result<(), String> {
+    if !path.starts_with(base_dir) || path == base_dir {
+        return Err(format!(
+            "{} resolves to {}, which is not inside of {}",
+            input,
+            path.to_string_lossy(),
+            base_dir.to_string_lossy()
+        ));
+    }
+    Ok(())
+}
+
+/// The directory that removed paths must be inside of, defaulting to the current working
+/// directory.
+fn base_dir<P: SystemProvider>(provider: &P, settings: Option<&RmSettings>) -> Result<PathBuf, String> {
+    let base_dir = match settings.and_then(|s| s.base_dir.as_deref()) {
+        Some(base_dir) => base_dir.to_string(),
+        None => provider
+            .cwd()
+            .map_err(|e| format!("failed to get the current directory: {}", e))?
+            .to_string_lossy()
+            .to_string(),
+    };
+    Ok(PathBuf::from(
+        canonicalize_path_sys(base_dir, provider).map_err(|e| e.to_string())?,
+    ))
+}
+
+async fn is_empty_dir(path: &Path) -> Result<bool, ToolExecutionError> {
+    let mut read_dir = tokio::fs::read_dir(path)
+        .await
+        .map_err(|e| ToolExecutionError::from_io_result("failed to read directory", Some(path), e))?;
+    Ok(read_dir
+        .next_entry()
+        .await
+        .map_err(|e| ToolExecutionError::from_io_result("failed to read directory", Some(path), e))?
+        .is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test::TestBase;
+
+    fn rm(test_base: &TestBase, path: &str, recursive: bool, confirm_non_empty: bool) -> Rm {
+        Rm {
+            path: test_base.join(path).to_string_lossy().to_string(),
+            glob: false,
+            recursive,
+            confirm_non_empty: Some(confirm_non_empty),
+            dry_run: false,
+        }
+    }
+
+    fn rm_glob(test_base: &TestBase, pattern: &str, recursive: bool, confirm_non_empty: bool) -> Rm {
+        Rm {
+            glob: true,
+            ..rm(test_base, pattern, recursive, confirm_non_empty)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rm_non_empty_directory() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("file.txt", "hello"))
+            .await
+            .with_file(("dir/nested.txt", "nested"))
+            .await;
+        tokio::fs::create_dir(test_base.join("empty")).await.unwrap();
+
+        rm(&test_base, "file.txt", false, false)
+            .execute(&test_base, None)
+            .await
+            .unwrap();
+        assert!(!test_base.join("file.txt").exists());
+
+        assert!(
+            rm(&test_base, "dir", false, false)
+                .execute(&test_base, None)
+                .await
+                .is_err()
+        );
+        let err = rm(&test_base, "dir", true, false)
+            .execute(&test_base, None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Directory is non-empty. Set confirmNonEmpty=true to proceed"
+        );
+        assert!(test_base.join("dir/nested.txt").exists());
+
+        rm(&test_base, "empty", true, false)
+            .execute(&test_base, None)
+            .await
+            .unwrap();
+        rm(&test_base, "dir", true, true)
+            .execute(&test_base, None)
+            .await
+            .unwrap();
+        assert!(!test_base.join("empty").exists());
+        assert!(!test_base.join("dir").exists());
+        assert!(
+            rm(&test_base, "dir", true, true)
+                .validate(&test_base, None)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rm_glob_guard() {
+        let mut test_base = TestBase::new().await;
+        for i in 0..12 {
+            test_base = test_base.with_file((format!("logs/{}.log", i), "log")).await;
+        }
+        test_base = test_base.with_file(("logs/keep.txt", "keep")).await;
+
+        let tool = rm_glob(&test_base, "logs/*.log", false, false);
+        tool.validate(&test_base, None).await.unwrap();
+        let err = tool.execute(&test_base, None).await.unwrap_err();
+        assert!(err.to_string().contains("matches 12 paths"), "{}", err);
+        assert!(test_base.join("logs/0.log").exists());
+
+        rm_glob(&test_base, "logs/1?.log", false, false)
+            .execute(&test_base, None)
+            .await
+            .unwrap();
+        assert!(!test_base.join("logs/10.log").exists());
+
+        rm_glob(&test_base, "logs/*.log", false, true)
+            .execute(&test_base, None)
+            .await
+            .unwrap();
+        assert!(!test_base.join("logs/0.log").exists());
+        assert!(test_base.join("logs/keep.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_rm_dry_run() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("dir/a.txt", "a"))
+            .await
+            .with_file(("dir/b.txt", "b"))
+            .await;
+
+        let tool: Rm = serde_json::from_value(serde_json::json!({
+            "path": test_base.join("dir/*.txt"),
+            "glob": true,
+            "confirmNonEmpty": false,
+            "dryRun": true,
+        }))
+        .unwrap();
+        assert!(tool.dry_run);
+        let output = tool.execute(&test_base, None).await.unwrap();
+        assert_eq!(
+            output.all_text(),
+            format!(
+                "Would remove 2 paths\n{}\n{}",
+                test_base.join("dir/a.txt").to_string_lossy(),
+                test_base.join("dir/b.txt").to_string_lossy()
+            )
+        );
+        assert!(test_base.join("dir/a.txt").exists());
+
+        // Confirmation is not required for a dry run, but recursive still is.
+        let mut tool = rm(&test_base, "dir", false, false);
+        tool.dry_run = true;
+        assert!(tool.execute(&test_base, None).await.is_err());
+        tool.recursive = true;
+        tool.execute(&test_base, None).await.unwrap();
+        assert!(test_base.join("dir/b.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_rm_glob_no_matches() {
+        let test_base = TestBase::new().await.with_file(("file.txt", "keep")).await;
+
+        let err = rm_glob(&test_base, "*.log", false, false)
+            .execute(&test_base, None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No paths matched pattern"), "{}", err);
+        assert!(test_base.join("file.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_rm_literal_glob_characters() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("file[1].txt", "literal"))
+            .await
+            .with_file(("file1.txt", "keep"))
+            .await;
+
+        // Without glob, metacharacters are matched literally.
+        let tool = rm(&test_base, "file[1].txt", false, false);
+        tool.validate(&test_base, None).await.unwrap();
+        tool.execute(&test_base, None).await.unwrap();
+        assert!(!test_base.join("file[1].txt").exists());
+        assert!(test_base.join("file1.txt").exists());
+
+        let err = rm(&test_base, "*.txt", false, false)
+            .validate(&test_base, None)
             .await
-            .map_err(|e| ToolExecutionError::io(format!("failed to create directory {}", path.to_string_lossy()), e))?;
-        Ok(Default::default())
+            .unwrap_err();
+        assert!(err.contains("Set glob=true"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_rm_symlink_to_directory() {
+        let test_base = TestBase::new().await.with_file(("target/file.txt", "keep")).await;
+        std::os::unix::fs::symlink(test_base.join("target"), test_base.join("link")).unwrap();
+
+        let tool = rm(&test_base, "link", true, true);
+        tool.validate(&test_base, None).await.unwrap();
+        tool.execute(&test_base, None).await.unwrap();
+        assert!(tokio::fs::symlink_metadata(test_base.join("link")).await.is_err());
+        assert!(test_base.join("target/file.txt").exists());
+
+        // Links found by a glob pattern are removed in the same way.
+        std::os::unix::fs::symlink(test_base.join("target"), test_base.join("link")).unwrap();
+        rm_glob(&test_base, "lin*", true, true)
+            .execute(&test_base, None)
+            .await
+            .unwrap();
+        assert!(tokio::fs::symlink_metadata(test_base.join("link")).await.is_err());
+        assert!(test_base.join("target/file.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_rm_glob_symlinked_directory() {
+        let outside = tempfile::tempdir().unwrap();
+        tokio::fs::write(outside.path().join("x.txt"), "keep").await.unwrap();
+        let test_base = TestBase::new().await.with_file(("dir/x.txt", "x")).await;
+        std::os::unix::fs::symlink(outside.path(), test_base.join("link")).unwrap();
+
+        // The pattern is inside of the base directory, but one of its matches is not.
+        let tool = rm_glob(&test_base, "*/x.txt", false, true);
+        tool.validate(&test_base, None).await.unwrap();
+        let err = tool.execute(&test_base, None).await.unwrap_err();
+        assert!(err.to_string().contains("not inside of"), "{}", err);
+        assert!(outside.path().join("x.txt").exists());
+        assert!(test_base.join("dir/x.txt").exists());
+
+        let mut tool = rm_glob(&test_base, "link/*", false, true);
+        tool.dry_run = true;
+        assert!(tool.execute(&test_base, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rm_validate_base_dir() {
+        let test_base = TestBase::new().await.with_file(("dir/a.txt", "a")).await;
+
+        let tool = |path: &str| Rm {
+            path: path.to_string(),
+            glob: false,
+            recursive: true,
+            confirm_non_empty: Some(true),
+            dry_run: false,
+        };
+        tool("dir/a.txt").validate(&test_base, None).await.unwrap();
+        assert!(tool("/").validate(&test_base, None).await.is_err());
+        assert!(tool("..").validate(&test_base, None).await.is_err());
+        assert!(tool(".").validate(&test_base, None).await.is_err());
+        assert!(tool("dir/../../outside").validate(&test_base, None).await.is_err());
+
+        let settings = RmSettings {
+            base_dir: Some(test_base.join("dir").to_string_lossy().to_string()),
+        };
+        tool("dir/a.txt").validate(&test_base, Some(&settings)).await.unwrap();
+        let err = tool("dir").validate(&test_base, Some(&settings)).await.unwrap_err();
+        assert!(err.contains("not inside of"), "{}", err);
     }
 }
@@ -0,0 +1,437 @@
+use std::collections::{
+    BTreeMap,
+    HashMap,
+};
+use std::path::PathBuf;
+use std::sync::{
+    LazyLock,
+    OnceLock,
+};
+
+use regex::Regex;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::{
+    BuiltInToolName,
+    BuiltInToolTrait,
+    ToolExecutionError,
+    ToolExecutionOutput,
+    ToolExecutionOutputItem,
+    ToolExecutionResult,
+    ToolLocale,
+};
+use crate::util::path::canonicalize_path_sys;
+use crate::util::providers::SystemProvider;
+
+const SPELL_CHECK_TOOL_DESCRIPTION: &str = r#"
+A tool for finding misspelled words in a text file.
+
+WHEN TO USE THIS TOOL:
+- Use after editing documentation, README files, or commit messages
+
+HOW TO USE:
+- Provide the path to the file to check
+- Optionally provide a language, defaults to "en"
+
+LIMITATIONS:
+- Only English ("en") is supported
+- Only text files are supported
+- Words containing digits or uppercase letters after the first character are ignored
+- Only the first 500 misspelled words are returned
+"#;
+
+const SPELL_CHECK_TOOL_DESCRIPTION_ZH_CN: &str = r#"
+用于查找文本文件中拼写错误单词的工具。
+
+何时使用此工具：
+- 在编辑文档、README 文件或提交信息后使用
+
+使用方法：
+- 提供要检查的文件路径
+- 可选地提供语言，默认为 "en"
+
+限制：
+- 仅支持英语（"en"）
+- 仅支持文本文件
+- 包含数字或在首字母之后包含大写字母的单词会被忽略
+- 仅返回前 500 个拼写错误的单词
+"#;
+
+const SPELL_CHECK_TOOL_DESCRIPTION_JA: &str = r#"
+テキストファイル内のスペルミスの単語を見つけるためのツールです。
+
+このツールを使用する場面:
+- ドキュメント、README ファイル、コミットメッセージを編集した後に使用します
+
+使い方:
+- チェックするファイルのパスを指定します
+- 必要に応じて言語を指定します。デフォルトは "en" です
+
+制限事項:
+- 英語 ("en") のみサポートされています
+- テキストファイルのみサポートされています
+- 数字を含む単語や、先頭以外に大文字を含む単語は無視されます
+- 返されるのは最初の 500 個のスペルミスのみです
+"#;
+
+const SPELL_CHECK_SCHEMA: &str = r#"
+{
+    "type": "object",
+    "properties": {
+        "path": {
+            "type": "string",
+            "description": "Path to the file to spell check"
+        },
+        "language": {
+            "type": "string",
+            "description": "Language of the file content",
+            "enum": ["en"],
+            "default": "en"
+        }
+    },
+    "required": [
+        "path"
+    ]
+}
+"#;
+
+/// Max number of misspelled words to return to the model.
+const MAX_MISSPELLINGS: usize = 500;
+
+/// Max number of suggestions to return per misspelled word.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Max edit distance for a dictionary word to be considered a suggestion.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// Max file size that will be checked.
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Suffixes of contractions whose stems are checked against the dictionary.
+const CONTRACTION_SUFFIXES: &[&str] = &["'s", "n't", "'re", "'ve", "'ll", "'d", "'m"];
+
+/// Matches words, including contractions such as `aren't`.
+static WORD_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[A-Za-z]+(?:'[A-Za-z]+)*").unwrap());
+
+/// Built-in word list for English, ordered from most to least frequent. See
+/// `dictionaries/LICENSE` for its source.
+const WORDS_EN: &str = include_str!("dictionaries/en.txt");
+
+impl BuiltInToolTrait for SpellCheck {
+    fn name() -> BuiltInToolName {
+        BuiltInToolName::SpellCheck
+    }
+
+    fn description() -> std::borrow::Cow<'static, str> {
+        SPELL_CHECK_TOOL_DESCRIPTION.into()
+    }
+
+    fn description_for_locale(locale: &str) -> std::borrow::Cow<'static, str> {
+        match ToolLocale::parse(locale) {
+            Some(ToolLocale::ZhCn) => SPELL_CHECK_TOOL_DESCRIPTION_ZH_CN.into(),
+            Some(ToolLocale::Ja) => SPELL_CHECK_TOOL_DESCRIPTION_JA.into(),
+            None => Self::description(),
+        }
+    }
+
+    fn input_schema() -> std::borrow::Cow<'static, str> {
+        SPELL_CHECK_SCHEMA.into()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpellCheck {
+    pub path: String,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Misspelling {
+    pub word: String,
+    pub line: usize,
+    pub suggestions: Vec<String>,
+}
+
+impl SpellCheck {
+    const DEFAULT_LANGUAGE: &str = "en";
+
+    fn language(&self) -> &str {
+        self.language.as_deref().unwrap_or(Self::DEFAULT_LANGUAGE)
+    }
+
+    pub async fn validate<P: SystemProvider>(&self, provider: &P) -> Result<(), String> {
+        let path = self.canonical_path(provider)?;
+        let md = tokio::fs::metadata(&path).await.map_err(|e| {
+            format!(
+                "failed to check file metadata for path '{}': {}",
+                path.to_string_lossy(),
+                e
+            )
+        })?;
+        if !md.is_file() {
+            return Err(format!("'{}' is not a file", path.to_string_lossy()));
+        }
+        if md.len() > MAX_FILE_SIZE {
+            return Err(format!(
+                "'{}' is too large to spell check ({} bytes, max {} bytes)",
+                path.to_string_lossy(),
+                md.len(),
+                MAX_FILE_SIZE
+            ));
+        }
+        dictionary(self.language())?;
+        Ok(())
+    }
+
+    pub async fn execute<P: SystemProvider>(&self, provider: &P) -> ToolExecutionResult {
+        let path = self.canonical_path(provider)?;
+        let content = tokio::fs::read(&path)
+            .await
+            .map_err(|e| ToolExecutionError::from_io_result("failed to read", Some(&path), e))?;
+        if is_binary(&content) {
+            return Err(ToolExecutionError::Custom(format!(
+                "'{}' appears to be a binary file",
+                path.to_string_lossy()
+            )));
+        }
+        let content = String::from_utf8_lossy(&content).into_owned();
+
+        // Parsing the dictionary and computing suggestions is CPU bound.
+        let language = self.language().to_string();
+        let misspellings =
+            tokio::task::spawn_blocking(move || dictionary(&language).map(|dictionary| dictionary.check(&content)))
+                .await
+                .map_err(|e| ToolExecutionError::Custom(format!("failed to spell check: {}", e)))??;
+        let result = serde_json::to_value(misspellings).map_err(|e| ToolExecutionError::Custom(e.to_string()))?;
+        Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Json(result)]))
+    }
+
+    fn canonical_path<P: SystemProvider>(&self, provider: &P) -> Result<PathBuf, String> {
+        Ok(PathBuf::from(
+            canonicalize_path_sys(&self.path, provider).map_err(|e| e.to_string())?,
+        ))
+    }
+}
+
+/// Returns the built-in dictionary for `language`, parsing it on first use.
+fn dictionary(language: &str) -> Result<&'static Dictionary, String> {
+    static EN: OnceLock<Dictionary> = OnceLock::new();
+    match language {
+        "en" => Ok(EN.get_or_init(|| Dictionary::new(WORDS_EN))),
+        other => Err(format!("Unsupported language '{}', expected one of: en", other)),
+    }
+}
+
+/// Uses the same heuristic as git - a file is binary if it contains a NUL byte within the first
+/// 8000 bytes.
+fn is_binary(content: &[u8]) -> bool {
+    content.iter().take(8000).any(|b| *b == 0)
+}
+
+struct Dictionary {
+    /// Words mapped to their position in the word list, used to rank suggestions so that more
+    /// frequent words come first.
+    words: HashMap<String, usize>,
+    /// Words and their rank grouped by first letter and length, so that suggestions are only
+    /// computed against words of a similar length.
+    candidates: HashMap<(char, usize), Vec<(usize, String)>>,
+}
+
+impl Dictionary {
+    /// Creates a dictionary from a newline separated list of words, ordered from most to least
+    /// frequent.
+    fn new(word_list: &str) -> Self {
+        let mut words = HashMap::new();
+        for word in word_list
+            .lines()
+            .map(|w| w.trim().to_lowercase())
+            .filter(|w| !w.is_empty())
+        {
+            let rank = words.len();
+            words.entry(word).or_insert(rank);
+        }
+        let mut candidates: HashMap<(char, usize), Vec<(usize, String)>> = HashMap::new();
+        for (word, rank) in &words {
+            if let Some(first) = word.chars().next() {
+                candidates
+                    .entry((first, word.chars().count()))
+                    .or_default()
+                    .push((*rank, word.clone()));
+            }
+        }
+        Self { words, candidates }
+    }
+
+    /// Whether `word` is in the dictionary, either directly or as a contraction of a word in the
+    /// dictionary, e.g. `aren't` or `you'll`.
+    fn contains(&self, word: &str) -> bool {
+        let word = word.to_lowercase();
+        self.words.contains_key(&word)
+            || CONTRACTION_SUFFIXES
+                .iter()
+                .any(|suffix| word.strip_suffix(suffix).is_some_and(|w| self.words.contains_key(w)))
+    }
+
+    fn check(&self, content: &str) -> Vec<Misspelling> {
+        let mut suggestions_cache: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut misspellings = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            for m in WORD_REGEX.find_iter(line) {
+                let word = m.as_str();
+                if !Self::should_check(line, m.start(), m.end()) || self.contains(word) {
+                    continue;
+                }
+                let suggestions = suggestions_cache
+                    .entry(word.to_lowercase())
+                    .or_insert_with_key(|w| self.suggestions(w))
+                    .clone();
+                misspellings.push(Misspelling {
+                    word: word.to_string(),
+                    line: i + 1,
+                    suggestions,
+                });
+                if misspellings.len() >= MAX_MISSPELLINGS {
+                    return misspellings;
+                }
+            }
+        }
+        misspellings
+    }
+
+    /// Skips single letters, acronyms, camelCase identifiers, and words that are part of a larger
+    /// token containing digits or underscores (e.g. `foo_bar`, `v2beta`).
+    fn should_check(line: &str, start: usize, end: usize) -> bool {
+        let word = &line[start..end];
+        if word.len() < 2 || word.chars().skip(1).any(|c| c.is_uppercase()) {
+            return false;
+        }
+        let is_ident_char = |c: char| c.is_ascii_digit() || c == '_';
+        let before = line[..start].chars().next_back();
+        let after = line[end..].chars().next();
+        !before.is_some_and(is_ident_char) && !after.is_some_and(is_ident_char)
+    }
+
+    /// Returns the most frequent dictionary words closest to `word`. Only words sharing the first
+    /// letter of `word` are considered.
+    fn suggestions(&self, word: &str) -> Vec<String> {
+        let Some(first) = word.chars().next() else {
+            return Vec::new();
+        };
+        let len = word.chars().count();
+        let mut candidates = (len.saturating_sub(MAX_SUGGESTION_DISTANCE)..=len + MAX_SUGGESTION_DISTANCE)
+            .filter_map(|len| self.candidates.get(&(first, len)))
+            .flatten()
+            .filter_map(|(rank, w)| {
+                let distance = edit_distance(word, w);
+                (distance <= MAX_SUGGESTION_DISTANCE).then_some((distance, *rank, w))
+            })
+            .collect::<Vec<_>>();
+        candidates.sort();
+        candidates
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, _, w)| w.clone())
+            .collect()
+    }
+}
+
+/// Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test::TestBase;
+
+    const WORDS: &str = "hello\nworld\nthe\nquick\nbrown\nfox\njumps\nover\nlazy\ndog\nword\n";
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+
+    #[test]
+    fn test_dictionary_check() {
+        let dictionary = Dictionary::new(WORDS);
+        let misspellings =
+            dictionary.check("Hello wrold!\nThe quikc brown fox_2 jumps over the lazy dog. HTTP someVar\n");
+        assert_eq!(misspellings, vec![
+            Misspelling {
+                word: "wrold".to_string(),
+                line: 1,
+                // Suggestions with the same distance are ordered by their position in the word list.
+                suggestions: vec!["world".to_string(), "word".to_string()],
+            },
+            Misspelling {
+                word: "quikc".to_string(),
+                line: 2,
+                suggestions: vec!["quick".to_string()],
+            },
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_spell_check() {
+        let test_base = TestBase::new()
+            .await
+            .with_file((
+                "doc.md",
+                "Spelling mistakes aren't hard to find.\nThe documentation was recieved yesterday, it's great.\n",
+            ))
+            .await
+            .with_file(("binary", "abc\0def"))
+            .await;
+
+        let tool = SpellCheck {
+            path: test_base.join("doc.md").to_string_lossy().to_string(),
+            language: None,
+        };
+        tool.validate(&test_base).await.unwrap();
+        let output = tool.execute(&test_base).await.unwrap();
+        let ToolExecutionOutputItem::Json(result) = &output.items[0] else {
+            panic!("expected json output");
+        };
+        let misspellings: Vec<Misspelling> = serde_json::from_value(result.clone()).unwrap();
+        assert_eq!(misspellings.len(), 1, "{:?}", misspellings);
+        assert_eq!(misspellings[0].word, "recieved");
+        assert_eq!(misspellings[0].line, 2);
+        assert!(
+            misspellings[0].suggestions.contains(&"received".to_string()),
+            "{:?}",
+            misspellings
+        );
+
+        let tool = SpellCheck {
+            path: test_base.join("binary").to_string_lossy().to_string(),
+            language: None,
+        };
+        assert!(tool.execute(&test_base).await.is_err());
+
+        for language in ["xx", "../../etc/passwd"] {
+            let tool = SpellCheck {
+                path: test_base.join("doc.md").to_string_lossy().to_string(),
+                language: Some(language.to_string()),
+            };
+            let err = tool.validate(&test_base).await.unwrap_err();
+            assert!(err.contains("Unsupported language"), "{}", err);
+        }
+    }
+}
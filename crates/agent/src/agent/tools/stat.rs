@@ -0,0 +1,294 @@
+use std::fs::Metadata;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::{
+    BuiltInToolName,
+    BuiltInToolTrait,
+    ToolExecutionError,
+    ToolExecutionOutput,
+    ToolExecutionOutputItem,
+    ToolExecutionResult,
+    ToolLocale,
+};
+use crate::util::path::canonicalize_parent_sys;
+use crate::util::providers::SystemProvider;
+
+const STAT_TOOL_DESCRIPTION: &str = r#"
+A tool for retrieving the metadata of a file, directory, or symbolic link.
+
+WHEN TO USE THIS TOOL:
+- Use instead of running the `stat` command to check a path's type, size, permissions, ownership, or timestamps
+
+HOW TO USE:
+- Provide the path to inspect
+- Symbolic links are not followed; the metadata of the link itself is returned along with its target
+
+LIMITATIONS:
+- permissions, uid, gid, inode, hardlinks, and device are only returned on Unix
+"#;
+
+const STAT_TOOL_DESCRIPTION_ZH_CN: &str = r#"
+用于获取文件、目录或符号链接元数据的工具。
+
+何时使用此工具：
+- 代替运行 `stat` 命令，用于检查路径的类型、大小、权限、所有者或时间戳
+
+使用方法：
+- 提供要检查的路径
+- 不会跟随符号链接；返回链接本身的元数据及其目标
+
+限制：
+- permissions、uid、gid、inode、hardlinks 和 device 仅在 Unix 上返回
+"#;
+
+const STAT_TOOL_DESCRIPTION_JA: &str = r#"
+ファイル、ディレクトリ、またはシンボリックリンクのメタデータを取得するためのツールです。
+
+このツールを使用する場面:
+- パスの種類、サイズ、パーミッション、所有者、タイムスタンプを確認する際に、`stat` コマンドを実行する代わりに使用します
+
+使い方:
+- 確認するパスを指定します
+- シンボリックリンクはたどりません。リンク自体のメタデータとリンク先が返されます
+
+制限事項:
+- permissions、uid、gid、inode、hardlinks、device は Unix でのみ返されます
+"#;
+
+const STAT_SCHEMA: &str = r#"
+{
+    "type": "object",
+    "properties": {
+        "path": {
+            "type": "string",
+            "description": "Path to the file, directory, or symbolic link"
+        }
+    },
+    "required": [
+        "path"
+    ]
+}
+"#;
+
+impl BuiltInToolTrait for Stat {
+    fn name() -> BuiltInToolName {
+        BuiltInToolName::Stat
+    }
+
+    fn description() -> std::borrow::Cow<'static, str> {
+        STAT_TOOL_DESCRIPTION.into()
+    }
+
+    fn description_for_locale(locale: &str) -> std::borrow::Cow<'static, str> {
+        match ToolLocale::parse(locale) {
+            Some(ToolLocale::ZhCn) => STAT_TOOL_DESCRIPTION_ZH_CN.into(),
+            Some(ToolLocale::Ja) => STAT_TOOL_DESCRIPTION_JA.into(),
+            None => Self::description(),
+        }
+    }
+
+    fn input_schema() -> std::borrow::Cow<'static, str> {
+        STAT_SCHEMA.into()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stat {
+    pub path: String,
+}
+
+/// The metadata returned by [Stat]. Fields that are unavailable on the current platform are
+/// omitted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct StatOutput {
+    pub path: String,
+    #[serde(rename = "type")]
+    pub file_type: FileType,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inode: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hardlinks: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accessed: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symlink_target: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileType {
+    File,
+    Directory,
+    Symlink,
+    Other,
+}
+
+impl Stat {
+    pub async fn validate<P: SystemProvider>(&self, provider: &P) -> Result<(), String> {
+        // A symlink at the final component is not resolved, so that the link itself is checked.
+        let path = PathBuf::from(canonicalize_parent_sys(&self.path, provider).map_err(|e| e.to_string())?);
+        if let Err(e) = tokio::fs::symlink_metadata(&path).await {
+            return Err(format!(
+                "failed to check metadata for path '{}': {}",
+                path.to_string_lossy(),
+                e
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn execute<P: SystemProvider>(&self, provider: &P) -> ToolExecutionResult {
+        let path = PathBuf::from(canonicalize_parent_sys(&self.path, provider).map_err(|e| e.to_string())?);
+        let md = tokio::fs::symlink_metadata(&path)
+            .await
+            .map_err(|e| ToolExecutionError::from_io_result("failed to get metadata", Some(&path), e))?;
+        let symlink_target = if md.is_symlink() {
+            let target = tokio::fs::read_link(&path)
+                .await
+                .map_err(|e| ToolExecutionError::from_io_result("failed to read link", Some(&path), e))?;
+            Some(target.to_string_lossy().to_string())
+        } else {
+            None
+        };
+
+        let output = StatOutput::new(path.to_string_lossy().to_string(), &md, symlink_target);
+        let output = serde_json::to_value(output).map_err(|e| ToolExecutionError::Custom(e.to_string()))?;
+        Ok(ToolExecutionOutput::new(vec![ToolExecutionOutputItem::Json(output)]))
+    }
+}
+
+impl StatOutput {
+    fn new(path: String, md: &Metadata, symlink_target: Option<String>) -> Self {
+        let file_type = if md.is_symlink() {
+            FileType::Symlink
+        } else if md.is_dir() {
+            FileType::Directory
+        } else if md.is_file() {
+            FileType::File
+        } else {
+            FileType::Other
+        };
+
+        #[cfg_attr(not(unix), allow(unused_mut))]
+        let mut output = Self {
+            path,
+            file_type,
+            size: md.len(),
+            permissions: None,
+            uid: None,
+            gid: None,
+            inode: None,
+            hardlinks: None,
+            created: md.created().ok().and_then(format_time),
+            modified: md.modified().ok().and_then(format_time),
+            accessed: md.accessed().ok().and_then(format_time),
+            device: None,
+            symlink_target,
+        };
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            output.permissions = Some(format!("{:04o}", md.mode() & 0o7777));
+            output.uid = Some(md.uid());
+            output.gid = Some(md.gid());
+            output.inode = Some(md.ino());
+            output.hardlinks = Some(md.nlink());
+            output.device = Some(md.dev());
+        }
+
+        output
+    }
+}
+
+/// Formats the given time as an RFC 3339 timestamp in UTC.
+fn format_time(time: SystemTime) -> Option<String> {
+    time::OffsetDateTime::from(time)
+        .format(&time::format_description::well_known::Rfc3339)
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test::TestBase;
+
+    async fn stat(test_base: &TestBase, path: &str) -> StatOutput {
+        let tool = Stat {
+            path: test_base.join(path).to_string_lossy().to_string(),
+        };
+        tool.validate(test_base).await.unwrap();
+        let output = tool.execute(test_base).await.unwrap();
+        let ToolExecutionOutputItem::Json(value) = &output.items[0] else {
+            panic!("expected json output");
+        };
+        serde_json::from_value(value.clone()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stat() {
+        let test_base = TestBase::new()
+            .await
+            .with_file(("file.txt", "hello"))
+            .await
+            .with_file(("dir/nested.txt", "nested"))
+            .await;
+
+        let output = stat(&test_base, "file.txt").await;
+        assert_eq!(output.file_type, FileType::File);
+        assert_eq!(output.size, 5);
+        assert!(output.modified.is_some());
+        assert!(output.symlink_target.is_none());
+
+        let output = stat(&test_base, "dir").await;
+        assert_eq!(output.file_type, FileType::Directory);
+
+        let tool = Stat {
+            path: test_base.join("missing.txt").to_string_lossy().to_string(),
+        };
+        assert!(tool.validate(&test_base).await.is_err());
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_stat_unix_fields() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let test_base = TestBase::new().await.with_file(("file.txt", "hello")).await;
+        std::fs::set_permissions(test_base.join("file.txt"), std::fs::Permissions::from_mode(0o640)).unwrap();
+        std::os::unix::fs::symlink(test_base.join("file.txt"), test_base.join("link")).unwrap();
+
+        let output = stat(&test_base, "file.txt").await;
+        assert_eq!(output.permissions.as_deref(), Some("0640"));
+        assert_eq!(output.hardlinks, Some(1));
+        assert!(output.uid.is_some() && output.gid.is_some() && output.inode.is_some());
+
+        let output = stat(&test_base, "link").await;
+        assert_eq!(output.file_type, FileType::Symlink);
+        assert_eq!(
+            output.symlink_target,
+            Some(test_base.join("file.txt").to_string_lossy().to_string())
+        );
+    }
+}
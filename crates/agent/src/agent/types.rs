@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::time::Duration;
 
 use chrono::{
@@ -129,6 +130,16 @@ impl AsRef<str> for ConversationSummary {
 pub struct AgentSettings {
     /// Timeout waiting for MCP servers to initialize during agent initialization.
     pub mcp_init_timeout: Duration,
+    /// Path to persist [ToolState](crate::agent::tools::ToolState) to across sessions.
+    ///
+    /// If set, tool state is loaded from this path when the agent is initialized and saved when
+    /// the agent exits.
+    #[serde(default)]
+    pub tool_state_path: Option<PathBuf>,
+    /// Path to a JSONL file that every tool execution is appended to. Audit logging is disabled
+    /// if unset.
+    #[serde(default)]
+    pub audit_log_path: Option<PathBuf>,
 }
 
 impl AgentSettings {
@@ -139,6 +150,8 @@ impl Default for AgentSettings {
     fn default() -> Self {
         Self {
             mcp_init_timeout: Self::DEFAULT_MCP_INIT_TIMEOUT,
+            tool_state_path: None,
+            audit_log_path: None,
         }
     }
 }
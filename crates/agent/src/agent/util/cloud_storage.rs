@@ -0,0 +1,628 @@
+//! Reading and writing objects in cloud storage, used for `s3://bucket/key` and
+//! `gcs://bucket/key` paths. Writing is only supported for S3.
+//!
+//! Requests are only made when built with the `cloud-storage` feature. Endpoints and credentials
+//! are passed to requests as a [CloudStorageConfig], usually resolved from the environment with
+//! [CloudStorageConfig::from_env] as follows:
+//! - S3: the `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`, and `AWS_SESSION_TOKEN` environment
+//!   variables, otherwise the default AWS credential provider chain, i.e. the shared config and
+//!   credentials files (selected with `AWS_PROFILE`), SSO, and instance metadata. The region is
+//!   taken from `AWS_REGION` or the profile, defaulting to `us-east-1`. Set `AWS_ENDPOINT_URL_S3`
+//!   or `AWS_ENDPOINT_URL` to use an S3 compatible endpoint.
+//! - GCS: an OAuth 2.0 access token from `GOOGLE_OAUTH_ACCESS_TOKEN`, otherwise from `gcloud auth
+//!   print-access-token`. Set `STORAGE_EMULATOR_HOST` to use an emulator, in which case no token is
+//!   required.
+
+use std::fmt;
+
+use aws_credential_types::Credentials;
+
+use super::providers::EnvProvider;
+
+/// Whether or not this build supports reading from cloud storage.
+pub const CLOUD_STORAGE_ENABLED: bool = cfg!(feature = "cloud-storage");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloudProvider {
+    S3,
+    Gcs,
+}
+
+impl fmt::Display for CloudProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloudProvider::S3 => write!(f, "s3"),
+            CloudProvider::Gcs => write!(f, "gcs"),
+        }
+    }
+}
+
+/// The location of an object in cloud storage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloudUri {
+    pub provider: CloudProvider,
+    pub bucket: String,
+    pub key: String,
+}
+
+impl CloudUri {
+    /// Whether `path` uses one of the cloud storage URI schemes, regardless of whether it is a
+    /// valid URI.
+    pub fn is_cloud_uri(path: &str) -> bool {
+        path.starts_with("s3://") || path.starts_with("gcs://")
+    }
+
+    /// Parses `path` as a cloud storage URI, returning `Ok(None)` if it is not one.
+    pub fn parse(path: &str) -> Result<Option<Self>, CloudStorageError> {
+        let (provider, rest) = if let Some(rest) = path.strip_prefix("s3://") {
+            (CloudProvider::S3, rest)
+        } else if let Some(rest) = path.strip_prefix("gcs://") {
+            (CloudProvider::Gcs, rest)
+        } else {
+            return Ok(None);
+        };
+        match rest.split_once('/') {
+            Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => Ok(Some(Self {
+                provider,
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+            })),
+            _ => Err(CloudStorageError::InvalidUri(path.to_string())),
+        }
+    }
+
+    /// Metadata describing where an object was read from, returned alongside its content.
+    pub fn metadata(&self, etag: Option<&str>) -> serde_json::Value {
+        serde_json::json!({
+            "source": self.provider.to_string(),
+            "bucket": self.bucket,
+            "key": self.key,
+            "etag": etag,
+        })
+    }
+}
+
+impl fmt::Display for CloudUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://{}/{}", self.provider, self.bucket, self.key)
+    }
+}
+
+/// An object read from cloud storage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloudObject {
+    pub content: Vec<u8>,
+    pub etag: Option<String>,
+    pub content_type: Option<String>,
+}
+
+/// Endpoints and credentials used for cloud storage requests. Fields that are not set fall back to
+/// the defaults described in the [module docs](self).
+#[derive(Debug, Clone, Default)]
+pub struct CloudStorageConfig {
+    /// An S3 compatible endpoint to use instead of AWS.
+    pub s3_endpoint: Option<String>,
+    pub s3_region: Option<String>,
+    pub s3_credentials: Option<Credentials>,
+    /// A GCS emulator to use instead of Google Cloud, which does not require an access token.
+    pub gcs_endpoint: Option<String>,
+    pub gcs_access_token: Option<String>,
+}
+
+impl CloudStorageConfig {
+    /// Resolves the config from the environment variables of `env`.
+    pub fn from_env(env: &impl EnvProvider) -> Self {
+        let s3_credentials = match (env.var("AWS_ACCESS_KEY_ID"), env.var("AWS_SECRET_ACCESS_KEY")) {
+            (Ok(access_key_id), Ok(secret_access_key)) => Some(Credentials::new(
+                access_key_id,
+                secret_access_key,
+                env.var("AWS_SESSION_TOKEN").ok(),
+                None,
+                "Environment",
+            )),
+            _ => None,
+        };
+        Self {
+            s3_endpoint: env
+                .var("AWS_ENDPOINT_URL_S3")
+                .or_else(|_| env.var("AWS_ENDPOINT_URL"))
+                .ok(),
+            s3_region: env.var("AWS_REGION").ok(),
+            s3_credentials,
+            gcs_endpoint: env.var("STORAGE_EMULATOR_HOST").ok(),
+            gcs_access_token: env.var("GOOGLE_OAUTH_ACCESS_TOKEN").ok(),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CloudStorageError {
+    #[error("'{}' is not a valid cloud storage URI, expected s3://bucket/key or gcs://bucket/key", .0)]
+    InvalidUri(String),
+    #[error("Cloud storage paths are not supported by this build")]
+    Unsupported,
+    #[error("Failed to get credentials: {}", .0)]
+    Credentials(String),
+    #[error("Failed to sign request: {}", .0)]
+    Signing(String),
+    #[error("Request to {uri} failed: {source}")]
+    Request {
+        uri: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("Writing to {} is not supported", .0)]
+    WriteUnsupported(CloudProvider),
+    #[error("{uri} does not exist")]
+    NotFound { uri: String },
+    #[error("{uri} was modified by someone else since it was read")]
+    PreconditionFailed { uri: String },
+    #[error("Request to {uri} failed with status {status}: {body}")]
+    Status { uri: String, status: u16, body: String },
+    #[error("{uri} has size {size} bytes, but the max supported size is {max}")]
+    TooLarge { uri: String, size: u64, max: u64 },
+}
+
+/// Max size of an object read by [get_object] without `max_bytes`.
+pub const MAX_OBJECT_SIZE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Reads the object at `uri`. If `max_bytes` is provided, at most that many bytes are downloaded.
+/// Otherwise, objects larger than [MAX_OBJECT_SIZE_BYTES] fail with
+/// [CloudStorageError::TooLarge].
+#[cfg(feature = "cloud-storage")]
+pub async fn get_object(
+    config: &CloudStorageConfig,
+    uri: &CloudUri,
+    max_bytes: Option<u64>,
+) -> Result<CloudObject, CloudStorageError> {
+    let range = max_bytes
+        .filter(|max| *max > 0)
+        .map(|max| format!("bytes=0-{}", max - 1));
+    let mut headers = Vec::new();
+    if let Some(range) = &range {
+        headers.push(("range", range.as_str()));
+    }
+
+    let request = match uri.provider {
+        CloudProvider::S3 => s3::request(config, reqwest::Method::GET, uri, &headers, &[]).await?,
+        CloudProvider::Gcs => gcs::get_request(config, uri, &headers).await?,
+    };
+    let response = send(uri, request).await?;
+    let etag = header_str(&response, "etag").or_else(|| header_str(&response, "x-goog-generation"));
+    let content_type = header_str(&response, "content-type");
+    let content = read_body(uri, response, max_bytes, MAX_OBJECT_SIZE_BYTES).await?;
+    Ok(CloudObject {
+        content,
+        etag,
+        content_type,
+    })
+}
+
+#[cfg(not(feature = "cloud-storage"))]
+pub async fn get_object(
+    _config: &CloudStorageConfig,
+    _uri: &CloudUri,
+    _max_bytes: Option<u64>,
+) -> Result<CloudObject, CloudStorageError> {
+    Err(CloudStorageError::Unsupported)
+}
+
+/// Writes `content` to the object at `uri`, returning the ETag of the new object.
+///
+/// If `if_match` is provided, the write only succeeds if the current ETag of the object matches
+/// it, otherwise [CloudStorageError::PreconditionFailed] is returned.
+#[cfg(feature = "cloud-storage")]
+pub async fn put_object(
+    config: &CloudStorageConfig,
+    uri: &CloudUri,
+    content: &[u8],
+    content_type: Option<&str>,
+    if_match: Option<&str>,
+) -> Result<Option<String>, CloudStorageError> {
+    if uri.provider != CloudProvider::S3 {
+        return Err(CloudStorageError::WriteUnsupported(uri.provider));
+    }
+    let mut headers = Vec::new();
+    if let Some(content_type) = content_type {
+        headers.push(("content-type", content_type));
+    }
+    if let Some(etag) = if_match {
+        headers.push(("if-match", etag));
+    }
+    let request = s3::request(config, reqwest::Method::PUT, uri, &headers, content).await?;
+    let response = send(uri, request).await?;
+    Ok(header_str(&response, "etag"))
+}
+
+#[cfg(not(feature = "cloud-storage"))]
+pub async fn put_object(
+    _config: &CloudStorageConfig,
+    _uri: &CloudUri,
+    _content: &[u8],
+    _content_type: Option<&str>,
+    _if_match: Option<&str>,
+) -> Result<Option<String>, CloudStorageError> {
+    Err(CloudStorageError::Unsupported)
+}
+
+/// Sends `request`, returning an error for unsuccessful responses.
+#[cfg(feature = "cloud-storage")]
+async fn send(uri: &CloudUri, request: reqwest::RequestBuilder) -> Result<reqwest::Response, CloudStorageError> {
+    let response = request.send().await.map_err(|source| CloudStorageError::Request {
+        uri: uri.to_string(),
+        source,
+    })?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Err(CloudStorageError::NotFound { uri: uri.to_string() });
+    }
+    if status == reqwest::StatusCode::PRECONDITION_FAILED {
+        return Err(CloudStorageError::PreconditionFailed { uri: uri.to_string() });
+    }
+    Err(CloudStorageError::Status {
+        uri: uri.to_string(),
+        status: status.as_u16(),
+        body: response.text().await.unwrap_or_default(),
+    })
+}
+
+/// Reads the body of `response` chunk by chunk, stopping after `max_bytes` if provided, since
+/// servers may ignore the range header. Otherwise, bodies larger than `max_size` are rejected
+/// without being buffered.
+#[cfg(feature = "cloud-storage")]
+async fn read_body(
+    uri: &CloudUri,
+    mut response: reqwest::Response,
+    max_bytes: Option<u64>,
+    max_size: u64,
+) -> Result<Vec<u8>, CloudStorageError> {
+    let too_large = |size: u64| CloudStorageError::TooLarge {
+        uri: uri.to_string(),
+        size,
+        max: max_size,
+    };
+    // Check the advertised size before downloading anything.
+    if let (None, Some(size)) = (max_bytes, response.content_length()) {
+        if size > max_size {
+            return Err(too_large(size));
+        }
+    }
+    let mut content = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|source| CloudStorageError::Request {
+        uri: uri.to_string(),
+        source,
+    })? {
+        content.extend_from_slice(&chunk);
+        match max_bytes {
+            Some(max) if content.len() as u64 >= max => {
+                content.truncate(max as usize);
+                break;
+            },
+            None if content.len() as u64 > max_size => return Err(too_large(content.len() as u64)),
+            _ => (),
+        }
+    }
+    Ok(content)
+}
+
+#[cfg(feature = "cloud-storage")]
+fn header_str(response: &reqwest::Response, name: &str) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from)
+}
+
+/// Percent-encodes each segment of an object key, preserving `/` separators.
+#[cfg(feature = "cloud-storage")]
+fn encode_key(key: &str) -> String {
+    use percent_encoding::{
+        NON_ALPHANUMERIC,
+        utf8_percent_encode,
+    };
+
+    const KEY_SEGMENT: &percent_encoding::AsciiSet =
+        &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+    key.split('/')
+        .map(|segment| utf8_percent_encode(segment, KEY_SEGMENT).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(feature = "cloud-storage")]
+mod s3 {
+    use std::time::SystemTime;
+
+    use aws_credential_types::provider::ProvideCredentials as _;
+    use aws_sigv4::http_request::{
+        PayloadChecksumKind,
+        PercentEncodingMode,
+        SignableBody,
+        SignableRequest,
+        SigningSettings,
+        UriPathNormalizationMode,
+        sign,
+    };
+    use aws_sigv4::sign::v4;
+
+    use super::{
+        CloudStorageConfig,
+        CloudStorageError,
+        CloudUri,
+        encode_key,
+    };
+
+    const DEFAULT_REGION: &str = "us-east-1";
+
+    /// Creates a SigV4 signed request for the object at `uri`.
+    pub(super) async fn request(
+        config: &CloudStorageConfig,
+        method: reqwest::Method,
+        uri: &CloudUri,
+        headers: &[(&str, &str)],
+        body: &[u8],
+    ) -> Result<reqwest::RequestBuilder, CloudStorageError> {
+        let (region, credentials) = match (&config.s3_region, &config.s3_credentials) {
+            (Some(region), Some(credentials)) => (region.clone(), credentials.clone()),
+            (region, credentials) => {
+                let sdk_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+                let region = region.clone().unwrap_or_else(|| {
+                    sdk_config
+                        .region()
+                        .map_or_else(|| DEFAULT_REGION.to_string(), |r| r.to_string())
+                });
+                let credentials = match credentials {
+                    Some(credentials) => credentials.clone(),
+                    None => sdk_config
+                        .credentials_provider()
+                        .ok_or_else(|| {
+                            CloudStorageError::Credentials("no AWS credentials provider is configured".to_string())
+                        })?
+                        .provide_credentials()
+                        .await
+                        .map_err(|e| CloudStorageError::Credentials(e.to_string()))?,
+                };
+                (region, credentials)
+            },
+        };
+
+        let url = object_url(uri, config.s3_endpoint.as_deref(), &region);
+        let identity = credentials.into();
+        let mut settings = SigningSettings::default();
+        // S3 expects the object key to only be encoded once, and not normalized.
+        settings.percent_encoding_mode = PercentEncodingMode::Single;
+        settings.uri_path_normalization_mode = UriPathNormalizationMode::Disabled;
+        settings.payload_checksum_kind = PayloadChecksumKind::XAmzSha256;
+        let params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&region)
+            .name("s3")
+            .time(SystemTime::now())
+            .settings(settings)
+            .build()
+            .map_err(|e| CloudStorageError::Signing(e.to_string()))?
+            .into();
+        let signable = SignableRequest::new(
+            method.as_str(),
+            url.as_str(),
+            headers.iter().copied(),
+            SignableBody::Bytes(body),
+        )
+        .map_err(|e| CloudStorageError::Signing(e.to_string()))?;
+        let (instructions, _) = sign(signable, &params)
+            .map_err(|e| CloudStorageError::Signing(e.to_string()))?
+            .into_parts();
+
+        let mut request = reqwest::Client::new().request(method, url).body(body.to_vec());
+        for (name, value) in headers.iter().copied().chain(instructions.headers()) {
+            request = request.header(name, value);
+        }
+        Ok(request)
+    }
+
+    /// Virtual-hosted style URLs are used for AWS, and path style URLs for custom endpoints and
+    /// bucket names containing dots, which are not valid in TLS certificates for the AWS domain.
+    fn object_url(uri: &CloudUri, endpoint: Option<&str>, region: &str) -> String {
+        let key = encode_key(&uri.key);
+        match endpoint {
+            Some(endpoint) => format!("{}/{}/{}", endpoint.trim_end_matches('/'), uri.bucket, key),
+            None if uri.bucket.contains('.') => format!("https://s3.{}.amazonaws.com/{}/{}", region, uri.bucket, key),
+            None => format!("https://{}.s3.{}.amazonaws.com/{}", uri.bucket, region, key),
+        }
+    }
+}
+
+#[cfg(feature = "cloud-storage")]
+mod gcs {
+    use super::{
+        CloudStorageConfig,
+        CloudStorageError,
+        CloudUri,
+    };
+
+    const DEFAULT_ENDPOINT: &str = "https://storage.googleapis.com";
+
+    /// Creates a request for downloading the object at `uri` with the JSON API.
+    pub(super) async fn get_request(
+        config: &CloudStorageConfig,
+        uri: &CloudUri,
+        headers: &[(&str, &str)],
+    ) -> Result<reqwest::RequestBuilder, CloudStorageError> {
+        let emulator = config.gcs_endpoint.as_deref();
+        let endpoint = emulator.unwrap_or(DEFAULT_ENDPOINT).trim_end_matches('/');
+        // The object name is a single path segment, so slashes must be encoded as well.
+        let name = percent_encoding::utf8_percent_encode(&uri.key, percent_encoding::NON_ALPHANUMERIC);
+        let url = format!("{}/storage/v1/b/{}/o/{}?alt=media", endpoint, uri.bucket, name);
+
+        let mut request = reqwest::Client::new().get(url);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        if emulator.is_none() {
+            let token = match &config.gcs_access_token {
+                Some(token) => token.clone(),
+                None => access_token().await?,
+            };
+            request = request.bearer_auth(token);
+        }
+        Ok(request)
+    }
+
+    /// Gets an access token from the gcloud CLI.
+    async fn access_token() -> Result<String, CloudStorageError> {
+        let output = tokio::process::Command::new("gcloud")
+            .args(["auth", "print-access-token"])
+            .output()
+            .await
+            .map_err(|e| CloudStorageError::Credentials(format!("failed to run gcloud: {}", e)))?;
+        if !output.status.success() {
+            return Err(CloudStorageError::Credentials(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+/// A minimal HTTP server standing in for cloud storage in tests.
+#[cfg(all(test, feature = "cloud-storage"))]
+pub(crate) mod test_server {
+    use tokio::io::{
+        AsyncBufReadExt as _,
+        AsyncReadExt as _,
+        AsyncWriteExt as _,
+        BufReader,
+    };
+    use tokio::task::JoinHandle;
+
+    #[derive(Debug, Clone)]
+    pub struct MockRequest {
+        pub method: String,
+        pub path: String,
+        pub headers: Vec<(String, String)>,
+        pub body: Vec<u8>,
+    }
+
+    impl MockRequest {
+        pub fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct MockResponse {
+        pub status: u16,
+        pub etag: Option<String>,
+        pub body: String,
+    }
+
+    impl MockResponse {
+        pub fn new(status: u16, etag: Option<&str>, body: impl Into<String>) -> Self {
+            Self {
+                status,
+                etag: etag.map(String::from),
+                body: body.into(),
+            }
+        }
+    }
+
+    /// Serves `responses` in order, one per connection, returning the base URL of the server and a
+    /// handle resolving to the received requests.
+    pub async fn serve(responses: Vec<MockResponse>) -> (String, JoinHandle<Vec<MockRequest>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut reader = BufReader::new(&mut stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).await.unwrap();
+                let mut parts = request_line.split_whitespace();
+                let (method, path) = (parts.next().unwrap().to_string(), parts.next().unwrap().to_string());
+                let mut headers = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).await.unwrap();
+                    let Some((name, value)) = line.trim_end().split_once(':') else {
+                        break;
+                    };
+                    headers.push((name.to_string(), value.trim().to_string()));
+                }
+                let mut request = MockRequest {
+                    method,
+                    path,
+                    headers,
+                    body: Vec::new(),
+                };
+                let len = request.header("content-length").map_or(0, |v| v.parse().unwrap());
+                request.body.resize(len, 0);
+                reader.read_exact(&mut request.body).await.unwrap();
+
+                let etag = response
+                    .etag
+                    .map(|etag| format!("ETag: {}\r\n", etag))
+                    .unwrap_or_default();
+                let raw = format!(
+                    "HTTP/1.1 {} Mock\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.status,
+                    etag,
+                    response.body.len(),
+                    response.body
+                );
+                stream.write_all(raw.as_bytes()).await.unwrap();
+                requests.push(request);
+            }
+            requests
+        });
+        (url, handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cloud_uri() {
+        assert_eq!(
+            CloudUri::parse("s3://my-bucket/path/to/file.txt").unwrap(),
+            Some(CloudUri {
+                provider: CloudProvider::S3,
+                bucket: "my-bucket".to_string(),
+                key: "path/to/file.txt".to_string(),
+            })
+        );
+        let uri = CloudUri::parse("gcs://bucket/file.txt").unwrap().unwrap();
+        assert_eq!(uri.provider, CloudProvider::Gcs);
+        assert_eq!(uri.to_string(), "gcs://bucket/file.txt");
+
+        assert_eq!(CloudUri::parse("/tmp/file.txt").unwrap(), None);
+        assert_eq!(CloudUri::parse("s3-notes.txt").unwrap(), None);
+        for invalid in ["s3://bucket", "s3://bucket/", "gcs:///key"] {
+            assert!(CloudUri::parse(invalid).is_err(), "{} should be invalid", invalid);
+        }
+    }
+
+    #[cfg(feature = "cloud-storage")]
+    #[tokio::test]
+    async fn test_read_body() {
+        let uri = CloudUri::parse("s3://bucket/file.txt").unwrap().unwrap();
+        let response = |body: &'static str| reqwest::Response::from(http::Response::new(body));
+
+        assert_eq!(read_body(&uri, response("abcdef"), None, 6).await.unwrap(), b"abcdef");
+        // The range is enforced even if the server ignores it.
+        assert_eq!(read_body(&uri, response("abcdef"), Some(4), 2).await.unwrap(), b"abcd");
+        assert!(matches!(
+            read_body(&uri, response("abcdef"), None, 5).await,
+            Err(CloudStorageError::TooLarge { size: 6, max: 5, .. })
+        ));
+    }
+}
@@ -1,3 +1,4 @@
+pub mod cloud_storage;
 pub mod consts;
 pub mod directories;
 pub mod error;
@@ -9,7 +10,6 @@ pub mod test;
 
 use std::collections::HashMap;
 use std::env::VarError;
-
 use std::path::Path;
 
 use bstr::ByteSlice as _;
@@ -47,6 +47,32 @@ where
     }
 }
 
+/// Substitutes all `{{VARIABLE_NAME}}` occurrences in `value` with the corresponding entry in
+/// `variables`.
+///
+/// Returns the names of any placeholders without a value, in order of first occurrence.
+pub fn expand_template_vars(value: &str, variables: &HashMap<String, String>) -> Result<String, Vec<String>> {
+    let re = Regex::new(r"\{\{([A-Za-z_][A-Za-z0-9_]*)\}\}").unwrap();
+    let mut undefined = Vec::new();
+    let expanded = re.replace_all(value, |caps: &regex::Captures<'_>| {
+        let var_name = &caps[1];
+        match variables.get(var_name) {
+            Some(value) => value.clone(),
+            None => {
+                if !undefined.iter().any(|v| v == var_name) {
+                    undefined.push(var_name.to_string());
+                }
+                caps[0].to_string()
+            },
+        }
+    });
+    if undefined.is_empty() {
+        Ok(expanded.to_string())
+    } else {
+        Err(undefined)
+    }
+}
+
 pub fn truncate_safe(s: &str, max_bytes: usize) -> &str {
     if s.len() <= max_bytes {
         return s;
@@ -88,6 +114,46 @@ pub fn truncate_safe_in_place(s: &mut String, max_bytes: usize, suffix: &str) {
     s.truncate(max_bytes);
 }
 
+/// Truncates `s` to a maximum length of `max_bytes`, appending a
+/// `[output truncated: N bytes omitted]` marker if `s` was truncated. Returns the number of bytes
+/// omitted from `s`.
+///
+/// `s` is cut at the end of the last line that fits, if any, so that only complete lines are kept.
+pub fn truncate_output_in_place(s: &mut String, max_bytes: usize) -> usize {
+    if s.len() <= max_bytes {
+        return 0;
+    }
+
+    let suffix = |omitted: usize| format!("\n[output truncated: {} bytes omitted]", omitted);
+    // The suffix can only get shorter once the actual number of omitted bytes is known.
+    let budget = max_bytes.saturating_sub(suffix(s.len()).len());
+    let kept = truncate_safe(s, budget);
+    let end = kept.rfind('\n').unwrap_or(kept.len());
+    let omitted = s.len() - end;
+    let suffix = suffix(omitted);
+    truncate_safe_in_place(s, (end + suffix.len()).min(max_bytes), &suffix);
+    omitted
+}
+
+/// Formats `bytes` as a human-readable size using binary units, similar to `du -h`, e.g.
+/// `512 B`, `1.5 KB`, or `2.0 GB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KB", "MB", "GB", "TB", "PB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next in &UNITS[1..] {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    format!("{:.1} {}", value, unit)
+}
+
 /// Reads a file to a maximum file length, returning the content and number of bytes truncated. If
 /// the file has to be truncated, content is suffixed with `truncated_suffix`.
 ///
@@ -138,10 +204,143 @@ pub fn is_integ_test() -> bool {
     std::env::var_os(CLI_IS_INTEG_TEST).is_some_and(|s| !s.is_empty())
 }
 
+/// Substrings of environment variable names, matched case-insensitively, of variables that are
+/// considered to hold secrets.
+const SENSITIVE_ENV_VAR_PATTERNS: &[&str] = &["TOKEN", "SECRET", "PASSWORD", "API_KEY", "CREDENTIAL"];
+
+/// Whether the environment variable `name` looks like it holds a secret, e.g. `GITHUB_TOKEN`.
+pub fn is_sensitive_env_var(name: &str) -> bool {
+    let upper = name.to_ascii_uppercase();
+    SENSITIVE_ENV_VAR_PATTERNS.iter().any(|p| upper.contains(p))
+}
+
+/// Splits a shell command line into the program and its arguments.
+///
+/// Supports single quotes, double quotes, and backslash escapes. Within double quotes, a
+/// backslash only escapes `"`, `\`, `$`, and `` ` ``, as in POSIX shells. Unterminated quotes
+/// are closed at the end of the input. Returns an empty program if `s` contains no words.
+pub fn parse_shell_command(s: &str) -> (String, Vec<String>) {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                for c in chars.by_ref() {
+                    if c == '\'' {
+                        break;
+                    }
+                    word.push(c);
+                }
+            },
+            '"' => {
+                in_word = true;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' if matches!(chars.peek(), Some('"' | '\\' | '$' | '`')) => {
+                            word.extend(chars.next());
+                        },
+                        c => word.push(c),
+                    }
+                }
+            },
+            '\\' => {
+                in_word = true;
+                word.extend(chars.next());
+            },
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            },
+            c => {
+                in_word = true;
+                word.push(c);
+            },
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+
+    let mut words = words.into_iter();
+    let program = words.next().unwrap_or_default();
+    (program, words.collect())
+}
+
+/// Quotes `arg` so that it is parsed as a single word by [parse_shell_command], leaving it as-is
+/// if no quoting is required.
+pub fn quote_shell_arg(arg: &str) -> String {
+    let is_safe = |c: char| c.is_alphanumeric() || "-_./=:,@%+".contains(c);
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_shell_command() {
+        let cases: &[(&str, &str, &[&str])] = &[
+            ("cargo test --test integration_tests", "cargo", &[
+                "test",
+                "--test",
+                "integration_tests",
+            ]),
+            ("  ls   -la  ", "ls", &["-la"]),
+            ("echo 'hello world'", "echo", &["hello world"]),
+            ("echo \"hello world\"", "echo", &["hello world"]),
+            ("echo \"a \\\"b\\\" \\n\"", "echo", &["a \"b\" \\n"]),
+            ("echo 'a \\ b'", "echo", &["a \\ b"]),
+            ("echo hello\\ world", "echo", &["hello world"]),
+            ("echo pre'quoted'post", "echo", &["prequotedpost"]),
+            ("echo '' \"\"", "echo", &["", ""]),
+            ("echo 'unterminated", "echo", &["unterminated"]),
+            ("", "", &[]),
+        ];
+        for &(input, program, args) in cases {
+            assert_eq!(
+                parse_shell_command(input),
+                (
+                    program.to_string(),
+                    args.iter().copied().map(String::from).collect::<Vec<_>>()
+                ),
+                "failed to parse {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_shell_command_round_trip() {
+        use rand::Rng as _;
+        use rand::seq::IndexedRandom as _;
+
+        const ALPHABET: &[char] = &['a', 'Z', '0', '-', '.', '/', ' ', '\'', '"', '\\', '$', '*', 'é'];
+        let mut rng = rand::rng();
+        for _ in 0..1000 {
+            let words = (0..rng.random_range(1..5))
+                .map(|_| {
+                    (0..rng.random_range(0..8))
+                        .map(|_| *ALPHABET.choose(&mut rng).unwrap())
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>();
+            let command = words.iter().map(|w| quote_shell_arg(w)).collect::<Vec<_>>().join(" ");
+            let (program, args) = parse_shell_command(&command);
+            assert_eq!(program, words[0], "{}", command);
+            assert_eq!(args, words[1..], "{}", command);
+        }
+    }
+
     #[test]
     fn test_truncate_safe() {
         assert_eq!(truncate_safe("Hello World", 5), "Hello");
@@ -150,6 +349,42 @@ mod tests {
         assert_eq!(truncate_safe("Hello World", 15), "Hello World");
     }
 
+    #[test]
+    fn test_truncate_output_in_place() {
+        let mut s = "line1\nline2\nline3".to_string();
+        assert_eq!(truncate_output_in_place(&mut s, 100), 0);
+        assert_eq!(s, "line1\nline2\nline3");
+
+        // Cut at the end of the last complete line.
+        let mut s = format!("{}\n{}", "a".repeat(10), "b".repeat(100));
+        let omitted = truncate_output_in_place(&mut s, 50);
+        assert_eq!(omitted, 101);
+        assert_eq!(s, format!("{}\n[output truncated: 101 bytes omitted]", "a".repeat(10)));
+        assert!(s.len() <= 50);
+
+        // A single long line is cut mid-line.
+        let mut s = "é".repeat(100);
+        let omitted = truncate_output_in_place(&mut s, 50);
+        assert!(s.len() <= 50, "{}", s);
+        assert!(
+            s.ends_with(&format!("\n[output truncated: {} bytes omitted]", omitted)),
+            "{}",
+            s
+        );
+        assert_eq!(s.find('\n').unwrap() + omitted, 200);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KB");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024 / 2), "1.5 GB");
+        assert_eq!(format_bytes(u64::MAX), "16384.0 PB");
+    }
+
     #[test]
     fn test_truncate_safe_in_place() {
         let suffix = "suffix";
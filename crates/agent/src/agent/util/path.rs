@@ -37,18 +37,12 @@ pub fn canonicalize_path(path: impl AsRef<str>) -> Result<String, UtilError> {
 }
 
 pub fn canonicalize_path_sys<P: SystemProvider>(path: impl AsRef<str>, provider: &P) -> Result<String, UtilError> {
-    let expanded =
-        shellexpand::full_with_context(path.as_ref(), shellexpand_home(provider), shellexpand_context(provider))?;
-    let path_buf = if !expanded.starts_with("/") {
-        // Convert relative paths to absolute paths
-        let current_dir = provider
-            .cwd()
-            .with_context(|| "could not get current directory".to_string())?;
-        current_dir.join(expanded.as_ref() as &str)
-    } else {
-        // Already absolute path
-        PathBuf::from(expanded.as_ref() as &str)
-    };
+    #[cfg(test)]
+    if let Some(mapped) = provider.mapped_path(path.as_ref()) {
+        return Ok(mapped.to_string_lossy().to_string());
+    }
+
+    let path_buf = absolute_path_sys(path.as_ref(), provider)?;
 
     // Try canonicalize first, fallback to manual normalization if it fails
     match path_buf.canonicalize() {
@@ -61,6 +55,39 @@ pub fn canonicalize_path_sys<P: SystemProvider>(path: impl AsRef<str>, provider:
     }
 }
 
+/// Same as [canonicalize_path_sys], except that a symlink at the final component of the path is
+/// not resolved, so that the returned path refers to the link itself rather than its target.
+pub fn canonicalize_parent_sys<P: SystemProvider>(path: impl AsRef<str>, provider: &P) -> Result<String, UtilError> {
+    #[cfg(test)]
+    if let Some(mapped) = provider.mapped_path(path.as_ref()) {
+        return Ok(mapped.to_string_lossy().to_string());
+    }
+
+    let path_buf = normalize_path(&absolute_path_sys(path.as_ref(), provider)?);
+    match (path_buf.parent(), path_buf.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = canonicalize_path_sys(parent.to_string_lossy(), provider)?;
+            Ok(Path::new(&parent).join(name).to_string_lossy().to_string())
+        },
+        _ => Ok(path_buf.to_string_lossy().to_string()),
+    }
+}
+
+/// Expands `path` and converts it to an absolute path relative to the current directory.
+fn absolute_path_sys<P: SystemProvider>(path: &str, provider: &P) -> Result<PathBuf, UtilError> {
+    let expanded = shellexpand::full_with_context(path, shellexpand_home(provider), shellexpand_context(provider))?;
+    if !expanded.starts_with("/") {
+        // Convert relative paths to absolute paths
+        let current_dir = provider
+            .cwd()
+            .with_context(|| "could not get current directory".to_string())?;
+        Ok(current_dir.join(expanded.as_ref() as &str))
+    } else {
+        // Already absolute path
+        Ok(PathBuf::from(expanded.as_ref() as &str))
+    }
+}
+
 /// Manually normalize a path by resolving . and .. components
 fn normalize_path(path: &Path) -> PathBuf {
     let mut components = Vec::new();
@@ -119,4 +146,23 @@ mod tests {
             );
         }
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_canonicalize_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join("target")).unwrap();
+        std::os::unix::fs::symlink(root.join("target"), root.join("link")).unwrap();
+        let sys = TestProvider::new().with_cwd(&root);
+
+        let canonical = |path: &str| PathBuf::from(canonicalize_path_sys(path, &sys).unwrap());
+        let parent = |path: &str| PathBuf::from(canonicalize_parent_sys(path, &sys).unwrap());
+        assert_eq!(canonical("link"), root.join("target"));
+        assert_eq!(parent("link"), root.join("link"));
+        assert_eq!(parent("link/"), root.join("link"));
+        // Links before the final component are still resolved.
+        assert_eq!(parent("link/file"), root.join("target/file"));
+        assert_eq!(parent("target/../link"), root.join("link"));
+    }
 }
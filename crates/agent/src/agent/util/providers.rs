@@ -3,10 +3,29 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use super::directories;
+#[cfg(any(test, feature = "test-util"))]
+use super::test::CommandStub;
 
 /// A trait for accessing system and process context (env vars, home dir, current working dir,
 /// etc.).
-pub trait SystemProvider: EnvProvider + HomeProvider + CwdProvider + std::fmt::Debug + Send + Sync + 'static {}
+pub trait SystemProvider: EnvProvider + HomeProvider + CwdProvider + std::fmt::Debug + Send + Sync + 'static {
+    /// Returns a fixed resolution for the given path, bypassing expansion and canonicalization.
+    ///
+    /// Only available to unit tests, for faking path resolution without touching the filesystem.
+    #[cfg(test)]
+    fn mapped_path(&self, _path: &str) -> Option<PathBuf> {
+        None
+    }
+
+    /// Returns stubbed output for the shell command `command`, which is then not executed.
+    ///
+    /// Only available to tests, see
+    /// [TestProvider::with_command_stub](super::test::TestProvider::with_command_stub).
+    #[cfg(any(test, feature = "test-util"))]
+    fn command_stub(&self, _command: &str) -> Option<CommandStub> {
+        None
+    }
+}
 
 impl EnvProvider for Box<dyn SystemProvider> {
     fn var(&self, input: &str) -> Result<String, VarError> {
@@ -26,7 +45,17 @@ impl CwdProvider for Box<dyn SystemProvider> {
     }
 }
 
-impl SystemProvider for Box<dyn SystemProvider> {}
+impl SystemProvider for Box<dyn SystemProvider> {
+    #[cfg(test)]
+    fn mapped_path(&self, path: &str) -> Option<PathBuf> {
+        (**self).mapped_path(path)
+    }
+
+    #[cfg(any(test, feature = "test-util"))]
+    fn command_stub(&self, command: &str) -> Option<CommandStub> {
+        (**self).command_stub(command)
+    }
+}
 
 impl EnvProvider for Arc<dyn SystemProvider> {
     fn var(&self, input: &str) -> Result<String, VarError> {
@@ -46,7 +75,17 @@ impl CwdProvider for Arc<dyn SystemProvider> {
     }
 }
 
-impl SystemProvider for Arc<dyn SystemProvider> {}
+impl SystemProvider for Arc<dyn SystemProvider> {
+    #[cfg(test)]
+    fn mapped_path(&self, path: &str) -> Option<PathBuf> {
+        (**self).mapped_path(path)
+    }
+
+    #[cfg(any(test, feature = "test-util"))]
+    fn command_stub(&self, command: &str) -> Option<CommandStub> {
+        (**self).command_stub(command)
+    }
+}
 
 /// A trait for accessing environment variables.
 ///
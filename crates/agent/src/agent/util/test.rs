@@ -1,11 +1,15 @@
 //! Module for common testing utilities
 
+#[cfg(test)]
+use std::collections::HashMap;
 use std::env::VarError;
 use std::path::{
     Path,
     PathBuf,
 };
 
+#[cfg(any(test, feature = "test-util"))]
+use super::glob::matches_any_pattern;
 use super::path::canonicalize_path_sys;
 use super::providers::{
     CwdProvider,
@@ -45,10 +49,23 @@ impl TestBase {
         &self.provider
     }
 
+    /// Sets the environment variable `key` as seen through the [EnvProvider] of this [TestBase].
+    pub fn with_var(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        self.provider = self.provider.with_var(key, value);
+        self
+    }
+
     pub async fn with_file(mut self, file: impl TestFile) -> Self {
         self.test_dir = self.test_dir.with_file_sys(file, &self.provider).await;
         self
     }
+
+    /// See [TestProvider::with_command_stub].
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn with_command_stub(mut self, command_pattern: &str, output: &str, exit_code: i32) -> Self {
+        self.provider = self.provider.with_command_stub(command_pattern, output, exit_code);
+        self
+    }
 }
 
 impl EnvProvider for TestBase {
@@ -69,7 +86,21 @@ impl CwdProvider for TestBase {
     }
 }
 
-impl SystemProvider for TestBase {}
+impl SystemProvider for TestBase {
+    #[cfg(any(test, feature = "test-util"))]
+    fn command_stub(&self, command: &str) -> Option<CommandStub> {
+        self.provider.command_stub(command)
+    }
+}
+
+/// Stubbed output for a shell command, registered with [TestProvider::with_command_stub].
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandStub {
+    pub command_pattern: String,
+    pub output: String,
+    pub exit_code: i32,
+}
 
 #[derive(Debug)]
 pub struct TestDir {
@@ -176,6 +207,8 @@ pub struct TestProvider {
     env: std::collections::HashMap<String, String>,
     home: Option<PathBuf>,
     cwd: Option<PathBuf>,
+    #[cfg(any(test, feature = "test-util"))]
+    command_stubs: Vec<CommandStub>,
 }
 
 impl TestProvider {
@@ -190,6 +223,8 @@ impl TestProvider {
             env,
             home: Some(PathBuf::from("/home/testuser")),
             cwd: Some(PathBuf::from("/home/testuser")),
+            #[cfg(any(test, feature = "test-util"))]
+            command_stubs: Vec::new(),
         }
     }
 
@@ -210,6 +245,8 @@ impl TestProvider {
             env,
             home: Some(base.to_owned()),
             cwd: Some(base.to_owned()),
+            #[cfg(any(test, feature = "test-util"))]
+            command_stubs: Vec::new(),
         }
     }
 
@@ -222,6 +259,20 @@ impl TestProvider {
         self.cwd = Some(PathBuf::from(cwd.as_ref()));
         self
     }
+
+    /// Stubs the output of `executeCmd` tool uses whose command matches `command_pattern`.
+    ///
+    /// `command_pattern` is either an exact command or a glob pattern. Piped commands are matched
+    /// as `command | pipe_to`. When several stubs match, the most recently registered one is used.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn with_command_stub(mut self, command_pattern: &str, output: &str, exit_code: i32) -> Self {
+        self.command_stubs.push(CommandStub {
+            command_pattern: command_pattern.to_string(),
+            output: output.to_string(),
+            exit_code,
+        });
+        self
+    }
 }
 
 impl Default for TestProvider {
@@ -251,7 +302,100 @@ impl CwdProvider for TestProvider {
     }
 }
 
-impl SystemProvider for TestProvider {}
+impl SystemProvider for TestProvider {
+    #[cfg(any(test, feature = "test-util"))]
+    fn command_stub(&self, command: &str) -> Option<CommandStub> {
+        self.command_stubs
+            .iter()
+            .rev()
+            .find(|stub| matches_any_pattern([&stub.command_pattern], command))
+            .cloned()
+    }
+}
+
+/// Test helper that implements [SystemProvider] with configurable path mappings.
+///
+/// Paths registered with [MockSystemProvider::with_path] are returned as-is by
+/// [canonicalize_path_sys] without touching the filesystem, allowing tool validation to be tested
+/// without creating a temporary directory. Only available to unit tests.
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct MockSystemProvider {
+    path_map: HashMap<String, PathBuf>,
+    home_dir: PathBuf,
+    current_dir: PathBuf,
+}
+
+#[cfg(test)]
+impl MockSystemProvider {
+    /// Creates a new mock provider with the following defaults:
+    /// - env vars: HOME=/home/testuser
+    /// - cwd: /home/testuser
+    /// - home: /home/testuser
+    /// - no path mappings
+    pub fn new() -> Self {
+        Self {
+            path_map: HashMap::new(),
+            home_dir: PathBuf::from("/home/testuser"),
+            current_dir: PathBuf::from("/home/testuser"),
+        }
+    }
+
+    /// Maps the path string `from`, exactly as passed to [canonicalize_path_sys], to `to`.
+    pub fn with_path(mut self, from: impl AsRef<str>, to: impl AsRef<Path>) -> Self {
+        self.path_map
+            .insert(from.as_ref().to_string(), to.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn with_home(mut self, home: impl AsRef<Path>) -> Self {
+        self.home_dir = home.as_ref().to_path_buf();
+        self
+    }
+
+    pub fn with_cwd(mut self, cwd: impl AsRef<Path>) -> Self {
+        self.current_dir = cwd.as_ref().to_path_buf();
+        self
+    }
+}
+
+#[cfg(test)]
+impl Default for MockSystemProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+impl EnvProvider for MockSystemProvider {
+    fn var(&self, input: &str) -> Result<String, VarError> {
+        match input {
+            "HOME" => Ok(self.home_dir.to_string_lossy().to_string()),
+            _ => Err(VarError::NotPresent),
+        }
+    }
+}
+
+#[cfg(test)]
+impl HomeProvider for MockSystemProvider {
+    fn home(&self) -> Option<PathBuf> {
+        Some(self.home_dir.clone())
+    }
+}
+
+#[cfg(test)]
+impl CwdProvider for MockSystemProvider {
+    fn cwd(&self) -> Result<PathBuf, std::io::Error> {
+        Ok(self.current_dir.clone())
+    }
+}
+
+#[cfg(test)]
+impl SystemProvider for MockSystemProvider {
+    fn mapped_path(&self, path: &str) -> Option<PathBuf> {
+        self.path_map.get(path).cloned()
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -274,6 +418,43 @@ mod tests {
         assert_eq!(fs::read_to_string(test_dir.join("home")).await.unwrap(), "home");
     }
 
+    #[test]
+    fn test_mock_system_provider_path_map() {
+        let sys = MockSystemProvider::new()
+            .with_path("~/foo", "/tmp/bar")
+            .with_cwd("/home/testuser/project");
+
+        assert_eq!(canonicalize_path_sys("~/foo", &sys).unwrap(), "/tmp/bar");
+        assert_eq!(
+            canonicalize_path_sys("~/other", &sys).unwrap(),
+            "/home/testuser/other",
+            "unmapped paths should use the mock home directory"
+        );
+        assert_eq!(
+            canonicalize_path_sys("src/main.rs", &sys).unwrap(),
+            "/home/testuser/project/src/main.rs",
+            "unmapped relative paths should use the mock cwd"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_command_stubs() {
+        let test_base = TestBase::new()
+            .await
+            .with_command_stub("cargo *", "cargo output", 0)
+            .with_command_stub("cargo test", "test output", 101);
+
+        let stub = test_base.command_stub("cargo test").unwrap();
+        assert_eq!(stub.output, "test output", "later stubs should take precedence");
+        assert_eq!(stub.exit_code, 101);
+        assert_eq!(test_base.command_stub("cargo build").unwrap().output, "cargo output");
+        assert!(test_base.command_stub("git status").is_none());
+        assert!(
+            TestBase::new().await.command_stub("cargo test").is_none(),
+            "stubs should not be shared between providers"
+        );
+    }
+
     #[tokio::test]
     #[should_panic]
     async fn test_tempdir_write_file_outside() {
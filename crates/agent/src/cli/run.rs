@@ -23,6 +23,7 @@ use agent::rts::{
     RtsModel,
     RtsModelState,
 };
+use agent::tools::ToolContext;
 use agent::types::AgentSnapshot;
 use agent::{
     Agent,
@@ -146,11 +147,23 @@ impl RunArgs {
                 AgentEvent::Stop(AgentStopReason::Error(agent_error)) => {
                     bail!("agent encountered an error: {:?}", agent_error)
                 },
-                AgentEvent::ApprovalRequest { id, tool_use, .. } => {
+                AgentEvent::ApprovalRequest {
+                    id,
+                    tool_use,
+                    context,
+                    preview,
+                } => {
+                    let summary = match context {
+                        Some(ToolContext::FileWrite(ctx)) => Some(ctx.diff_summary()),
+                        _ => preview.clone(),
+                    };
                     if !self.dangerously_trust_all_tools {
-                        bail!("Tool approval is required: {:?}", tool_use);
+                        match summary {
+                            Some(summary) => bail!("Tool approval is required ({}): {:?}", summary, tool_use),
+                            None => bail!("Tool approval is required: {:?}", tool_use),
+                        }
                     } else {
-                        warn!(?tool_use, "trust all is enabled, ignoring approval request");
+                        warn!(?tool_use, ?summary, "trust all is enabled, ignoring approval request");
                         agent
                             .send_tool_use_approval_result(SendApprovalResultArgs {
                                 id: id.clone(),
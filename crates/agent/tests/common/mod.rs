@@ -31,7 +31,10 @@ use agent::protocol::{
     SendApprovalResultArgs,
     SendPromptArgs,
 };
-use agent::types::AgentSnapshot;
+use agent::types::{
+    AgentSettings,
+    AgentSnapshot,
+};
 use agent::util::test::{
     TestBase,
     TestFile,
@@ -51,7 +54,9 @@ type MockResponseStreams = Vec<Vec<StreamResult>>;
 pub struct TestCaseBuilder {
     test_name: Option<String>,
     agent_config: Option<AgentConfig>,
+    agent_settings: Option<AgentSettings>,
     files: Vec<Box<dyn TestFile>>,
+    command_stubs: Vec<(String, String, i32)>,
     mock_responses: Vec<MockResponse>,
     trust_all_tools: bool,
     tool_use_approvals: Vec<SendApprovalResultArgs>,
@@ -68,11 +73,23 @@ impl TestCaseBuilder {
         self
     }
 
+    pub fn with_agent_settings(mut self, agent_settings: AgentSettings) -> Self {
+        self.agent_settings = Some(agent_settings);
+        self
+    }
+
     pub fn with_file(mut self, file: impl TestFile + 'static) -> Self {
         self.files.push(Box::new(file));
         self
     }
 
+    /// See [TestBase::with_command_stub].
+    pub fn with_command_stub(mut self, command_pattern: &str, output: &str, exit_code: i32) -> Self {
+        self.command_stubs
+            .push((command_pattern.to_string(), output.to_string(), exit_code));
+        self
+    }
+
     pub fn with_responses(mut self, responses: MockResponseStreams) -> Self {
         for response in responses {
             self.mock_responses.push(response.into());
@@ -93,7 +110,10 @@ impl TestCaseBuilder {
     }
 
     pub async fn build(self) -> Result<TestCase> {
-        let snapshot = AgentSnapshot::new_empty(self.agent_config.unwrap_or_default());
+        let mut snapshot = AgentSnapshot::new_empty(self.agent_config.unwrap_or_default());
+        if let Some(settings) = self.agent_settings {
+            snapshot.settings = settings;
+        }
 
         let mut model = MockModel::new();
         for response in self.mock_responses {
@@ -106,6 +126,9 @@ impl TestCaseBuilder {
         for file in self.files {
             test_base = test_base.with_file(file).await;
         }
+        for (command_pattern, output, exit_code) in &self.command_stubs {
+            test_base = test_base.with_command_stub(command_pattern, output, *exit_code);
+        }
 
         agent.set_sys_provider(test_base.provider().clone());
 
@@ -164,6 +187,11 @@ impl TestCase {
         &self.sent_requests
     }
 
+    /// History of all events emitted by the agent so far.
+    pub fn agent_events(&self) -> &[AgentEvent] {
+        &self.agent_events
+    }
+
     pub async fn wait_until_agent_stop(&mut self, timeout: Duration) {
         let timeout_at = Instant::now() + timeout;
         loop {
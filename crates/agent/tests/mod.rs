@@ -1,12 +1,28 @@
 mod common;
 
+use std::sync::Arc;
 use std::time::Duration;
 
+use agent::Agent;
 use agent::agent_config::definitions::AgentConfig;
+use agent::agent_loop::model::MockModel;
+use agent::audit::AuditEvent;
+use agent::mcp::McpManager;
 use agent::protocol::{
+    AgentEvent,
     ApprovalResult,
+    InternalEvent,
     SendApprovalResultArgs,
 };
+use agent::tools::ToolState;
+use agent::tools::fs_write::{
+    FileLineTracker,
+    FsWriteState,
+};
+use agent::types::{
+    AgentSettings,
+    AgentSnapshot,
+};
 use common::*;
 
 #[tokio::test]
@@ -71,3 +87,155 @@ async fn test_agent_defaults() {
         assert_contains(SUB_LOCAL_RULE_MD_CONTENT);
     }
 }
+
+fn tool_state_with_lines(lines: usize) -> ToolState {
+    ToolState {
+        file_write: Some(FsWriteState {
+            line_tracker: FileLineTracker {
+                prev_fswrite_lines: lines,
+                ..Default::default()
+            },
+        }),
+    }
+}
+
+#[tokio::test]
+async fn test_tool_state_disk_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session").join("tool_state.json");
+
+    tool_state_with_lines(42).serialize_to_disk(&path).await.unwrap();
+    let entries = std::fs::read_dir(path.parent().unwrap()).unwrap().count();
+    assert_eq!(entries, 1, "no temporary files should be left behind");
+
+    let tool_state = ToolState::deserialize_from_disk(&path).await.unwrap();
+    assert_eq!(tool_state.file_write.unwrap().line_tracker.prev_fswrite_lines, 42);
+
+    // Saving again overwrites the previous state.
+    tool_state_with_lines(7).serialize_to_disk(&path).await.unwrap();
+    let tool_state = ToolState::deserialize_from_disk(&path).await.unwrap();
+    assert_eq!(tool_state.file_write.unwrap().line_tracker.prev_fswrite_lines, 7);
+
+    assert!(
+        ToolState::deserialize_from_disk(dir.path().join("missing.json"))
+            .await
+            .is_err()
+    );
+    std::fs::write(&path, "not json").unwrap();
+    assert!(ToolState::deserialize_from_disk(&path).await.is_err());
+}
+
+#[tokio::test]
+async fn test_agent_persists_tool_state() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("tool_state.json");
+    tool_state_with_lines(42).serialize_to_disk(&path).await.unwrap();
+
+    let mut snapshot = AgentSnapshot::new_empty(AgentConfig::default());
+    snapshot.settings.tool_state_path = Some(path.clone());
+    let mut agent = Agent::new(snapshot, Arc::new(MockModel::new()), McpManager::new().spawn())
+        .await
+        .unwrap()
+        .spawn();
+    while let Ok(evt) = agent.recv().await {
+        if matches!(evt, AgentEvent::Initialized) {
+            break;
+        }
+    }
+
+    // State is restored at session start.
+    let snapshot = agent.create_snapshot().await.unwrap();
+    assert_eq!(
+        snapshot.tool_state.file_write.unwrap().line_tracker.prev_fswrite_lines,
+        42
+    );
+
+    // State is saved again at session end.
+    std::fs::remove_file(&path).unwrap();
+    drop(agent);
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while !path.exists() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("tool state should be persisted when the agent exits");
+    let tool_state = ToolState::deserialize_from_disk(&path).await.unwrap();
+    assert_eq!(tool_state.file_write.unwrap().line_tracker.prev_fswrite_lines, 42);
+}
+
+#[tokio::test]
+async fn test_agent_audit_log() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let dir = tempfile::tempdir().unwrap();
+    let audit_log_path = dir.path().join("audit.jsonl");
+    let mut test = TestCase::builder()
+        .test_name("agent audit log")
+        .with_agent_settings(AgentSettings {
+            audit_log_path: Some(audit_log_path.clone()),
+            ..Default::default()
+        })
+        .with_responses(
+            parse_response_streams(include_str!("./mock_responses/builtin_tools.jsonl"))
+                .await
+                .unwrap(),
+        )
+        .with_trust_all_tools(true)
+        .build()
+        .await
+        .unwrap();
+
+    test.send_prompt("start turn".to_string()).await;
+    test.wait_until_agent_stop(Duration::from_secs(2)).await;
+
+    let events = std::fs::read_to_string(&audit_log_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<AuditEvent>(line).unwrap())
+        .collect::<Vec<_>>();
+    let tools = events.iter().map(|e| e.tool.as_str()).collect::<Vec<_>>();
+    assert_eq!(tools, ["fsWrite", "ls", "fsRead"]);
+    for event in &events {
+        assert!(event.success, "{:?}", event);
+        serde_json::from_str::<serde_json::Value>(&event.args_json).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_agent_execute_cmd_stub() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let mut test = TestCase::builder()
+        .test_name("agent execute cmd stub")
+        .with_command_stub("cargo test", "test result: ok. 42 passed", 0)
+        .with_responses(
+            parse_response_streams(include_str!("./mock_responses/execute_cmd_stub.jsonl"))
+                .await
+                .unwrap(),
+        )
+        .with_trust_all_tools(true)
+        .build()
+        .await
+        .unwrap();
+
+    test.send_prompt("run the tests".to_string()).await;
+    test.wait_until_agent_stop(Duration::from_secs(2)).await;
+
+    // The stubbed output is sent back to the model instead of running the command.
+    let tool_result = serde_json::to_string(test.requests().last().unwrap().messages().last().unwrap()).unwrap();
+    assert!(tool_result.contains("test result: ok. 42 passed"), "{}", tool_result);
+
+    // Metrics about the output are emitted for telemetry.
+    let item_counts = test
+        .agent_events()
+        .iter()
+        .find_map(|evt| match evt {
+            AgentEvent::Internal(InternalEvent::ToolOutputMetrics { item_counts, .. }) => Some(*item_counts),
+            _ => None,
+        })
+        .expect("expected a tool output metrics event");
+    assert!(item_counts.json > 0, "{:?}", item_counts);
+}
@@ -215,7 +215,7 @@ impl RtsModel {
         let user_input_message = match messages.pop() {
             Some(m) if m.role == Role::User => {
                 let content = m.text();
-                let (tool_results, images) = extract_tool_results_and_images(&m);
+                let (tool_results, images) = extract_tool_results_and_images(&m)?;
                 let user_input_message_context = Some(UserInputMessageContext {
                     env_state: None,
                     git_state: None,
@@ -240,7 +240,7 @@ impl RtsModel {
             .map(|m| match m.role {
                 Role::User => {
                     let content = m.text();
-                    let (tool_results, _) = extract_tool_results_and_images(&m);
+                    let (tool_results, _) = extract_tool_results_and_images(&m)?;
                     let ctx = if tool_results.is_some() {
                         Some(UserInputMessageContext {
                             env_state: None,
@@ -258,7 +258,7 @@ impl RtsModel {
                         images: None,
                         model_id: None,
                     };
-                    rts::ChatMessage::UserInputMessage(msg)
+                    Ok(rts::ChatMessage::UserInputMessage(msg))
                 },
                 Role::Assistant => {
                     let msg = rts::AssistantResponseMessage {
@@ -266,10 +266,10 @@ impl RtsModel {
                         content: m.text(),
                         tool_uses: m.tool_uses().map(|v| v.into_iter().map(Into::into).collect()),
                     };
-                    rts::ChatMessage::AssistantResponseMessage(msg)
+                    Ok(rts::ChatMessage::AssistantResponseMessage(msg))
                 },
             })
-            .collect();
+            .collect::<Result<_, String>>()?;
 
         Ok(ConversationState {
             conversation_id: Some(self.conversation_id.to_string()),
@@ -291,9 +291,14 @@ impl StreamErrorSource for ApiClientError {
     }
 }
 
+/// Tool results and images extracted from a [Message] by [extract_tool_results_and_images].
+type ToolResultsAndImages = (Option<Vec<rts::ToolResult>>, Option<Vec<rts::ImageBlock>>);
+
 /// Annoyingly, the RTS API doesn't allow images as tool use results, so we have to extract tool
 /// results and image content separately.
-fn extract_tool_results_and_images(message: &Message) -> (Option<Vec<rts::ToolResult>>, Option<Vec<rts::ImageBlock>>) {
+///
+/// Returns an error if an image is in a format that is not accepted by the model.
+fn extract_tool_results_and_images(message: &Message) -> Result<ToolResultsAndImages, String> {
     let mut images = Vec::new();
     let mut tool_results = Vec::new();
     for item in &message.content {
@@ -308,10 +313,7 @@ fn extract_tool_results_and_images(message: &Message) -> (Option<Vec<rts::ToolRe
                         ToolResultContentBlock::Json(v) => {
                             content.push(rts::ToolResultContentBlock::Json(serde_value_to_document(v.clone())));
                         },
-                        ToolResultContentBlock::Image(img) => images.push(rts::ImageBlock {
-                            format: img.format.into(),
-                            source: img.source.clone().into(),
-                        }),
+                        ToolResultContentBlock::Image(img) => images.push(img.clone().try_into()?),
                     }
                 }
                 tool_results.push(rts::ToolResult {
@@ -320,22 +322,19 @@ fn extract_tool_results_and_images(message: &Message) -> (Option<Vec<rts::ToolRe
                     status,
                 });
             },
-            ContentBlock::Image(img) => images.push(rts::ImageBlock {
-                format: img.format.into(),
-                source: img.source.clone().into(),
-            }),
+            ContentBlock::Image(img) => images.push(img.clone().try_into()?),
             _ => (),
         }
     }
 
-    (
+    Ok((
         if tool_results.is_empty() {
             None
         } else {
             Some(tool_results)
         },
         if images.is_empty() { None } else { Some(images) },
-    )
+    ))
 }
 
 impl Model for RtsModel {
@@ -689,6 +688,11 @@ enum RecvError {
 
 #[cfg(test)]
 mod tests {
+    use agent::agent_loop::types::{
+        ImageBlock,
+        ImageFormat,
+        ImageSource,
+    };
     use tokio_stream::StreamExt as _;
 
     use super::*;
@@ -699,6 +703,30 @@ mod tests {
     };
     use crate::util::env_var::is_integ_test;
 
+    #[test]
+    fn test_extract_images_rejects_unsupported_formats() {
+        let message = |format| {
+            Message::new(
+                Role::User,
+                vec![ContentBlock::Image(ImageBlock {
+                    format,
+                    source: ImageSource::Bytes(vec![1, 2, 3]),
+                })],
+                None,
+            )
+        };
+
+        let (_, images) = extract_tool_results_and_images(&message(ImageFormat::Png)).unwrap();
+        assert!(matches!(
+            images.as_deref(),
+            Some([rts::ImageBlock {
+                format: rts::ImageFormat::Png,
+                ..
+            }])
+        ));
+        assert!(extract_tool_results_and_images(&message(ImageFormat::Tiff)).is_err());
+    }
+
     /// Manual test to verify cancellation succeeds in a timely manner.
     #[tokio::test]
     async fn integ_test_rts_cancel() {
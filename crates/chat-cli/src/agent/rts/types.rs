@@ -3,22 +3,29 @@ use agent::agent_loop::types::*;
 use crate::api_client::model;
 use crate::cli::chat::util::serde_value_to_document;
 
-impl From<ImageBlock> for model::ImageBlock {
-    fn from(v: ImageBlock) -> Self {
-        Self {
-            format: v.format.into(),
+impl TryFrom<ImageBlock> for model::ImageBlock {
+    type Error = String;
+
+    fn try_from(v: ImageBlock) -> Result<Self, Self::Error> {
+        Ok(Self {
+            format: v.format.try_into()?,
             source: v.source.into(),
-        }
+        })
     }
 }
 
-impl From<ImageFormat> for model::ImageFormat {
-    fn from(value: ImageFormat) -> Self {
+impl TryFrom<ImageFormat> for model::ImageFormat {
+    type Error = String;
+
+    fn try_from(value: ImageFormat) -> Result<Self, Self::Error> {
         match value {
-            ImageFormat::Gif => Self::Gif,
-            ImageFormat::Jpeg => Self::Jpeg,
-            ImageFormat::Png => Self::Png,
-            ImageFormat::Webp => Self::Webp,
+            ImageFormat::Gif => Ok(Self::Gif),
+            ImageFormat::Jpeg => Ok(Self::Jpeg),
+            ImageFormat::Png => Ok(Self::Png),
+            ImageFormat::Webp => Ok(Self::Webp),
+            // TIFF is not accepted by the model, and must be converted to a supported format
+            // beforehand, e.g. by ImageRead.
+            ImageFormat::Tiff => Err(format!("Unsupported image format: {}", value)),
         }
     }
 }
@@ -27,6 +34,8 @@ impl From<ImageSource> for model::ImageSource {
     fn from(value: ImageSource) -> Self {
         match value {
             ImageSource::Bytes(items) => Self::Bytes(items),
+            // The model only accepts image bytes, which are already downloaded.
+            ImageSource::Url { bytes, .. } => Self::Bytes(bytes),
         }
     }
 }